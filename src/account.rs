@@ -0,0 +1,261 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use chrono::Local;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::errors::AccountError;
+
+// 默认账号文件
+const DEFAULT_ACCOUNT_STORE: &str = ".accounts.enc";
+
+// 系统钥匙串中的服务名/用户名
+const KEYCHAIN_SERVICE: &str = "dm-ticket";
+const KEYCHAIN_USER: &str = "account-store";
+
+// 账号文件格式版本
+const STORE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Account {
+    pub name: String,
+
+    pub cookie: String,
+
+    #[serde(default)]
+    pub nickname: String,
+
+    pub updated_at: i64,
+}
+
+impl Account {
+    pub fn new(name: &str, cookie: &str, nickname: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            cookie: cookie.trim().to_string(),
+            nickname: nickname.to_string(),
+            updated_at: Local::now().timestamp_millis(),
+        }
+    }
+}
+
+// 解析粘贴或从标准输入读取的cookie, 支持多行及"Cookie:"请求头, 统一为name=value;name=value;
+pub fn parse_cookie(input: &str) -> Result<String> {
+    let mut cookie = String::new();
+    for line in input.lines() {
+        let line = line.trim().trim_matches(['\'', '"']);
+        let line = match line.split_once(':') {
+            Some((name, value)) if name.trim().eq_ignore_ascii_case("cookie") => value,
+            _ => line,
+        };
+        for pair in line.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some((name, _)) if !name.trim().is_empty() => {
+                    cookie.push_str(pair);
+                    cookie.push(';');
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "cookie格式错误:{}, 应为name=value;name=value",
+                        pair
+                    ))
+                }
+            }
+        }
+    }
+    match cookie.is_empty() {
+        true => Err(anyhow!("未读取到cookie")),
+        false => Ok(cookie),
+    }
+}
+
+// 加密后写入磁盘的内容
+#[derive(Serialize, Deserialize, Debug)]
+struct EncryptedFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    data: String,
+}
+
+// 加密保存的账号, 密钥由主密码经argon2派生, 使用ChaCha20-Poly1305加密
+pub struct AccountStore {
+    path: PathBuf,
+    passphrase: String,
+    accounts: Vec<Account>,
+}
+
+impl AccountStore {
+    // 打开账号文件, 文件不存在时为空
+    pub fn open(path: impl Into<PathBuf>, passphrase: &str) -> Result<Self> {
+        let path = path.into();
+
+        let accounts = match path.exists() {
+            true => Self::decrypt(&fs::read_to_string(&path)?, passphrase)?,
+            false => vec![],
+        };
+
+        Ok(Self {
+            path,
+            passphrase: passphrase.to_string(),
+            accounts,
+        })
+    }
+
+    // ACCOUNT_STORE: 账号文件路径
+    // ACCOUNT_PASSPHRASE: 主密码, 未配置时从系统钥匙串读取或提示输入
+    pub fn from_env() -> Result<Self> {
+        let path = env::var("ACCOUNT_STORE").unwrap_or(DEFAULT_ACCOUNT_STORE.to_string());
+        let passphrase = passphrase()?;
+        Self::open(path, &passphrase)
+    }
+
+    fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow!("派生密钥失败:{}", e))?;
+        Ok(key)
+    }
+
+    fn encrypt(accounts: &[Account], passphrase: &str) -> Result<String> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        let key = Self::derive_key(passphrase, &salt)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let plaintext = serde_json::to_vec(accounts)?;
+        let ciphertext = ChaCha20Poly1305::new(&key)
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| anyhow!("加密账号失败"))?;
+
+        let file = EncryptedFile {
+            version: STORE_VERSION,
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            data: STANDARD.encode(ciphertext),
+        };
+
+        Ok(serde_json::to_string_pretty(&file)?)
+    }
+
+    fn decrypt(content: &str, passphrase: &str) -> Result<Vec<Account>> {
+        let file: EncryptedFile = serde_json::from_str(content)?;
+        if file.version != STORE_VERSION {
+            return Err(anyhow!("不支持的账号文件版本:{}", file.version));
+        }
+
+        let salt = STANDARD.decode(file.salt)?;
+        let nonce = STANDARD.decode(file.nonce)?;
+        let ciphertext = STANDARD.decode(file.data)?;
+        if nonce.len() != 12 {
+            return Err(AccountError::Corrupted.into());
+        }
+
+        let key = Self::derive_key(passphrase, &salt)?;
+        let plaintext = ChaCha20Poly1305::new(&key)
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| AccountError::InvalidPassphrase)?;
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    // 保存到磁盘, 先写临时文件再替换, 避免写入中断损坏账号文件
+    pub fn save(&self) -> Result<()> {
+        let content = Self::encrypt(&self.accounts, &self.passphrase)?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, content)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))?;
+        }
+
+        fs::rename(&tmp, &self.path)?;
+        debug!("账号已保存至:{:?}", self.path);
+        Ok(())
+    }
+
+    pub fn list(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Account> {
+        self.accounts.iter().find(|a| a.name == name)
+    }
+
+    // 添加账号, 同名账号已存在时覆盖
+    pub fn upsert(&mut self, account: Account) {
+        match self.accounts.iter_mut().find(|a| a.name == account.name) {
+            Some(old) => *old = account,
+            None => self.accounts.push(account),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Account> {
+        let index =
+            self.accounts
+                .iter()
+                .position(|a| a.name == name)
+                .ok_or(AccountError::NotFound {
+                    name: name.to_string(),
+                })?;
+        Ok(self.accounts.remove(index))
+    }
+}
+
+// 是否使用系统钥匙串保存主密码, ACCOUNT_KEYCHAIN=true时开启
+fn keychain_enabled() -> bool {
+    env::var("ACCOUNT_KEYCHAIN")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+// 获取主密码: 环境变量 > 系统钥匙串 > 终端输入
+fn passphrase() -> Result<String> {
+    if let Ok(passphrase) = env::var("ACCOUNT_PASSPHRASE") {
+        if !passphrase.is_empty() {
+            return Ok(passphrase);
+        }
+    }
+
+    let keychain = match keychain_enabled() {
+        true => match keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!("无法访问系统钥匙串:{:?}", e);
+                None
+            }
+        },
+        false => None,
+    };
+
+    if let Some(entry) = &keychain {
+        if let Ok(passphrase) = entry.get_password() {
+            return Ok(passphrase);
+        }
+    }
+
+    let passphrase = rpassword::prompt_password("请输入账号主密码:")?;
+    if passphrase.is_empty() {
+        return Err(AccountError::InvalidPassphrase.into());
+    }
+
+    if let Some(entry) = &keychain {
+        if let Err(e) = entry.set_password(&passphrase) {
+            warn!("主密码保存至系统钥匙串失败:{:?}", e);
+        }
+    }
+
+    Ok(passphrase)
+}
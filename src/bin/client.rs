@@ -1,32 +1,1099 @@
 use anyhow::Result;
+use chrono::{Local, TimeZone};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+#[cfg(feature = "interactive")]
 use dm_ticket::client::Client;
+#[cfg(feature = "http-login")]
+use dm_ticket::clients::login::LoginClient;
+use dm_ticket::daemon::DaemonOptions;
+#[cfg(feature = "http-login")]
+use dm_ticket::qrcode_render::{self, QrOptions};
+#[cfg(windows)]
+use dm_ticket::winservice;
+use dm_ticket::{
+    account::{parse_cookie, Account, AccountStore},
+    calendar::{self, SaleEvent},
+    clients::{dm::DmClient, notify::NotifyClient, record},
+    config, config_check, doctor,
+    errors::AccountError,
+    exit::{self, ExitStatus},
+    format_timestamp,
+    history::{RunStore, ShowStats},
+    logger,
+    models::{
+        address::{Address, NewAddress},
+        order::OrderSummary,
+        ticket::{parse_date, TicketFilter},
+        viewer::{self, Viewer},
+    },
+    output::{self, OutputFormat},
+    platform::TicketPlatform,
+    resume::{self, ArmedStore},
+    sale_timezone,
+    simulator::{simulate, SimulatorConfig},
+};
 use dotenv::dotenv;
-use std::env;
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    env, fs,
+    io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+use tracing::{error, info, warn};
 
+// 监控订单的轮询间隔, 秒
+const ORDER_WATCH_INTERVAL: u64 = 60;
+
+// 待付款订单距离付款截止小于该时长时提醒, 毫秒
+const ORDER_EXPIRE_WARN: i64 = 5 * 60 * 1000;
+
+#[derive(Parser, Debug)]
+#[command(name = "dm-client", version, about = "大麦抢票客户端")]
+struct Args {
+    /// 记录所有请求/响应到指定文件(jsonl), 已去除签名及cookie/证件号/手机号等个人信息
+    #[arg(long, value_name = "FILE")]
+    record: Option<String>,
+
+    /// 试运行: 登录/预热/生成订单, 不提交订单, 输出将提交的数据
+    #[arg(long)]
+    dry_run: bool,
+
+    /// 回放记录文件, 使用解析代码重新解析响应后退出
+    #[arg(long, value_name = "FILE")]
+    replay: Option<String>,
+
+    /// 输出格式: text/json, json时标准输出只输出命令结果, 日志输出到标准错误
+    #[arg(long, value_name = "FORMAT")]
+    output: Option<OutputFormat>,
+
+    /// 使用配置文件中的环境配置([profiles.<NAME>]), 同环境变量TICK_PROFILE
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// 交互模式即将开抢列表的分类, 可重复, 如--category 话剧 --category 音乐节, 默认演唱会
+    #[arg(long = "category", value_name = "CATEGORY")]
+    categories: Vec<String>,
+
+    /// 交互模式即将开抢列表的城市, 可重复
+    #[arg(long = "city", value_name = "CITY")]
+    cities: Vec<String>,
+
+    /// 交互模式即将开抢列表的场馆关键词
+    #[arg(long)]
+    venue: Option<String>,
+
+    /// 开抢日期起始, YYYY-MM-DD
+    #[arg(long, value_name = "DATE")]
+    from: Option<String>,
+
+    /// 开抢日期截止, YYYY-MM-DD
+    #[arg(long, value_name = "DATE")]
+    to: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 管理加密保存的账号
+    Accounts {
+        #[command(subcommand)]
+        action: AccountsAction,
+    },
+
+    /// 检查运行环境: webdriver/chrome版本/cookie/时钟偏差/网关延迟/代理
+    Doctor {
+        /// 检查该账号的cookie是否有效
+        #[arg(long)]
+        account: Option<String>,
+    },
+
+    /// 恢复进程中断前已创建的抢票任务(仅限使用已保存账号创建的任务)
+    Resume,
+
+    /// 在终端显示二维码, 如其他工具获取的登录链接, 未指定内容时从标准输入读取
+    Qrcode {
+        /// 二维码内容
+        content: Option<String>,
+    },
+
+    /// 搜索演出, 未指定关键词时列出即将开抢的演出(按--category/--city等筛选)
+    Search {
+        /// 搜索关键词, 如艺人名
+        keyword: Option<String>,
+    },
+
+    /// 统计门票的历史运行记录: 下单次数、错误码分布、最短耗时、开售后首次库存不足的时间
+    Stats {
+        /// 门票ID
+        ticket_id: String,
+    },
+
+    /// 查看等待开抢/可恢复的抢票任务
+    Status {
+        /// 导出任务的开售时间到日历文件(.ics)
+        #[arg(long, value_name = "FILE")]
+        ics: Option<PathBuf>,
+    },
+
+    /// 以守护进程运行任务目录中的抢票任务, 支持systemd(Type=notify), SIGHUP重新加载配置
+    Daemon {
+        /// pid文件路径
+        #[arg(long, value_name = "FILE")]
+        pid_file: Option<PathBuf>,
+
+        /// 扫描任务目录的间隔, 秒
+        #[arg(long, default_value_t = 30)]
+        scan_interval: u64,
+    },
+
+    /// 管理Windows服务, 开机自动运行守护进程
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+
+    /// 启动内置的模拟大麦服务, 执行完整的抢票流程
+    Simulate {
+        /// 每个请求的响应延迟, 毫秒
+        #[arg(long, default_value_t = 50)]
+        latency: u64,
+
+        /// 库存
+        #[arg(long, default_value_t = 10)]
+        stock: usize,
+
+        /// 生成/提交订单触发风控的概率, 0-1
+        #[arg(long, default_value_t = 0.1)]
+        error_rate: f64,
+
+        /// 距离开售的时长, 秒
+        #[arg(long, default_value_t = 10)]
+        sale_in: u64,
+
+        /// 购票数量
+        #[arg(long, default_value_t = 1)]
+        ticket_num: usize,
+    },
+
+    /// 查看最近的订单, 可取消未付款的订单
+    Orders {
+        /// 下单的账号名称
+        #[arg(long)]
+        account: String,
+
+        /// 持续监控, 待付款订单即将超时时通知
+        #[arg(long)]
+        watch: bool,
+
+        #[command(subcommand)]
+        action: Option<OrdersAction>,
+    },
+
+    /// 管理账号的实名观演人
+    Viewers {
+        /// 账号名称
+        #[arg(long)]
+        account: String,
+
+        #[command(subcommand)]
+        action: ViewersAction,
+    },
+
+    /// 管理账号的收货地址(纸质票快递配送)
+    Address {
+        /// 账号名称
+        #[arg(long)]
+        account: String,
+
+        #[command(subcommand)]
+        action: AddressAction,
+    },
+
+    /// 检查配置文件及任务文件
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// 生成shell补全脚本, 输出到标准输出
+    Completions {
+        /// shell类型: bash/zsh/fish/powershell/elvish
+        shell: Shell,
+    },
+
+    /// 生成man手册, 未指定目录时输出主命令的手册到标准输出
+    Man {
+        /// 输出目录, 为主命令及每个子命令分别生成手册(dm-client-<子命令>.1)
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AccountsAction {
+    /// 添加账号, 同名账号已存在时覆盖
+    Add {
+        /// 账号名称
+        name: String,
+
+        /// 账号cookie, 未指定时扫码登录获取
+        #[arg(long)]
+        cookie: Option<String>,
+
+        /// 使用账号密码/短信验证码登录的手机号/账号, 启动后提示输入密码
+        #[arg(long, conflicts_with = "cookie")]
+        login_id: Option<String>,
+
+        /// 从标准输入读取cookie, 如: pbpaste | dm-client accounts add <name> --cookie-stdin
+        #[arg(long, conflicts_with_all = ["cookie", "login_id"])]
+        cookie_stdin: bool,
+    },
+
+    /// 删除账号
+    Remove {
+        /// 账号名称
+        name: String,
+    },
+
+    /// 列出已保存的账号
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+enum ServiceAction {
+    /// 安装为开机自动启动的服务(需管理员权限), 使用当前的配置目录
+    Install {
+        /// 扫描任务目录的间隔, 秒
+        #[arg(long, default_value_t = 30)]
+        scan_interval: u64,
+    },
+
+    /// 停止并删除服务
+    Uninstall,
+
+    /// 由服务管理器调用, 无需手动执行
+    Run {
+        #[arg(long, default_value_t = 30)]
+        scan_interval: u64,
+
+        /// 配置目录
+        #[arg(long)]
+        config_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// 检查settings.toml及任务文件的格式和字段约束, 输出问题所在的行/字段
+    Check {
+        /// 要检查的文件(.toml/.json), 默认为settings.toml及任务目录中的所有任务
+        files: Vec<PathBuf>,
+
+        /// 联网校验场次/票档是否属于门票、是否已开售
+        #[arg(long)]
+        online: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum OrdersAction {
+    /// 取消未付款的订单
+    Cancel {
+        /// 订单ID
+        order_id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ViewersAction {
+    /// 列出实名观演人
+    List,
+
+    /// 添加实名观演人
+    Add {
+        /// 姓名
+        name: String,
+
+        /// 证件号
+        cert_no: String,
+
+        /// 证件类型, 1: 身份证
+        #[arg(long, default_value = viewer::ID_CARD)]
+        cert_type: String,
+    },
+
+    /// 删除实名观演人
+    Remove {
+        /// 姓名或证件号后几位
+        key: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AddressAction {
+    /// 列出收货地址, 默认地址以*标记, 地址ID可填写到任务的address_id
+    List,
+
+    /// 添加收货地址
+    Add {
+        /// 收货人
+        name: String,
+
+        /// 手机号
+        phone: String,
+
+        /// 省
+        province: String,
+
+        /// 市
+        city: String,
+
+        /// 区/县
+        area: String,
+
+        /// 详细地址
+        detail: String,
+
+        /// 设为默认地址, 未配置address_id的任务使用默认地址
+        #[arg(long)]
+        default: bool,
+    },
+}
+
+fn print_orders(orders: &[OrderSummary]) -> Result<()> {
+    if output::is_json() {
+        return output::print_json(orders);
+    }
+    let tz = sale_timezone();
+    for order in orders {
+        println!(
+            "{}\t{}\t{}\t¥{}\t付款截止:{}",
+            order.order_id,
+            order.item_name,
+            order.status,
+            order.amount,
+            order
+                .pay_deadline
+                .map_or("-".to_string(), |deadline| format_timestamp(deadline, tz))
+        );
+    }
+    Ok(())
+}
+
+// 轮询订单列表, 待付款订单即将超时时通知, 每个订单只通知一次
+async fn watch_orders(dm: &DmClient) -> Result<()> {
+    let mut notified = HashSet::new();
+    loop {
+        match dm.orders().await {
+            Ok(orders) => {
+                for order in orders.iter().filter(|order| order.is_unpaid()) {
+                    let left = match order.pay_deadline {
+                        Some(deadline) => deadline - Local::now().timestamp_millis(),
+                        None => continue,
+                    };
+                    if left <= 0
+                        || left > ORDER_EXPIRE_WARN
+                        || !notified.insert(order.order_id.clone())
+                    {
+                        continue;
+                    }
+                    let content = format!(
+                        "订单:{}, {}, 将在{}秒后超时, 请尽快前往手机APP付款!",
+                        order.order_id,
+                        order.item_name,
+                        left / 1000
+                    );
+                    warn!("{}", content);
+                    if env::var("NOTIFY_TOKEN").is_ok() {
+                        let _ = NotifyClient::notify(&content).await;
+                    }
+                }
+            }
+            Err(e) => warn!("获取订单列表失败:{:?}", e),
+        }
+        tokio::time::sleep(Duration::from_secs(ORDER_WATCH_INTERVAL)).await;
+    }
+}
+
+async fn orders(account: String, watch: bool, action: Option<OrdersAction>) -> Result<()> {
+    let store = AccountStore::from_env()?;
+    let account = store
+        .get(&account)
+        .ok_or(AccountError::NotFound { name: account })?;
+    let dm = DmClient::new(Some(account.cookie.clone()), None).await?;
+
+    match action {
+        Some(OrdersAction::Cancel { order_id }) => {
+            dm.cancel_order(&order_id).await?;
+            info!("订单:{}已取消", order_id);
+            if output::is_json() {
+                output::print_json(&json!({ "order_id": order_id, "cancelled": true }))?;
+            }
+        }
+        None => {
+            print_orders(&dm.orders().await?)?;
+            if watch {
+                info!("正在监控待付款订单...");
+                watch_orders(&dm).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_viewers(viewers: &[Viewer]) -> Result<()> {
+    // 证件号脱敏后输出
+    let viewers = viewers
+        .iter()
+        .map(|viewer| Viewer {
+            cert_no: viewer::mask_cert_no(&viewer.cert_no),
+            ..viewer.clone()
+        })
+        .collect::<Vec<_>>();
+    if output::is_json() {
+        return output::print_json(&viewers);
+    }
+    for (i, viewer) in viewers.iter().enumerate() {
+        println!(
+            "{}\t{}\t{}\t{}",
+            i + 1,
+            viewer.viewer_id,
+            viewer.name,
+            viewer.cert_no
+        );
+    }
+    Ok(())
+}
+
+async fn viewers(account: String, action: ViewersAction) -> Result<()> {
+    let store = AccountStore::from_env()?;
+    let account = store
+        .get(&account)
+        .ok_or(AccountError::NotFound { name: account })?;
+    let dm = DmClient::new(Some(account.cookie.clone()), None).await?;
+
+    match action {
+        ViewersAction::List => print_viewers(&dm.viewers().await?)?,
+        ViewersAction::Add {
+            name,
+            cert_no,
+            cert_type,
+        } => {
+            viewer::check_cert_no(&cert_type, &cert_no)?;
+            dm.add_viewer(&name, &cert_type, &cert_no).await?;
+            info!("已添加观演人:{}, {}", name, viewer::mask_cert_no(&cert_no));
+            print_viewers(&dm.viewers().await?)?;
+        }
+        ViewersAction::Remove { key } => {
+            let viewers = dm.viewers().await?;
+            let viewer = viewer::find_viewer(&viewers, &key)?;
+            dm.remove_viewer(&viewer.viewer_id).await?;
+            info!(
+                "已删除观演人:{}, {}",
+                viewer.name,
+                viewer::mask_cert_no(&viewer.cert_no)
+            );
+            if output::is_json() {
+                output::print_json(&json!({ "viewer_id": viewer.viewer_id, "removed": true }))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_addresses(addresses: &[Address]) -> Result<()> {
+    if output::is_json() {
+        return output::print_json(addresses);
+    }
+    for address in addresses {
+        println!(
+            "{}\t{}\t{}",
+            match address.is_default {
+                true => "*",
+                false => "",
+            },
+            address.address_id,
+            address.desc()
+        );
+    }
+    Ok(())
+}
+
+async fn address(account: String, action: AddressAction) -> Result<()> {
+    let store = AccountStore::from_env()?;
+    let account = store
+        .get(&account)
+        .ok_or(AccountError::NotFound { name: account })?;
+    let dm = DmClient::new(Some(account.cookie.clone()), None).await?;
+
+    match action {
+        AddressAction::List => print_addresses(&dm.addresses().await?)?,
+        AddressAction::Add {
+            name,
+            phone,
+            province,
+            city,
+            area,
+            detail,
+            default,
+        } => {
+            let address = NewAddress {
+                name,
+                phone,
+                province,
+                city,
+                area,
+                detail,
+                is_default: default,
+            };
+            address.check()?;
+            dm.add_address(&address).await?;
+            info!("已添加收货地址:{} {}", address.name, address.detail);
+            print_addresses(&dm.addresses().await?)?;
+        }
+    }
+    Ok(())
+}
+
+// 按关键词搜索演出, 未指定关键词时列出即将开抢的演出
+async fn search(keyword: Option<String>, filter: &TicketFilter) -> Result<()> {
+    let dm = DmClient::new(None, None).await?;
+    let keyword = match keyword {
+        Some(keyword) => keyword,
+        None => {
+            let tickets = filter.apply(dm.search().await?);
+            if output::is_json() {
+                return output::print_json(&tickets);
+            }
+            let tz = sale_timezone();
+            for ticket in tickets.iter() {
+                println!(
+                    "{}\t{}\t{}\t{}\t开抢:{}\t{}",
+                    ticket.ticket_id,
+                    ticket.ticket_name,
+                    ticket.city_name,
+                    ticket.venue_name,
+                    format_timestamp(ticket.sale_time as i64, tz),
+                    ticket.price_desc()
+                );
+            }
+            return Ok(());
+        }
+    };
+
+    let stops = dm.search_projects(&keyword).await?;
+    if output::is_json() {
+        return output::print_json(&stops);
+    }
+    for stop in stops.iter() {
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            stop.item_id, stop.name, stop.city_name, stop.venue_name, stop.show_time
+        );
+    }
+    Ok(())
+}
+
+// 等待开抢/可恢复的抢票任务
+fn status(ics: Option<PathBuf>) -> Result<()> {
+    let now = Local::now().timestamp_millis();
+    let tasks = ArmedStore::from_env().list()?;
+    if let Some(path) = ics {
+        // 开抢时间未确定的任务跳过
+        let events = tasks
+            .iter()
+            .filter(|armed| !armed.expired(now))
+            .filter_map(|armed| {
+                armed
+                    .sale_start
+                    .map(|sale_start| SaleEvent::from_task(&armed.task, sale_start, ""))
+            })
+            .collect::<Vec<_>>();
+        calendar::export(&path, &events)?;
+        info!("已导出{}个任务的开售时间:{}", events.len(), path.display());
+    }
+    if output::is_json() {
+        let tasks = tasks
+            .iter()
+            .map(|armed| {
+                json!({
+                    "account": armed.account,
+                    "nickname": armed.task.nickname,
+                    "ticket_id": armed.task.ticket_id,
+                    "ticket_name": armed.task.ticket_name,
+                    "perform_name": armed.task.ticket_perform_name,
+                    "sku_name": armed.task.ticket_perform_sku_name,
+                    "sale_start": armed.sale_start,
+                    "armed_at": armed.armed_at,
+                    "expired": armed.expired(now),
+                })
+            })
+            .collect::<Vec<_>>();
+        return output::print_json(&tasks);
+    }
+    for armed in tasks.iter() {
+        let tz = armed.task.tz();
+        println!(
+            "{}\t{}\t{}\t{}\t开抢:{}{}",
+            armed.account,
+            armed.task.ticket_name,
+            armed.task.ticket_perform_name,
+            armed.task.ticket_perform_sku_name,
+            armed
+                .sale_start
+                .map_or("-".to_string(), |sale_start| format_timestamp(
+                    sale_start, tz
+                )),
+            match armed.expired(now) {
+                true => "\t已过期",
+                false => "",
+            }
+        );
+    }
+    Ok(())
+}
+
+// 门票的历史运行统计
+fn stats(ticket_id: &str) -> Result<()> {
+    let store = RunStore::from_env();
+    let runs = store.list(ticket_id)?;
+    let stats = ShowStats::from_runs(ticket_id, &runs);
+    if output::is_json() {
+        return output::print_json(&json!({ "stats": stats, "runs": runs }));
+    }
+    if runs.is_empty() {
+        println!(
+            "没有门票:{}的运行记录, 保存目录:{}",
+            ticket_id,
+            store.dir().display()
+        );
+        return Ok(());
+    }
+    println!("{}", stats.text());
+    Ok(())
+}
+
+fn daemon_options(pid_file: Option<PathBuf>, scan_interval: u64) -> DaemonOptions {
+    DaemonOptions {
+        pid_file,
+        scan_interval: Duration::from_secs(scan_interval.max(1)),
+    }
+}
+
+// 守护进程
+#[cfg(unix)]
+async fn run_daemon(pid_file: Option<PathBuf>, scan_interval: u64) -> Result<()> {
+    dm_ticket::daemon::run(daemon_options(pid_file, scan_interval)).await
+}
+
+#[cfg(not(unix))]
+async fn run_daemon(_pid_file: Option<PathBuf>, _scan_interval: u64) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "守护进程仅支持Linux/macOS, Windows请使用: service install"
+    ))
+}
+
+// Windows服务
+#[cfg(windows)]
+async fn service(action: ServiceAction) -> Result<()> {
+    match action {
+        ServiceAction::Install { scan_interval } => winservice::install(scan_interval),
+        ServiceAction::Uninstall => winservice::uninstall(),
+        ServiceAction::Run {
+            scan_interval,
+            config_dir,
+        } => {
+            if let Some(dir) = config_dir {
+                winservice::use_config_dir(dir)?;
+            }
+            let options = daemon_options(None, scan_interval);
+            tokio::task::spawn_blocking(move || winservice::start(options)).await?
+        }
+    }
+}
+
+#[cfg(not(windows))]
+async fn service(_action: ServiceAction) -> Result<()> {
+    Err(anyhow::anyhow!("仅支持Windows, Linux/macOS请使用: daemon"))
+}
+
+// 扫码登录获取cookie
+#[cfg(feature = "http-login")]
+async fn qrcode_login() -> Result<String> {
+    LoginClient::new()
+        .await?
+        .login(&config::get("WEBDRIVER_URL")?, false)
+        .await
+}
+
+#[cfg(not(feature = "http-login"))]
+async fn qrcode_login() -> Result<String> {
+    Err(anyhow::anyhow!(
+        "未启用扫码登录(http-login), 请使用--cookie指定cookie"
+    ))
+}
+
+// 读取标准输入的全部内容, 在终端中运行时提示粘贴
+fn read_stdin(prompt: &str) -> Result<String> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprintln!("{}, 完成后按Ctrl-D(Windows为Ctrl-Z后回车)结束:", prompt);
+    }
+    let mut input = String::new();
+    stdin.read_to_string(&mut input)?;
+    Ok(input)
+}
+
+// 在终端显示二维码, 内容为-或未指定时从标准输入读取
+#[cfg(feature = "http-login")]
+fn show_qrcode(content: Option<String>) -> Result<()> {
+    let content = match content.filter(|content| content != "-") {
+        Some(content) => content,
+        None => read_stdin("请粘贴二维码内容")?,
+    };
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(anyhow::anyhow!("二维码内容为空"));
+    }
+    let modules = qrcode_render::encode(content)?;
+    println!("{}\n", QrOptions::from_env()?.render(&modules));
+    Ok(())
+}
+
+#[cfg(not(feature = "http-login"))]
+fn show_qrcode(_content: Option<String>) -> Result<()> {
+    Err(anyhow::anyhow!("未启用http-login功能, 无法生成二维码"))
+}
+
+// 账号密码/短信验证码登录获取cookie, 未输入密码时使用短信验证码登录
+#[cfg(feature = "http-login")]
+async fn credential_login(login_id: &str) -> Result<String> {
+    let password = rpassword::prompt_password("请输入密码(直接回车使用短信验证码登录): ")?;
+    LoginClient::new()
+        .await?
+        .login_with_credential(&config::get("WEBDRIVER_URL")?, login_id, Some(&password))
+        .await
+}
+
+#[cfg(not(feature = "http-login"))]
+async fn credential_login(_login_id: &str) -> Result<String> {
+    Err(anyhow::anyhow!(
+        "未启用http-login功能, 请使用--cookie指定cookie"
+    ))
+}
+
+// 交互式选择门票并执行抢票
+#[cfg(feature = "interactive")]
+async fn interactive(filter: TicketFilter) -> Result<()> {
+    let webdriver_url = config::get("WEBDRIVER_URL")?;
+    let client = Client::new(webdriver_url).await?.with_filter(filter);
+
+    client.run().await
+}
+
+#[cfg(not(feature = "interactive"))]
+async fn interactive(_filter: TicketFilter) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "未启用交互模式(interactive), 请使用子命令, 查看帮助: --help"
+    ))
+}
+
+// 命令行指定的即将开抢列表筛选条件, 未指定分类时只显示演唱会
+fn ticket_filter(args: &Args) -> Result<TicketFilter> {
+    let tz = sale_timezone();
+    let mut filter = match args.categories.is_empty() {
+        true => TicketFilter::concerts(),
+        false => TicketFilter {
+            categories: args.categories.clone(),
+            ..Default::default()
+        },
+    };
+    filter.cities = args.cities.clone();
+    filter.venue = args.venue.clone();
+    filter.sale_from = args
+        .from
+        .as_deref()
+        .map(|date| parse_date(date, tz, false))
+        .transpose()?;
+    filter.sale_to = args
+        .to
+        .as_deref()
+        .map(|date| parse_date(date, tz, true))
+        .transpose()?;
+    Ok(filter)
+}
+
+async fn accounts(action: AccountsAction) -> Result<()> {
+    let mut store = AccountStore::from_env()?;
+
+    match action {
+        AccountsAction::Add {
+            name,
+            cookie,
+            login_id,
+            cookie_stdin,
+        } => {
+            let cookie = match (cookie, login_id) {
+                _ if cookie_stdin => parse_cookie(&read_stdin("请粘贴cookie")?)?,
+                (Some(cookie), _) => parse_cookie(&cookie)?,
+                (None, Some(login_id)) => credential_login(&login_id).await?,
+                (None, None) => qrcode_login().await?,
+            };
+            // 校验cookie并获取账号昵称
+            let nickname = DmClient::new(Some(cookie.clone()), None)
+                .await?
+                .user_info()
+                .await
+                .map_err(|e| anyhow::anyhow!("cookie无效或登录已失效:{}", e))?
+                .nickname;
+            store.upsert(Account::new(&name, &cookie, &nickname));
+            store.save()?;
+            info!("账号:{}({})已保存", name, nickname);
+        }
+        AccountsAction::Remove { name } => {
+            store.remove(&name)?;
+            store.save()?;
+            info!("账号:{}已删除", name);
+        }
+        AccountsAction::List => {
+            if output::is_json() {
+                // 不输出cookie
+                let accounts = store
+                    .list()
+                    .iter()
+                    .map(|account| {
+                        json!({
+                            "name": account.name,
+                            "nickname": account.nickname,
+                            "updated_at": account.updated_at,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                return output::print_json(&accounts);
+            }
+            for account in store.list() {
+                // 账号文件中的时间戳异常时原样输出
+                let updated_at = match chrono::Local
+                    .timestamp_millis_opt(account.updated_at)
+                    .single()
+                {
+                    Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    None => account.updated_at.to_string(),
+                };
+                println!(
+                    "{}\t{}\t更新时间:{}",
+                    account.name, account.nickname, updated_at
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// 退出码见exit::ExitStatus
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     dotenv().ok();
-    if env::var("RUST_LOG").is_err() {
-        env::set_var("RUST_LOG", "INFO");
+    let args = Args::parse();
+
+    let res = start(args).await;
+    if let Err(e) = &res {
+        eprintln!("Error: {:?}", e);
+    }
+    ExitCode::from(exit::status(&res).code())
+}
+
+async fn start(args: Args) -> Result<()> {
+    if let Some(path) = &args.record {
+        env::set_var("RECORD_PATH", path);
+    }
+
+    if args.dry_run {
+        env::set_var("DRY_RUN", "true");
+    }
+
+    if let Some(format) = args.output {
+        env::set_var("OUTPUT_FORMAT", format.name());
     }
 
-    if env::var("WEBDRIVER_URL").is_err() {
-        env::set_var("WEBDRIVER_URL", "http://localhost:9515");
+    if let Some(profile) = &args.profile {
+        env::set_var("TICK_PROFILE", profile);
     }
 
-    if env::var("REDIS_URL").is_err() {
-        env::set_var("REDIS_URL", "redis://127.0.0.1:6379/0");
+    // 以下命令不依赖已加载的配置, 配置有误时也可执行
+    match args.command {
+        Some(Command::Completions { shell }) => return completions(shell),
+        Some(Command::Man { out }) => return man(out.as_deref()),
+        Some(Command::Config {
+            action: ConfigAction::Check { files, online },
+        }) => return check_config(files, online).await,
+        _ => {}
     }
 
-    if env::var("QRCODE_PATH").is_err() {
-        env::set_var("QRCODE_PATH", ".qrcode.png");
+    config::init()?;
+    logger::init();
+
+    let res = dispatch(args).await;
+    logger::shutdown();
+    res
+}
+
+// 检查配置文件及任务文件, 发现问题时以配置错误退出
+async fn check_config(files: Vec<PathBuf>, online: bool) -> Result<()> {
+    let now = Local::now().timestamp_millis();
+    let files = match files.is_empty() {
+        true => config_check::default_files()?,
+        false => files,
+    };
+
+    let mut issues = vec![];
+    for path in files.iter() {
+        issues.extend(config_check::check_file(path, now)?);
+    }
+
+    // 配置文件有误时已在上面报告, 无法加载配置时跳过在线检查
+    if online && config::init().is_ok() {
+        let dm = DmClient::new(None, None).await?;
+        for path in files.iter().filter(|path| config_check::is_task_file(path)) {
+            issues.extend(config_check::check_file_online(&dm, path, now).await?);
+        }
     }
 
-    pretty_env_logger::init();
+    match output::is_json() {
+        true => output::print_json(&issues)?,
+        false => {
+            for issue in issues.iter() {
+                println!("{}", issue);
+            }
+            match issues.is_empty() {
+                true => println!("已检查{}个文件, 未发现问题", files.len()),
+                false => println!("已检查{}个文件, 发现{}个问题", files.len(), issues.len()),
+            }
+        }
+    }
+    if !issues.is_empty() {
+        exit::record(ExitStatus::ConfigError);
+    }
+    Ok(())
+}
+
+// 输出shell补全脚本
+fn completions(shell: Shell) -> Result<()> {
+    let mut cmd = Args::command();
+    let name = cmd.get_name().to_string();
+    clap_complete::generate(shell, &mut cmd, name, &mut io::stdout());
+    Ok(())
+}
 
-    let webdriver_url = env::var("WEBDRIVER_URL").unwrap();
-    let client = Client::new(webdriver_url).await?;
+// 生成man手册, 指定目录时每个子命令单独生成一页
+fn man(out: Option<&Path>) -> Result<()> {
+    let mut cmd = Args::command();
+    cmd.build();
+    match out {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            let name = cmd.get_name().to_string();
+            write_man(dir, &name, &cmd)?;
+            println!("man手册已生成到: {}", dir.display());
+        }
+        None => clap_mangen::Man::new(cmd).render(&mut io::stdout())?,
+    }
+    Ok(())
+}
 
-    client.run().await?;
+// 写入命令及其子命令的手册, 文件名为: 父命令-子命令.1
+fn write_man(dir: &Path, name: &str, cmd: &clap::Command) -> Result<()> {
+    let mut page = Vec::new();
+    clap_mangen::Man::new(cmd.clone())
+        .title(name.to_uppercase())
+        .render(&mut page)?;
+    fs::write(dir.join(format!("{}.1", name)), page)?;
+    for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        write_man(dir, &format!("{}-{}", name, sub.get_name()), sub)?;
+    }
     Ok(())
 }
+
+// 执行命令
+async fn dispatch(args: Args) -> Result<()> {
+    if let Some(path) = &args.replay {
+        let failed = record::replay(path)?;
+        if failed > 0 {
+            error!("共{}条记录解析失败!", failed);
+        }
+        return Ok(());
+    }
+
+    let filter = ticket_filter(&args)?;
+
+    match args.command {
+        Some(Command::Accounts { action }) => return accounts(action).await,
+        Some(Command::Orders {
+            account,
+            watch,
+            action,
+        }) => return orders(account, watch, action).await,
+        Some(Command::Viewers { account, action }) => return viewers(account, action).await,
+        Some(Command::Address { account, action }) => return address(account, action).await,
+        Some(Command::Doctor { account }) => {
+            let cookie = match account {
+                Some(name) => {
+                    let store = AccountStore::from_env()?;
+                    let account = store.get(&name).ok_or(AccountError::NotFound { name })?;
+                    Some(account.cookie.clone())
+                }
+                None => None,
+            };
+            let checks = doctor::diagnose(&config::get("WEBDRIVER_URL")?, cookie).await?;
+            let passed = match output::is_json() {
+                true => {
+                    let passed = doctor::passed(&checks);
+                    output::print_json(&json!({ "passed": passed, "checks": checks }))?;
+                    passed
+                }
+                false => doctor::report(&checks),
+            };
+            if !passed {
+                error!("环境检查未通过, 请按建议处理!");
+                exit::record(ExitStatus::Failure);
+            }
+            return Ok(());
+        }
+        Some(Command::Resume) => return resume::resume().await,
+        Some(Command::Qrcode { content }) => return show_qrcode(content),
+        Some(Command::Search { keyword }) => return search(keyword, &filter).await,
+        Some(Command::Status { ics }) => return status(ics),
+        Some(Command::Stats { ticket_id }) => return stats(&ticket_id),
+        Some(Command::Daemon {
+            pid_file,
+            scan_interval,
+        }) => return run_daemon(pid_file, scan_interval).await,
+        Some(Command::Service { action }) => return service(action).await,
+        Some(Command::Simulate {
+            latency,
+            stock,
+            error_rate,
+            sale_in,
+            ticket_num,
+        }) => {
+            let config = SimulatorConfig {
+                latency,
+                stock,
+                error_rate,
+                sale_in,
+                ticket_num,
+            };
+            return simulate(config).await;
+        }
+        Some(Command::Completions { .. } | Command::Man { .. } | Command::Config { .. }) | None => {
+        }
+    }
+
+    interactive(filter).await
+}
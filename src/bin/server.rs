@@ -1,36 +1,17 @@
 use anyhow::Result;
-use dm_ticket::server::Server;
+use dm_ticket::{config, logger, server::Server};
 use dotenv::dotenv;
-use log::error;
-use std::env;
+use tracing::error;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
-    if env::var("RUST_LOG").is_err() {
-        env::set_var("RUST_LOG", "INFO");
-    }
-
-    if env::var("REDIS_URL").is_err() {
-        env::set_var("REDIS_URL", "redis://127.0.0.1:6379/0");
-    }
-
-    if env::var("WEBDRIVER_URL").is_err() {
-        env::set_var("WEBDRIVER_URL", "http://localhost:9515");
-    }
-
-    if env::var("TOTAL_TOKEN_NUM").is_err() {
-        env::set_var("TOTAL_TOKEN_NUM", "100");
-    }
-
-    if env::var("BATCH_TOKEN_NUM").is_err() {
-        env::set_var("BATCH_TOKEN_NUM", "10");
-    }
+    config::init()?;
 
-    pretty_env_logger::init();
+    logger::init();
 
-    let webdriver_url = env::var("WEBDRIVER_URL").unwrap();
-    let redis_url = env::var("REDIS_URL").unwrap();
+    let webdriver_url = config::get("WEBDRIVER_URL")?;
+    let redis_url = config::get("REDIS_URL")?;
 
     match Server::new(webdriver_url, redis_url).await {
         Ok(mut server) => {
@@ -45,5 +26,6 @@ async fn main() -> Result<()> {
             error!("服务启动失败, 原因:{}!", e.to_string());
         }
     }
+    logger::shutdown();
     Ok(())
 }
@@ -0,0 +1,139 @@
+use std::{env, fs, path::Path};
+
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+
+use crate::models::task::Task;
+
+// 默认提前提醒时长, 分钟
+const DEFAULT_REMINDER_MINUTES: u32 = 10;
+
+// 日历事件时长, 分钟, 覆盖抢票及付款时间
+const EVENT_MINUTES: i64 = 30;
+
+// ics每行最多75字节, 超出后折行
+const LINE_LIMIT: usize = 75;
+
+// 开售提醒事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaleEvent {
+    pub uid: String,
+    pub title: String,
+    pub sale_start: i64, // 开售时间戳, 毫秒
+    pub location: String,
+    pub description: String,
+    pub url: String,
+}
+
+impl SaleEvent {
+    // 抢票任务的开售提醒
+    pub fn from_task(task: &Task, sale_start: i64, location: &str) -> Self {
+        Self {
+            uid: format!("{}-{}@dm-ticket", task.key(), sale_start),
+            title: format!("开抢: {}", task.ticket_name),
+            sale_start,
+            location: location.to_string(),
+            description: format!(
+                "账号: {}\n场次: {}\n票档: {}\n购票数量: {}\n请准备好完成人机验证, 下单成功后尽快在大麦APP付款",
+                task.nickname,
+                task.ticket_perform_name,
+                task.ticket_perform_sku_name,
+                task.ticket_num
+            ),
+            url: format!("https://m.damai.cn/damai/detail/item.html?itemId={}", task.ticket_id),
+        }
+    }
+}
+
+// 提前提醒时长, 环境变量ICS_REMINDER配置, 分钟
+pub fn reminder_minutes() -> u32 {
+    env::var("ICS_REMINDER")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_REMINDER_MINUTES)
+}
+
+// utc时间, 如20230701T120000Z
+fn format_utc(millis: i64) -> String {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map_or(String::new(), |t| t.format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+// 转义文本字段中的特殊字符
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\r', "")
+        .replace('\n', "\\n")
+}
+
+// 按字节折行, 不拆分多字节字符, 续行以空格开头
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        // 续行开头的空格占一个字节
+        let limit = match folded.is_empty() {
+            true => LINE_LIMIT,
+            false => LINE_LIMIT - 1,
+        };
+        if len + c.len_utf8() > limit {
+            folded.push_str("\r\n ");
+            len = 0;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded
+}
+
+// 生成ics日历, 每个事件在开售前reminder_minutes分钟提醒
+pub fn render_ics(events: &[SaleEvent], reminder_minutes: u32, now: i64) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//dm-ticket//sale reminder//CN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", format_utc(now)));
+        lines.push(format!("DTSTART:{}", format_utc(event.sale_start)));
+        lines.push(format!(
+            "DTEND:{}",
+            format_utc(event.sale_start + EVENT_MINUTES * 60 * 1000)
+        ));
+        lines.push(format!("SUMMARY:{}", escape(&event.title)));
+        if !event.location.is_empty() {
+            lines.push(format!("LOCATION:{}", escape(&event.location)));
+        }
+        lines.push(format!("DESCRIPTION:{}", escape(&event.description)));
+        lines.push(format!("URL:{}", event.url));
+        lines.push("BEGIN:VALARM".to_string());
+        lines.push("ACTION:DISPLAY".to_string());
+        lines.push(format!("DESCRIPTION:{}", escape(&event.title)));
+        lines.push(format!("TRIGGER:-PT{}M", reminder_minutes));
+        lines.push("END:VALARM".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold(line) + "\r\n")
+        .collect::<String>()
+}
+
+// 保存ics文件
+pub fn export(path: &Path, events: &[SaleEvent]) -> Result<()> {
+    let content = render_ics(
+        events,
+        reminder_minutes(),
+        chrono::Local::now().timestamp_millis(),
+    );
+    fs::write(path, content)?;
+    Ok(())
+}
@@ -1,33 +1,43 @@
-use std::{
-    io::{self, Write},
-    time::Duration,
-};
+use std::{env, path::Path};
 
 use crate::{
+    account::{Account, AccountStore},
+    calendar::{self, SaleEvent},
     clients::{dm::DmClient, login::LoginClient},
     errors::ClientError,
+    format_timestamp,
+    i18n::tr,
     models::{
-        perform::{PerformForm, PerformInfo, PerformItem, PerformParams, SkuItem},
-        task::Task,
-        ticket::{
-            GetTicketListForm, GetTicketListParams, Ticket, TicketInfo, TicketInfoForm,
-            TicketInfoParams, TicketList,
-        },
+        address::select_address,
+        perform::{PerformItem, SkuItem, SkuStatus},
+        seat::SeatPreference,
+        task::{default_timezone, default_warmup_time, Task},
+        ticket::{parse_date, Ticket, TicketFilter},
     },
-    ticket::DmTicket,
+    platform::{ItemDetail, TicketPlatform},
+    resume::run_armed,
+    sale_timezone,
+    ticket::run_task,
 };
-use anyhow::Result;
-use chrono::{Local, TimeZone};
+use anyhow::{anyhow, Result};
+use chrono::Local;
 
-use log::{debug, error, info};
-use terminal_menu::{button, label, menu, mut_menu, numeric, run};
-use thirtyfour::{
-    cookie::SameSite, prelude::ElementQueryable, By, Cookie, DesiredCapabilities, WebDriver,
+use dialoguer::{
+    theme::ColorfulTheme, Confirm, FuzzySelect, Input, MultiSelect, Password, Select, Sort,
 };
+use tracing::{info, warn};
+
+// 列表每页显示的条数
+const PAGE_SIZE: usize = 15;
+
+fn theme() -> ColorfulTheme {
+    ColorfulTheme::default()
+}
 
 pub struct Client {
     webdriver_url: String,
     client: LoginClient,
+    filter: TicketFilter,
 }
 
 impl Client {
@@ -35,289 +45,430 @@ impl Client {
         Ok(Self {
             webdriver_url,
             client: LoginClient::new().await?,
+            filter: TicketFilter::concerts(),
         })
     }
 
-    pub async fn qrcode_login(&self) -> Result<String> {
-        info!("正在获取二维码...\n");
-        let qrcode_data = match self.client.generate_qrcode().await {
-            Ok(data) => {
-                debug!("Get qrcode data:{:?}", data);
-                data
-            }
-            Err(e) => {
-                error!("Fail to get qrcode data, error:{:?}", e);
-                return Err(e);
-            }
-        };
-
-        let qrcode = match self.client.get_qrcode(qrcode_data.code_content).await {
-            Ok(code) => {
-                debug!("success to get qrcode!");
-                code
-            }
-            Err(e) => {
-                error!("Fail to get qrcode, error:{:?}", e);
-                return Err(e);
-            }
-        };
-
-        println!("{}\n", qrcode.to_str());
+    // 即将开抢列表的默认筛选条件
+    pub fn with_filter(mut self, filter: TicketFilter) -> Self {
+        self.filter = filter;
+        self
+    }
 
-        let t = qrcode_data.t;
-        let ck = qrcode_data.ck.clone();
+    pub async fn login(&self) -> Result<(String, String)> {
+        self.login_with_notify(false).await
+    }
 
-        let max_times = 60 * 5;
+    // 扫码登录, notify为true时同时通过通知渠道推送二维码
+    pub async fn login_with_notify(&self, notify: bool) -> Result<(String, String)> {
+        let cookie = self.client.login(&self.webdriver_url, notify).await?;
+        Ok((cookie, "".to_string()))
+    }
 
-        for i in 0..max_times {
-            let qrcode_scan_status = self.client.get_login_result(t, ck.clone()).await?;
+    // 账号密码/短信验证码登录, 未输入密码时使用短信验证码登录
+    pub async fn credential_login(&self) -> Result<String> {
+        let login_id: String = Input::with_theme(&theme())
+            .with_prompt(tr("请输入手机号/账号"))
+            .interact_text()?;
+        let password = Password::with_theme(&theme())
+            .with_prompt(tr("请输入密码(直接回车使用短信验证码登录)"))
+            .allow_empty_password(true)
+            .interact()?;
+        self.client
+            .login_with_credential(&self.webdriver_url, &login_id, Some(&password))
+            .await
+    }
 
-            match qrcode_scan_status.qrcode_status.as_str() {
-                "NEW" => {
-                    print!("\r请使用大麦APP扫码, 倒计时:{}秒\t", max_times - i);
-                    let _ = io::stdout().flush();
-                    tokio::time::sleep(Duration::from_secs(1)).await;
-                }
-                "SCANED" => {
-                    print!("\r##########请点击确认登录#######\t\t");
-                    let _ = io::stdout().flush();
-                    tokio::time::sleep(Duration::from_secs(1)).await;
-                }
-                "CONFIRMED" => {
-                    let cookie2 = qrcode_scan_status.cookie2.unwrap();
-                    let return_url = qrcode_scan_status.return_url.unwrap();
-                    let st = qrcode_scan_status.st.unwrap();
-                    let _ = self.client.get_cookie(&cookie2, return_url, st).await?;
-                    println!("\r\n扫码登录成功!");
-                    return Ok(cookie2);
-                }
-                "EXPIRED" => {
-                    print!("\r\t二维码已过期, 请重新执行程序...");
-                    return Err(ClientError::LoginFailed.into());
-                }
-                _ => {
-                    error!("未知状态:{:?}, 退出...", qrcode_scan_status);
-                    return Err(ClientError::LoginFailed.into());
-                }
-            }
+    // 从加密保存的账号中选择
+    pub fn select_account(&self) -> Result<Account> {
+        let store = AccountStore::from_env()?;
+        let accounts = store.list();
+        if accounts.is_empty() {
+            return Err(anyhow!(
+                "暂无保存的账号, 请先执行: dm-client accounts add <name>"
+            ));
         }
 
-        info!("二维码已过期, 请重新执行程序...");
-
-        Err(ClientError::LoginFailed.into())
+        let items = accounts
+            .iter()
+            .map(|account| format!("{} {}", account.name, account.nickname))
+            .collect::<Vec<String>>();
+        let index = FuzzySelect::with_theme(&theme())
+            .with_prompt(tr("请选择账号"))
+            .items(&items)
+            .default(0)
+            .interact()?;
+
+        Ok(accounts[index].clone())
     }
 
-    pub async fn get_driver(&self, webdriver_url: String) -> Result<WebDriver> {
-        let mut caps = DesiredCapabilities::chrome();
-        caps.set_disable_dev_shm_usage()?;
-        caps.set_headless()?;
-        caps.set_disable_gpu()?;
-        caps.set_disable_web_security()?;
-        caps.set_ignore_certificate_errors()?;
-        caps.add_chrome_arg("--disable-blink-features=AutomationControlled")?;
-        caps.add_chrome_arg("--disable-logging")?;
-        //caps.add_chrome_arg("--blink-settings=imagesEnabled=false")?;
-        caps.add_chrome_arg("--incognito")?;
-        caps.add_chrome_arg("--disable-stylesheet")?;
-        caps.add_chrome_arg("--excludeSwitches=[\"enable-automation\"]")?;
-        caps.add_chrome_arg("--useAutomationExtension=false")?;
-        caps.add_chrome_arg("--disable-infobars")?;
-        caps.add_chrome_arg("--disable-software-rasterizer")?;
-        caps.add_chrome_arg("--disable-extensions")?;
-        caps.add_chrome_arg("--no-sandbox")?;
-        caps.add_chrome_arg("--user-agent=Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36")?;
-        caps.add_chrome_arg("--window-size=1920,1080")?;
-        caps.add_chrome_arg("--single-process")?;
-        let driver: WebDriver = WebDriver::new(&webdriver_url, caps.clone())
-            .await
-            .map_err(|_| ClientError::WebdriverConnectionError)?;
-        Ok(driver)
-    }
+    // 修改筛选条件, 直接回车保留当前条件
+    pub fn edit_filter(&self) -> Result<TicketFilter> {
+        let mut filter = self.filter.clone();
+        info!("当前筛选条件:{}", filter.desc());
+        if !Confirm::with_theme(&theme())
+            .with_prompt(tr("是否修改筛选条件?"))
+            .default(false)
+            .interact()?
+        {
+            return Ok(filter);
+        }
 
-    pub async fn login(&self) -> Result<(String, String)> {
-        let cookie2 = self.qrcode_login().await?;
-
-        info!("正在获取cookie...");
-        let driver = self.get_driver(self.webdriver_url.clone()).await?;
-        let mut c = Cookie::new("cookie2", cookie2);
-        c.set_domain("damai.cn");
-        c.set_path("/");
-        c.set_same_site(Some(SameSite::Lax));
-
-        driver.goto("https://m.damai.cn/").await?;
-        let _ = driver.add_cookie(c).await;
-
-        let h5_url = "https://m.damai.cn/damai/mine/my/index.html?spm=a2o71.home.top.duserinfo";
-        driver.goto(h5_url).await?;
-
-        let css = r#"body > div.my > div.my-hd > div.user-name > div.nickname"#;
-        let _ = driver
-            .query(By::Css(css))
-            .wait(Duration::from_secs(10), Duration::from_millis(100))
-            .first()
-            .await;
-        let cookies = driver.get_all_cookies().await?;
-
-        let mut cookie_string = String::new();
-
-        for item in cookies {
-            if item.name().starts_with("_m_h5_tk") {
-                continue;
-            }
-            cookie_string.push_str(&format!("{}={};", item.name(), item.value()));
+        let split = |text: String| {
+            text.split([',', '，', ' '])
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<String>>()
+        };
+        let tz = sale_timezone();
+
+        let categories: String = Input::with_theme(&theme())
+            .with_prompt(tr("分类, 多个用逗号分隔, 如演唱会,话剧,音乐节(留空不限)"))
+            .with_initial_text(filter.categories.join(","))
+            .allow_empty(true)
+            .interact_text()?;
+        filter.categories = split(categories);
+
+        let cities: String = Input::with_theme(&theme())
+            .with_prompt(tr("城市, 多个用逗号分隔(留空不限)"))
+            .with_initial_text(filter.cities.join(","))
+            .allow_empty(true)
+            .interact_text()?;
+        filter.cities = split(cities);
+
+        let venue: String = Input::with_theme(&theme())
+            .with_prompt(tr("场馆关键词(留空不限)"))
+            .with_initial_text(filter.venue.clone().unwrap_or_default())
+            .allow_empty(true)
+            .interact_text()?;
+        filter.venue = Some(venue.trim().to_string()).filter(|venue| !venue.is_empty());
+
+        let from: String = Input::with_theme(&theme())
+            .with_prompt(tr("开抢日期起始, YYYY-MM-DD(留空不限)"))
+            .allow_empty(true)
+            .validate_with(|date: &String| match date.trim().is_empty() {
+                true => Ok(()),
+                false => parse_date(date, tz, false)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+            })
+            .interact_text()?;
+        if !from.trim().is_empty() {
+            filter.sale_from = Some(parse_date(&from, tz, false)?);
         }
 
-        let _ = driver.quit().await;
+        let to: String = Input::with_theme(&theme())
+            .with_prompt(tr("开抢日期截止, YYYY-MM-DD(留空不限)"))
+            .allow_empty(true)
+            .validate_with(|date: &String| match date.trim().is_empty() {
+                true => Ok(()),
+                false => parse_date(date, tz, true)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+            })
+            .interact_text()?;
+        if !to.trim().is_empty() {
+            filter.sale_to = Some(parse_date(&to, tz, true)?);
+        }
 
-        Ok((cookie_string, "".to_string()))
+        Ok(filter)
     }
 
-    // 获取演唱会ID
-    pub async fn get_ticket_id(&self) -> Result<Ticket> {
+    // 获取即将开抢的演出ID
+    pub async fn get_ticket_id(&self, filter: &TicketFilter) -> Result<Ticket> {
         let dm = DmClient::new(None, None).await?;
-        let url = "https://mtop.damai.cn/h5/mtop.damai.wireless.search.broadcast.list/1.0/";
-        let params = GetTicketListParams::build()?;
-        let form = GetTicketListForm::build()?;
 
-        let res = dm.request(url, params, form).await?;
+        let tickets = filter.apply(dm.search().await?);
 
-        // 今日必抢
-        let today_ticket_list: TicketList = serde_json::from_value(res.data["modules"][0].clone())?;
+        if tickets.is_empty() {
+            return Err(anyhow!(
+                "暂无符合条件的即将开抢演出, 筛选条件:{}",
+                filter.desc()
+            ));
+        }
 
-        // 即将开抢
-        let ticket_list: TicketList = serde_json::from_value(res.data["modules"][1].clone())?;
+        self.select_ticket(tickets)
+    }
 
-        let mut tickets: Vec<Ticket> = Vec::new();
+    // 从账号的想看列表中选择
+    pub async fn get_wishlist_ticket(&self, cookie: &str) -> Result<Ticket> {
+        let dm = DmClient::new(Some(cookie.to_string()), None).await?;
 
-        for ticket in today_ticket_list.items {
-            if !ticket.category_name.contains("演唱会") {
-                continue;
-            }
-            tickets.push(ticket);
+        let tickets = dm.wishlist().await?;
+        if tickets.is_empty() {
+            return Err(anyhow!("想看列表为空, 请先在大麦APP中标记想看的演出"));
         }
 
-        for ticket in ticket_list.items {
-            if !ticket.category_name.contains("演唱会") {
-                continue;
-            }
-            tickets.push(ticket);
-        }
+        self.select_ticket(tickets)
+    }
 
-        let mut select_list = vec![label("请选择演唱会:")];
-        for ticket in tickets.iter() {
-            let date_time = Local.timestamp_millis_opt(ticket.sale_time as i64).unwrap();
-            select_list.push(button(format!(
-                "{}, 开抢时间:{}",
-                ticket.ticket_name,
-                date_time.format("%Y-%m-%d %H:%M:%S")
-            )));
-        }
-        let m = menu(select_list);
-        run(&m);
-        let index = mut_menu(&m).selected_item_index() - 1;
+    fn select_ticket(&self, tickets: Vec<Ticket>) -> Result<Ticket> {
+        let tz = sale_timezone();
+        let items = tickets
+            .iter()
+            .map(|ticket| {
+                format!(
+                    "{} | {} {} | {} | 开抢时间:{}",
+                    ticket.ticket_name,
+                    ticket.city_name,
+                    ticket.venue_name,
+                    ticket.price_desc(),
+                    format_timestamp(ticket.sale_time as i64, tz)
+                )
+            })
+            .collect::<Vec<String>>();
+
+        let index = FuzzySelect::with_theme(&theme())
+            .with_prompt(tr("请选择演出(输入关键字过滤)"))
+            .items(&items)
+            .default(0)
+            .max_length(PAGE_SIZE)
+            .interact()?;
 
         Ok(tickets[index].clone())
     }
 
-    pub async fn get_perform(&self, ticket_id: &String) -> Result<PerformItem> {
+    pub async fn get_perform(&self, ticket_id: &str) -> Result<PerformItem> {
         let dm = DmClient::new(None, None).await?;
 
-        let url = "https://mtop.damai.cn/h5/mtop.alibaba.damai.detail.getdetail/1.2";
+        let performs = dm.performs(ticket_id).await?;
 
-        let params = TicketInfoParams::build()?;
+        let items = performs
+            .iter()
+            .map(|perform| perform.perfrom_name.clone())
+            .collect::<Vec<String>>();
 
-        let data = TicketInfoForm::build(ticket_id)?;
+        let index = FuzzySelect::with_theme(&theme())
+            .with_prompt(tr("请选择场次"))
+            .items(&items)
+            .default(0)
+            .max_length(PAGE_SIZE)
+            .interact()?;
 
-        let res = dm.request(url, params, data).await?;
-
-        let ticket_info: TicketInfo =
-            serde_json::from_str(res.data["result"].clone().as_str().unwrap())?;
+        Ok(performs[index].clone())
+    }
 
-        let perform_list = ticket_info
-            .detail_view_component_map
-            .item
-            .item
-            .perform_bases;
+    // 选择票档, 可多选, 按优先级排序, 第一个为首选票档
+    pub async fn get_skus(&self, ticket_id: String, perfrom_id: String) -> Result<Vec<SkuItem>> {
+        let dm = DmClient::new(None, None).await?;
 
-        let mut performs: Vec<PerformItem> = Vec::new();
+        let mut skus = dm.skus(&ticket_id, &perfrom_id).await?;
 
-        for perform in perform_list.iter() {
-            for item in perform.performs.iter() {
-                performs.push(PerformItem {
-                    perfrom_name: item.perform_name.clone(),
-                    perform_id: item.perform_id.clone(),
-                })
+        // 按开售时间修正票档状态, 已开售仍不可售的票档为售罄
+        match dm.detail(&ticket_id).await {
+            Ok(detail) => {
+                let sale_started = Local::now().timestamp_millis() >= detail.sell_start_timestamp;
+                for sku in skus.iter_mut() {
+                    sku.status = sku.status.resolve(sale_started);
+                }
             }
+            Err(e) => warn!("获取开售时间失败, 票档状态可能不准确:{:?}", e),
         }
 
-        let mut select_list = vec![label("请选择场次:")];
+        let items = skus.iter().map(|sku| sku.desc()).collect::<Vec<String>>();
 
-        for perform in performs.iter() {
-            select_list.push(button(perform.perfrom_name.clone()));
+        let mut selected = vec![];
+        while selected.is_empty() {
+            selected = MultiSelect::with_theme(&theme())
+                .with_prompt(tr("请选择票档(空格选择, 可多选, 回车确认)"))
+                .items(&items)
+                .max_length(PAGE_SIZE)
+                .interact()?;
         }
 
-        let m = menu(select_list);
-        run(&m);
+        if selected.len() > 1 {
+            let selected_items = selected
+                .iter()
+                .map(|i| items[*i].clone())
+                .collect::<Vec<String>>();
+            let order = Sort::with_theme(&theme())
+                .with_prompt(tr("请调整票档优先级(空格选中后上下移动, 回车确认)"))
+                .items(&selected_items)
+                .interact()?;
+            selected = order.into_iter().map(|i| selected[i]).collect();
+        }
 
-        let index = mut_menu(&m).selected_item_index() - 1;
+        if selected
+            .iter()
+            .all(|i| skus[*i].status == SkuStatus::SoldOut)
+            && !Confirm::with_theme(&theme())
+                .with_prompt(tr("所选票档均已售罄, 仅能等待回流票, 是否继续?"))
+                .default(false)
+                .interact()?
+        {
+            return Err(anyhow!("所选票档均已售罄"));
+        }
 
-        Ok(performs[index].clone())
+        Ok(selected.into_iter().map(|i| skus[i].clone()).collect())
     }
 
-    pub async fn get_sku(&self, ticket_id: String, perfrom_id: String) -> Result<SkuItem> {
-        let dm = DmClient::new(None, None).await?;
-
-        let url = "https://mtop.damai.cn/h5/mtop.alibaba.detail.subpage.getdetail/2.0/";
-
-        let params = PerformParams::build()?;
+    // 快递配送纸质票的演出选择收货地址, 默认选中账号默认地址
+    pub async fn get_address(&self, cookie: &str, ticket_id: &str) -> Result<Option<String>> {
+        let dm = DmClient::new(Some(cookie.to_string()), None).await?;
+        if dm.detail(ticket_id).await?.express_delivery != Some(true) {
+            return Ok(None);
+        }
 
-        let data = PerformForm::build(&ticket_id, &perfrom_id)?;
+        let addresses = dm.addresses().await?;
+        let default = select_address(&addresses, None).ok_or(anyhow!(
+            "该演出需要快递配送纸质票, 请先在大麦APP中添加收货地址"
+        ))?;
+
+        let items = addresses
+            .iter()
+            .map(|address| address.desc())
+            .collect::<Vec<String>>();
+        let index = Select::with_theme(&theme())
+            .with_prompt(tr("请选择收货地址"))
+            .items(&items)
+            .default(
+                addresses
+                    .iter()
+                    .position(|address| address == default)
+                    .unwrap_or(0),
+            )
+            .interact()?;
+
+        Ok(Some(addresses[index].address_id.clone()))
+    }
 
-        let res = dm.request(url, params, data).await?;
+    // 创建任务前展示门票信息, 确认后才创建
+    pub async fn confirm_task(&self, task: &Task) -> Result<bool> {
+        let dm = DmClient::new(None, None).await?;
+        let detail = dm.detail(&task.ticket_id).await?;
 
-        let perform_info: PerformInfo = serde_json::from_str(res.data["result"].as_str().unwrap())?;
+        let or_unknown = |value: &str| match value.is_empty() {
+            true => "-".to_string(),
+            false => value.to_string(),
+        };
 
-        let mut skus: Vec<SkuItem> = vec![];
-        for item in perform_info.perform.sku_list.iter() {
-            skus.push(SkuItem {
-                sku_id: item.sku_id.clone(),
-                sku_name: item.price_name.clone(),
-            })
+        println!(
+            "\n\t门票名称: {}
+            \n\t城市: {}
+            \n\t场馆: {}
+            \n\t演出时间: {}
+            \n\t价格区间: {}
+            \n\t场次: {}
+            \n\t票档: {}
+            \n\t备选票档: {}个
+            \n\t购票数量: {}
+            \n\t开售时间: {}
+            \n\t抢票时区: {}
+            \n\t每单限购: {}
+            \n\t实名购票: {}
+            \n\t快递配送: {}
+            \n\t购买状态: {}\n",
+            detail.item_name,
+            or_unknown(&detail.city_name),
+            or_unknown(&detail.venue_name),
+            or_unknown(&detail.show_time),
+            or_unknown(&detail.price_range),
+            task.ticket_perform_name,
+            task.ticket_perform_sku_name,
+            task.backup_sku_ids.len(),
+            task.ticket_num,
+            format_timestamp(detail.sell_start_timestamp, task.tz()),
+            task.timezone,
+            detail
+                .purchase_limit
+                .map_or("-".to_string(), |limit| format!("{}张", limit)),
+            match detail.real_name_required {
+                Some(true) => "是",
+                Some(false) => "否",
+                None => "-",
+            },
+            match detail.express_delivery {
+                Some(true) => "是",
+                Some(false) => "否",
+                None => "-",
+            },
+            detail.buy_btn_text,
+        );
+
+        if !detail.supported {
+            warn!("该渠道不支持购买, 请使用APP购票!");
         }
 
-        let mut select_list = vec![label("请选择票档:")];
-        for sku in skus.iter() {
-            select_list.push(button(sku.sku_name.clone()));
+        detail.validate(task)?;
+
+        let confirmed = Confirm::with_theme(&theme())
+            .with_prompt(tr("请确认以上信息, 是否创建抢票任务?"))
+            .default(false)
+            .interact()?;
+        if confirmed {
+            self.export_calendar(task, &detail)?;
         }
+        Ok(confirmed)
+    }
 
-        let m = menu(select_list);
-        run(&m);
-        let index = mut_menu(&m).selected_item_index() - 1;
+    // 导出开售时间日历, 配置了ICS_EXPORT时直接保存, 否则询问
+    pub fn export_calendar(&self, task: &Task, detail: &ItemDetail) -> Result<()> {
+        let path = match env::var("ICS_EXPORT") {
+            Ok(path) if !path.is_empty() => path,
+            _ => {
+                let export = Confirm::with_theme(&theme())
+                    .with_prompt(tr("是否导出开售时间到日历(.ics)?"))
+                    .default(false)
+                    .interact()?;
+                if !export {
+                    return Ok(());
+                }
+                Input::with_theme(&theme())
+                    .with_prompt(tr("日历文件路径"))
+                    .default("dm-ticket.ics".to_string())
+                    .interact_text()?
+            }
+        };
 
-        Ok(skus[index].clone())
+        let location = [detail.city_name.as_str(), detail.venue_name.as_str()]
+            .iter()
+            .filter(|s| !s.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let event = SaleEvent::from_task(task, detail.sell_start_timestamp, &location);
+        match calendar::export(Path::new(&path), &[event]) {
+            Ok(_) => info!("已导出开售时间日历:{}", path),
+            Err(e) => warn!("导出日历失败:{:?}", e),
+        }
+        Ok(())
     }
 
     pub async fn run(&self) -> Result<()> {
-        let m = menu(vec![
-            label("请选择登录方式:"),
-            button("1.扫码登录"),
-            button("2.输入cookie"),
-        ]);
-        run(&m);
-
-        let selected = mut_menu(&m).selected_item_index();
-
-        let (cookie, nickname) = match selected {
-            1 => {
+        let selected = Select::with_theme(&theme())
+            .with_prompt(tr("请选择登录方式"))
+            .items(&[
+                tr("1.扫码登录"),
+                tr("2.输入cookie"),
+                tr("3.使用已保存账号"),
+                tr("4.账号密码/短信验证码登录"),
+            ])
+            .default(0)
+            .interact()?;
+
+        // 使用已保存账号时记录账号名, 进程中断后可恢复任务
+        let (cookie, nickname, account) = match selected {
+            0 => {
                 let (cookie, nickname) =
                     self.login().await.map_err(|_| ClientError::LoginFailed)?;
-                (cookie, nickname)
+                (cookie, nickname, None)
+            }
+            1 => {
+                let cookie: String = Input::with_theme(&theme())
+                    .with_prompt(tr("请输入cookie"))
+                    .interact_text()?;
+                (cookie, "xxx".to_string(), None)
             }
             2 => {
-                let mut cookie = String::new();
-                println!("\r\n请输入cookie:");
-                let _ = std::io::stdin().read_line(&mut cookie).expect("输入错误!");
-                (cookie, "xxx".to_string())
+                let account = self.select_account()?;
+                (account.cookie, account.nickname, Some(account.name))
+            }
+            3 => {
+                let cookie = self
+                    .credential_login()
+                    .await
+                    .map_err(|_| ClientError::LoginFailed)?;
+                (cookie, "".to_string(), None)
             }
             _ => {
                 panic!("error: unexpected");
@@ -327,68 +478,97 @@ impl Client {
         if !cookie.contains("cookie2") {
             return Err(ClientError::CookieError.into());
         }
-        info!("正在获取演唱会ID");
-        let ticket = self.get_ticket_id().await?;
+        let source = Select::with_theme(&theme())
+            .with_prompt(tr("请选择演出来源"))
+            .items(&[tr("1.即将开抢的演出"), tr("2.我的想看")])
+            .default(0)
+            .interact()?;
+
+        let ticket = match source {
+            0 => {
+                let filter = self.edit_filter()?;
+                info!("正在获取演出ID");
+                self.get_ticket_id(&filter).await?
+            }
+            _ => self.get_wishlist_ticket(&cookie).await?,
+        };
 
         let perform = self.get_perform(&ticket.ticket_id.to_string()).await?;
 
-        let sku = self
-            .get_sku(ticket.ticket_id.to_string(), perform.perform_id.to_string())
+        let mut skus = self
+            .get_skus(ticket.ticket_id.to_string(), perform.perform_id.to_string())
             .await?;
+        let sku = skus.remove(0);
+
+        let ticket_num: usize = Input::with_theme(&theme())
+            .with_prompt(tr("购票数量"))
+            .default(1)
+            .validate_with(|n: &usize| match (1..=4).contains(n) {
+                true => Ok(()),
+                false => Err(tr("购票数量为1-4")),
+            })
+            .interact_text()?;
+
+        let retry_times: u64 = Input::with_theme(&theme())
+            .with_prompt(tr("重试次数"))
+            .default(50)
+            .interact_text()?;
+
+        let retry_interval: u64 = Input::with_theme(&theme())
+            .with_prompt(tr("重试间隔(毫秒)"))
+            .default(100)
+            .interact_text()?;
+
+        let wati_for_submit_interval: u64 = Input::with_theme(&theme())
+            .with_prompt(tr("生成-提交订单间隔(毫秒)"))
+            .default(30)
+            .interact_text()?;
+
+        let request_time_offset: i64 = Input::with_theme(&theme())
+            .with_prompt(tr("请求时间偏移量(毫秒)"))
+            .default(0)
+            .interact_text()?;
+
+        let priority_purchase_time: i64 = Input::with_theme(&theme())
+            .with_prompt(tr("优先购时长(分钟, 0为自动识别)"))
+            .default(0)
+            .interact_text()?;
+
+        let waitlist_on_sold_out = Confirm::with_theme(&theme())
+            .with_prompt(tr("库存不足时是否进行缺货登记?"))
+            .default(false)
+            .interact()?;
+
+        let seats = match Confirm::with_theme(&theme())
+            .with_prompt(tr("是否为选座演出?"))
+            .default(false)
+            .interact()?
+        {
+            true => {
+                let zones: String = Input::with_theme(&theme())
+                    .with_prompt(tr("优先区域(多个用逗号分隔, 留空不限区域)"))
+                    .allow_empty(true)
+                    .interact_text()?;
+                Some(SeatPreference {
+                    zones: zones
+                        .split([',', '，'])
+                        .map(|zone| zone.trim().to_string())
+                        .filter(|zone| !zone.is_empty())
+                        .collect(),
+                    ..Default::default()
+                })
+            }
+            false => None,
+        };
 
-        let m = menu(vec![
-            numeric(
-                "购票数量",
-                1.0, //default
-                Some(1.0),
-                Some(1.0),
-                Some(4.0),
-            ),
-            button("确定"),
-        ]);
-        run(&m);
-        let ticket_num = mut_menu(&m).numeric_value("购票数量");
-
-        let m = menu(vec![
-            numeric("重试次数", 50.0, Some(1.0), None, Some(100.0)),
-            button("确定"),
-        ]);
-        run(&m);
-        let retry_times = mut_menu(&m).numeric_value("重试次数");
-
-        let m = menu(vec![
-            numeric("重试间隔", 100.0, Some(10.0), None, Some(1000.0)),
-            button("确定"),
-        ]);
-        run(&m);
-        let retry_interval = mut_menu(&m).numeric_value("重试间隔");
-
-        let m = menu(vec![
-            numeric("生成-提交订单间隔(毫秒)", 30.0, Some(10.0), Some(0.0), None),
-            button("确定"),
-        ]);
-        run(&m);
-        let wati_for_submit_interval = mut_menu(&m).numeric_value("生成-提交订单间隔(毫秒)");
-
-        let m = menu(vec![
-            numeric(
-                "请求时间偏移量(毫秒)",
-                0.0,
-                Some(10.0),
-                Some(-100.0),
-                Some(1000.0),
-            ),
-            button("确定"),
-        ]);
-        run(&m);
-        let request_time_offset = mut_menu(&m).numeric_value("请求时间偏移量(毫秒)");
-
-        let m = menu(vec![
-            numeric("优先购时长(分钟)", 0.0, Some(20.0), Some(0.0), Some(60.0)),
-            button("确定"),
-        ]);
-        run(&m);
-        let priority_purchase_time = mut_menu(&m).numeric_value("优先购时长(分钟)");
+        let use_coupons = Confirm::with_theme(&theme())
+            .with_prompt(tr("是否使用优惠券?"))
+            .default(false)
+            .interact()?;
+
+        let address_id = self
+            .get_address(&cookie, &ticket.ticket_id.to_string())
+            .await?;
 
         let task = Task {
             nickname,
@@ -398,17 +578,43 @@ impl Client {
             ticket_perform_name: perform.perfrom_name,
             ticket_perform_sku_id: sku.sku_id,
             ticket_perform_sku_name: sku.sku_name,
-            ticket_num: ticket_num as usize,
-            priority_purchase_time: priority_purchase_time as i64,
-            request_time_offset: request_time_offset as i64,
-            retry_interval: retry_interval as u64,
-            retry_times: retry_times as u64,
-            wait_for_submit_interval: wati_for_submit_interval as u64,
+            ticket_num,
+            priority_purchase_time,
+            request_time_offset,
+            retry_interval,
+            retry_times,
+            wait_for_submit_interval: wati_for_submit_interval,
             real_names: vec![],
+            viewers: vec![],
+            split_per_viewer: false,
+            warmup_time: default_warmup_time(),
+            stop_after_seconds: None,
+            stop_after_sale_plus: None,
+            stop_on_error_codes: vec![],
+            backup_sku_ids: skus.into_iter().map(|sku| sku.sku_id).collect(),
+            timezone: default_timezone(),
+            waitlist_on_sold_out,
+            seats,
+            address_id,
+            use_coupons,
+            cancel_duplicates: false,
+            allow_existing_order: false,
+            keyword: None,
+            cities: vec![],
+            watch_status: false,
+            privilege_code: None,
+            variant: None,
         };
 
-        let mut app = DmTicket::new(cookie, task).await?;
-        app.run().await?;
+        if !self.confirm_task(&task).await? {
+            info!("已取消创建抢票任务");
+            return Ok(());
+        }
+
+        match account {
+            Some(account) => run_armed(cookie, task, account).await?,
+            None => run_task(cookie, task).await?,
+        }
 
         Ok(())
     }
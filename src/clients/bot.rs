@@ -0,0 +1,119 @@
+use std::env;
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Local;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+const DINGTALK_URL: &str = "https://oapi.dingtalk.com/robot/send";
+
+const WECOM_URL: &str = "https://qyapi.weixin.qq.com/cgi-bin/webhook/send";
+
+// 群机器人
+#[derive(Debug, Clone)]
+pub enum Bot {
+    // 钉钉群机器人, 安全设置为加签时需配置secret
+    DingTalk {
+        token: String,
+        secret: Option<String>,
+    },
+
+    // 企业微信群机器人
+    WeCom {
+        key: String,
+    },
+}
+
+// 钉钉加签: base64(hmac_sha256(secret, "timestamp\nsecret")), 再进行url编码
+pub fn dingtalk_sign(timestamp: i64, secret: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(format!("{}\n{}", timestamp, secret).as_bytes());
+    let sign = STANDARD.encode(mac.finalize().into_bytes());
+    Ok(urlencoding::encode(&sign).into_owned())
+}
+
+impl Bot {
+    // 从环境变量读取配置:
+    // DINGTALK_TOKEN/DINGTALK_SECRET: 钉钉群机器人access_token及加签密钥
+    // WECOM_KEY: 企业微信群机器人key
+    pub fn from_env() -> Vec<Bot> {
+        let mut bots = vec![];
+        if let Ok(token) = env::var("DINGTALK_TOKEN") {
+            if !token.is_empty() {
+                bots.push(Bot::DingTalk {
+                    token,
+                    secret: env::var("DINGTALK_SECRET").ok().filter(|s| !s.is_empty()),
+                });
+            }
+        }
+        if let Ok(key) = env::var("WECOM_KEY") {
+            if !key.is_empty() {
+                bots.push(Bot::WeCom { key });
+            }
+        }
+        bots
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bot::DingTalk { .. } => "钉钉",
+            Bot::WeCom { .. } => "企业微信",
+        }
+    }
+
+    // 请求地址, 钉钉加签时带上时间戳和签名
+    pub fn url(&self, timestamp: i64) -> Result<String> {
+        match self {
+            Bot::DingTalk {
+                token,
+                secret: Some(secret),
+            } => Ok(format!(
+                "{}?access_token={}&timestamp={}&sign={}",
+                DINGTALK_URL,
+                token,
+                timestamp,
+                dingtalk_sign(timestamp, secret)?
+            )),
+            Bot::DingTalk { token, .. } => Ok(format!("{}?access_token={}", DINGTALK_URL, token)),
+            Bot::WeCom { key } => Ok(format!("{}?key={}", WECOM_URL, key)),
+        }
+    }
+
+    // markdown消息
+    pub fn body(&self, title: &str, content: &str) -> Value {
+        match self {
+            Bot::DingTalk { .. } => json!({
+                "msgtype": "markdown",
+                "markdown": {
+                    "title": title,
+                    "text": format!("### {}\n\n{}", title, content),
+                }
+            }),
+            Bot::WeCom { .. } => json!({
+                "msgtype": "markdown",
+                "markdown": {
+                    "content": format!("### {}\n{}", title, content),
+                }
+            }),
+        }
+    }
+
+    // 发送消息, errcode不为0时返回错误
+    pub async fn send(&self, title: &str, content: &str) -> Result<()> {
+        let url = self.url(Local::now().timestamp_millis())?;
+        let response = reqwest::Client::new()
+            .post(url)
+            .json(&self.body(title, content))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        match response["errcode"].as_i64() {
+            Some(0) => Ok(()),
+            _ => Err(anyhow!("{}机器人返回:{}", self.name(), response)),
+        }
+    }
+}
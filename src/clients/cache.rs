@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+
+use super::endpoint::Api;
+
+// 默认缓存时长, 秒; 详情/票档含开售状态和库存, 只短暂缓存
+const DEFAULT_TTLS: [(Api, u64); 5] = [
+    (Api::TicketDetail, 2),
+    (Api::PerformSkus, 2),
+    (Api::TicketList, 60),
+    (Api::WishList, 60),
+    (Api::Search, 60),
+];
+
+// 缓存的响应
+#[derive(Debug, Clone)]
+struct Entry {
+    expires: Instant,
+    value: Value,
+}
+
+// 只读接口的响应缓存, 相同参数的请求在缓存时长内直接返回, 每个客户端单独缓存
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCache {
+    ttls: Arc<HashMap<&'static str, Duration>>,
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl ResponseCache {
+    // ttls为各接口的缓存时长, 未配置的接口不缓存
+    pub fn new(ttls: HashMap<&'static str, Duration>) -> Self {
+        Self {
+            ttls: Arc::new(ttls.into_iter().filter(|(_, ttl)| !ttl.is_zero()).collect()),
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // 从环境变量读取配置:
+    // RESPONSE_CACHE: 是否启用, 默认开启
+    // RESPONSE_CACHE_TTL: 各接口的缓存时长, 格式: api=秒,api=..., 0为不缓存
+    pub fn from_env() -> Self {
+        let enabled = env::var("RESPONSE_CACHE")
+            .map(|v| !v.eq_ignore_ascii_case("false") && v != "0")
+            .unwrap_or(true);
+        if !enabled {
+            return Self::default();
+        }
+        let mut ttls = DEFAULT_TTLS
+            .iter()
+            .map(|(api, secs)| (api.name(), Duration::from_secs(*secs)))
+            .collect::<HashMap<_, _>>();
+        if let Ok(value) = env::var("RESPONSE_CACHE_TTL") {
+            ttls.extend(Self::parse_ttls(&value));
+        }
+        Self::new(ttls)
+    }
+
+    // 解析各接口的缓存时长, 忽略未知的接口
+    pub fn parse_ttls(value: &str) -> HashMap<&'static str, Duration> {
+        value
+            .split(',')
+            .filter_map(|item| item.split_once('='))
+            .filter_map(|(api, secs)| {
+                let api = Api::from_name(api.trim())?;
+                let secs = secs.trim().parse::<f64>().ok().filter(|s| *s >= 0.0)?;
+                Some((api.name(), Duration::from_secs_f64(secs)))
+            })
+            .collect()
+    }
+
+    // 接口的缓存时长, 不缓存时为None
+    pub fn ttl(&self, api: Api) -> Option<Duration> {
+        self.ttls.get(api.name()).copied()
+    }
+
+    // 缓存键: 接口及业务参数, 签名/时间戳等公共参数每次不同, 不参与
+    fn key(api: Api, data: &Value) -> String {
+        format!("{}:{}", api.name(), data)
+    }
+
+    pub fn get(&self, api: Api, data: &Value, now: Instant) -> Option<Value> {
+        self.ttl(api)?;
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(&Self::key(api, data))
+            .filter(|entry| entry.expires > now)
+            .map(|entry| entry.value.clone())
+    }
+
+    // 只缓存调用成功的响应, 同时清理已过期的缓存
+    pub fn put(&self, api: Api, data: &Value, value: &Value, now: Instant) {
+        let ttl = match self.ttl(api) {
+            Some(ttl) => ttl,
+            None => return,
+        };
+        let success = value["ret"].as_array().is_some_and(|ret| {
+            ret.iter()
+                .any(|r| r.as_str().is_some_and(|r| r.starts_with("SUCCESS")))
+        });
+        if !success {
+            return;
+        }
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|_, entry| entry.expires > now);
+            entries.insert(
+                Self::key(api, data),
+                Entry {
+                    expires: now + ttl,
+                    value: value.clone(),
+                },
+            );
+        }
+    }
+
+    // 清空缓存, 如重新登录后
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
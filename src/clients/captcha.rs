@@ -0,0 +1,93 @@
+use std::{env, time::Duration};
+
+use anyhow::{anyhow, Result};
+use thirtyfour::{cookie::SameSite, Cookie, DesiredCapabilities, WebDriver};
+use tracing::{info, warn};
+
+use super::{notify::NotifyClient, webdriver};
+
+// 等待人工完成验证的默认时长, 秒
+const DEFAULT_CAPTCHA_TIMEOUT: u64 = 120;
+
+// 人机验证, 打开浏览器窗口由用户手动完成滑块验证
+pub struct CaptchaClient {
+    webdriver_url: String,
+    timeout: Duration,
+}
+
+impl CaptchaClient {
+    pub fn new(webdriver_url: String) -> Self {
+        let timeout = env::var("CAPTCHA_TIMEOUT")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_CAPTCHA_TIMEOUT);
+
+        Self {
+            webdriver_url,
+            timeout: Duration::from_secs(timeout),
+        }
+    }
+
+    // 验证页面地址
+    fn is_challenge_url(url: &str) -> bool {
+        url.contains("punish") || url.contains("_____tmd_____")
+    }
+
+    // 打开验证页面, 等待用户完成验证, 返回验证后的cookie
+    pub async fn solve(&self, url: &str, cookie: &str) -> Result<String> {
+        let mut caps = DesiredCapabilities::chrome();
+        caps.add_chrome_arg("--window-size=420,800")?;
+
+        let driver: WebDriver = webdriver::new_driver(&self.webdriver_url, caps).await?;
+
+        driver.goto("https://m.damai.cn/").await?;
+        for item in cookie.split(';') {
+            if let Some((name, value)) = item.split_once('=') {
+                let mut c = Cookie::new(name.trim().to_string(), value.trim().to_string());
+                c.set_domain("damai.cn");
+                c.set_path("/");
+                c.set_same_site(Some(SameSite::Lax));
+                let _ = driver.add_cookie(c).await;
+            }
+        }
+
+        driver.goto(url).await?;
+
+        warn!("触发滑块验证, 请在浏览器窗口中完成验证: {}", url);
+        if env::var("NOTIFY_TOKEN").is_ok() {
+            let content = format!("触发滑块验证, 请尽快完成验证: {}", url);
+            let _ = NotifyClient::notify(&content).await;
+        }
+
+        let start = tokio::time::Instant::now();
+        let result = loop {
+            if start.elapsed() > self.timeout {
+                break Err(anyhow!("等待滑块验证超时"));
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+
+            match driver.current_url().await {
+                Ok(current) if !Self::is_challenge_url(current.as_str()) => {
+                    info!("滑块验证已完成...");
+                    break Ok(());
+                }
+                Ok(_) => continue,
+                Err(e) => break Err(e.into()),
+            }
+        };
+
+        let cookies = driver.get_all_cookies().await;
+        let _ = driver.quit().await;
+        result?;
+
+        let mut cookie_string = String::new();
+        for item in cookies? {
+            if item.name().starts_with("_m_h5_tk") {
+                continue;
+            }
+            cookie_string.push_str(&format!("{}={};", item.name(), item.value()));
+        }
+
+        Ok(cookie_string)
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use async_channel::{Receiver, Sender};
+
+// 信号量, 通道中预先放入的空位即为可用的并发数
+#[derive(Debug, Clone)]
+pub struct Semaphore {
+    tx: Sender<()>,
+    rx: Receiver<()>,
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        let (tx, rx) = async_channel::bounded(permits.max(1));
+        for _ in 0..permits.max(1) {
+            let _ = tx.try_send(());
+        }
+        Self { tx, rx }
+    }
+
+    // 等待空位, 返回的许可释放时归还
+    pub async fn acquire(&self) -> Permit {
+        let _ = self.rx.recv().await;
+        Permit {
+            tx: Some(self.tx.clone()),
+        }
+    }
+
+    // 当前可用的并发数
+    pub fn available(&self) -> usize {
+        self.rx.len()
+    }
+}
+
+// 并发许可, 请求结束(含出错/超时取消)时归还
+#[derive(Debug, Default)]
+pub struct Permit {
+    tx: Option<Sender<()>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.try_send(());
+        }
+    }
+}
+
+// 同一账号及全局的许可
+#[derive(Debug, Default)]
+pub struct Permits {
+    _account: Permit,
+    _global: Permit,
+}
+
+// 并发请求数限制: 全局及每个账号同时进行中的请求数, 进程内所有任务共用
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyLimiter {
+    global: Option<Semaphore>,
+    per_account: usize,
+    accounts: Arc<Mutex<HashMap<String, Semaphore>>>,
+}
+
+impl ConcurrencyLimiter {
+    // global/per_account为0时不限制
+    pub fn new(global: usize, per_account: usize) -> Self {
+        Self {
+            global: match global {
+                0 => None,
+                n => Some(Semaphore::new(n)),
+            },
+            per_account,
+            accounts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // 从环境变量读取配置:
+    // MAX_INFLIGHT: 全局同时进行中的请求数, 默认0不限制
+    // MAX_INFLIGHT_PER_ACCOUNT: 每个账号同时进行中的请求数, 默认0不限制
+    pub fn from_env() -> Self {
+        let number = |key: &str| {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .unwrap_or(0)
+        };
+        Self::new(number("MAX_INFLIGHT"), number("MAX_INFLIGHT_PER_ACCOUNT"))
+    }
+
+    // 进程内共用的并发配置
+    pub fn shared() -> Self {
+        static LIMITER: OnceLock<ConcurrencyLimiter> = OnceLock::new();
+        LIMITER.get_or_init(Self::from_env).clone()
+    }
+
+    // 账号的信号量, 首次请求时创建
+    fn account(&self, account: &str) -> Option<Semaphore> {
+        if self.per_account == 0 {
+            return None;
+        }
+        let mut accounts = self.accounts.lock().ok()?;
+        Some(
+            accounts
+                .entry(account.to_string())
+                .or_insert_with(|| Semaphore::new(self.per_account))
+                .clone(),
+        )
+    }
+
+    // 等待账号及全局的空位, 先占账号的空位, 避免排队时占用全局的空位
+    pub async fn acquire(&self, account: &str) -> Permits {
+        let account = match self.account(account) {
+            Some(semaphore) => semaphore.acquire().await,
+            None => Permit::default(),
+        };
+        let global = match &self.global {
+            Some(semaphore) => semaphore.acquire().await,
+            None => Permit::default(),
+        };
+        Permits {
+            _account: account,
+            _global: global,
+        }
+    }
+
+    // 账号当前可用的并发数, 未限制时为None
+    pub fn available(&self, account: &str) -> Option<usize> {
+        self.account(account).map(|semaphore| semaphore.available())
+    }
+}
+
+// 按cookie中的用户ID(munb/unb)区分账号, 没有时使用整个cookie
+pub fn account_key(cookie: &str) -> String {
+    let find = |name: &str| {
+        cookie
+            .split(';')
+            .filter_map(|item| item.split_once('='))
+            .find(|(n, v)| n.trim() == name && !v.trim().is_empty())
+            .map(|(_, v)| v.trim().to_string())
+    };
+    find("munb")
+        .or_else(|| find("unb"))
+        .unwrap_or(cookie.to_string())
+}
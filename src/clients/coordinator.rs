@@ -0,0 +1,113 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use redis::{aio::MultiplexedConnection, AsyncCommands, Client};
+use tracing::warn;
+
+use crate::{errors::ClientError, models::task::Task};
+
+// 抢票成功标记的保留时长, 秒
+const DONE_KEY_TTL: usize = 24 * 60 * 60;
+
+// 后台查询成功标记的间隔
+const DONE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// 多机协同抢票, 任一机器下单成功后通知其他机器停止
+// 成功标记由后台任务轮询, 下单循环中只读取本地标志, 不访问redis
+#[derive(Debug, Clone)]
+pub struct Coordinator {
+    client: Client,
+    key: String,
+    done: Arc<AtomicBool>,
+}
+
+impl Coordinator {
+    pub async fn new(redis_url: String, task: &Task) -> Result<Self> {
+        let client =
+            redis::Client::open(redis_url).map_err(|_| ClientError::RedisConnectionError)?;
+
+        let key = format!("dm_ticket:done:{}", task.key());
+        let done = Arc::new(AtomicBool::new(false));
+
+        tokio::spawn(poll_done(
+            client.clone(),
+            key.clone(),
+            Arc::downgrade(&done),
+        ));
+
+        Ok(Self { client, key, done })
+    }
+
+    // 从环境变量COORDINATOR_REDIS_URL初始化, 未配置时不启用
+    pub async fn from_env(task: &Task) -> Result<Option<Self>> {
+        match std::env::var("COORDINATOR_REDIS_URL") {
+            Ok(url) if !url.is_empty() => Ok(Some(Self::new(url, task).await?)),
+            _ => Ok(None),
+        }
+    }
+
+    // 是否已有机器下单成功, 读取后台任务最近一次查询的结果
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    // 标记下单成功, 返回false表示已被其他机器抢先标记
+    pub async fn mark_done(&self, owner: &str) -> Result<bool> {
+        let mut con = self.client.get_multiplexed_tokio_connection().await?;
+        let res: Option<String> = redis::cmd("SET")
+            .arg(&self.key)
+            .arg(owner)
+            .arg("NX")
+            .arg("EX")
+            .arg(DONE_KEY_TTL)
+            .query_async(&mut con)
+            .await?;
+        Ok(res.is_some())
+    }
+
+    // 获取下单成功的机器标识
+    pub async fn owner(&self) -> Result<Option<String>> {
+        let mut con = self.client.get_multiplexed_tokio_connection().await?;
+        let owner: Option<String> = con.get(&self.key).await?;
+        Ok(owner)
+    }
+}
+
+// 复用一个连接轮询成功标记, 所有Coordinator释放后退出, 连接断开时重连
+async fn poll_done(client: Client, key: String, done: Weak<AtomicBool>) {
+    let mut con: Option<MultiplexedConnection> = None;
+    let mut failing = false;
+    while let Some(flag) = done.upgrade() {
+        if flag.load(Ordering::Relaxed) {
+            return;
+        }
+        let res = match con.as_mut() {
+            Some(con) => con.exists::<_, bool>(&key).await,
+            None => match client.get_multiplexed_tokio_connection().await {
+                Ok(c) => con.insert(c).exists::<_, bool>(&key).await,
+                Err(e) => Err(e),
+            },
+        };
+        match res {
+            Ok(exists) => {
+                failing = false;
+                flag.store(exists, Ordering::Relaxed);
+            }
+            Err(e) => {
+                if !failing {
+                    warn!("查询协同状态失败, {:?}", e);
+                }
+                failing = true;
+                con = None;
+            }
+        }
+        drop(flag);
+        tokio::time::sleep(DONE_POLL_INTERVAL).await;
+    }
+}
@@ -1,32 +1,136 @@
-use super::token::TokenClient;
-use crate::models::{ticket::TicketInfoParams, DmRes, DmToken};
-use anyhow::Result;
+use std::{env, sync::Arc, time::Instant};
+
+#[cfg(feature = "browser-login")]
+use super::captcha::CaptchaClient;
+use super::{
+    cache::ResponseCache,
+    concurrency::account_key,
+    endpoint::{Api, EndpointRegistry},
+    middleware::{
+        default_chain, Chain, HttpTransport, MetricsMiddleware, MtopRequest, PreparedBody,
+    },
+    queue::{QueuePolicy, QueueTicket},
+    record::Recorder,
+    session::{self, Session, SessionStore, SharedSession},
+    sign::{token_valid, TokenCache},
+    token::TokenClient,
+};
+use crate::{
+    errors::{MtopError, PlatformError},
+    models::{
+        address::{parse_address_list, AddAddressForm, Address, GetAddressListForm, NewAddress},
+        coupon::{parse_coupon_list, Coupon, GetCouponListForm},
+        order::{
+            parse_order_list, CancelOrderForm, OrderForm, OrderInfo, OrderListForm, OrderParams,
+            OrderSummary, SubmitOrderForm, SubmitOrderParams, WaitlistForm,
+        },
+        perform::{PerformForm, PerformInfo, PerformItem, PerformParams, SkuItem},
+        privilege::{parse_privilege_check, PrivilegeCheck, PrivilegeCheckForm},
+        search::{parse_city_stops, CityStop, SearchProjectForm},
+        seat::{parse_seat_map, Seat, SeatMapForm},
+        task::Task,
+        ticket::{
+            GetTicketListForm, GetTicketListParams, PurchaseRules, Ticket, TicketInfo,
+            TicketInfoForm, TicketInfoParams, TicketList,
+        },
+        user::{
+            parse_wish_list, GetUserInfoForm, GetUserInfoParams, GetWishListForm,
+            GetWishListParams, UserInfoData,
+        },
+        viewer::{parse_viewer_list, AddViewerForm, GetViewerListForm, RemoveViewerForm, Viewer},
+        CommonParams, DmRes, DmToken,
+    },
+    platform::{ItemDetail, SubmitResult, TicketPlatform},
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Local;
 use reqwest::{
     header::{HeaderMap, HeaderValue},
-    Client,
+    Client, ClientBuilder,
 };
 use serde_json::{json, Value};
+use tracing::{debug, error, info};
+
+pub const SUCCESS_FLAG: &str = "SUCCESS::调用成功";
+
+const USER_VALIDATE_FLAG: &str = "FAIL_SYS_USER_VALIDATE";
+
+// 是否为风控拦截的返回
+pub fn is_risk_control(ret: &str) -> bool {
+    MtopError::from_ret(ret) == MtopError::RiskControl
+}
+
+// 接口调用失败的错误
+fn ret_error(res: &DmRes) -> anyhow::Error {
+    res.error()
+        .unwrap_or_else(|| MtopError::Unknown(format!("{:?}", res.ret)))
+        .into()
+}
+
+// 合并cookie, 同名字段以新值为准
+pub fn merge_cookie(old: &str, new: &str) -> String {
+    let mut items: Vec<(String, String)> = Vec::new();
+    for item in old.split(';').chain(new.split(';')) {
+        if let Some((name, value)) = item.split_once('=') {
+            let name = name.trim().to_string();
+            let value = value.trim().to_string();
+            match items.iter_mut().find(|(n, _)| *n == name) {
+                Some(entry) => entry.1 = value,
+                None => items.push((name, value)),
+            }
+        }
+    }
+    items
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+// 开售前预先构造的生成订单参数
+#[derive(Debug, Clone)]
+pub struct PreparedOrder {
+    pub item_id: String,
+    pub sku_id: String,
+    pub buy_num: usize,
+    pub params: Value,
+    pub data: Value,
+    pub body: Arc<PreparedBody>, // 编码好的请求, 开抢时只补上时间戳/签名
+}
 
 #[derive(Debug)]
 pub struct DmClient {
     pub client: Client,
+    pub race_client: Option<Client>, // 与直连竞速的代理线路
     pub token_client: Option<TokenClient>,
     pub token: DmToken,
+    pub cookie: String,
+    pub endpoints: EndpointRegistry,
+    pub chain: Chain,               // 请求中间件链
+    pub metrics: MetricsMiddleware, // 各接口耗时统计
+    pub cache: ResponseCache,       // 只读接口的响应缓存
+    pub queue: QueuePolicy,         // 下单接口返回排队响应时的轮询配置
+    pub prepared: Option<PreparedOrder>,
+    pub coupon_id: Option<String>,      // 生成订单时使用的优惠券
+    pub privilege_code: Option<String>, // 生成订单时携带的优先购码
+    pub session: SharedSession,         // 同一账号的客户端共用的登录会话
+    pub version: u64,                   // 构建客户端时的会话版本
 }
 
 // 获取token
-pub async fn get_token(cookie: &str) -> Result<DmToken> {
+pub async fn get_token(cookie: &str, endpoints: &EndpointRegistry) -> Result<DmToken> {
     let mut headers = HeaderMap::new();
 
-    let url = "https://mtop.damai.cn/";
+    let url = format!("{}/", endpoints.gateway.trim_end_matches('/'));
 
-    headers.append("origin", HeaderValue::from_str(url)?);
-    headers.append("referer", HeaderValue::from_str(url)?);
+    headers.append("origin", HeaderValue::from_str(&url)?);
+    headers.append("referer", HeaderValue::from_str(&url)?);
     headers.append("cookie", HeaderValue::from_str(cookie)?);
-    let client = reqwest::Client::builder()
+    let client = endpoints
+        .client_builder()
         .default_headers(headers)
         .cookie_store(true)
-        .http2_prior_knowledge()
         .build()?;
 
     let mut token = DmToken {
@@ -35,7 +139,7 @@ pub async fn get_token(cookie: &str) -> Result<DmToken> {
         token: "".to_string(),
     };
 
-    let url = "https://mtop.damai.cn/h5/mtop.damai.wireless.search.broadcast.list/1.0/?";
+    let url = endpoints.url(Api::TicketList);
     let params = TicketInfoParams::build()?;
     let response = client.get(url).form(&params).send().await?;
 
@@ -51,86 +155,891 @@ pub async fn get_token(cookie: &str) -> Result<DmToken> {
     Ok(token)
 }
 
+// 获取cookie对应的token, 优先复用未过期的token
+async fn fetch_token(cookie: &str, endpoints: &EndpointRegistry) -> Result<DmToken> {
+    if let Some(token) = TokenCache::global().get(cookie) {
+        return Ok(token);
+    }
+    let token = get_token(cookie, endpoints).await?;
+    if token_valid(&token, Local::now().timestamp_millis()) {
+        TokenCache::global().put(cookie, token.clone());
+    }
+    Ok(token)
+}
+
+// 按cookie和token构建请求客户端
+fn build_client(
+    builder: ClientBuilder,
+    cookie: &str,
+    token: &DmToken,
+    endpoints: &EndpointRegistry,
+) -> Result<Client> {
+    let mut headers = HeaderMap::new();
+
+    let base_url = format!("{}/", endpoints.gateway.trim_end_matches('/'));
+
+    headers.append("origin", HeaderValue::from_str(&base_url)?);
+
+    headers.append("referer", HeaderValue::from_str(&base_url)?);
+
+    headers.append(
+        "cookie",
+        HeaderValue::from_str(
+            format!(
+                "{};_m_h5_tk_enc={};_m_h5_tk={};",
+                cookie, token.enc_token, token.token_with_time
+            )
+            .as_str(),
+        )?,
+    );
+    let client = builder
+        .default_headers(headers)
+        .cookie_store(true)
+        .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36")
+        .use_rustls_tls()
+        .build()?;
+    Ok(client)
+}
+
+// 请求客户端、竞速的代理线路客户端及发送请求的transport, https网关另外构建http1.1客户端用于回退
+fn build_transport(
+    cookie: &str,
+    token: &DmToken,
+    endpoints: &EndpointRegistry,
+) -> Result<(Client, Option<Client>, HttpTransport)> {
+    let client = build_client(endpoints.client_builder(), cookie, token, endpoints)?;
+    let fallback = endpoints
+        .http1_builder()
+        .map(|builder| build_client(builder, cookie, token, endpoints))
+        .transpose()?;
+    let race = endpoints
+        .race_builder()?
+        .map(|builder| build_client(builder, cookie, token, endpoints))
+        .transpose()?;
+    let transport = HttpTransport::new(client.clone(), fallback).with_race(race.clone());
+    Ok((client, race, transport))
+}
+
 impl DmClient {
     // 初始化请求客户端
     pub async fn new(cookie: Option<String>, token_client: Option<TokenClient>) -> Result<Self> {
+        Self::with_endpoints(cookie, token_client, EndpointRegistry::from_env()).await
+    }
+
+    // 指定网关地址初始化, 可用于连接本地模拟服务
+    pub async fn with_base_url(
+        cookie: Option<String>,
+        token_client: Option<TokenClient>,
+        base_url: &str,
+    ) -> Result<Self> {
+        let endpoints = EndpointRegistry {
+            gateway: base_url.to_string(),
+            ..Default::default()
+        };
+        Self::with_endpoints(cookie, token_client, endpoints).await
+    }
+
+    // 使用指定的接口配置初始化
+    pub async fn with_endpoints(
+        cookie: Option<String>,
+        token_client: Option<TokenClient>,
+        endpoints: EndpointRegistry,
+    ) -> Result<Self> {
         let cookie = cookie
             .unwrap_or("".to_string())
-            .replace(' ', "")
-            .replace('\n', "")
+            .replace([' ', '\n'], "")
             .split(';')
             .filter(|e| !e.starts_with("_m_h5_tk"))
             .collect::<Vec<&str>>()
             .join(";");
 
-        let token = get_token(&cookie).await?;
-
-        let mut headers = HeaderMap::new();
-
-        let base_url = "https://mtop.damai.cn/";
-
-        headers.append("origin", HeaderValue::from_str(base_url)?);
-
-        headers.append("referer", HeaderValue::from_str(base_url)?);
+        // 同一账号已有会话时复用, 使用其中最新的cookie和token
+        let account = account_key(&cookie);
+        let session = match SessionStore::global().get(&account) {
+            Some(session) => session,
+            None => {
+                let token = fetch_token(&cookie, &endpoints).await?;
+                SessionStore::global().join(&account, Session::new(cookie, token))
+            }
+        };
+        // 共享会话的token已过期时重新获取, 其他客户端同步使用
+        let current = session::snapshot(&session);
+        if !token_valid(&current.token, Local::now().timestamp_millis()) {
+            let token = fetch_token(&current.cookie, &endpoints).await?;
+            session::publish(&session, current.cookie, token);
+        }
 
-        headers.append(
-            "cookie",
-            HeaderValue::from_str(
-                format!(
-                    "{};_m_h5_tk_enc={};_m_h5_tk={};",
-                    &cookie, token.enc_token, token.token_with_time
-                )
-                .as_str(),
-            )?,
+        let Session {
+            cookie,
+            token,
+            version,
+        } = session::snapshot(&session);
+        let (client, race_client, transport) = build_transport(&cookie, &token, &endpoints)?;
+        let cache = ResponseCache::from_env();
+        let metrics = MetricsMiddleware::default();
+        let chain = default_chain(
+            transport,
+            &account,
+            &token.token,
+            token_client.clone(),
+            &endpoints,
+            metrics.clone(),
+            Recorder::from_env()?,
         );
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .cookie_store(true)
-            .http2_prior_knowledge()
-            .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36")
-            .use_rustls_tls()
-            .build()?;
         Ok(Self {
             client,
+            race_client,
             token,
             token_client,
+            cookie,
+            endpoints,
+            chain,
+            metrics,
+            cache,
+            queue: QueuePolicy::from_env(),
+            prepared: None,
+            coupon_id: None,
+            privilege_code: None,
+            session,
+            version,
         })
     }
 
-    // 请求API
-    pub async fn request(&self, url: &str, mut params: Value, data: Value) -> Result<DmRes> {
-        let s = format!(
-            "{}&{}&{}&{}",
-            self.token.token,
-            params["t"].as_str().unwrap(),
-            params["appKey"].as_str().unwrap(),
-            serde_json::to_string(&data)?,
+    // 使用新的cookie刷新token并更新共享会话, 同一账号的其他客户端随后同步
+    pub async fn refresh(&mut self, cookie: &str) -> Result<()> {
+        let cookie = merge_cookie(&self.cookie, cookie);
+        let token = fetch_token(&cookie, &self.endpoints).await?;
+        session::publish(&self.session, cookie, token);
+        self.cache.clear();
+        self.rebuild()
+    }
+
+    // 按共享会话重建客户端, 保留预构造的订单参数/优惠券/耗时统计
+    fn rebuild(&mut self) -> Result<()> {
+        let Session {
+            cookie,
+            token,
+            version,
+        } = session::snapshot(&self.session);
+        let (client, race_client, transport) = build_transport(&cookie, &token, &self.endpoints)?;
+        self.client = client;
+        self.race_client = race_client;
+        self.chain = default_chain(
+            transport,
+            &account_key(&cookie),
+            &token.token,
+            self.token_client.clone(),
+            &self.endpoints,
+            self.metrics.clone(),
+            Recorder::from_env()?,
         );
+        self.cookie = cookie;
+        self.token = token;
+        self.version = version;
+        Ok(())
+    }
+
+    // 请求API, 下单接口限制单次请求(含切换备用网关)的总时长
+    pub async fn request(&self, api: Api, params: Value, data: Value) -> Result<DmRes> {
+        self.request_prepared(api, params, data, None).await
+    }
+
+    // 使用预先编码的请求体请求API, prepared需与params/data一致
+    pub async fn request_prepared(
+        &self,
+        api: Api,
+        params: Value,
+        data: Value,
+        prepared: Option<Arc<PreparedBody>>,
+    ) -> Result<DmRes> {
+        match api.is_order() {
+            true => self.order_request(api, params, data, prepared).await,
+            false => self.send_request(api, params, data, prepared).await,
+        }
+    }
+
+    // 下单接口返回排队响应时, 按网关指定的间隔携带排队token重新请求, 直到返回结果;
+    // 排队等待不计入单次请求的总时长
+    async fn order_request(
+        &self,
+        api: Api,
+        params: Value,
+        mut data: Value,
+        mut prepared: Option<Arc<PreparedBody>>,
+    ) -> Result<DmRes> {
+        let deadline = self.endpoints.attempt_deadline;
+        let start = Instant::now();
+        loop {
+            let res = tokio::time::timeout(
+                deadline,
+                self.send_request(api, params.clone(), data.clone(), prepared.clone()),
+            )
+            .await
+            .map_err(|_| anyhow!("请求{}超时({:?})", api.name(), deadline))??;
+
+            let ticket = match QueueTicket::detect(&res, &self.queue) {
+                Some(ticket) => ticket,
+                None => return Ok(res),
+            };
+            let waited = start.elapsed();
+            if waited + ticket.interval > self.queue.max_wait {
+                return Err(PlatformError::Queued {
+                    waited_ms: waited.as_millis() as u64,
+                    status: ticket.describe(),
+                }
+                .into());
+            }
+            info!(
+                "请求{}{}, {:?}后继续查询...",
+                api.name(),
+                ticket.describe(),
+                ticket.interval
+            );
+            tokio::time::sleep(ticket.interval).await;
+            if let (Some(token), Some(data)) = (ticket.token, data.as_object_mut()) {
+                data.insert("queueToken".to_string(), token.into());
+                // data已变化, 不再使用预先编码的请求体
+                prepared = None;
+            }
+        }
+    }
+
+    async fn send_request(
+        &self,
+        api: Api,
+        mut params: Value,
+        data: Value,
+        prepared: Option<Arc<PreparedBody>>,
+    ) -> Result<DmRes> {
+        params["api"] = api.name().into();
+        params["v"] = self.endpoints.version(api).into();
+
+        // 只读接口在缓存时长内直接返回缓存的响应, 不占用限速和并发
+        let value = match self.cache.get(api, &data, Instant::now()) {
+            Some(value) => {
+                debug!("请求{}命中缓存", api.name());
+                value
+            }
+            None => {
+                let cached = self.cache.ttl(api).map(|_| data.clone());
+                let req = MtopRequest {
+                    api,
+                    url: self.endpoints.url(api),
+                    params,
+                    data,
+                    timeout: self.endpoints.timeout(api),
+                    prepared,
+                };
+                let value = self.chain.run(req).await?;
+                if let Some(data) = cached {
+                    self.cache.put(api, &data, &value, Instant::now());
+                }
+                value
+            }
+        };
+
+        let data: DmRes = serde_json::from_value(value)?;
+
+        if data.ret.iter().any(|r| r.starts_with(USER_VALIDATE_FLAG)) {
+            let url = data.data["url"].as_str().unwrap_or_default().to_string();
+            return Err(PlatformError::Challenge { url }.into());
+        }
+
+        match data.error() {
+            Some(MtopError::SessionExpired) => Err(PlatformError::SessionExpired.into()),
+            Some(MtopError::RiskControl) => Err(PlatformError::RiskControl {
+                message: data.ret.join(","),
+            }
+            .into()),
+            // token失效, 下次重建客户端时重新获取
+            Some(MtopError::TokenEmpty) => {
+                TokenCache::global().remove(&self.cookie);
+                Ok(data)
+            }
+            _ => Ok(data),
+        }
+    }
+
+    // 预热连接: 并发请求各网关, 建立连接后保留在连接池中, 配置竞速线路时同时预热代理线路
+    pub async fn prewarm_connections(&self) -> Result<()> {
+        let mut set = tokio::task::JoinSet::new();
+        let clients = [Some(&self.client), self.race_client.as_ref()];
+        for client in clients.into_iter().flatten() {
+            for gateway in self.endpoints.gateways() {
+                for _ in 0..self.endpoints.pool_size {
+                    let client = client.clone();
+                    let gateway = gateway.clone();
+                    set.spawn(async move { client.head(&gateway).send().await });
+                }
+            }
+        }
+
+        let mut connected = 0;
+        while let Some(res) = set.join_next().await {
+            match res? {
+                Ok(_) => connected += 1,
+                Err(e) => debug!("预热连接失败:{:?}", e),
+            }
+        }
+
+        match connected {
+            0 => Err(anyhow!("预热连接失败, 无法连接网关")),
+            _ => {
+                debug!("预热连接完成, 可用连接:{}", connected);
+                Ok(())
+            }
+        }
+    }
+
+    // 构造提交订单的params/data
+    pub fn submit_payload(&self, task: &Task, order_info: OrderInfo) -> Result<(Value, Value)> {
+        if task.viewers.is_empty() && task.real_names.is_empty() && order_info.requires_viewers() {
+            info!(
+                "{}, 未配置实名观演人, 默认选择前{}位观演人...",
+                task.nickname, task.ticket_num
+            );
+        }
+        let data = SubmitOrderForm::build(&order_info, task)?;
+        let params = SubmitOrderParams::build(order_info.global.secret_value)?;
+        Ok((params, data))
+    }
+
+    // 获取门票信息
+    pub async fn get_ticket_info(&self, ticket_id: &str) -> Result<TicketInfo> {
+        let value = self.get_ticket_value(ticket_id).await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    // 生成订单表单, 配置了优惠券/优先购码时一并使用
+    fn order_form(&self, data: Value) -> Result<Value> {
+        let data = match &self.coupon_id {
+            Some(coupon_id) => OrderForm::with_coupon(data, coupon_id)?,
+            None => data,
+        };
+        match &self.privilege_code {
+            Some(code) => OrderForm::with_privilege_code(data, code),
+            None => Ok(data),
+        }
+    }
+
+    // 获取门票信息原始数据
+    pub async fn get_ticket_value(&self, ticket_id: &str) -> Result<Value> {
+        let api = Api::TicketDetail;
+
+        let params = TicketInfoParams::build()?;
+
+        let data = TicketInfoForm::build(ticket_id)?;
+
+        let res = self.request(api, params, data).await?;
+
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => {
+                debug!("获取门票信息成功, {:?}", res);
+
+                let result = res.data["result"]
+                    .as_str()
+                    .ok_or(anyhow!("获取门票信息失败, 缺少result字段"))?;
+                Ok(serde_json::from_str(result)?)
+            }
+            false => {
+                error!("获取门票信息失败, 结果:{:?}", res.ret);
+                Err(anyhow!("获取门票信息失败..."))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TicketPlatform for DmClient {
+    type Order = OrderInfo;
+
+    fn name(&self) -> &'static str {
+        "damai"
+    }
+
+    // 获取用户信息
+    async fn user_info(&self) -> Result<UserInfoData> {
+        let api = Api::UserInfo;
+        let params = GetUserInfoParams::build()?;
+        let form = GetUserInfoForm::build()?;
+        let res = self.request(api, params, form).await?;
+        if res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            let user_info_data = serde_json::from_value(res.data)?;
+            Ok(user_info_data)
+        } else {
+            Err(anyhow!("{}", res.ret[0]))
+        }
+    }
+
+    // 获取今日必抢/即将开抢的门票
+    async fn search(&self) -> Result<Vec<Ticket>> {
+        let api = Api::TicketList;
+        let params = GetTicketListParams::build()?;
+        let form = GetTicketListForm::build()?;
+
+        let res = self.request(api, params, form).await?;
+
+        // 今日必抢
+        let today_ticket_list: TicketList = serde_json::from_value(res.data["modules"][0].clone())?;
+
+        // 即将开抢
+        let ticket_list: TicketList = serde_json::from_value(res.data["modules"][1].clone())?;
+
+        let mut tickets = today_ticket_list.items;
+        tickets.extend(ticket_list.items);
+
+        Ok(tickets)
+    }
+
+    // 获取想看列表
+    async fn wishlist(&self) -> Result<Vec<Ticket>> {
+        let api = Api::WishList;
+        let params = GetWishListParams::build()?;
+        let form = GetWishListForm::build(1)?;
+
+        let res = self.request(api, params, form).await?;
+
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(parse_wish_list(&res.data)),
+            false => Err(anyhow!("获取想看列表失败:{:?}", res.ret)),
+        }
+    }
+
+    // 获取收货地址
+    async fn addresses(&self) -> Result<Vec<Address>> {
+        let api = Api::AddressList;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = GetAddressListForm::build();
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(parse_address_list(&res.data)),
+            false => Err(anyhow!("获取收货地址失败:{:?}", res.ret)),
+        }
+    }
 
-        let sign = format!("{:?}", md5::compute(s));
+    // 添加收货地址
+    async fn add_address(&self, address: &NewAddress) -> Result<()> {
+        let api = Api::AddressAdd;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = AddAddressForm::build(address);
 
-        params["sign"] = sign.into();
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(()),
+            false => Err(anyhow!("添加收货地址失败:{:?}", res.ret)),
+        }
+    }
+
+    // 获取实名观演人
+    async fn viewers(&self) -> Result<Vec<Viewer>> {
+        let api = Api::ViewerList;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = GetViewerListForm::build();
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(parse_viewer_list(&res.data)),
+            false => Err(anyhow!("获取实名观演人失败:{:?}", res.ret)),
+        }
+    }
+
+    // 添加实名观演人
+    async fn add_viewer(&self, name: &str, cert_type: &str, cert_no: &str) -> Result<()> {
+        let api = Api::ViewerAdd;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = AddViewerForm::build(name, cert_type, cert_no);
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(()),
+            false => Err(anyhow!("添加观演人失败:{:?}", res.ret)),
+        }
+    }
+
+    // 删除实名观演人
+    async fn remove_viewer(&self, viewer_id: &str) -> Result<()> {
+        let api = Api::ViewerRemove;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = RemoveViewerForm::build(viewer_id);
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(()),
+            false => Err(anyhow!("删除观演人失败:{:?}", res.ret)),
+        }
+    }
 
-        if self.token_client.is_some() {
-            let token_client = self.token_client.clone().unwrap();
-            params["bx-umidtoken"] = token_client.get_bx_token().await?.into();
-            params["bx-ua"] = token_client.get_bx_ua().await?.into();
+    // 获取门票可用的优惠券
+    async fn coupons(&self, item_id: &str) -> Result<Vec<Coupon>> {
+        let api = Api::CouponList;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = GetCouponListForm::build(item_id);
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(parse_coupon_list(&res.data)),
+            false => Err(anyhow!("获取优惠券失败:{:?}", res.ret)),
+        }
+    }
+
+    // 生成订单时使用优惠券
+    fn use_coupon(&mut self, coupon_id: &str) -> Result<()> {
+        self.coupon_id = Some(coupon_id.to_string());
+        Ok(())
+    }
+
+    // 校验会员/粉丝优先购码
+    async fn check_privilege(&self, item_id: &str, code: &str) -> Result<PrivilegeCheck> {
+        let api = Api::Privilege;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = PrivilegeCheckForm::build(item_id, code);
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(parse_privilege_check(&res.data)),
+            false => Err(anyhow!("校验优先购码失败:{:?}", res.ret)),
         }
+    }
+
+    // 生成订单时携带优先购码
+    fn use_privilege_code(&mut self, code: &str) -> Result<()> {
+        self.privilege_code = Some(code.to_string());
+        Ok(())
+    }
+
+    // 获取门票信息
+    async fn detail(&self, ticket_id: &str) -> Result<ItemDetail> {
+        let value = self.get_ticket_value(ticket_id).await?;
+        let rules = PurchaseRules::parse(&value["detailViewComponentMap"]);
+        let ticket_info: TicketInfo = serde_json::from_value(value)?;
 
-        let form = json!({
-            "data": serde_json::to_string(&data)?,
+        let item = ticket_info.detail_view_component_map.item;
+        let item_base = item.static_data.item_base;
+
+        Ok(ItemDetail {
+            item_id: item_base.item_id,
+            item_name: item_base.item_name,
+            venue_name: item_base.venue_name,
+            city_name: item_base.city_name,
+            show_time: item_base.show_time,
+            price_range: item_base.price_range,
+            sell_start_timestamp: item.item.sell_start_timestamp.parse::<i64>()?,
+            sell_start_time_str: item.item.sell_start_time_str,
+            supported: !item.item.buy_btn_text.contains("不支持"),
+            buy_btn_text: item.item.buy_btn_text,
+            purchase_limit: rules.purchase_limit,
+            real_name_required: rules.real_name_required,
+            express_delivery: rules.express_delivery,
+            priority_purchase_time: rules.priority_purchase_minutes(),
+        })
+    }
+
+    // 获取场次列表
+    async fn performs(&self, ticket_id: &str) -> Result<Vec<PerformItem>> {
+        let ticket_info = self.get_ticket_info(ticket_id).await?;
+
+        let perform_list = ticket_info
+            .detail_view_component_map
+            .item
+            .item
+            .perform_bases;
+
+        let mut performs: Vec<PerformItem> = Vec::new();
+
+        for perform in perform_list.iter() {
+            for item in perform.performs.iter() {
+                performs.push(PerformItem {
+                    perfrom_name: item.perform_name.clone(),
+                    perform_id: item.perform_id.clone(),
+                })
+            }
+        }
+
+        Ok(performs)
+    }
+
+    // 获取票档列表
+    async fn skus(&self, ticket_id: &str, perform_id: &str) -> Result<Vec<SkuItem>> {
+        let api = Api::PerformSkus;
+
+        let params = PerformParams::build()?;
+
+        let data = PerformForm::build(ticket_id, perform_id)?;
+
+        let res = self.request(api, params, data).await?;
+
+        let result = res.data["result"]
+            .as_str()
+            .ok_or_else(|| anyhow!("获取票档列表失败, 返回数据缺少result:{}", res.data))?;
+        let perform_info: PerformInfo = serde_json::from_str(result)?;
+
+        let mut skus: Vec<SkuItem> = vec![];
+        for item in perform_info.perform.sku_list.iter() {
+            skus.push(SkuItem {
+                sku_id: item.sku_id.clone(),
+                sku_name: item.price_name.clone(),
+                price: item.price.clone(),
+                status: item.status(),
+                bundle: item.bundle(),
+            })
+        }
+
+        Ok(skus)
+    }
+
+    // 生成订单
+    async fn build_order(&self, item_id: &str, sku_id: &str, buy_num: usize) -> Result<OrderInfo> {
+        let start = Instant::now();
+
+        let api = Api::BuildOrder;
+
+        // 优先使用开售前预先构造的参数
+        let (params, data, body) = match &self.prepared {
+            Some(prepared)
+                if prepared.item_id == item_id
+                    && prepared.sku_id == sku_id
+                    && prepared.buy_num == buy_num =>
+            {
+                let mut params = prepared.params.clone();
+                CommonParams::refresh(&mut params);
+                (params, prepared.data.clone(), Some(prepared.body.clone()))
+            }
+            _ => (
+                OrderParams::build()?,
+                self.order_form(OrderForm::build(item_id, sku_id, buy_num)?)?,
+                None,
+            ),
+        };
+
+        let res = self.request_prepared(api, params, data, body).await?;
+
+        debug!("生成订单结果:{:?}, 花费时间:{:?}", res, start.elapsed());
+
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => {
+                let order_info: OrderInfo = serde_json::from_value(res.data)?;
+                Ok(order_info)
+            }
+            false => Err(ret_error(&res)),
+        }
+    }
+
+    // 获取场次座位图
+    async fn seats(&self, item_id: &str, perform_id: &str) -> Result<Vec<Seat>> {
+        let api = Api::SeatMap;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = SeatMapForm::build(item_id, perform_id)?;
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => parse_seat_map(&res.data),
+            false => Err(anyhow!("获取座位图失败:{:?}", res.ret)),
+        }
+    }
+
+    // 选座演出生成订单
+    async fn build_seat_order(
+        &self,
+        item_id: &str,
+        sku_id: &str,
+        seats: &[Seat],
+    ) -> Result<OrderInfo> {
+        let start = Instant::now();
+
+        let api = Api::BuildOrder;
+        let params = OrderParams::build()?;
+        let data = self.order_form(OrderForm::build_with_seats(item_id, sku_id, seats)?)?;
+
+        let res = self.request(api, params, data).await?;
+
+        debug!("选座生成订单结果:{:?}, 花费时间:{:?}", res, start.elapsed());
+
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(serde_json::from_value(res.data)?),
+            false => Err(ret_error(&res)),
+        }
+    }
+
+    // 提交订单
+    async fn submit_order(&self, task: &Task, order_info: OrderInfo) -> Result<SubmitResult> {
+        let start = Instant::now();
+
+        let api = Api::CreateOrder;
+
+        let (params, data) = self.submit_payload(task, order_info)?;
+
+        let res = self.request(api, params, data).await?;
+        debug!("提交订单结果:{:?}, 花费时间:{:?}", res, start.elapsed());
+
+        Ok(SubmitResult {
+            success: res.ret.contains(&SUCCESS_FLAG.to_string()),
+            message: res.ret.first().cloned().unwrap_or_default(),
+            data: res.data,
+        })
+    }
+
+    // 预览提交订单的数据
+    async fn preview_submit(&self, task: &Task, order_info: OrderInfo) -> Result<Value> {
+        let (params, data) = self.submit_payload(task, order_info)?;
+        Ok(json!({"params": params, "data": data}))
+    }
+
+    // 生成订单返回的submitref
+    fn submit_token(&self, order_info: &OrderInfo) -> Option<String> {
+        Some(order_info.global.secret_value.clone()).filter(|token| !token.is_empty())
+    }
+
+    // 生成/提交订单的接口地址(主网关)
+    fn endpoint(&self, stage: &str) -> String {
+        match stage {
+            "build" => self.endpoints.url(Api::BuildOrder),
+            _ => self.endpoints.url(Api::CreateOrder),
+        }
+    }
+
+    // 按关键词搜索演出
+    async fn search_projects(&self, keyword: &str) -> Result<Vec<CityStop>> {
+        let api = Api::Search;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = SearchProjectForm::build(keyword, 1)?;
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(parse_city_stops(&res.data)),
+            false => Err(anyhow!("搜索演出失败:{:?}", res.ret)),
+        }
+    }
+
+    // 获取最近的订单
+    async fn orders(&self) -> Result<Vec<OrderSummary>> {
+        let api = Api::OrderList;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = OrderListForm::build(1)?;
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(parse_order_list(&res.data)),
+            false => Err(anyhow!("获取订单列表失败:{:?}", res.ret)),
+        }
+    }
+
+    // 取消订单
+    async fn cancel_order(&self, order_id: &str) -> Result<()> {
+        let api = Api::CancelOrder;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = CancelOrderForm::build(order_id)?;
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(()),
+            false => Err(anyhow!("取消订单失败:{:?}", res.ret)),
+        }
+    }
+
+    // 缺货登记
+    async fn register_waitlist(&self, item_id: &str, perform_id: &str, sku_id: &str) -> Result<()> {
+        let api = Api::Waitlist;
+        let params = serde_json::to_value(CommonParams::build())?;
+        let data = WaitlistForm::build(item_id, perform_id, sku_id)?;
+
+        let res = self.request(api, params, data).await?;
+        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            true => Ok(()),
+            false => Err(anyhow!("{:?}", res.ret)),
+        }
+    }
+
+    // 打开浏览器由用户完成滑块验证, 完成后刷新cookie和token
+    #[cfg(feature = "browser-login")]
+    async fn resolve_challenge(&mut self, url: &str) -> Result<()> {
+        if crate::config::skip_webdriver() {
+            return Err(anyhow!(
+                "需要完成滑块验证, 但已配置SKIP_WEBDRIVER=true:{}",
+                url
+            ));
+        }
+        let webdriver_url = env::var("WEBDRIVER_URL")?;
+        let cookie = CaptchaClient::new(webdriver_url)
+            .solve(url, &self.cookie)
+            .await?;
+        self.refresh(&cookie).await
+    }
+
+    // 未启用browser-login时无法完成滑块验证
+    #[cfg(not(feature = "browser-login"))]
+    async fn resolve_challenge(&mut self, url: &str) -> Result<()> {
+        Err(anyhow!(
+            "需要完成滑块验证, 但未启用browser-login功能:{}",
+            url
+        ))
+    }
+
+    // 预先构造生成订单参数, 并编码好请求体和除时间戳/签名外的查询参数
+    async fn prepare(&mut self, item_id: &str, sku_id: &str, buy_num: usize) -> Result<()> {
+        let api = Api::BuildOrder;
+        let mut params = OrderParams::build()?;
+        params["api"] = api.name().into();
+        params["v"] = self.endpoints.version(api).into();
+        let data = self.order_form(OrderForm::build(item_id, sku_id, buy_num)?)?;
+        let body = Arc::new(PreparedBody::build(&params, &data)?);
+        debug!("已预先编码生成订单请求, 请求体{}字节", body.form.len());
+        self.prepared = Some(PreparedOrder {
+            item_id: item_id.to_string(),
+            sku_id: sku_id.to_string(),
+            buy_num,
+            params,
+            data,
+            body,
         });
+        Ok(())
+    }
+
+    // 预先解析网关域名, 固定解析结果后重建客户端
+    async fn pin_dns(&mut self) -> Result<()> {
+        let race = env::var("MTOP_IP_RACE")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        self.endpoints.resolve_hosts(race).await?;
+        self.rebuild()
+    }
 
-        let response = self
+    // 本地时钟与服务器时钟的偏差, 以请求往返的中间时刻估算
+    async fn clock_offset(&self) -> Result<i64> {
+        let send = Local::now().timestamp_millis();
+        let value = self
             .client
-            .post(url)
-            .query(&params)
-            .form(&form)
+            .get(self.endpoints.url(Api::Timestamp))
             .send()
+            .await?
+            .json::<Value>()
             .await?;
+        let recv = Local::now().timestamp_millis();
 
-        let data = response.json::<DmRes>().await?;
+        let server = value["data"]["t"]
+            .as_str()
+            .and_then(|t| t.parse::<i64>().ok())
+            .ok_or(anyhow!("获取服务器时间失败:{:?}", value))?;
+        Ok(server - (send + recv) / 2)
+    }
+
+    // 预热连接
+    async fn prewarm(&self) -> Result<()> {
+        self.prewarm_connections().await
+    }
 
-        Ok(data)
+    // 重新登录后更新cookie
+    async fn update_cookie(&mut self, cookie: &str) -> Result<()> {
+        self.refresh(cookie).await
+    }
+
+    // 同一账号的其他任务重新登录/刷新token后, 使用新的会话重建客户端
+    async fn sync_session(&mut self) -> Result<bool> {
+        if session::version(&self.session) == self.version {
+            return Ok(false);
+        }
+        info!("账号会话已在其他任务中更新, 使用新的cookie和token");
+        self.rebuild()?;
+        Ok(true)
     }
 }
@@ -0,0 +1,87 @@
+use std::env;
+
+use anyhow::{anyhow, Result};
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+// 默认SMTP端口(SSL)
+const DEFAULT_SMTP_PORT: u16 = 465;
+
+// SMTP邮件通知
+#[derive(Debug, Clone)]
+pub struct EmailClient {
+    host: String,
+    port: u16,
+    username: String,
+    password: String,
+    from: String,
+    to: Vec<String>,
+}
+
+impl EmailClient {
+    // 从环境变量读取配置, 未配置SMTP_HOST时不启用:
+    // SMTP_HOST/SMTP_PORT: SMTP服务器, 端口默认465
+    // SMTP_USERNAME/SMTP_PASSWORD: 登录账号/授权码
+    // SMTP_FROM: 发件人, 默认为SMTP_USERNAME
+    // SMTP_TO: 收件人, 多个用逗号分隔
+    pub fn from_env() -> Result<Option<Self>> {
+        let host = match env::var("SMTP_HOST") {
+            Ok(host) if !host.is_empty() => host,
+            _ => return Ok(None),
+        };
+
+        let port = match env::var("SMTP_PORT") {
+            Ok(port) => port
+                .parse::<u16>()
+                .map_err(|_| anyhow!("SMTP_PORT配置错误:{}", port))?,
+            Err(_) => DEFAULT_SMTP_PORT,
+        };
+
+        let username = env::var("SMTP_USERNAME").unwrap_or_default();
+        let password = env::var("SMTP_PASSWORD").unwrap_or_default();
+        let from = env::var("SMTP_FROM").unwrap_or(username.clone());
+        let to = env::var("SMTP_TO")
+            .unwrap_or_default()
+            .split(',')
+            .map(|to| to.trim().to_string())
+            .filter(|to| !to.is_empty())
+            .collect::<Vec<String>>();
+        if to.is_empty() {
+            return Err(anyhow!("未配置收件人SMTP_TO"));
+        }
+
+        Ok(Some(Self {
+            host,
+            port,
+            username,
+            password,
+            from,
+            to,
+        }))
+    }
+
+    // 发送纯文本邮件
+    pub async fn send(&self, subject: &str, body: &str) -> Result<()> {
+        let mut builder = Message::builder()
+            .from(self.from.parse::<Mailbox>()?)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in self.to.iter() {
+            builder = builder.to(to.parse::<Mailbox>()?);
+        }
+        let message = builder.body(body.to_string())?;
+
+        let mailer = AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)?
+            .port(self.port)
+            .credentials(Credentials::new(
+                self.username.clone(),
+                self.password.clone(),
+            ))
+            .build();
+        mailer.send(message).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    collections::HashMap,
+    env,
+    net::SocketAddr,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use tokio::net::{lookup_host, TcpStream};
+use tracing::{debug, info, warn};
+
+// 默认网关
+const DEFAULT_GATEWAY: &str = "https://mtop.damai.cn";
+
+// 默认每个网关保持的连接数
+const DEFAULT_POOL_SIZE: usize = 4;
+
+// 空闲连接保持时长, 秒
+const POOL_IDLE_TIMEOUT: u64 = 90;
+
+// tcp keepalive间隔, 秒
+const TCP_KEEPALIVE: u64 = 30;
+
+// http2连接的ping间隔, 秒, 保持开售前建立的连接
+const HTTP2_KEEPALIVE: u64 = 20;
+
+// 测试ip连接耗时的超时时长, 毫秒
+const IP_RACE_TIMEOUT: u64 = 1000;
+
+// 默认建立连接超时, 毫秒
+const DEFAULT_CONNECT_TIMEOUT: u64 = 1000;
+
+// 默认查询接口请求超时, 毫秒
+const DEFAULT_QUERY_TIMEOUT: u64 = 5000;
+
+// 默认下单接口请求超时, 毫秒
+const DEFAULT_ORDER_TIMEOUT: u64 = 2000;
+
+// 默认单次下单(含切换备用网关)的总时长, 毫秒
+const DEFAULT_ATTEMPT_DEADLINE: u64 = 3000;
+
+// 大麦mtop接口
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Api {
+    UserInfo,     // 用户信息
+    TicketList,   // 今日必抢/即将开抢
+    TicketDetail, // 门票详情
+    PerformSkus,  // 场次票档
+    BuildOrder,   // 生成订单
+    CreateOrder,  // 提交订单
+    Timestamp,    // 服务器时间
+    WishList,     // 想看列表
+    Waitlist,     // 缺货登记
+    SeatMap,      // 座位图
+    AddressList,  // 收货地址
+    CouponList,   // 可用优惠券
+    CancelOrder,  // 取消订单
+    OrderList,    // 订单列表
+    Search,       // 搜索演出
+    Privilege,    // 校验优先购码
+    ViewerList,   // 实名观演人
+    ViewerAdd,    // 添加观演人
+    ViewerRemove, // 删除观演人
+    AddressAdd,   // 添加收货地址
+}
+
+impl Api {
+    const ALL: [Api; 20] = [
+        Api::UserInfo,
+        Api::TicketList,
+        Api::TicketDetail,
+        Api::PerformSkus,
+        Api::BuildOrder,
+        Api::CreateOrder,
+        Api::Timestamp,
+        Api::WishList,
+        Api::Waitlist,
+        Api::SeatMap,
+        Api::AddressList,
+        Api::CouponList,
+        Api::CancelOrder,
+        Api::OrderList,
+        Api::Search,
+        Api::Privilege,
+        Api::ViewerList,
+        Api::ViewerAdd,
+        Api::ViewerRemove,
+        Api::AddressAdd,
+    ];
+
+    // 根据接口名称查找
+    pub fn from_name(name: &str) -> Option<Api> {
+        Self::ALL.into_iter().find(|api| api.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Api::UserInfo => "mtop.damai.wireless.user.session.transform",
+            Api::TicketList => "mtop.damai.wireless.search.broadcast.list",
+            Api::TicketDetail => "mtop.alibaba.damai.detail.getdetail",
+            Api::PerformSkus => "mtop.alibaba.detail.subpage.getdetail",
+            Api::BuildOrder => "mtop.trade.order.build.h5",
+            Api::CreateOrder => "mtop.trade.order.create.h5",
+            Api::Timestamp => "mtop.common.getTimestamp",
+            Api::WishList => "mtop.damai.wireless.user.wantsee.list",
+            Api::Waitlist => "mtop.damai.wireless.item.stockout.register",
+            Api::SeatMap => "mtop.damai.wireless.perform.seat.query",
+            Api::AddressList => "mtop.damai.wireless.user.address.list",
+            Api::CouponList => "mtop.damai.wireless.coupon.item.usable.list",
+            Api::CancelOrder => "mtop.damai.wireless.order.cancel",
+            Api::OrderList => "mtop.damai.wireless.order.list",
+            Api::Search => "mtop.damai.wireless.search.search",
+            Api::Privilege => "mtop.damai.wireless.item.privilege.verify",
+            Api::ViewerList => "mtop.damai.wireless.user.customerlist.get",
+            Api::ViewerAdd => "mtop.damai.wireless.user.customer.add",
+            Api::ViewerRemove => "mtop.damai.wireless.user.customer.delete",
+            Api::AddressAdd => "mtop.damai.wireless.user.address.add",
+        }
+    }
+
+    pub fn default_version(&self) -> &'static str {
+        match self {
+            Api::UserInfo => "1.0",
+            Api::TicketList => "1.0",
+            Api::TicketDetail => "1.2",
+            Api::PerformSkus => "2.0",
+            Api::BuildOrder => "4.0",
+            Api::CreateOrder => "4.0",
+            Api::Timestamp => "*",
+            Api::WishList => "1.0",
+            Api::Waitlist => "1.0",
+            Api::SeatMap => "1.0",
+            Api::AddressList => "1.0",
+            Api::CouponList => "1.0",
+            Api::CancelOrder => "1.0",
+            Api::OrderList => "1.0",
+            Api::Search => "1.0",
+            Api::Privilege => "1.0",
+            Api::ViewerList => "2.0",
+            Api::ViewerAdd => "1.0",
+            Api::ViewerRemove => "1.0",
+            Api::AddressAdd => "1.0",
+        }
+    }
+
+    // 下单接口, 网关异常时切换备用网关重试
+    pub fn is_order(&self) -> bool {
+        matches!(self, Api::BuildOrder | Api::CreateOrder)
+    }
+}
+
+// https网关的http2模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Http2Mode {
+    #[default]
+    Prior, // 直接使用http2, 开售时的请求复用同一连接
+    Alpn, // tls握手时协商, 网关不支持时使用http1.1
+    Off,  // 只使用http1.1连接池
+}
+
+impl FromStr for Http2Mode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "prior" | "true" => Ok(Self::Prior),
+            "alpn" | "auto" => Ok(Self::Alpn),
+            "off" | "false" | "http1" => Ok(Self::Off),
+            other => Err(anyhow!("不支持的http2模式:{}, 可选: prior/alpn/off", other)),
+        }
+    }
+}
+
+// 接口地址/版本配置
+#[derive(Debug, Clone)]
+pub struct EndpointRegistry {
+    pub gateway: String,
+    pub failover_gateway: Option<String>,
+    pub versions: HashMap<String, String>,
+    pub pool_size: usize,                      // 每个网关保持的连接数
+    pub resolved: HashMap<String, SocketAddr>, // 固定的域名解析结果
+    pub connect_timeout: Duration,             // 建立连接超时
+    pub query_timeout: Duration,               // 查询接口请求超时
+    pub order_timeout: Duration,               // 下单接口请求超时
+    pub attempt_deadline: Duration,            // 单次下单的总时长
+    pub compression: bool,                     // 是否协商gzip/deflate/brotli压缩响应
+    pub http2: Http2Mode,                      // https网关的http2模式
+    pub race_proxy: Option<String>,            // 与直连竞速的代理线路
+}
+
+impl Default for EndpointRegistry {
+    fn default() -> Self {
+        Self {
+            gateway: DEFAULT_GATEWAY.to_string(),
+            failover_gateway: None,
+            versions: HashMap::new(),
+            pool_size: DEFAULT_POOL_SIZE,
+            resolved: HashMap::new(),
+            connect_timeout: Duration::from_millis(DEFAULT_CONNECT_TIMEOUT),
+            query_timeout: Duration::from_millis(DEFAULT_QUERY_TIMEOUT),
+            order_timeout: Duration::from_millis(DEFAULT_ORDER_TIMEOUT),
+            attempt_deadline: Duration::from_millis(DEFAULT_ATTEMPT_DEADLINE),
+            compression: true,
+            http2: Http2Mode::default(),
+            race_proxy: None,
+        }
+    }
+}
+
+impl EndpointRegistry {
+    // 从环境变量读取配置:
+    // MTOP_GATEWAY: 网关地址, 默认https://mtop.damai.cn
+    // MTOP_FAILOVER_GATEWAY: 备用网关, 如https://acs.m.taobao.com
+    // MTOP_API_VERSIONS: 接口版本覆盖, 格式: api=version,api=version
+    // MTOP_POOL_SIZE: 开售前预热的连接数
+    // MTOP_RESOLVE: 手动指定域名解析, 格式: host=ip,host=ip
+    // MTOP_CONNECT_TIMEOUT/MTOP_QUERY_TIMEOUT/MTOP_ORDER_TIMEOUT: 建立连接/查询接口/下单接口超时, 毫秒
+    // MTOP_ATTEMPT_DEADLINE: 单次下单(含切换备用网关)的总时长, 毫秒
+    // MTOP_COMPRESSION: 是否请求压缩的响应, 默认开启
+    // MTOP_HTTP2: https网关的http2模式, prior/alpn/off, 默认prior
+    // MTOP_RACE_PROXY: 代理地址, 配置后首次生成订单同时通过直连和代理发送, 之后使用先返回的线路
+    pub fn from_env() -> Self {
+        let mut registry = Self::default();
+
+        if let Ok(gateway) = env::var("MTOP_GATEWAY") {
+            if !gateway.is_empty() {
+                registry.gateway = gateway;
+            }
+        }
+
+        if let Ok(gateway) = env::var("MTOP_FAILOVER_GATEWAY") {
+            if !gateway.is_empty() {
+                registry.failover_gateway = Some(gateway);
+            }
+        }
+
+        if let Ok(versions) = env::var("MTOP_API_VERSIONS") {
+            registry.versions = Self::parse_versions(&versions);
+        }
+
+        if let Ok(compression) = env::var("MTOP_COMPRESSION") {
+            registry.compression = !compression.eq_ignore_ascii_case("false");
+        }
+
+        if let Some(http2) = env::var("MTOP_HTTP2").ok().and_then(|v| v.parse().ok()) {
+            registry.http2 = http2;
+        }
+
+        if let Ok(proxy) = env::var("MTOP_RACE_PROXY") {
+            if !proxy.is_empty() {
+                registry.race_proxy = Some(proxy);
+            }
+        }
+
+        if let Some(pool_size) = env::var("MTOP_POOL_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+        {
+            registry.pool_size = pool_size.max(1);
+        }
+
+        if let Ok(resolve) = env::var("MTOP_RESOLVE") {
+            registry.resolved = Self::parse_resolve(&resolve);
+        }
+
+        let millis = |key: &str| {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .map(Duration::from_millis)
+        };
+        if let Some(timeout) = millis("MTOP_CONNECT_TIMEOUT") {
+            registry.connect_timeout = timeout;
+        }
+        if let Some(timeout) = millis("MTOP_QUERY_TIMEOUT") {
+            registry.query_timeout = timeout;
+        }
+        if let Some(timeout) = millis("MTOP_ORDER_TIMEOUT") {
+            registry.order_timeout = timeout;
+        }
+        if let Some(deadline) = millis("MTOP_ATTEMPT_DEADLINE") {
+            registry.attempt_deadline = deadline;
+        }
+
+        registry
+    }
+
+    fn parse_versions(value: &str) -> HashMap<String, String> {
+        value
+            .split(',')
+            .filter_map(|item| item.split_once('='))
+            .map(|(api, version)| (api.trim().to_string(), version.trim().to_string()))
+            .collect()
+    }
+
+    // 解析手动指定的域名解析, 未指定端口时默认443
+    fn parse_resolve(value: &str) -> HashMap<String, SocketAddr> {
+        value
+            .split(',')
+            .filter_map(|item| item.split_once('='))
+            .filter_map(|(host, ip)| {
+                let ip = ip.trim();
+                let addr = ip
+                    .parse::<SocketAddr>()
+                    .or_else(|_| format!("{}:443", ip).parse::<SocketAddr>())
+                    .or_else(|_| format!("[{}]:443", ip).parse::<SocketAddr>());
+                match addr {
+                    Ok(addr) => Some((host.trim().to_string(), addr)),
+                    Err(_) => {
+                        warn!("MTOP_RESOLVE配置错误:{}={}", host, ip);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    // 预先解析各网关域名并固定解析结果, 已手动指定的域名不再解析
+    // race为true时测试所有ip的连接耗时, 选择最快的ip
+    pub async fn resolve_hosts(&mut self, race: bool) -> Result<()> {
+        for gateway in self.gateways() {
+            let url = Url::parse(&gateway)?;
+            let host = url.host_str().ok_or(anyhow!("网关地址错误:{}", gateway))?;
+            let port = url.port_or_known_default().unwrap_or(443);
+            if self.resolved.contains_key(host) {
+                continue;
+            }
+
+            let addrs = lookup_host((host, port))
+                .await?
+                .collect::<Vec<SocketAddr>>();
+            let addr = match race && addrs.len() > 1 {
+                true => Self::fastest(&addrs).await,
+                false => addrs.first().copied(),
+            };
+
+            match addr {
+                Some(addr) => {
+                    info!("域名:{}, 固定解析至:{}", host, addr);
+                    self.resolved.insert(host.to_string(), addr);
+                }
+                None => warn!("域名:{}解析失败", host),
+            }
+        }
+        Ok(())
+    }
+
+    // 并发测试tcp连接耗时, 返回最快的ip
+    async fn fastest(addrs: &[SocketAddr]) -> Option<SocketAddr> {
+        let mut set = tokio::task::JoinSet::new();
+        for addr in addrs.iter().copied() {
+            set.spawn(async move {
+                let start = Instant::now();
+                let timeout = Duration::from_millis(IP_RACE_TIMEOUT);
+                match tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+                    Ok(Ok(_)) => Some((addr, start.elapsed())),
+                    _ => None,
+                }
+            });
+        }
+
+        let mut fastest: Option<(SocketAddr, Duration)> = None;
+        while let Some(res) = set.join_next().await {
+            if let Ok(Some((addr, elapsed))) = res {
+                debug!("ip:{}, 连接耗时:{:?}", addr, elapsed);
+                match fastest {
+                    Some((_, best)) if best <= elapsed => {}
+                    _ => fastest = Some((addr, elapsed)),
+                }
+            }
+        }
+        fastest.map(|(addr, _)| addr)
+    }
+
+    // 接口版本
+    pub fn version(&self, api: Api) -> String {
+        self.versions
+            .get(api.name())
+            .cloned()
+            .unwrap_or(api.default_version().to_string())
+    }
+
+    fn build_url(&self, gateway: &str, api: Api) -> String {
+        format!(
+            "{}/h5/{}/{}/",
+            gateway.trim_end_matches('/'),
+            api.name(),
+            self.version(api)
+        )
+    }
+
+    // 接口请求超时, 下单接口与查询接口分别配置
+    pub fn timeout(&self, api: Api) -> Duration {
+        match api.is_order() {
+            true => self.order_timeout,
+            false => self.query_timeout,
+        }
+    }
+
+    // 连接池/超时/压缩等公共配置
+    fn base_builder(&self) -> reqwest::ClientBuilder {
+        let builder = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .pool_max_idle_per_host(self.pool_size)
+            .pool_idle_timeout(Duration::from_secs(POOL_IDLE_TIMEOUT))
+            .tcp_keepalive(Duration::from_secs(TCP_KEEPALIVE))
+            .gzip(self.compression)
+            .brotli(self.compression)
+            .deflate(self.compression);
+        // 竞速时主线路为直连, 不使用系统代理
+        let builder = match self.race_proxy.is_some() {
+            true => builder.no_proxy(),
+            false => builder,
+        };
+        self.resolved.iter().fold(builder, |builder, (host, addr)| {
+            builder.resolve(host, *addr)
+        })
+    }
+
+    // 请求客户端, https网关默认直接使用http2, 所有请求复用同一连接, 空闲时定时ping保持连接
+    pub fn client_builder(&self) -> reqwest::ClientBuilder {
+        let builder = self.base_builder();
+        let http2 = |builder: reqwest::ClientBuilder| {
+            builder
+                .http2_keep_alive_interval(Duration::from_secs(HTTP2_KEEPALIVE))
+                .http2_keep_alive_while_idle(true)
+                .http2_adaptive_window(true)
+        };
+        match (self.gateway.starts_with("https://"), self.http2) {
+            (_, Http2Mode::Off) => builder.http1_only(),
+            (true, Http2Mode::Prior) => http2(builder).http2_prior_knowledge(),
+            (true, Http2Mode::Alpn) => http2(builder),
+            (false, _) => builder,
+        }
+    }
+
+    // http1.1客户端, http2连接异常时回退使用, 不需要回退时为None
+    pub fn http1_builder(&self) -> Option<reqwest::ClientBuilder> {
+        match self.gateway.starts_with("https://") && self.http2 != Http2Mode::Off {
+            true => Some(self.base_builder().http1_only()),
+            false => None,
+        }
+    }
+
+    // 通过代理的竞速线路客户端, 未配置MTOP_RACE_PROXY时为None
+    pub fn race_builder(&self) -> Result<Option<reqwest::ClientBuilder>> {
+        match &self.race_proxy {
+            Some(proxy) => Ok(Some(
+                self.client_builder().proxy(reqwest::Proxy::all(proxy)?),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    // 接口地址
+    pub fn url(&self, api: Api) -> String {
+        self.build_url(&self.gateway, api)
+    }
+
+    // 需要预热连接的网关
+    pub fn gateways(&self) -> Vec<String> {
+        let mut gateways = vec![self.gateway.clone()];
+        if let Some(gateway) = &self.failover_gateway {
+            gateways.push(gateway.clone());
+        }
+        gateways
+    }
+
+    // 备用网关接口地址
+    pub fn failover_url(&self, api: Api) -> Option<String> {
+        self.failover_gateway
+            .as_ref()
+            .map(|gateway| self.build_url(gateway, api))
+    }
+}
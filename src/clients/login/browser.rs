@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use super::LoginClient;
+use crate::clients::webdriver;
+use anyhow::Result;
+use thirtyfour::{cookie::SameSite, prelude::ElementQueryable, By, Cookie, WebDriver};
+use tracing::info;
+
+impl LoginClient {
+    // 使用cookie2在浏览器中打开大麦, 获取完整的cookie
+    pub async fn browser_cookie(&self, webdriver_url: &str, cookie2: String) -> Result<String> {
+        info!("正在获取cookie...");
+        let driver = chrome_driver(webdriver_url).await?;
+        let mut c = Cookie::new("cookie2", cookie2);
+        c.set_domain("damai.cn");
+        c.set_path("/");
+        c.set_same_site(Some(SameSite::Lax));
+
+        driver.goto("https://m.damai.cn/").await?;
+        let _ = driver.add_cookie(c).await;
+
+        let h5_url = "https://m.damai.cn/damai/mine/my/index.html?spm=a2o71.home.top.duserinfo";
+        driver.goto(h5_url).await?;
+
+        let css = r#"body > div.my > div.my-hd > div.user-name > div.nickname"#;
+        let _ = driver
+            .query(By::Css(css))
+            .wait(Duration::from_secs(10), Duration::from_millis(100))
+            .first()
+            .await;
+        let cookies = driver.get_all_cookies().await?;
+
+        let mut cookie_string = String::new();
+
+        for item in cookies {
+            if item.name().starts_with("_m_h5_tk") {
+                continue;
+            }
+            cookie_string.push_str(&format!("{}={};", item.name(), item.value()));
+        }
+
+        let _ = driver.quit().await;
+
+        Ok(cookie_string)
+    }
+}
+
+// 创建chrome会话
+pub async fn chrome_driver(webdriver_url: &str) -> Result<WebDriver> {
+    webdriver::new_driver(webdriver_url, webdriver::chrome_caps(true)?).await
+}
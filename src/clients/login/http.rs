@@ -0,0 +1,359 @@
+use std::env;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use super::{LoginClient, LOGIN_PAGE_URL};
+use crate::clients::{notify::NotifyClient, qrcode_page::QrcodePage};
+use crate::config;
+use crate::errors::ClientError;
+use crate::models::credential::{
+    is_phone, parse_rsa_key, CredentialLoginData, PasswordLoginForm, SmsLoginForm, SmsSendForm,
+};
+use crate::models::qrcode::{QrCodeLoginGetResParams, QrcodeData, QrcodeStatus};
+use crate::qrcode_render::{self, QrOptions};
+use anyhow::{anyhow, Result};
+use fast_qr::{QRBuilder, QRCode};
+use reqwest::cookie::CookieStore;
+use rsa::{BigUint, Pkcs1v15Encrypt, RsaPublicKey};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{debug, error, info, warn};
+
+// 扫码登录默认总时长, 秒
+const DEFAULT_LOGIN_TIMEOUT: u64 = 600;
+
+// 单个二维码的有效时长, 超过后主动刷新
+const QRCODE_TTL: Duration = Duration::from_secs(180);
+
+// 账号密码/短信登录成功后的跳转地址
+const LOGIN_RETURN_URL: &str = "https://passport.damai.cn/dologin.htm?redirectUrl=https%253A%252F%252Fwww.damai.cn%252F&platform=106002";
+
+impl LoginClient {
+    // 获取二维码, 返回识别后的二维码及原始图片(png)
+    pub async fn get_qrcode(&self, qrcode_content: String) -> Result<(QRCode, Vec<u8>)> {
+        let qrcode_path = config::qrcode_path()?;
+        let url = Self::qrcode_image_url(&qrcode_content);
+        let mut source = self.client.get(&url).send().await?;
+
+        let mut dest = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&qrcode_path)
+            .await?;
+
+        let mut png = Vec::new();
+        while let Some(chunk) = source.chunk().await? {
+            dest.write_all(&chunk).await?;
+            png.extend_from_slice(&chunk);
+        }
+
+        let img = image::open(&qrcode_path)?.to_luma8();
+
+        let mut img = rqrr::PreparedImage::prepare(img);
+
+        let grids = img.detect_grids();
+        let (_, content) = grids[0].decode()?;
+
+        let qrcode = QRBuilder::new(content).build().unwrap();
+
+        let _ = fs::remove_file(qrcode_path).await;
+
+        Ok((qrcode, png))
+    }
+
+    // 生成二维码并输出到终端, 按需推送及更新二维码页面
+    async fn show_qrcode(&self, notify: bool, page: Option<&QrcodePage>) -> Result<QrcodeData> {
+        info!("正在获取二维码...\n");
+        let qrcode_data = match self.generate_qrcode().await {
+            Ok(data) => {
+                debug!("Get qrcode data:{:?}", data);
+                data
+            }
+            Err(e) => {
+                error!("Fail to get qrcode data, error:{:?}", e);
+                return Err(e);
+            }
+        };
+
+        if (notify || Self::push_enabled()) && env::var("NOTIFY_TOKEN").is_ok() {
+            let title = match notify {
+                true => "登录已过期, 请使用大麦APP扫码重新登录",
+                false => "请使用大麦APP扫码登录",
+            };
+            let content = format!(
+                "{}:\n\n![]({})",
+                title,
+                Self::qrcode_image_url(&qrcode_data.code_content)
+            );
+            if let Err(e) = NotifyClient::notify(&content).await {
+                warn!("推送登录二维码失败:{:?}", e);
+            }
+        }
+
+        let (qrcode, png) = match self.get_qrcode(qrcode_data.code_content.clone()).await {
+            Ok(res) => {
+                debug!("success to get qrcode!");
+                res
+            }
+            Err(e) => {
+                error!("Fail to get qrcode, error:{:?}", e);
+                return Err(e);
+            }
+        };
+
+        let options = QrOptions::from_env()?;
+        let modules = qrcode_render::modules(&qrcode);
+        println!("{}\n", options.render(&modules));
+        match options.export(&modules, &png) {
+            Ok(Some(path)) => println!("二维码已保存到:{}\n", path.display()),
+            Ok(None) => {}
+            Err(e) => warn!("保存二维码失败:{:?}", e),
+        }
+        if let Some(page) = page {
+            page.update(png);
+            println!("终端二维码无法扫描时, 请在浏览器中打开:{}\n", page.url);
+        }
+        Ok(qrcode_data)
+    }
+
+    // 环境变量QRCODE_PUSH=true时首次登录也推送二维码, 默认只在重新登录时推送
+    fn push_enabled() -> bool {
+        env::var("QRCODE_PUSH").is_ok_and(|v| v == "true" || v == "1")
+    }
+
+    // 扫码登录总时长, 环境变量QRCODE_LOGIN_TIMEOUT配置, 秒
+    fn login_timeout() -> Duration {
+        Duration::from_secs(
+            env::var("QRCODE_LOGIN_TIMEOUT")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_LOGIN_TIMEOUT),
+        )
+    }
+
+    // 扫码登录, 返回cookie2和通过http获取的cookie
+    // 二维码失效/取消登录后自动刷新, 直到登录成功或超时
+    pub async fn qrcode_login(&self, notify: bool) -> Result<(String, String)> {
+        let deadline = Instant::now() + Self::login_timeout();
+        let page = QrcodePage::from_env().await?;
+        let mut qrcode_data = self.show_qrcode(notify, page.as_ref()).await?;
+        let mut shown_at = Instant::now();
+
+        while Instant::now() < deadline {
+            let qrcode_scan_status = self
+                .get_login_result(qrcode_data.t, qrcode_data.ck.clone())
+                .await?;
+
+            let expired = match qrcode_scan_status.status() {
+                QrcodeStatus::New => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    print!("\r请使用大麦APP扫码, 倒计时:{}秒\t", left.as_secs());
+                    let _ = io::stdout().flush();
+                    shown_at.elapsed() >= QRCODE_TTL
+                }
+                QrcodeStatus::Scanned => {
+                    print!("\r##########请点击确认登录#######\t\t");
+                    let _ = io::stdout().flush();
+                    false
+                }
+                QrcodeStatus::Confirmed => {
+                    let cookie2 = qrcode_scan_status.cookie2.unwrap();
+                    let return_url = qrcode_scan_status.return_url.unwrap();
+                    let st = qrcode_scan_status.st.unwrap();
+                    let cookie = self.get_cookie(&cookie2, return_url, st).await?;
+                    println!("\r\n扫码登录成功!");
+                    return Ok((cookie2, cookie));
+                }
+                QrcodeStatus::Expired | QrcodeStatus::Canceled => true,
+                QrcodeStatus::Unknown(_) => {
+                    error!("未知状态:{:?}, 退出...", qrcode_scan_status);
+                    return Err(ClientError::LoginFailed.into());
+                }
+            };
+
+            if expired {
+                println!("\r\n二维码已失效, 正在刷新...");
+                qrcode_data = self.show_qrcode(notify, page.as_ref()).await?;
+                shown_at = Instant::now();
+            } else {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+
+        info!("扫码登录超时, 请重新执行程序...");
+
+        Err(ClientError::LoginFailed.into())
+    }
+
+    // 通过浏览器获取完整的cookie, SKIP_WEBDRIVER=true时直接使用登录获取的cookie
+    #[cfg(feature = "browser-login")]
+    async fn full_cookie(
+        &self,
+        webdriver_url: &str,
+        cookie2: String,
+        cookie: String,
+    ) -> Result<String> {
+        match config::skip_webdriver() {
+            true => Ok(cookie),
+            false => self.browser_cookie(webdriver_url, cookie2).await,
+        }
+    }
+
+    // 未启用browser-login时直接使用登录获取的cookie
+    #[cfg(not(feature = "browser-login"))]
+    async fn full_cookie(
+        &self,
+        _webdriver_url: &str,
+        _cookie2: String,
+        cookie: String,
+    ) -> Result<String> {
+        Ok(cookie)
+    }
+
+    // 扫码登录, 返回完整的cookie
+    pub async fn login(&self, webdriver_url: &str, notify: bool) -> Result<String> {
+        let (cookie2, cookie) = self.qrcode_login(notify).await?;
+        self.full_cookie(webdriver_url, cookie2, cookie).await
+    }
+
+    // 账号密码/短信验证码登录, 返回完整的cookie
+    pub async fn login_with_credential(
+        &self,
+        webdriver_url: &str,
+        login_id: &str,
+        password: Option<&str>,
+    ) -> Result<String> {
+        let (cookie2, cookie) = self.credential_login(login_id, password).await?;
+        self.full_cookie(webdriver_url, cookie2, cookie).await
+    }
+
+    // 读取登录页面中的rsa公钥
+    async fn rsa_key(&self) -> Result<(String, String)> {
+        let html = self.client.get(LOGIN_PAGE_URL).send().await?.text().await?;
+        parse_rsa_key(&html).ok_or(anyhow!("获取登录公钥失败"))
+    }
+
+    // 账号密码登录
+    pub async fn password_login(
+        &self,
+        login_id: &str,
+        password: &str,
+    ) -> Result<CredentialLoginData> {
+        let password2 = encrypt_password(password, &self.rsa_key().await?)?;
+        let url = "https://ipassport.damai.cn/newlogin/login.do";
+        let form = PasswordLoginForm::build(login_id, &password2)?;
+        let res = self
+            .request(url, QrCodeLoginGetResParams::build()?, form)
+            .await?;
+        Ok(serde_json::from_value(res.content.data)?)
+    }
+
+    // 发送短信验证码, 返回验证码token
+    pub async fn send_sms(&self, phone: &str) -> Result<String> {
+        let url = "https://ipassport.damai.cn/newlogin/sms/send.do";
+        let form = SmsSendForm::build(phone)?;
+        let res = self
+            .request(url, QrCodeLoginGetResParams::build()?, form)
+            .await?;
+        match res.content.data["smsToken"].as_str() {
+            Some(token) => Ok(token.to_string()),
+            None => Err(anyhow!(
+                "发送短信验证码失败:{}",
+                res.content.data["titleMsg"].as_str().unwrap_or_default()
+            )),
+        }
+    }
+
+    // 短信验证码登录
+    pub async fn sms_login(
+        &self,
+        phone: &str,
+        sms_code: &str,
+        sms_token: &str,
+    ) -> Result<CredentialLoginData> {
+        let url = "https://ipassport.damai.cn/newlogin/sms/login.do";
+        let form = SmsLoginForm::build(phone, sms_code, sms_token)?;
+        let res = self
+            .request(url, QrCodeLoginGetResParams::build()?, form)
+            .await?;
+        Ok(serde_json::from_value(res.content.data)?)
+    }
+
+    // 登录过程中保存的cookie
+    fn jar_cookie(&self, name: &str) -> Option<String> {
+        let url = "https://ipassport.damai.cn/".parse().ok()?;
+        let cookies = self.jar.cookies(&url)?;
+        cookies.to_str().ok()?.split(';').find_map(|item| {
+            let (key, value) = item.trim().split_once('=')?;
+            (key == name).then(|| value.to_string())
+        })
+    }
+
+    // 账号密码登录, 需要二次验证或未输入密码时使用短信验证码登录, 返回cookie2和通过http获取的cookie
+    pub async fn credential_login(
+        &self,
+        login_id: &str,
+        password: Option<&str>,
+    ) -> Result<(String, String)> {
+        let password = password.filter(|password| !password.is_empty());
+        let mut data = match password {
+            Some(password) => {
+                info!("正在使用账号密码登录...");
+                self.password_login(login_id, password).await?
+            }
+            None => CredentialLoginData::default(),
+        };
+
+        if !data.success() {
+            if password.is_some() && !data.needs_verify() {
+                error!("账号密码登录失败:{}", data.title_msg.unwrap_or_default());
+                return Err(ClientError::LoginFailed.into());
+            }
+            let phone = match is_phone(login_id) {
+                true => login_id.to_string(),
+                false => prompt("请输入账号绑定的手机号")?,
+            };
+            let sms_token = self.send_sms(&phone).await?;
+            info!("短信验证码已发送");
+            let sms_code = prompt("请输入短信验证码")?;
+            data = self.sms_login(&phone, &sms_code, &sms_token).await?;
+            if !data.success() {
+                error!("短信验证码登录失败:{}", data.title_msg.unwrap_or_default());
+                return Err(ClientError::LoginFailed.into());
+            }
+        }
+
+        let cookie2 = match data.cookie2.or(self.jar_cookie("cookie2")) {
+            Some(cookie2) => cookie2,
+            None => {
+                error!("登录成功但未获取到cookie2");
+                return Err(ClientError::LoginFailed.into());
+            }
+        };
+        let return_url = data.return_url.unwrap_or(LOGIN_RETURN_URL.to_string());
+        let cookie = self
+            .get_cookie(&cookie2, return_url, data.st.unwrap_or_default())
+            .await?;
+        info!("登录成功!");
+        Ok((cookie2, cookie))
+    }
+}
+
+// rsa加密密码, 返回十六进制
+pub fn encrypt_password(password: &str, key: &(String, String)) -> Result<String> {
+    let parse =
+        |hex: &str| BigUint::parse_bytes(hex.as_bytes(), 16).ok_or(anyhow!("登录公钥格式错误"));
+    let key = RsaPublicKey::new(parse(&key.0)?, parse(&key.1)?)?;
+    let mut rng = rand::thread_rng();
+    let encrypted = key.encrypt(&mut rng, Pkcs1v15Encrypt, password.as_bytes())?;
+    Ok(encrypted.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// 读取终端输入
+fn prompt(label: &str) -> Result<String> {
+    print!("{}: ", label);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
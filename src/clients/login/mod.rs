@@ -1,44 +1,60 @@
-use std::env;
+use std::sync::Arc;
 
 use crate::clients::token::TokenClient;
+use crate::config;
 use crate::models::qrcode::{
     QrCodeLoginGetResForm, QrCodeLoginGetResParams, QrCodeLoginStatusData, QrcodeContentGetParams,
     QrcodeData,
 };
 use crate::models::DmLoginRes;
 use anyhow::Result;
-use fast_qr::{QRBuilder, QRCode};
-
 use reqwest::{
+    cookie::Jar,
     header::{HeaderMap, HeaderValue},
     Client,
 };
 use serde_json::{json, Value};
-use tokio::{fs, io::AsyncWriteExt};
 use urlencoding;
 
+// 扫码/账号密码登录
+#[cfg(feature = "http-login")]
+mod http;
+#[cfg(feature = "http-login")]
+pub use http::encrypt_password;
+
+// 通过浏览器获取完整的cookie
+#[cfg(feature = "browser-login")]
+mod browser;
+#[cfg(feature = "browser-login")]
+pub use browser::chrome_driver;
+
+// 登录页面
+const LOGIN_PAGE_URL: &str = "https://ipassport.damai.cn/mini_login.htm?lang=zh_cn&appName=damai&appEntrance=default&styleType=vertical&bizParams=&notLoadSsoView=true&notKeepLogin=false&isMobile=false&showSnsLogin=false&regUrl=https%3A%2F%2Fpassport.damai.cn%2Fregister&plainReturnUrl=https%3A%2F%2Fpassport.damai.cn%2Flogin&returnUrl=https%3A%2F%2Fpassport.damai.cn%2Fdologin.htm%3FredirectUrl%3Dhttps%25253A%25252F%25252Fwww.damai.cn%25252F%26platform%3D106002&rnd=0.6260742856882737";
+
 #[derive(Debug)]
 pub struct LoginClient {
     pub token_client: TokenClient,
     pub client: Client,
+    pub jar: Arc<Jar>, // 登录过程中的cookie, 用于读取cookie2
 }
 
 impl LoginClient {
     pub async fn new() -> Result<Self> {
-        let redis_url = env::var("REDIS_URL").unwrap();
+        let redis_url = config::get("REDIS_URL")?;
         let token_client = TokenClient::new(redis_url).await?;
 
         let mut headers = HeaderMap::new();
         headers.append("user-agent", HeaderValue::from_str("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/113.0.0.0 Safari/537.36")?);
-        headers.append("referer", HeaderValue::from_str("https://ipassport.damai.cn/mini_login.htm?lang=zh_cn&appName=damai&appEntrance=default&styleType=vertical&bizParams=&notLoadSsoView=true&notKeepLogin=false&isMobile=false&showSnsLogin=false&regUrl=https%3A%2F%2Fpassport.damai.cn%2Fregister&plainReturnUrl=https%3A%2F%2Fpassport.damai.cn%2Flogin&returnUrl=https%3A%2F%2Fpassport.damai.cn%2Fdologin.htm%3FredirectUrl%3Dhttps%25253A%25252F%25252Fwww.damai.cn%25252F%26platform%3D106002&rnd=0.6260742856882737")?);
+        headers.append("referer", HeaderValue::from_str(LOGIN_PAGE_URL)?);
         headers.append(
             "content-type",
             HeaderValue::from_str("application/json;charset=UTF-8")?,
         );
 
+        let jar = Arc::new(Jar::default());
         let client = reqwest::Client::builder()
             .default_headers(headers)
-            .cookie_store(true)
+            .cookie_provider(jar.clone())
             .http2_prior_knowledge()
             .user_agent("Mozilla/5.0 (iPhone; CPU iPhone OS 13_2_3 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/13.0.3")
             .use_rustls_tls()
@@ -46,6 +62,7 @@ impl LoginClient {
         Ok(Self {
             token_client,
             client,
+            jar,
         })
     }
 
@@ -76,37 +93,12 @@ impl LoginClient {
         Ok(data)
     }
 
-    // 获取二维码
-    pub async fn get_qrcode(&self, qrcode_content: String) -> Result<QRCode> {
-        let qrcode_path = env::var("QRCODE_PATH").unwrap();
-        let url = format!(
+    // 二维码图片地址
+    pub fn qrcode_image_url(qrcode_content: &str) -> String {
+        format!(
             "https://gcodex.alicdn.com/qrcode.do?biz_code=havana&size=140&content={}",
-            urlencoding::encode(&qrcode_content)
-        );
-        let mut source = self.client.get(&url).send().await?;
-
-        let mut dest = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .open(&qrcode_path)
-            .await?;
-
-        while let Some(chunk) = source.chunk().await? {
-            dest.write_all(&chunk).await?;
-        }
-
-        let img = image::open(&qrcode_path)?.to_luma8();
-
-        let mut img = rqrr::PreparedImage::prepare(img);
-
-        let grids = img.detect_grids();
-        let (_, content) = grids[0].decode()?;
-
-        let qrcode = QRBuilder::new(content).build().unwrap();
-
-        let _ = fs::remove_file(qrcode_path).await;
-
-        Ok(qrcode)
+            urlencoding::encode(qrcode_content)
+        )
     }
 
     // 获取登录结果
@@ -0,0 +1,553 @@
+use std::{
+    collections::HashMap,
+    env, fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use super::{
+    concurrency::ConcurrencyLimiter,
+    endpoint::{Api, EndpointRegistry},
+    rate_limit::RateLimiter,
+    record::Recorder,
+    sign::{sign_json, sign_params},
+    token::TokenClient,
+};
+use crate::logger::scrub;
+
+// 发送时才确定的查询参数, 其余参数可在开售前编码
+const PATCHED_PARAMS: [&str; 5] = ["t", "requestStart", "sign", "bx-umidtoken", "bx-ua"];
+
+// 一次mtop接口请求
+#[derive(Debug, Clone)]
+pub struct MtopRequest {
+    pub api: Api,
+    pub url: String,
+    pub params: Value,
+    pub data: Value,
+    pub timeout: Duration,
+    pub prepared: Option<Arc<PreparedBody>>, // 开售前编码好的请求, 与params/data一致
+}
+
+// 开售前预先编码的请求, 开抢时只需补上时间戳/签名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedBody {
+    pub data: String,  // data的json, 用于签名
+    pub form: String,  // 请求体: data=<json>
+    pub query: String, // 不含时间戳/签名的查询参数
+}
+
+// 查询参数的值, 字符串不带引号
+fn param_value(value: &Value) -> String {
+    match value.as_str() {
+        Some(s) => s.to_string(),
+        None => value.to_string(),
+    }
+}
+
+fn encode_pairs<'a>(pairs: impl Iterator<Item = (&'a String, &'a Value)>) -> String {
+    pairs
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                urlencoding::encode(key),
+                urlencoding::encode(&param_value(value))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+impl PreparedBody {
+    // params需已包含api/v等固定参数
+    pub fn build(params: &Value, data: &Value) -> Result<Self> {
+        let data = serde_json::to_string(data)?;
+        let form = format!("data={}", urlencoding::encode(&data));
+        let query = match params.as_object() {
+            Some(params) => encode_pairs(
+                params
+                    .iter()
+                    .filter(|(key, _)| !PATCHED_PARAMS.contains(&key.as_str())),
+            ),
+            None => String::new(),
+        };
+        Ok(Self { data, form, query })
+    }
+
+    // 补上发送时确定的参数后的完整查询参数
+    pub fn query(&self, params: &Value) -> String {
+        let patched = match params.as_object() {
+            Some(params) => encode_pairs(
+                params
+                    .iter()
+                    .filter(|(key, _)| PATCHED_PARAMS.contains(&key.as_str())),
+            ),
+            None => String::new(),
+        };
+        [self.query.as_str(), &patched]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+// 中间件, 处理请求后交给下一层
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    async fn handle(&self, req: MtopRequest, next: Next<'_>) -> Result<Value>;
+}
+
+// 最内层, 实际发送请求
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, req: &MtopRequest) -> Result<Value>;
+}
+
+// 剩余的中间件
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    transport: &'a dyn Transport,
+}
+
+impl<'a> Next<'a> {
+    pub async fn run(self, req: MtopRequest) -> Result<Value> {
+        match self.middlewares.split_first() {
+            Some((first, rest)) => {
+                let next = Next {
+                    middlewares: rest,
+                    transport: self.transport,
+                };
+                first.handle(req, next).await
+            }
+            None => self.transport.send(&req).await,
+        }
+    }
+}
+
+// 中间件链, 按添加顺序由外到内执行
+#[derive(Clone)]
+pub struct Chain {
+    middlewares: Vec<Arc<dyn Middleware>>,
+    transport: Arc<dyn Transport>,
+}
+
+impl fmt::Debug for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chain")
+            .field("middlewares", &self.middlewares.len())
+            .finish()
+    }
+}
+
+impl Chain {
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        Self {
+            middlewares: Vec::new(),
+            transport: Arc::new(transport),
+        }
+    }
+
+    pub fn with(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    pub async fn run(&self, req: MtopRequest) -> Result<Value> {
+        let next = Next {
+            middlewares: &self.middlewares,
+            transport: self.transport.as_ref(),
+        };
+        next.run(req).await
+    }
+}
+
+// 下单请求的网络线路
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkPath {
+    Direct, // 直连
+    Proxy,  // 通过MTOP_RACE_PROXY代理
+}
+
+impl NetworkPath {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Direct => "直连",
+            Self::Proxy => "代理",
+        }
+    }
+}
+
+// 使用reqwest发送请求, http2连接异常时回退到http1.1连接池
+pub struct HttpTransport {
+    pub client: Client,
+    pub fallback: Option<Client>,
+    pub race: Option<Client>, // 代理线路, 首次生成订单时与直连竞速
+    degraded: AtomicBool,     // 已回退到http1.1
+    winner: OnceLock<NetworkPath>,
+}
+
+impl HttpTransport {
+    pub fn new(client: Client, fallback: Option<Client>) -> Self {
+        Self {
+            client,
+            fallback,
+            race: None,
+            degraded: AtomicBool::new(false),
+            winner: OnceLock::new(),
+        }
+    }
+
+    pub fn with_race(mut self, race: Option<Client>) -> Self {
+        self.race = race;
+        self
+    }
+
+    // 竞速胜出的线路, 未竞速时为None
+    pub fn winner(&self) -> Option<NetworkPath> {
+        self.winner.get().copied()
+    }
+
+    async fn post(client: &Client, req: &MtopRequest) -> Result<Value> {
+        let builder = match &req.prepared {
+            // 使用开售前编码好的请求体, 只拼接时间戳/签名
+            Some(prepared) => client
+                .post(format!("{}?{}", req.url, prepared.query(&req.params)))
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(prepared.form.clone()),
+            // 直接编码序列化后的data, 不再构造中间的json对象
+            None => client
+                .post(&req.url)
+                .query(&req.params)
+                .form(&[("data", serde_json::to_string(&req.data)?)]),
+        };
+        let value = builder
+            .timeout(req.timeout)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+        Ok(value)
+    }
+}
+
+// 是否为连接/协议错误, 超时不回退
+fn is_connection_error(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| !e.is_timeout() && (e.is_connect() || e.is_request()))
+}
+
+impl HttpTransport {
+    // 通过直连发送, http2连接异常时回退到http1.1
+    async fn send_direct(&self, req: &MtopRequest) -> Result<Value> {
+        let fallback = match &self.fallback {
+            Some(fallback) => fallback,
+            None => return Self::post(&self.client, req).await,
+        };
+        if self.degraded.load(Ordering::Relaxed) {
+            return Self::post(fallback, req).await;
+        }
+        match Self::post(&self.client, req).await {
+            Err(e) if is_connection_error(&e) => {
+                warn!("http2连接异常:{:?}, 回退使用http1.1连接", e);
+                self.degraded.store(true, Ordering::Relaxed);
+                Self::post(fallback, req).await
+            }
+            res => res,
+        }
+    }
+
+    // 同时通过直连和代理发送, 先成功返回的线路胜出, 另一个请求随即取消
+    async fn race(&self, proxy: &Client, req: &MtopRequest) -> Result<Value> {
+        let start = Instant::now();
+        let direct = self.send_direct(req);
+        let proxied = Self::post(proxy, req);
+        tokio::pin!(direct, proxied);
+        let (path, res) = tokio::select! {
+            res = &mut direct => match res {
+                Ok(value) => (NetworkPath::Direct, Ok(value)),
+                Err(e) => {
+                    debug!("直连请求失败, 等待代理线路:{:?}", e);
+                    (NetworkPath::Proxy, proxied.await)
+                }
+            },
+            res = &mut proxied => match res {
+                Ok(value) => (NetworkPath::Proxy, Ok(value)),
+                Err(e) => {
+                    debug!("代理请求失败, 等待直连线路:{:?}", e);
+                    (NetworkPath::Direct, direct.await)
+                }
+            },
+        };
+        // 两条线路都失败时下次继续竞速
+        if res.is_ok() && self.winner.set(path).is_ok() {
+            info!(
+                "线路竞速:{}胜出, 耗时:{:?}, 之后的下单请求使用该线路",
+                path.name(),
+                start.elapsed()
+            );
+        }
+        res
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&self, req: &MtopRequest) -> Result<Value> {
+        // 只有下单请求走竞速线路; 提交订单不重复发送, 避免重复下单
+        match (&self.race, self.winner.get()) {
+            (Some(proxy), Some(NetworkPath::Proxy)) if req.api.is_order() => {
+                Self::post(proxy, req).await
+            }
+            (Some(proxy), None) if req.api == Api::BuildOrder => self.race(proxy, req).await,
+            _ => self.send_direct(req).await,
+        }
+    }
+}
+
+// 签名, 并附加风控参数
+pub struct SignMiddleware {
+    pub token: String,
+    pub token_client: Option<TokenClient>,
+}
+
+#[async_trait]
+impl Middleware for SignMiddleware {
+    async fn handle(&self, mut req: MtopRequest, next: Next<'_>) -> Result<Value> {
+        match &req.prepared {
+            Some(prepared) => sign_json(&self.token, &mut req.params, &prepared.data)?,
+            None => sign_params(&self.token, &mut req.params, &req.data)?,
+        }
+
+        if let Some(token_client) = &self.token_client {
+            req.params["bx-umidtoken"] = token_client.get_bx_token().await?.into();
+            req.params["bx-ua"] = token_client.get_bx_ua().await?.into();
+        }
+        next.run(req).await
+    }
+}
+
+// 按限速等待后再发送请求
+pub struct RateLimitMiddleware {
+    pub limiter: RateLimiter,
+}
+
+#[async_trait]
+impl Middleware for RateLimitMiddleware {
+    async fn handle(&self, req: MtopRequest, next: Next<'_>) -> Result<Value> {
+        let wait = self.limiter.acquire(req.api);
+        if !wait.is_zero() {
+            debug!("请求{}超出限速, 等待{:?}", req.api.name(), wait);
+            tokio::time::sleep(wait).await;
+        }
+        next.run(req).await
+    }
+}
+
+// 限制同时进行中的请求数, 等待空位后再发送请求
+pub struct ConcurrencyMiddleware {
+    pub limiter: ConcurrencyLimiter,
+    pub account: String,
+}
+
+#[async_trait]
+impl Middleware for ConcurrencyMiddleware {
+    async fn handle(&self, req: MtopRequest, next: Next<'_>) -> Result<Value> {
+        let _permits = self.limiter.acquire(&self.account).await;
+        next.run(req).await
+    }
+}
+
+// 是否为网络错误, 解析响应失败不重试
+fn is_transient(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| !e.is_decode())
+}
+
+// 连接失败, 请求未发出
+fn is_not_sent(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect())
+}
+
+// 网络错误时重试, 下单接口优先切换备用网关
+// 提交订单只在连接失败时重发, 超时等错误时服务器可能已创建订单, 重发会重复下单
+pub struct RetryMiddleware {
+    pub endpoints: EndpointRegistry,
+    pub retries: u32,
+}
+
+impl RetryMiddleware {
+    // 环境变量MTOP_NETWORK_RETRIES配置网络错误的重试次数, 默认不重试
+    pub fn from_env(endpoints: EndpointRegistry) -> Self {
+        let retries = env::var("MTOP_NETWORK_RETRIES")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(0);
+        Self { endpoints, retries }
+    }
+}
+
+#[async_trait]
+impl Middleware for RetryMiddleware {
+    async fn handle(&self, mut req: MtopRequest, next: Next<'_>) -> Result<Value> {
+        let mut retried = 0;
+        let mut failed_over = false;
+        loop {
+            let e = match next.run(req.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(e) if is_transient(&e) => e,
+                Err(e) => return Err(e),
+            };
+            if req.api == Api::CreateOrder && !is_not_sent(&e) {
+                return Err(e);
+            }
+            match self.endpoints.failover_url(req.api) {
+                Some(url) if req.api.is_order() && !failed_over => {
+                    warn!("请求{}失败:{:?}, 切换备用网关重试...", req.url, e);
+                    req.url = url;
+                    failed_over = true;
+                }
+                _ if retried < self.retries => {
+                    retried += 1;
+                    warn!("请求{}失败:{:?}, 第{}次重试...", req.url, e, retried);
+                }
+                _ => return Err(e),
+            }
+        }
+    }
+}
+
+// 请求日志, 输出前脱敏
+pub struct LogMiddleware;
+
+#[async_trait]
+impl Middleware for LogMiddleware {
+    async fn handle(&self, req: MtopRequest, next: Next<'_>) -> Result<Value> {
+        let api = req.api;
+        debug!(
+            "请求{}, 参数:{}",
+            api.name(),
+            scrub(&req.params.to_string())
+        );
+        let res = next.run(req).await;
+        match &res {
+            Ok(value) => debug!("{}返回:{}", api.name(), scrub(&value["ret"].to_string())),
+            Err(e) => debug!("{}请求失败:{}", api.name(), scrub(&e.to_string())),
+        }
+        res
+    }
+}
+
+// 单个接口的耗时统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiLatency {
+    pub count: u64,
+    pub errors: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+impl ApiLatency {
+    pub fn avg_ms(&self) -> u64 {
+        match self.count {
+            0 => 0,
+            n => self.total_ms / n,
+        }
+    }
+}
+
+// 记录各接口耗时
+#[derive(Debug, Clone, Default)]
+pub struct MetricsMiddleware {
+    stats: Arc<Mutex<HashMap<&'static str, ApiLatency>>>,
+}
+
+impl MetricsMiddleware {
+    // 各接口的耗时统计
+    pub fn snapshot(&self) -> HashMap<&'static str, ApiLatency> {
+        self.stats.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Middleware for MetricsMiddleware {
+    async fn handle(&self, req: MtopRequest, next: Next<'_>) -> Result<Value> {
+        let api = req.api;
+        let start = Instant::now();
+        let res = next.run(req).await;
+        let elapsed = start.elapsed().as_millis() as u64;
+        // 单次请求的耗时记录, 带有所在任务/尝试span的运行ID/尝试ID
+        debug!(
+            target: "metrics",
+            api = api.name(),
+            elapsed_ms = elapsed,
+            ok = res.is_ok(),
+            "接口耗时"
+        );
+        if let Ok(mut stats) = self.stats.lock() {
+            let entry = stats.entry(api.name()).or_default();
+            entry.count += 1;
+            entry.total_ms += elapsed;
+            entry.max_ms = entry.max_ms.max(elapsed);
+            if res.is_err() {
+                entry.errors += 1;
+            }
+        }
+        res
+    }
+}
+
+// 记录请求/响应, 用于回放
+#[async_trait]
+impl Middleware for Recorder {
+    async fn handle(&self, req: MtopRequest, next: Next<'_>) -> Result<Value> {
+        let (api, params, data) = (req.api, req.params.clone(), req.data.clone());
+        let value = next.run(req).await?;
+        if let Err(e) = self.record(api, &params, &data, &value) {
+            warn!("记录请求失败:{:?}", e);
+        }
+        Ok(value)
+    }
+}
+
+// 默认的中间件链: 限速 -> 并发限制 -> 签名 -> 重试 -> 日志 -> 耗时统计 -> 记录
+pub fn default_chain(
+    transport: HttpTransport,
+    account: &str,
+    token: &str,
+    token_client: Option<TokenClient>,
+    endpoints: &EndpointRegistry,
+    metrics: MetricsMiddleware,
+    recorder: Option<Recorder>,
+) -> Chain {
+    let chain = Chain::new(transport)
+        .with(RateLimitMiddleware {
+            limiter: RateLimiter::shared(),
+        })
+        .with(ConcurrencyMiddleware {
+            limiter: ConcurrencyLimiter::shared(),
+            account: account.to_string(),
+        })
+        .with(SignMiddleware {
+            token: token.to_string(),
+            token_client,
+        })
+        .with(RetryMiddleware::from_env(endpoints.clone()))
+        .with(LogMiddleware)
+        .with(metrics);
+    match recorder {
+        Some(recorder) => chain.with(recorder),
+        None => chain,
+    }
+}
@@ -1,4 +1,22 @@
+pub mod bot;
+pub mod cache;
+#[cfg(feature = "browser-login")]
+pub mod captcha;
+pub mod concurrency;
+pub mod coordinator;
 pub mod dm;
+pub mod email;
+pub mod endpoint;
 pub mod login;
+pub mod middleware;
 pub mod notify;
+pub mod qrcode_page;
+pub mod queue;
+pub mod rate_limit;
+pub mod record;
+pub mod session;
+pub mod sign;
 pub mod token;
+#[cfg(feature = "browser-login")]
+pub mod webdriver;
+pub mod webhook;
@@ -5,7 +5,7 @@ pub struct NotifyClient {}
 
 impl NotifyClient {
     pub async fn notify(content: &str) -> Result<bool> {
-        let token = std::env::var("NOTIFY_TOKEN").unwrap();
+        let token = crate::config::get("NOTIFY_TOKEN")?;
         let client = reqwest::Client::builder().cookie_store(true).build()?;
 
         let url = format!("http://www.pushplus.plus/send/{}", token);
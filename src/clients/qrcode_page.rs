@@ -0,0 +1,113 @@
+use std::{
+    env,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::Result;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tracing::{debug, info};
+
+// 页面每隔几秒刷新, 二维码失效刷新后自动显示新的二维码
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="5">
+<title>大麦扫码登录</title>
+</head>
+<body style="text-align:center;font-family:sans-serif">
+<h3>请使用大麦APP扫码登录</h3>
+<img src="/qrcode.png" width="280" height="280" alt="二维码">
+</body>
+</html>"#;
+
+// 在本地http地址展示登录二维码, 用于无法正常显示终端二维码的服务器
+// 登录结束(drop)后停止服务
+pub struct QrcodePage {
+    pub url: String,
+    image: Arc<RwLock<Vec<u8>>>,
+    handle: JoinHandle<()>,
+}
+
+impl QrcodePage {
+    // 监听指定地址, 如0.0.0.0:8765
+    pub async fn start(addr: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let image = Arc::new(RwLock::new(Vec::new()));
+
+        let server_image = image.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let image = server_image.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, image).await {
+                        debug!("二维码页面连接异常:{:?}", e);
+                    }
+                });
+            }
+        });
+
+        info!("二维码页面已启动, 请在浏览器中打开:{}", url);
+        Ok(Self { url, image, handle })
+    }
+
+    // 环境变量QRCODE_SERVE配置监听地址, 未配置时不启动
+    pub async fn from_env() -> Result<Option<Self>> {
+        match env::var("QRCODE_SERVE") {
+            Ok(addr) if !addr.is_empty() => Ok(Some(Self::start(&addr).await?)),
+            _ => Ok(None),
+        }
+    }
+
+    // 更新展示的二维码图片(png)
+    pub fn update(&self, png: Vec<u8>) {
+        if let Ok(mut image) = self.image.write() {
+            *image = png;
+        }
+    }
+}
+
+impl Drop for QrcodePage {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+// 处理一个请求: /qrcode.png返回图片, 其他路径返回页面
+async fn serve(stream: TcpStream, image: Arc<RwLock<Vec<u8>>>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .split('?')
+        .next()
+        .unwrap_or("/")
+        .to_string();
+
+    let (content_type, body) = match path.as_str() {
+        "/qrcode.png" => (
+            "image/png",
+            image.read().map(|image| image.clone()).unwrap_or_default(),
+        ),
+        _ => ("text/html; charset=utf-8", PAGE.as_bytes().to_vec()),
+    };
+    let header = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: {}\r\ncontent-length: {}\r\ncache-control: no-store\r\nconnection: close\r\n\r\n",
+        content_type,
+        body.len()
+    );
+    writer.write_all(header.as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::{env, time::Duration};
+
+use serde_json::Value;
+
+use crate::models::DmRes;
+
+// 排队响应中的提示文本/错误码
+const QUEUE_FLAGS: [&str; 4] = ["排队", "QUEUE", "WAITING_ROOM", "WAIT_IN_LINE"];
+
+// 排队token/轮询间隔/排队位置的字段名
+const TOKEN_KEYS: [&str; 3] = ["queueToken", "waitingToken", "pollToken"];
+const INTERVAL_KEYS: [&str; 2] = ["pollInterval", "interval"];
+const POSITION_KEYS: [&str; 3] = ["position", "queuePosition", "rank"];
+
+// 排队轮询配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueuePolicy {
+    pub max_wait: Duration,         // 排队的总时长, 超过后放弃本次请求, 0为不轮询
+    pub default_interval: Duration, // 响应中未指定间隔时的轮询间隔
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+}
+
+impl Default for QueuePolicy {
+    fn default() -> Self {
+        Self {
+            max_wait: Duration::from_secs(30),
+            default_interval: Duration::from_millis(1000),
+            min_interval: Duration::from_millis(200),
+            max_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl QueuePolicy {
+    // 从环境变量读取配置:
+    // QUEUE_MAX_WAIT: 排队的总时长, 毫秒, 0为不轮询
+    // QUEUE_POLL_INTERVAL: 响应中未指定间隔时的轮询间隔, 毫秒
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        let millis = |key: &str| {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_millis)
+        };
+        if let Some(max_wait) = millis("QUEUE_MAX_WAIT") {
+            policy.max_wait = max_wait;
+        }
+        if let Some(interval) = millis("QUEUE_POLL_INTERVAL").filter(|v| !v.is_zero()) {
+            policy.default_interval = interval;
+        }
+        policy
+    }
+
+    // 网关指定的间隔限制在合理范围内, 过短时避免刷接口, 过长时避免错过结果
+    pub fn clamp(&self, interval: Duration) -> Duration {
+        interval.clamp(self.min_interval, self.max_interval)
+    }
+}
+
+// 网关返回的排队占位响应
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueTicket {
+    pub token: Option<String>, // 轮询时携带的排队token
+    pub interval: Duration,    // 下次轮询前等待的时长
+    pub position: Option<u64>, // 排队位置, 网关通常不返回
+}
+
+impl QueueTicket {
+    // 识别排队响应, 不是排队响应时返回None
+    pub fn detect(res: &DmRes, policy: &QueuePolicy) -> Option<Self> {
+        let token = find(&res.data, &TOKEN_KEYS).and_then(text);
+        let flagged = res
+            .ret
+            .iter()
+            .any(|r| QUEUE_FLAGS.iter().any(|flag| r.contains(flag)));
+        if !flagged && token.is_none() {
+            return None;
+        }
+        let interval = find(&res.data, &INTERVAL_KEYS)
+            .and_then(number)
+            .map(Duration::from_millis)
+            .unwrap_or(policy.default_interval);
+        Some(Self {
+            token,
+            interval: policy.clamp(interval),
+            position: find(&res.data, &POSITION_KEYS).and_then(number),
+        })
+    }
+
+    // 排队状态描述
+    pub fn describe(&self) -> String {
+        match self.position {
+            Some(position) => format!("排队中, 当前位置:{}", position),
+            None => "排队中, 位置未知".to_string(),
+        }
+    }
+}
+
+fn find<'a>(data: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter()
+        .find_map(|key| data.get(key).filter(|v| !v.is_null()))
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+// 数字字段, 兼容字符串形式
+fn number(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => s.trim().parse().ok(),
+        _ => value.as_u64(),
+    }
+}
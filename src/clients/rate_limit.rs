@@ -0,0 +1,138 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use super::endpoint::Api;
+
+// 默认每秒请求数及突发数, 下单接口默认不限制
+const DEFAULT_RATE: f64 = 5.0;
+const DEFAULT_BURST: f64 = 10.0;
+
+// 令牌桶, 令牌不足时预支, 后来的请求依次排队等待
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,     // 每秒补充的令牌数
+    capacity: f64, // 最多积攒的令牌数
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, burst: f64, now: Instant) -> Self {
+        let capacity = burst.max(1.0);
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    // 取一个令牌, 返回需要等待的时长
+    pub fn acquire(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = self.updated.max(now);
+        self.tokens -= 1.0;
+        match self.tokens >= 0.0 {
+            true => Duration::ZERO,
+            false => Duration::from_secs_f64(-self.tokens / self.rate),
+        }
+    }
+}
+
+// 请求限速: 全局限速及各接口单独的限速, 进程内所有任务/账号共用
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    global: Option<Arc<Mutex<TokenBucket>>>,
+    endpoints: Arc<HashMap<String, Mutex<TokenBucket>>>,
+}
+
+impl RateLimiter {
+    // global/endpoints为(每秒请求数, 突发数), 每秒请求数为0时不限制
+    pub fn new(global: Option<(f64, f64)>, endpoints: HashMap<String, (f64, f64)>) -> Self {
+        let now = Instant::now();
+        let bucket = |(rate, burst): (f64, f64)| TokenBucket::new(rate, burst, now);
+        Self {
+            global: global
+                .filter(|(rate, _)| *rate > 0.0)
+                .map(|limit| Arc::new(Mutex::new(bucket(limit)))),
+            endpoints: Arc::new(
+                endpoints
+                    .into_iter()
+                    .filter(|(_, (rate, _))| *rate > 0.0)
+                    .map(|(api, limit)| (api, Mutex::new(bucket(limit))))
+                    .collect(),
+            ),
+        }
+    }
+
+    // 从环境变量读取配置:
+    // RATE_LIMIT: 全局每秒请求数, 默认5, 0为不限制, 不包含下单接口
+    // RATE_LIMIT_BURST: 全局突发请求数, 默认10
+    // RATE_LIMIT_ENDPOINTS: 各接口单独的限速, 格式: api=每秒请求数[/突发数],api=...
+    pub fn from_env() -> Self {
+        let number = |key: &str, default: f64| {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .filter(|v| *v >= 0.0)
+                .unwrap_or(default)
+        };
+        let global = (
+            number("RATE_LIMIT", DEFAULT_RATE),
+            number("RATE_LIMIT_BURST", DEFAULT_BURST),
+        );
+        let endpoints = env::var("RATE_LIMIT_ENDPOINTS")
+            .map(|value| Self::parse_endpoints(&value))
+            .unwrap_or_default();
+        Self::new(Some(global), endpoints)
+    }
+
+    // 进程内共用的限速配置
+    pub fn shared() -> Self {
+        static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+        LIMITER.get_or_init(Self::from_env).clone()
+    }
+
+    // 解析各接口的限速, 未指定突发数时与每秒请求数相同
+    pub fn parse_endpoints(value: &str) -> HashMap<String, (f64, f64)> {
+        value
+            .split(',')
+            .filter_map(|item| item.split_once('='))
+            .filter_map(|(api, limit)| {
+                let (rate, burst) = match limit.split_once('/') {
+                    Some((rate, burst)) => (rate, Some(burst)),
+                    None => (limit, None),
+                };
+                let rate = rate.trim().parse::<f64>().ok()?;
+                let burst = match burst {
+                    Some(burst) => burst.trim().parse::<f64>().ok()?,
+                    None => rate,
+                };
+                Some((api.trim().to_string(), (rate, burst)))
+            })
+            .collect()
+    }
+
+    // 取令牌, 返回需要等待的时长, 同时受全局和接口限速时取较长者
+    pub fn acquire(&self, api: Api) -> Duration {
+        let now = Instant::now();
+        let take = |bucket: &Mutex<TokenBucket>| {
+            bucket
+                .lock()
+                .map(|mut bucket| bucket.acquire(now))
+                .unwrap_or_default()
+        };
+        let endpoint = self.endpoints.get(api.name()).map(take).unwrap_or_default();
+        // 下单接口只受单独配置的限速, 避免开售时被其他请求拖慢
+        let global = match (&self.global, api.is_order()) {
+            (Some(bucket), false) => take(bucket),
+            _ => Duration::ZERO,
+        };
+        endpoint.max(global)
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{error, info};
+
+use super::{dm::SUCCESS_FLAG, endpoint::Api};
+use crate::logger::scrub;
+use crate::models::{
+    address::parse_address_list,
+    coupon::parse_coupon_list,
+    order::{parse_order_list, OrderInfo},
+    perform::PerformInfo,
+    search::parse_city_stops,
+    seat::parse_seat_map,
+    ticket::TicketInfo,
+    ticket::TicketList,
+    user::{parse_wish_list, UserInfoData},
+    viewer::parse_viewer_list,
+    DmRes,
+};
+
+// 记录时需要去除的参数
+const SCRUB_PARAMS: [&str; 3] = ["sign", "bx-ua", "bx-umidtoken"];
+
+// 记录时整体替换的字段: 登录凭证及观演人/收货人信息
+const SCRUB_FIELDS: [&str; 10] = [
+    "cookie",
+    "token",
+    "fullName",
+    "viewerName",
+    "certNo",
+    "idNo",
+    "identityNo",
+    "mobile",
+    "phone",
+    "addressDetail",
+];
+
+// 一次请求/响应记录
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordEntry {
+    pub time: i64,
+    pub api: String,
+    pub params: Value,
+    pub data: Value,
+    pub response: Value,
+}
+
+// 请求记录器, 每行一条json记录
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    file: Arc<Mutex<File>>,
+}
+
+impl Recorder {
+    pub fn new(path: &str) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    // 从环境变量RECORD_PATH初始化, 未配置时不记录
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var("RECORD_PATH") {
+            Ok(path) if !path.is_empty() => Ok(Some(Self::new(&path)?)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn record(&self, api: Api, params: &Value, data: &Value, response: &Value) -> Result<()> {
+        let mut params = params.clone();
+        if let Some(map) = params.as_object_mut() {
+            for key in SCRUB_PARAMS {
+                map.remove(key);
+            }
+        }
+
+        let entry = RecordEntry {
+            time: Local::now().timestamp_millis(),
+            api: api.name().to_string(),
+            params: scrub_value(&params),
+            data: scrub_value(data),
+            response: scrub_value(response),
+        };
+
+        let line = serde_json::to_string(&entry)?;
+        let mut file = self.file.lock().map_err(|_| anyhow!("记录文件被占用"))?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+// 写入前脱敏, 保持json结构以便回放解析
+// 个人信息字段替换为***, 其余字符串按日志规则去除cookie/token/身份证号/手机号
+pub fn scrub_value(value: &Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(scrub(s)),
+        Value::Array(items) => Value::Array(items.iter().map(scrub_value).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = match SCRUB_FIELDS.contains(&key.as_str()) && !value.is_null() {
+                        true => Value::String("***".to_string()),
+                        false => scrub_value(value),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+// 使用解析代码重新解析一条响应
+fn parse_entry(entry: &RecordEntry) -> Result<()> {
+    let res: DmRes = serde_json::from_value(entry.response.clone())?;
+
+    let api = Api::from_name(&entry.api).ok_or(anyhow!("未知接口:{}", entry.api))?;
+
+    // 调用失败的响应没有业务数据
+    if !res.ret.contains(&SUCCESS_FLAG.to_string()) {
+        return Ok(());
+    }
+
+    match api {
+        Api::UserInfo => {
+            let _: UserInfoData = serde_json::from_value(res.data)?;
+        }
+        Api::TicketList => {
+            let _: TicketList = serde_json::from_value(res.data["modules"][0].clone())?;
+            let _: TicketList = serde_json::from_value(res.data["modules"][1].clone())?;
+        }
+        Api::TicketDetail => {
+            let result = res.data["result"]
+                .as_str()
+                .ok_or(anyhow!("缺少result字段"))?;
+            let _: TicketInfo = serde_json::from_str(result)?;
+        }
+        Api::PerformSkus => {
+            let result = res.data["result"]
+                .as_str()
+                .ok_or(anyhow!("缺少result字段"))?;
+            let _: PerformInfo = serde_json::from_str(result)?;
+        }
+        Api::BuildOrder => {
+            let _: OrderInfo = serde_json::from_value(res.data)?;
+        }
+        Api::WishList => {
+            let _ = parse_wish_list(&res.data);
+        }
+        Api::OrderList => {
+            let _ = parse_order_list(&res.data);
+        }
+        Api::Search => {
+            let _ = parse_city_stops(&res.data);
+        }
+        Api::CouponList => {
+            let _ = parse_coupon_list(&res.data);
+        }
+        Api::AddressList => {
+            let _ = parse_address_list(&res.data);
+        }
+        Api::SeatMap => {
+            let _ = parse_seat_map(&res.data)?;
+        }
+        Api::ViewerList => {
+            let _ = parse_viewer_list(&res.data);
+        }
+        Api::CreateOrder
+        | Api::Timestamp
+        | Api::Waitlist
+        | Api::CancelOrder
+        | Api::Privilege
+        | Api::ViewerAdd
+        | Api::ViewerRemove
+        | Api::AddressAdd => {}
+    }
+    Ok(())
+}
+
+// 回放记录文件, 返回解析失败的记录数
+pub fn replay(path: &str) -> Result<usize> {
+    let file = File::open(path)?;
+
+    let mut failed = 0;
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: RecordEntry = serde_json::from_str(&line)?;
+        match parse_entry(&entry) {
+            Ok(_) => info!("第{}条记录, {}, 解析成功", i + 1, entry.api),
+            Err(e) => {
+                failed += 1;
+                error!("第{}条记录, {}, 解析失败:{:?}", i + 1, entry.api, e);
+            }
+        }
+    }
+
+    Ok(failed)
+}
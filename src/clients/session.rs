@@ -0,0 +1,87 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, RwLock},
+};
+
+use crate::models::DmToken;
+
+// 账号的登录会话, 同一账号的所有客户端共用
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub cookie: String,
+    pub token: DmToken,
+    pub version: u64, // 每次更新加1, 客户端据此判断是否需要重建
+}
+
+impl Session {
+    pub fn new(cookie: String, token: DmToken) -> Self {
+        Self {
+            cookie,
+            token,
+            version: 0,
+        }
+    }
+
+    // 重新登录/刷新token后更新
+    pub fn update(&mut self, cookie: String, token: DmToken) {
+        self.cookie = cookie;
+        self.token = token;
+        self.version += 1;
+    }
+}
+
+pub type SharedSession = Arc<RwLock<Session>>;
+
+// 读取会话快照, 锁中毒时仍返回最后写入的值
+pub fn snapshot(session: &SharedSession) -> Session {
+    match session.read() {
+        Ok(session) => session.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
+
+// 会话当前的版本
+pub fn version(session: &SharedSession) -> u64 {
+    match session.read() {
+        Ok(session) => session.version,
+        Err(e) => e.into_inner().version,
+    }
+}
+
+// 更新共享会话, 其他客户端在下次同步时使用新的cookie和token
+pub fn publish(session: &SharedSession, cookie: String, token: DmToken) -> u64 {
+    let mut session = match session.write() {
+        Ok(session) => session,
+        Err(e) => e.into_inner(),
+    };
+    session.update(cookie, token);
+    session.version
+}
+
+// 按账号保存的会话, 进程内的所有任务共用
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, SharedSession>>,
+}
+
+impl SessionStore {
+    pub fn global() -> &'static SessionStore {
+        static STORE: OnceLock<SessionStore> = OnceLock::new();
+        STORE.get_or_init(SessionStore::default)
+    }
+
+    pub fn get(&self, account: &str) -> Option<SharedSession> {
+        self.sessions.lock().ok()?.get(account).cloned()
+    }
+
+    // 加入账号的会话, 已有会话时使用已有的
+    pub fn join(&self, account: &str, session: Session) -> SharedSession {
+        match self.sessions.lock() {
+            Ok(mut sessions) => sessions
+                .entry(account.to_string())
+                .or_insert_with(|| Arc::new(RwLock::new(session)))
+                .clone(),
+            Err(_) => Arc::new(RwLock::new(session)),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use serde_json::Value;
+
+use crate::models::DmToken;
+
+// token过期前提前刷新的时长, 毫秒
+const TOKEN_REFRESH_BEFORE: i64 = 60 * 1000;
+
+// h5接口签名: md5(token&t&appKey&data)
+pub fn sign(token: &str, t: &str, app_key: &str, data: &str) -> String {
+    format!(
+        "{:?}",
+        md5::compute(format!("{}&{}&{}&{}", token, t, app_key, data))
+    )
+}
+
+// 按请求参数计算签名, 写入params["sign"]
+pub fn sign_params(token: &str, params: &mut Value, data: &Value) -> Result<()> {
+    sign_json(token, params, &serde_json::to_string(data)?)
+}
+
+// 使用已序列化的data计算签名, 写入params["sign"]
+pub fn sign_json(token: &str, params: &mut Value, data: &str) -> Result<()> {
+    let t = params["t"]
+        .as_str()
+        .ok_or(anyhow!("签名缺少参数:t"))?
+        .to_string();
+    let app_key = params["appKey"]
+        .as_str()
+        .ok_or(anyhow!("签名缺少参数:appKey"))?
+        .to_string();
+    params["sign"] = sign(token, &t, &app_key, data).into();
+    Ok(())
+}
+
+// _m_h5_tk的过期时间, 格式: token_过期时间戳(毫秒)
+pub fn token_expire_at(token_with_time: &str) -> Option<i64> {
+    token_with_time
+        .split_once('_')
+        .and_then(|(_, t)| t.parse::<i64>().ok())
+}
+
+// token是否仍然可用
+pub fn token_valid(token: &DmToken, now: i64) -> bool {
+    !token.token.is_empty()
+        && token_expire_at(&token.token_with_time).is_some_and(|t| t - TOKEN_REFRESH_BEFORE > now)
+}
+
+// 按cookie缓存的token, 重建客户端时复用未过期的token
+#[derive(Debug, Default)]
+pub struct TokenCache {
+    tokens: Mutex<HashMap<String, DmToken>>,
+}
+
+impl TokenCache {
+    // 全局缓存
+    pub fn global() -> &'static TokenCache {
+        static CACHE: OnceLock<TokenCache> = OnceLock::new();
+        CACHE.get_or_init(TokenCache::default)
+    }
+
+    // 缓存键不保存cookie原文
+    fn key(cookie: &str) -> String {
+        format!("{:?}", md5::compute(cookie))
+    }
+
+    pub fn get(&self, cookie: &str) -> Option<DmToken> {
+        let tokens = self.tokens.lock().ok()?;
+        tokens
+            .get(&Self::key(cookie))
+            .filter(|token| token_valid(token, Local::now().timestamp_millis()))
+            .cloned()
+    }
+
+    pub fn put(&self, cookie: &str, token: DmToken) {
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.insert(Self::key(cookie), token);
+        }
+    }
+
+    pub fn remove(&self, cookie: &str) {
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.remove(&Self::key(cookie));
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use thirtyfour::{ChromeCapabilities, DesiredCapabilities, WebDriver};
+use tracing::warn;
+
+use crate::{config, errors::ClientError, pacer::Backoff};
+
+const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36";
+
+// 在容器中运行时等待selenium启动的默认时长, 秒
+const DEFAULT_CONTAINER_WEBDRIVER_WAIT: u64 = 60;
+
+// 重试连接webdriver的初始间隔及上限, 毫秒
+const WEBDRIVER_RETRY_BASE: u64 = 500;
+const WEBDRIVER_RETRY_MAX: u64 = 5000;
+
+// chrome启动参数
+const CHROME_ARGS: [&str; 9] = [
+    "--disable-logging",
+    "--incognito",
+    "--disable-stylesheet",
+    "--disable-infobars",
+    "--disable-software-rasterizer",
+    "--disable-extensions",
+    "--no-sandbox",
+    "--window-size=1920,1080",
+    "--single-process",
+];
+
+// chrome启动配置, 抓取ua等数据时可关闭图片加载
+pub fn chrome_caps(load_images: bool) -> Result<ChromeCapabilities> {
+    let mut caps = DesiredCapabilities::chrome();
+    caps.set_disable_dev_shm_usage()?;
+    caps.set_headless()?;
+    caps.set_disable_gpu()?;
+    caps.set_disable_web_security()?;
+    caps.set_ignore_certificate_errors()?;
+    for arg in CHROME_ARGS {
+        caps.add_chrome_arg(arg)?;
+    }
+    caps.add_chrome_arg(&format!("--user-agent={}", USER_AGENT))?;
+    if !load_images {
+        caps.add_chrome_arg("--blink-settings=imagesEnabled=false")?;
+    }
+    Ok(caps)
+}
+
+// WEBDRIVER_WAIT: webdriver未就绪时的最长等待时长, 秒; 在容器中运行时默认60, 否则不等待
+pub fn webdriver_wait() -> Duration {
+    let default = match config::in_container() {
+        true => DEFAULT_CONTAINER_WEBDRIVER_WAIT,
+        false => 0,
+    };
+    let secs = env::var("WEBDRIVER_WAIT")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(default);
+    Duration::from_secs(secs)
+}
+
+// 连接webdriver, 连接失败时按指数退避重试, 超过wait后返回错误, 用于等待selenium容器启动
+pub async fn connect(
+    webdriver_url: &str,
+    caps: ChromeCapabilities,
+    wait: Duration,
+) -> Result<WebDriver> {
+    let start = Instant::now();
+    let mut n = 0;
+    loop {
+        let e = match WebDriver::new(webdriver_url, caps.clone()).await {
+            Ok(driver) => return Ok(driver),
+            Err(e) => e,
+        };
+        n += 1;
+        let delay = Backoff::Exponential.delay(WEBDRIVER_RETRY_BASE, WEBDRIVER_RETRY_MAX, n, 0);
+        let delay = Duration::from_millis(delay);
+        if start.elapsed() + delay > wait {
+            warn!("连接webdriver失败:{}, {}", webdriver_url, e);
+            return Err(ClientError::WebdriverConnectionError.into());
+        }
+        warn!(
+            "webdriver尚未就绪:{}, {:?}后第{}次重试...",
+            webdriver_url, delay, n
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+// 创建浏览器会话, webdriver未就绪时按WEBDRIVER_WAIT等待
+pub async fn new_driver(webdriver_url: &str, caps: ChromeCapabilities) -> Result<WebDriver> {
+    connect(webdriver_url, caps, webdriver_wait()).await
+}
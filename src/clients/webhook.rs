@@ -0,0 +1,96 @@
+use std::{collections::HashMap, env, fs};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+// 通用webhook, 按模板渲染请求体后POST
+#[derive(Debug, Clone)]
+pub struct WebhookClient {
+    client: reqwest::Client,
+    url: String,
+    template: Option<String>,
+    events: Vec<String>,
+}
+
+impl WebhookClient {
+    // 从环境变量读取配置, 未配置WEBHOOK_URL时不启用:
+    // WEBHOOK_URL: 请求地址
+    // WEBHOOK_TEMPLATE: 请求体模板, 或WEBHOOK_TEMPLATE_FILE: 模板文件, 未配置时发送事件原始json
+    // WEBHOOK_EVENTS: 只发送指定的事件, 格式: order_created,run_finished
+    pub fn from_env() -> Result<Option<Self>> {
+        let url = match env::var("WEBHOOK_URL") {
+            Ok(url) if !url.is_empty() => url,
+            _ => return Ok(None),
+        };
+
+        let template = match (
+            env::var("WEBHOOK_TEMPLATE"),
+            env::var("WEBHOOK_TEMPLATE_FILE"),
+        ) {
+            (Ok(template), _) if !template.is_empty() => Some(template),
+            (_, Ok(path)) if !path.is_empty() => Some(fs::read_to_string(path)?),
+            _ => None,
+        };
+
+        let events = env::var("WEBHOOK_EVENTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|event| event.trim().to_string())
+            .filter(|event| !event.is_empty())
+            .collect();
+
+        Ok(Some(Self {
+            client: reqwest::Client::new(),
+            url,
+            template,
+            events,
+        }))
+    }
+
+    // 是否发送该事件
+    pub fn accepts(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+
+    // 发送事件, vars为模板变量
+    pub async fn send(&self, vars: &HashMap<String, Value>) -> Result<()> {
+        let body = match &self.template {
+            Some(template) => render(template, vars),
+            None => serde_json::to_string(vars)?,
+        };
+
+        let response = self
+            .client
+            .post(&self.url)
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(anyhow!("webhook返回状态码:{}", response.status())),
+        }
+    }
+}
+
+// 渲染模板, {{name}}替换为变量值, 字符串按json转义以便放在json字符串中,
+// {{payload}}替换为所有变量的json
+pub fn render(template: &str, vars: &HashMap<String, Value>) -> String {
+    let mut body = template.replace(
+        "{{payload}}",
+        &serde_json::to_string(vars).unwrap_or_default(),
+    );
+    for (name, value) in vars {
+        let value = match value {
+            Value::String(s) => {
+                let escaped = serde_json::to_string(s).unwrap_or_default();
+                escaped[1..escaped.len() - 1].to_string()
+            }
+            Value::Null => String::new(),
+            value => value.to_string(),
+        };
+        body = body.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    body
+}
@@ -0,0 +1,337 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::{errors::ClientError, i18n::Lang};
+
+// 配置目录名
+const APP_DIR: &str = "dm-ticket";
+
+// 配置文件名
+const SETTINGS_FILE: &str = "settings.toml";
+
+// 未配置时使用的默认值
+const DEFAULTS: [(&str, &str); 5] = [
+    ("RUST_LOG", "INFO"),
+    ("WEBDRIVER_URL", "http://localhost:9515"),
+    ("REDIS_URL", "redis://127.0.0.1:6379/0"),
+    ("TOTAL_TOKEN_NUM", "100"),
+    ("BATCH_TOKEN_NUM", "10"),
+];
+
+// 在容器中运行时的默认值, 按docker-compose.yml中的服务名连接selenium
+const CONTAINER_DEFAULTS: [(&str, &str); 1] = [("WEBDRIVER_URL", "http://selenium:4444")];
+
+// 存在以下文件时视为在容器中运行(docker/podman)
+const CONTAINER_MARKERS: [&str; 2] = ["/.dockerenv", "/run/.containerenv"];
+
+// 配置文件中各环境配置所在的表, 如[profiles.home]
+pub const PROFILES_KEY: &str = "profiles";
+
+// 由配置文件写入的环境变量, 重新加载配置时可覆盖
+static APPLIED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+// 配置目录, 环境变量CONFIG_DIR优先, 默认为系统配置目录下的dm-ticket:
+// Linux: ~/.config/dm-ticket, macOS: ~/Library/Application Support/dm-ticket, Windows: %APPDATA%\dm-ticket
+pub fn config_dir() -> PathBuf {
+    match env::var("CONFIG_DIR") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs_next::config_dir()
+            .map(|dir| dir.join(APP_DIR))
+            .unwrap_or_else(|| PathBuf::from(".")),
+    }
+}
+
+// 缓存目录, 用于二维码等临时文件
+pub fn cache_dir() -> PathBuf {
+    dirs_next::cache_dir()
+        .map(|dir| dir.join(APP_DIR))
+        .unwrap_or_else(env::temp_dir)
+}
+
+// 配置文件路径
+pub fn settings_path() -> PathBuf {
+    config_dir().join(SETTINGS_FILE)
+}
+
+// 配置文件, 键为环境变量名(不区分大小写), 环境变量优先于配置文件:
+// webdriver_url = "http://localhost:9515"
+// redis_url = "redis://127.0.0.1:6379/0"
+// notify_token = "..."
+//
+// 可按环境(机器)分组配置, 通过--profile或TICK_PROFILE选择, 覆盖顶层的同名配置项:
+// [profiles.vps-shanghai]
+// webdriver_url = "http://10.0.0.2:9515"
+#[derive(Deserialize, Debug, Default)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub values: HashMap<String, toml::Value>,
+}
+
+impl Settings {
+    // 读取配置文件, 不存在时为空配置
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| {
+            ClientError::InvalidSetting {
+                key: path.display().to_string(),
+                reason: e.to_string(),
+            }
+            .into()
+        })
+    }
+
+    // 环境配置名称
+    pub fn profiles(&self) -> Vec<String> {
+        let mut names = match self.values.get(PROFILES_KEY) {
+            Some(toml::Value::Table(profiles)) => profiles.keys().cloned().collect(),
+            _ => vec![],
+        };
+        names.sort();
+        names
+    }
+
+    // 环境配置中的配置项
+    pub fn profile(&self, name: &str) -> Option<&toml::Table> {
+        self.values.get(PROFILES_KEY)?.get(name)?.as_table()
+    }
+
+    // 使用指定环境的配置, 环境中的配置项覆盖顶层的同名配置项
+    pub fn with_profile(mut self, name: &str) -> Result<Self> {
+        let profile = match self.profile(name) {
+            Some(profile) => profile.clone(),
+            None => {
+                return Err(ClientError::InvalidSetting {
+                    key: "TICK_PROFILE".to_string(),
+                    reason: format!(
+                        "配置文件中没有环境:{}, 可选:{}",
+                        name,
+                        self.profiles().join(",")
+                    ),
+                }
+                .into())
+            }
+        };
+        for (key, value) in profile {
+            self.values.retain(|k, _| !k.eq_ignore_ascii_case(&key));
+            self.values.insert(key, value);
+        }
+        Ok(self)
+    }
+
+    // 配置项的字符串值, 键统一为大写
+    pub fn vars(&self) -> Vec<(String, String)> {
+        self.values
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    toml::Value::String(s) => s.clone(),
+                    toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                        value.to_string()
+                    }
+                    _ => return None,
+                };
+                Some((key.to_uppercase(), value))
+            })
+            .collect()
+    }
+
+    // 写入未设置的环境变量
+    pub fn apply(&self) {
+        let mut applied = APPLIED.lock().unwrap_or_else(|e| e.into_inner());
+        let applied = applied.get_or_insert_with(HashSet::new);
+        for (key, value) in self.vars() {
+            if env::var(&key).is_err() {
+                env::set_var(&key, value);
+                applied.insert(key);
+            }
+        }
+    }
+
+    // 重新写入配置, 覆盖之前由配置文件写入的值, 配置文件中已删除的项恢复为未设置
+    pub fn reapply(&self) {
+        let previous = APPLIED
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .unwrap_or_default();
+        let vars = self.vars();
+        for key in previous.iter() {
+            if !vars.iter().any(|(k, _)| k == key) {
+                env::remove_var(key);
+            }
+        }
+        for (key, _) in vars.iter() {
+            if previous.contains(key) {
+                env::remove_var(key);
+            }
+        }
+        self.apply();
+    }
+}
+
+// 读取必填配置
+pub fn get(key: &str) -> Result<String> {
+    match env::var(key) {
+        Ok(value) if !value.is_empty() => Ok(value),
+        _ => Err(ClientError::MissingSetting {
+            key: key.to_string(),
+        }
+        .into()),
+    }
+}
+
+// 二维码图片路径, 默认保存在缓存目录
+pub fn qrcode_path() -> Result<PathBuf> {
+    let path = match env::var("QRCODE_PATH") {
+        Ok(path) if !path.is_empty() => PathBuf::from(path),
+        _ => cache_dir().join("qrcode.png"),
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| ClientError::InvalidSetting {
+            key: "QRCODE_PATH".to_string(),
+            reason: format!("无法创建目录{}:{}", dir.display(), e),
+        })?;
+    }
+    Ok(path)
+}
+
+// 需要校验格式的数值配置, 毫秒/分钟
+const NUMERIC_KEYS: [&str; 9] = [
+    "TOTAL_TOKEN_NUM",
+    "BATCH_TOKEN_NUM",
+    "QUEUE_MAX_WAIT",
+    "QUEUE_POLL_INTERVAL",
+    "SALE_CHECK_INTERVAL",
+    "STATUS_WATCH_INTERVAL",
+    "SPIN_WAIT",
+    "HEALTH_MAX_CLOCK_OFFSET",
+    "WEBDRIVER_WAIT",
+];
+
+// 校验单个配置项的值, 返回不合法的原因, 未收录的配置项不校验
+pub fn check_value(key: &str, value: &str) -> Option<String> {
+    match key {
+        "WEBDRIVER_URL" if !value.starts_with("http://") && !value.starts_with("https://") => {
+            Some(format!("{}不是http地址", value))
+        }
+        "MTOP_RACE_PROXY" if !value.starts_with("http://") && !value.starts_with("https://") => {
+            Some(format!("{}不是http代理地址", value))
+        }
+        "REDIS_URL" if !value.starts_with("redis://") && !value.starts_with("rediss://") => {
+            Some(format!("{}不是redis地址", value))
+        }
+        "SALE_TIMEZONE" if value.parse::<chrono_tz::Tz>().is_err() => {
+            Some(format!("未知的时区:{}, 如Asia/Shanghai", value))
+        }
+        "REQUEST_TIME_OFFSET" if value.parse::<i64>().is_err() => {
+            Some("应为整数, 毫秒".to_string())
+        }
+        "TICK_LANG" => value.parse::<Lang>().err().map(|e| e.to_string()),
+        "EXPERIMENT_VARIANTS" => crate::experiment::parse_variants(value)
+            .err()
+            .map(|e| e.to_string()),
+        key if NUMERIC_KEYS.contains(&key) && value.parse::<u32>().is_err() => {
+            Some("应为正整数".to_string())
+        }
+        _ => None,
+    }
+}
+
+// 校验配置
+pub fn validate() -> Result<()> {
+    for key in [
+        "WEBDRIVER_URL",
+        "REDIS_URL",
+        "TOTAL_TOKEN_NUM",
+        "BATCH_TOKEN_NUM",
+    ] {
+        if let Some(reason) = check_value(key, &get(key)?) {
+            return Err(ClientError::InvalidSetting {
+                key: key.to_string(),
+                reason,
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+// 使用的环境配置, 由--profile或环境变量TICK_PROFILE指定
+pub fn profile() -> Option<String> {
+    env::var("TICK_PROFILE")
+        .ok()
+        .filter(|name| !name.is_empty())
+}
+
+// 读取settings.toml, 并应用选择的环境配置
+fn load_settings() -> Result<Settings> {
+    let settings = Settings::load(&settings_path())?;
+    match profile() {
+        Some(name) => settings.with_profile(&name),
+        None => Ok(settings),
+    }
+}
+
+// 启动时加载配置: 环境变量 > .env > settings.toml(环境配置 > 顶层) > 默认值, 并校验
+pub fn init() -> Result<()> {
+    load_settings()?.apply();
+    apply_defaults();
+    validate()
+}
+
+// 重新加载settings.toml, 环境变量/.env中的配置不变, 用于守护进程收到SIGHUP时
+pub fn reload() -> Result<()> {
+    let settings = load_settings()?;
+    settings.reapply();
+    apply_defaults();
+    validate()
+}
+
+// 是否在容器中运行, CONTAINER_MODE=true/false时强制开启/关闭, 未配置时自动识别
+pub fn in_container() -> bool {
+    match env::var("CONTAINER_MODE")
+        .map(|v| v.to_lowercase())
+        .as_deref()
+    {
+        Ok("true") => true,
+        Ok("false") => false,
+        _ => {
+            CONTAINER_MARKERS
+                .iter()
+                .any(|path| Path::new(path).exists())
+                || env::var("KUBERNETES_SERVICE_HOST").is_ok()
+        }
+    }
+}
+
+// 是否不连接webdriver, SKIP_WEBDRIVER=true时已有登录会话的流程不再打开浏览器
+pub fn skip_webdriver() -> bool {
+    env::var("SKIP_WEBDRIVER")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+fn apply_defaults() {
+    if in_container() {
+        for (key, value) in CONTAINER_DEFAULTS {
+            if env::var(key).is_err() {
+                env::set_var(key, value);
+            }
+        }
+    }
+    for (key, value) in DEFAULTS {
+        if env::var(key).is_err() {
+            env::set_var(key, value);
+        }
+    }
+}
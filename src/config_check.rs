@@ -0,0 +1,440 @@
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::{
+    config::{self, Settings},
+    format_timestamp,
+    models::{seat::SeatStrategy, task::Task},
+    platform::TicketPlatform,
+    resume::{ArmedStore, ArmedTask},
+};
+
+// 配置检查发现的问题
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub file: String,
+    pub line: Option<usize>, // 所在行, 从1开始, 无法定位时为空
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(
+                f,
+                "{}:{}: {}: {}",
+                self.file, line, self.field, self.message
+            ),
+            None => write!(f, "{}: {}: {}", self.file, self.field, self.message),
+        }
+    }
+}
+
+// 默认检查的文件: settings.toml及任务目录(ARMED_DIR)中的任务文件
+pub fn default_files() -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let settings = config::settings_path();
+    if settings.exists() {
+        files.push(settings);
+    }
+    let store = ArmedStore::from_env();
+    if store.dir().exists() {
+        let mut tasks = vec![];
+        for entry in fs::read_dir(store.dir())? {
+            let path = entry?.path();
+            if is_task_file(&path) {
+                tasks.push(path);
+            }
+        }
+        tasks.sort();
+        files.extend(tasks);
+    }
+    Ok(files)
+}
+
+// 任务文件为json, 其余按配置文件(toml)检查
+pub fn is_task_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+// 检查单个文件
+pub fn check_file(path: &Path, now: i64) -> Result<Vec<Issue>> {
+    let content = fs::read_to_string(path)?;
+    let file = path.display().to_string();
+    Ok(match is_task_file(path) {
+        true => check_task_file(&file, &content, now),
+        false => check_settings(&file, &content),
+    })
+}
+
+// 检查配置文件, 键不区分大小写
+pub fn check_settings(file: &str, content: &str) -> Vec<Issue> {
+    let settings = match toml::from_str::<Settings>(content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            return vec![Issue {
+                file: file.to_string(),
+                line: e.span().map(|span| line_at(content, span.start)),
+                field: "-".to_string(),
+                message: e.message().trim().to_string(),
+            }]
+        }
+    };
+
+    let mut issues = settings
+        .values
+        .iter()
+        .filter(|(key, _)| key.as_str() != config::PROFILES_KEY)
+        .filter_map(|(key, value)| {
+            Some(Issue {
+                file: file.to_string(),
+                line: find_line(content, key, '='),
+                field: key.clone(),
+                message: check_entry(key, value)?,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // 环境配置中的配置项, 行号从对应的表头开始查找
+    for name in settings.profiles() {
+        let header = format!("[{}.{}]", config::PROFILES_KEY, name);
+        for (key, value) in settings.profile(&name).into_iter().flatten() {
+            if let Some(message) = check_entry(key, value) {
+                issues.push(Issue {
+                    file: file.to_string(),
+                    line: find_line_in(content, &header, key),
+                    field: format!("{}.{}.{}", config::PROFILES_KEY, name, key),
+                    message,
+                });
+            }
+        }
+    }
+    issues.sort_by_key(|issue| issue.line.unwrap_or(usize::MAX));
+
+    if let Some(name) = config::profile().filter(|name| settings.profile(name).is_none()) {
+        issues.push(Issue {
+            file: file.to_string(),
+            line: None,
+            field: config::PROFILES_KEY.to_string(),
+            message: format!(
+                "没有TICK_PROFILE/--profile指定的环境:{}, 可选:{}",
+                name,
+                settings.profiles().join(",")
+            ),
+        });
+    }
+    issues
+}
+
+// 校验单个配置项, 返回不合法的原因
+fn check_entry(key: &str, value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => config::check_value(&key.to_uppercase(), s),
+        toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+            config::check_value(&key.to_uppercase(), &value.to_string())
+        }
+        _ => Some("不支持的值类型, 应为字符串/数字/布尔值".to_string()),
+    }
+}
+
+// 检查任务文件
+pub fn check_task_file(file: &str, content: &str, now: i64) -> Vec<Issue> {
+    match serde_json::from_str::<ArmedTask>(content) {
+        Ok(armed) => locate(file, content, check_armed(&armed, now)),
+        Err(e) => vec![Issue {
+            file: file.to_string(),
+            line: Some(e.line()).filter(|line| *line > 0),
+            field: "-".to_string(),
+            message: e.to_string(),
+        }],
+    }
+}
+
+// 检查任务文件中的任务、账号及开售时间
+pub fn check_armed(armed: &ArmedTask, now: i64) -> Vec<(&'static str, String)> {
+    let mut issues = check_task(&armed.task);
+    if armed.account.trim().is_empty() {
+        issues.push(("account", "账号名称为空".to_string()));
+    }
+    if let Some(sale_start) = armed.sale_start.filter(|_| armed.expired(now)) {
+        issues.push((
+            "sale_start",
+            format!(
+                "开售时间{}已超过恢复时限, 任务不会恢复, 请删除该文件",
+                format_timestamp(sale_start, armed.task.tz())
+            ),
+        ));
+    }
+    issues
+}
+
+// 检查任务字段之间的约束, 返回(字段, 原因)
+pub fn check_task(task: &Task) -> Vec<(&'static str, String)> {
+    let mut issues = vec![];
+    let tour = task.keyword.is_some();
+
+    // 巡演任务开售前按城市选择门票, 无需配置ID
+    if !tour {
+        let ids = [
+            ("ticket_id", &task.ticket_id),
+            ("ticket_perform_id", &task.ticket_perform_id),
+            ("ticket_perform_sku_id", &task.ticket_perform_sku_id),
+        ];
+        for (field, id) in ids {
+            if id.trim().is_empty() {
+                issues.push((field, "未配置巡演关键词keyword时不能为空".to_string()));
+            }
+        }
+    }
+    if !task.cities.is_empty() && !tour {
+        issues.push(("cities", "按城市选择门票需同时配置keyword".to_string()));
+    }
+
+    if task.ticket_num == 0 {
+        issues.push(("ticket_num", "购票数量应大于0".to_string()));
+    }
+    if !task.viewers.is_empty() {
+        if task.viewers.len() != task.ticket_num {
+            issues.push((
+                "viewers",
+                format!(
+                    "观演人数量:{}与购票数量:{}不一致",
+                    task.viewers.len(),
+                    task.ticket_num
+                ),
+            ));
+        }
+        if has_duplicates(&task.viewers) {
+            issues.push(("viewers", "观演人重复".to_string()));
+        }
+    } else if !task.real_names.is_empty() {
+        if task.real_names.len() != task.ticket_num {
+            issues.push((
+                "real_names",
+                format!(
+                    "实名人数量:{}与购票数量:{}不一致",
+                    task.real_names.len(),
+                    task.ticket_num
+                ),
+            ));
+        }
+        if task.real_names.contains(&0) {
+            issues.push(("real_names", "实名人序号从1开始".to_string()));
+        }
+        if has_duplicates(&task.real_names) {
+            issues.push(("real_names", "实名人重复".to_string()));
+        }
+    }
+
+    if !tour && task.backup_sku_ids.contains(&task.ticket_perform_sku_id) {
+        issues.push((
+            "backup_sku_ids",
+            format!("备选票档与首选票档{}重复", task.ticket_perform_sku_id),
+        ));
+    }
+    if task.timezone.parse::<Tz>().is_err() {
+        issues.push((
+            "timezone",
+            format!("未知的时区:{}, 如Asia/Shanghai", task.timezone),
+        ));
+    }
+    if task
+        .stop_on_error_codes
+        .iter()
+        .any(|code| code.trim().is_empty())
+    {
+        issues.push(("stop_on_error_codes", "错误码不能为空".to_string()));
+    }
+    if task
+        .privilege_code
+        .as_deref()
+        .is_some_and(|code| code.trim().is_empty())
+    {
+        issues.push((
+            "privilege_code",
+            "优先购码为空, 不使用时请删除该字段".to_string(),
+        ));
+    }
+    if let Some(seats) = &task.seats {
+        if seats.strategy == SeatStrategy::Explicit && seats.seat_ids.len() != task.ticket_num {
+            issues.push((
+                "seat_ids",
+                format!(
+                    "指定座位数量:{}与购票数量:{}不一致",
+                    seats.seat_ids.len(),
+                    task.ticket_num
+                ),
+            ));
+        }
+    }
+    issues
+}
+
+// 在线检查任务文件: 场次/票档是否属于该门票, 是否已开售
+pub async fn check_file_online<P: TicketPlatform>(
+    platform: &P,
+    path: &Path,
+    now: i64,
+) -> Result<Vec<Issue>> {
+    let content = fs::read_to_string(path)?;
+    let armed = match serde_json::from_str::<ArmedTask>(&content) {
+        Ok(armed) => armed,
+        Err(_) => return Ok(vec![]), // 解析失败已在离线检查中报告
+    };
+    let issues = check_online(platform, &armed.task, now).await;
+    Ok(locate(&path.display().to_string(), &content, issues))
+}
+
+// 查询门票/场次/票档, 校验任务中的ID是否对应
+pub async fn check_online<P: TicketPlatform>(
+    platform: &P,
+    task: &Task,
+    now: i64,
+) -> Vec<(&'static str, String)> {
+    let mut issues = vec![];
+    // 巡演任务开售前才确定门票
+    if task.keyword.is_some() {
+        return issues;
+    }
+
+    match platform.detail(&task.ticket_id).await {
+        Ok(detail) => {
+            if detail.sell_start_timestamp <= 0 && !task.watch_status {
+                issues.push((
+                    "watch_status",
+                    format!(
+                        "{}未公布开售时间, 可开启watch_status监控票档状态",
+                        detail.item_name
+                    ),
+                ));
+            } else if detail.sell_start_timestamp <= now && !task.watch_status {
+                issues.push((
+                    "ticket_id",
+                    format!("{}已于{}开售", detail.item_name, detail.sell_start_time_str),
+                ));
+            }
+            if let Err(e) = detail.validate(task) {
+                issues.push(("ticket_num", e.to_string()));
+            }
+        }
+        Err(e) => {
+            issues.push(("ticket_id", format!("查询门票失败:{}", e)));
+            return issues;
+        }
+    }
+
+    match platform.performs(&task.ticket_id).await {
+        Ok(performs)
+            if !performs
+                .iter()
+                .any(|p| p.perform_id == task.ticket_perform_id) =>
+        {
+            let names = performs
+                .iter()
+                .map(|p| format!("{}({})", p.perfrom_name, p.perform_id))
+                .collect::<Vec<_>>();
+            issues.push((
+                "ticket_perform_id",
+                format!(
+                    "场次{}不属于门票{}, 可选: {}",
+                    task.ticket_perform_id,
+                    task.ticket_id,
+                    names.join(", ")
+                ),
+            ));
+            return issues;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            issues.push(("ticket_perform_id", format!("查询场次失败:{}", e)));
+            return issues;
+        }
+    }
+
+    match platform
+        .skus(&task.ticket_id, &task.ticket_perform_id)
+        .await
+    {
+        Ok(skus) => {
+            let names = skus
+                .iter()
+                .map(|sku| format!("{}({})", sku.sku_name, sku.sku_id))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let sku_ids = std::iter::once(("ticket_perform_sku_id", &task.ticket_perform_sku_id))
+                .chain(task.backup_sku_ids.iter().map(|id| ("backup_sku_ids", id)));
+            for (field, sku_id) in sku_ids {
+                if !skus.iter().any(|sku| &sku.sku_id == sku_id) {
+                    issues.push((
+                        field,
+                        format!(
+                            "票档{}不属于场次{}, 可选: {}",
+                            sku_id, task.ticket_perform_id, names
+                        ),
+                    ));
+                }
+            }
+        }
+        Err(e) => issues.push(("ticket_perform_sku_id", format!("查询票档失败:{}", e))),
+    }
+    issues
+}
+
+// 按字段名定位任务文件中的行号
+fn locate(file: &str, content: &str, issues: Vec<(&'static str, String)>) -> Vec<Issue> {
+    issues
+        .into_iter()
+        .map(|(field, message)| Issue {
+            file: file.to_string(),
+            line: find_line(content, &format!("\"{}\"", field), ':'),
+            field: field.to_string(),
+            message,
+        })
+        .collect()
+}
+
+// 以key开头、其后为分隔符的第一行
+fn find_line(content: &str, key: &str, sep: char) -> Option<usize> {
+    content
+        .lines()
+        .position(|line| {
+            line.trim_start()
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with(sep))
+        })
+        .map(|idx| idx + 1)
+}
+
+// 表头(如[profiles.home])之后、下一个表头之前以key开头的行
+fn find_line_in(content: &str, header: &str, key: &str) -> Option<usize> {
+    let start = content.lines().position(|line| line.trim() == header)? + 1;
+    let section = content
+        .lines()
+        .skip(start)
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .collect::<Vec<_>>()
+        .join("\n");
+    find_line(&section, key, '=').map(|line| line + start)
+}
+
+// 字节偏移所在的行号
+fn line_at(content: &str, offset: usize) -> usize {
+    content.as_bytes()[..offset.min(content.len())]
+        .iter()
+        .filter(|b| **b == b'\n')
+        .count()
+        + 1
+}
+
+fn has_duplicates<T: Eq + std::hash::Hash>(items: &[T]) -> bool {
+    let mut seen = HashSet::new();
+    !items.iter().all(|item| seen.insert(item))
+}
@@ -0,0 +1,311 @@
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    account::AccountStore,
+    config, experiment,
+    health::{Health, HealthServer},
+    resume::{restore_cookie, run_armed, ArmedStore},
+};
+
+// 守护进程配置
+#[derive(Debug, Clone)]
+pub struct DaemonOptions {
+    pub pid_file: Option<PathBuf>,
+    pub scan_interval: Duration, // 扫描任务目录的间隔, 发现新任务后启动
+}
+
+// systemd通知, 未在systemd(Type=notify)下运行时忽略
+#[derive(Debug, Default)]
+pub struct SdNotify {
+    socket: Option<String>,
+}
+
+impl SdNotify {
+    // 环境变量NOTIFY_SOCKET由systemd设置, 以@开头时为抽象socket
+    pub fn from_env() -> Self {
+        Self {
+            socket: env::var("NOTIFY_SOCKET").ok().filter(|s| !s.is_empty()),
+        }
+    }
+
+    pub fn notify(&self, state: &str) {
+        let socket = match &self.socket {
+            Some(socket) => socket,
+            None => return,
+        };
+        if let Err(e) = send(socket, state) {
+            debug!("sd_notify失败:{:?}", e);
+        }
+    }
+
+    // 看门狗间隔, 取WATCHDOG_USEC的一半; WATCHDOG_PID不是本进程时不启用
+    pub fn watchdog_interval() -> Option<Duration> {
+        if let Ok(pid) = env::var("WATCHDOG_PID") {
+            if pid.parse::<u32>().ok() != Some(process::id()) {
+                return None;
+            }
+        }
+        env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|usec| *usec > 0)
+            .map(|usec| Duration::from_micros(usec / 2))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn send(socket: &str, state: &str) -> Result<()> {
+    use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+
+    let sock = UnixDatagram::unbound()?;
+    match socket.strip_prefix('@') {
+        Some(name) => {
+            let addr = SocketAddr::from_abstract_name(name.as_bytes())?;
+            sock.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        None => {
+            sock.send_to(state.as_bytes(), socket)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn send(socket: &str, state: &str) -> Result<()> {
+    UnixDatagram::unbound()?.send_to(state.as_bytes(), socket)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &str, _state: &str) -> Result<()> {
+    Ok(())
+}
+
+// pid文件, 退出时删除
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    // 已有进程在运行时返回错误
+    pub fn create(path: &Path) -> Result<Self> {
+        if let Ok(content) = fs::read_to_string(path) {
+            let pid = content.trim();
+            if !pid.is_empty() && Path::new("/proc").join(pid).exists() {
+                return Err(anyhow!(
+                    "守护进程已在运行, pid:{}, pid文件:{}",
+                    pid,
+                    path.display()
+                ));
+            }
+        }
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, format!("{}\n", process::id()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// 正在运行的任务, 键为任务文件名
+#[derive(Default)]
+struct Tasks {
+    running: HashMap<String, JoinHandle<bool>>,
+    failed: HashSet<String>, // 启动失败的任务, 重新加载配置后重试
+}
+
+impl Tasks {
+    // 扫描任务目录, 启动未运行的任务
+    async fn scan(&mut self) -> Result<()> {
+        let finished = self
+            .running
+            .iter()
+            .filter(|(_, handle)| handle.is_finished())
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        for name in finished {
+            if let Some(handle) = self.running.remove(&name) {
+                if !handle.await.unwrap_or(false) {
+                    self.failed.insert(name);
+                }
+            }
+        }
+
+        let store = ArmedStore::from_env();
+        let now = Local::now().timestamp_millis();
+        let mut accounts = None;
+        let variants = experiment::variants()?;
+        let mut list = store.list()?;
+        experiment::assign(list.iter_mut().map(|armed| &mut armed.task), &variants);
+        for armed in list {
+            let file_name = armed.file_name();
+            if self.running.contains_key(&file_name) || self.failed.contains(&file_name) {
+                continue;
+            }
+            let name = format!("{}, {}", armed.task.nickname, armed.task.ticket_name);
+            if armed.expired(now) {
+                info!("{}, 已开售超过恢复时限, 删除任务", name);
+                store.remove(&armed)?;
+                continue;
+            }
+            if accounts.is_none() {
+                accounts = Some(AccountStore::from_env()?);
+            }
+            let cookie = match restore_cookie(accounts.as_ref().unwrap(), &armed.account).await {
+                Ok(cookie) => cookie,
+                Err(e) => {
+                    error!("{}, {}", name, e);
+                    self.failed.insert(file_name);
+                    continue;
+                }
+            };
+
+            info!("{}, 启动抢票任务...", name);
+            let handle = tokio::spawn(async move {
+                match run_armed(cookie, armed.task, armed.account).await {
+                    Ok(_) => true,
+                    Err(e) => {
+                        error!("{}, 抢票任务失败:{}", name, e);
+                        false
+                    }
+                }
+            });
+            self.running.insert(file_name, handle);
+        }
+        Ok(())
+    }
+
+    // 扫描任务目录, 并更新健康检查的调度状态
+    async fn rescan(&mut self, interval: Duration) {
+        let res = self.scan().await;
+        if let Err(e) = &res {
+            warn!("扫描任务失败:{:?}", e);
+        }
+        Health::global().scanned(
+            interval,
+            self.running.len(),
+            self.failed.len(),
+            res.err().map(|e| e.to_string()),
+        );
+    }
+
+    fn status(&self) -> String {
+        format!("STATUS=运行中的任务:{}个", self.running.len())
+    }
+
+    fn abort(&mut self) {
+        for (_, handle) in self.running.drain() {
+            handle.abort();
+        }
+    }
+}
+
+// 守护进程的控制指令, 来自信号或Windows服务管理器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Reload, // 重新加载settings.toml并重试启动失败的任务
+    Stop,   // 停止所有任务后退出
+}
+
+// 运行任务目录(ARMED_DIR)中的任务, 定时扫描新任务, 收到Stop或指令通道关闭时退出
+pub async fn serve(
+    options: DaemonOptions,
+    control: async_channel::Receiver<Control>,
+    notifier: &SdNotify,
+) -> Result<()> {
+    let _pid_file = options
+        .pid_file
+        .as_deref()
+        .map(PidFile::create)
+        .transpose()?;
+    let _health = HealthServer::from_env().await?;
+
+    let mut scan_timer = tokio::time::interval(options.scan_interval);
+    let watchdog = SdNotify::watchdog_interval();
+    let mut watchdog_timer = tokio::time::interval(watchdog.unwrap_or(Duration::from_secs(3600)));
+
+    let mut tasks = Tasks::default();
+    tasks.rescan(options.scan_interval).await;
+    info!("守护进程已启动, pid:{}", process::id());
+    notifier.notify(&format!(
+        "READY=1\nMAINPID={}\n{}",
+        process::id(),
+        tasks.status()
+    ));
+
+    loop {
+        tokio::select! {
+            control = control.recv() => match control {
+                Ok(Control::Reload) => {
+                    info!("重新加载配置...");
+                    notifier.notify("RELOADING=1");
+                    if let Err(e) = config::reload() {
+                        error!("重新加载配置失败, 继续使用原配置:{:?}", e);
+                    }
+                    tasks.failed.clear();
+                    tasks.rescan(options.scan_interval).await;
+                    notifier.notify(&format!("READY=1\n{}", tasks.status()));
+                }
+                Ok(Control::Stop) | Err(_) => break,
+            },
+            _ = scan_timer.tick() => {
+                tasks.rescan(options.scan_interval).await;
+                notifier.notify(&tasks.status());
+            }
+            _ = watchdog_timer.tick(), if watchdog.is_some() => {
+                notifier.notify("WATCHDOG=1");
+            }
+        }
+    }
+
+    info!("守护进程正在停止...");
+    notifier.notify("STOPPING=1");
+    tasks.abort();
+    Ok(())
+}
+
+// 以守护进程运行, SIGHUP: 重新加载配置; SIGTERM/SIGINT: 停止所有任务后退出
+#[cfg(unix)]
+pub async fn run(options: DaemonOptions) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+
+    let (tx, rx) = async_channel::unbounded();
+    tokio::spawn(async move {
+        loop {
+            let control = tokio::select! {
+                _ = hangup.recv() => Control::Reload,
+                _ = terminate.recv() => Control::Stop,
+                _ = interrupt.recv() => Control::Stop,
+            };
+            if tx.send(control).await.is_err() || control == Control::Stop {
+                break;
+            }
+        }
+    });
+    serve(options, rx, &SdNotify::from_env()).await
+}
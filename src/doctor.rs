@@ -0,0 +1,300 @@
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+#[cfg(feature = "browser-login")]
+use thirtyfour::{DesiredCapabilities, WebDriver};
+
+use crate::{
+    clients::{dm::DmClient, endpoint::EndpointRegistry},
+    config,
+    platform::TicketPlatform,
+};
+
+// 测试网关往返耗时的次数
+const RTT_SAMPLES: usize = 3;
+
+// 单项检查超时, 秒
+const CHECK_TIMEOUT: u64 = 10;
+
+// 服务器时钟偏差超过该值时提示, 毫秒
+const CLOCK_OFFSET_WARN: i64 = 100;
+
+// 网关往返耗时超过该值时提示, 毫秒
+const RTT_WARN: u128 = 200;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skip,
+}
+
+impl CheckStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "通过",
+            CheckStatus::Warn => "警告",
+            CheckStatus::Fail => "失败",
+            CheckStatus::Skip => "跳过",
+        }
+    }
+}
+
+// 单项检查结果
+#[derive(Serialize, Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub hint: Option<&'static str>, // 失败时的处理建议
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: String) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail,
+            hint: None,
+        }
+    }
+
+    fn fail(name: &'static str, detail: String, hint: &'static str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail,
+            hint: Some(hint),
+        }
+    }
+
+    fn warn(name: &'static str, detail: String, hint: &'static str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail,
+            hint: Some(hint),
+        }
+    }
+
+    fn skip(name: &'static str, detail: &str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Skip,
+            detail: detail.to_string(),
+            hint: None,
+        }
+    }
+}
+
+async fn with_timeout<T>(fut: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(Duration::from_secs(CHECK_TIMEOUT), fut)
+        .await
+        .map_err(|_| anyhow!("超时({}秒)", CHECK_TIMEOUT))?
+}
+
+// webdriver是否可以连接, 返回chromedriver版本
+async fn check_webdriver(webdriver_url: &str) -> Check {
+    let name = "webdriver连接";
+    let url = format!("{}/status", webdriver_url.trim_end_matches('/'));
+    let res = with_timeout(async {
+        Ok::<_, anyhow::Error>(reqwest::get(&url).await?.json::<Value>().await?)
+    })
+    .await;
+    match res {
+        Ok(value) => {
+            let version = value["value"]["build"]["version"]
+                .as_str()
+                .unwrap_or("未知版本");
+            Check::pass(name, format!("{}, chromedriver:{}", webdriver_url, version))
+        }
+        Err(e) => Check::fail(
+            name,
+            format!("{}, {}", webdriver_url, e),
+            "请确认chromedriver(--port=9515 --whitelisted-ips=)或selenium已启动并检查WEBDRIVER_URL",
+        ),
+    }
+}
+
+// 创建浏览器会话, chromedriver与chrome版本不一致时会失败
+#[cfg(feature = "browser-login")]
+async fn check_browser(webdriver_url: &str) -> Check {
+    let name = "chrome版本";
+    let res = with_timeout(async {
+        let mut caps = DesiredCapabilities::chrome();
+        caps.set_headless()?;
+        caps.set_disable_dev_shm_usage()?;
+        caps.add_chrome_arg("--no-sandbox")?;
+        let driver = WebDriver::new(webdriver_url, caps).await?;
+        let _ = driver.quit().await;
+        Ok::<_, anyhow::Error>(())
+    })
+    .await;
+    match res {
+        Ok(_) => Check::pass(name, "chromedriver与chrome版本匹配".to_string()),
+        Err(e) => Check::fail(
+            name,
+            e.to_string(),
+            "请下载与chrome版本一致的chromedriver(地址栏输入chrome://version/查看版本)",
+        ),
+    }
+}
+
+#[cfg(not(feature = "browser-login"))]
+async fn check_browser(_webdriver_url: &str) -> Check {
+    Check::skip("chrome版本", "未启用browser-login功能")
+}
+
+// cookie是否有效
+async fn check_cookie(dm: &DmClient, has_cookie: bool) -> Check {
+    let name = "cookie";
+    if !has_cookie {
+        return Check::skip(name, "未指定账号(--account)");
+    }
+    match with_timeout(dm.user_info()).await {
+        Ok(user) => Check::pass(name, format!("登录有效, 昵称:{}", user.nickname)),
+        Err(e) => Check::fail(
+            name,
+            e.to_string(),
+            "cookie已失效, 请执行dm-client accounts add <name>重新登录",
+        ),
+    }
+}
+
+// 本地时钟与服务器时钟的偏差
+async fn check_clock(dm: &DmClient) -> Check {
+    let name = "服务器时钟";
+    match with_timeout(dm.clock_offset()).await {
+        Ok(offset) if offset.abs() > CLOCK_OFFSET_WARN => Check::warn(
+            name,
+            format!("偏差:{}毫秒", offset),
+            "本机时钟偏差较大, 抢票时会自动校准, 建议同步系统时间(ntp)",
+        ),
+        Ok(offset) => Check::pass(name, format!("偏差:{}毫秒", offset)),
+        Err(e) => Check::fail(name, e.to_string(), "无法获取服务器时间, 请检查网络"),
+    }
+}
+
+// 各网关往返耗时
+async fn check_rtt(dm: &DmClient) -> Vec<Check> {
+    let name = "网关延迟";
+    let mut checks = vec![];
+    for gateway in dm.endpoints.gateways() {
+        let mut samples = vec![];
+        let mut error = None;
+        for _ in 0..RTT_SAMPLES {
+            let start = Instant::now();
+            let res = with_timeout(async {
+                Ok::<_, anyhow::Error>(dm.client.head(&gateway).send().await?)
+            })
+            .await;
+            match res {
+                Ok(_) => samples.push(start.elapsed().as_millis()),
+                Err(e) => error = Some(e),
+            }
+        }
+        let check = match (samples.iter().min(), error) {
+            (None, Some(e)) => Check::fail(
+                name,
+                format!("{}, {}", gateway, e),
+                "无法连接网关, 请检查网络/代理或MTOP_GATEWAY配置",
+            ),
+            (Some(min), _) if *min > RTT_WARN => Check::warn(
+                name,
+                format!("{}, 最小耗时:{}毫秒", gateway, min),
+                "网络延迟较高, 建议使用MTOP_IP_RACE=true或更换网络",
+            ),
+            (Some(min), _) => Check::pass(name, format!("{}, 最小耗时:{}毫秒", gateway, min)),
+            (None, None) => Check::skip(name, "未测试"),
+        };
+        checks.push(check);
+    }
+    checks
+}
+
+// 配置了代理时检查代理是否可用
+async fn check_proxy(endpoints: &EndpointRegistry) -> Check {
+    let name = "代理";
+    let proxy = ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+        .iter()
+        .find_map(|key| env::var(key).ok().filter(|v| !v.is_empty()));
+    let proxy = match proxy {
+        Some(proxy) => proxy,
+        None => return Check::skip(name, "未配置代理"),
+    };
+
+    let res = with_timeout(async {
+        let client = reqwest::Client::builder()
+            .proxy(reqwest::Proxy::all(&proxy)?)
+            .build()?;
+        Ok::<_, anyhow::Error>(client.head(&endpoints.gateway).send().await?)
+    })
+    .await;
+    match res {
+        Ok(_) => Check::pass(name, proxy),
+        Err(e) => Check::fail(
+            name,
+            format!("{}, {}", proxy, e),
+            "代理不可用, 请检查代理配置或取消HTTPS_PROXY/ALL_PROXY",
+        ),
+    }
+}
+
+// 执行所有检查
+pub async fn diagnose(webdriver_url: &str, cookie: Option<String>) -> Result<Vec<Check>> {
+    let has_cookie = cookie.is_some();
+    // 已有登录会话且配置SKIP_WEBDRIVER=true时不需要webdriver
+    let mut checks = match has_cookie && config::skip_webdriver() {
+        true => vec![
+            Check::skip("webdriver连接", "已配置SKIP_WEBDRIVER=true"),
+            Check::skip("chrome版本", "已配置SKIP_WEBDRIVER=true"),
+        ],
+        false => vec![
+            check_webdriver(webdriver_url).await,
+            check_browser(webdriver_url).await,
+        ],
+    };
+
+    match with_timeout(DmClient::new(cookie, None)).await {
+        Ok(dm) => {
+            checks.push(check_cookie(&dm, has_cookie).await);
+            checks.push(check_clock(&dm).await);
+            checks.extend(check_rtt(&dm).await);
+            checks.push(check_proxy(&dm.endpoints).await);
+        }
+        Err(e) => checks.push(Check::fail(
+            "mtop网关",
+            e.to_string(),
+            "无法获取token, 请检查网络/代理或MTOP_GATEWAY配置",
+        )),
+    }
+    Ok(checks)
+}
+
+// 输出检查报告, 返回是否全部通过
+pub fn report(checks: &[Check]) -> bool {
+    for check in checks {
+        println!(
+            "[{}] {}: {}",
+            check.status.label(),
+            check.name,
+            check.detail
+        );
+        if let Some(hint) = check.hint {
+            println!("       建议: {}", hint);
+        }
+    }
+    passed(checks)
+}
+
+// 是否没有失败的检查项
+pub fn passed(checks: &[Check]) -> bool {
+    !checks.iter().any(|check| check.status == CheckStatus::Fail)
+}
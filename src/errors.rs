@@ -1,5 +1,6 @@
 use thiserror::Error;
 
+#[cfg(feature = "browser-login")]
 #[derive(Error, Debug)]
 pub(crate) enum ServerError {
     #[error("无法成功连接webdriver")]
@@ -17,14 +18,77 @@ pub(crate) enum ClientError {
     #[error("无法成功连接redis")]
     RedisConnectionError,
 
+    #[cfg(feature = "browser-login")]
     #[error("无法成功连接webdriver")]
     WebdriverConnectionError,
 
+    #[cfg(any(feature = "http-login", feature = "interactive"))]
     #[error("登录失败")]
     LoginFailed,
 
+    #[cfg(feature = "interactive")]
     #[error("cookie有错误")]
     CookieError,
+
+    #[error("缺少配置:{key}, 请设置环境变量或在settings.toml中配置")]
+    MissingSetting { key: String },
+
+    #[error("配置:{key}错误, {reason}")]
+    InvalidSetting { key: String, reason: String },
+}
+
+#[derive(Error, Debug)]
+pub enum PlatformError {
+    #[error("{platform}平台暂不支持{action}")]
+    Unsupported {
+        platform: &'static str,
+        action: &'static str,
+    },
+
+    #[error("需要完成人机验证:{url}")]
+    Challenge { url: String },
+
+    #[error("触发风控:{message}")]
+    RiskControl { message: String },
+
+    #[error("登录已过期, 请重新登录")]
+    SessionExpired,
+
+    #[error("排队{waited_ms}毫秒仍未轮到, {status}")]
+    Queued { waited_ms: u64, status: String },
+}
+
+#[derive(Error, Debug)]
+pub enum AccountError {
+    #[error("主密码错误或账号文件已损坏")]
+    InvalidPassphrase,
+
+    #[error("账号文件已损坏")]
+    Corrupted,
+
+    #[error("账号:{name}不存在")]
+    NotFound { name: String },
+}
+
+#[derive(Error, Debug)]
+pub enum ViewerError {
+    #[error("实名观演人:{key}不存在, 请先在大麦App或通过viewers add添加")]
+    NotFound { key: String },
+
+    #[error("匹配到多位实名观演人:{key}, 请使用完整姓名或更多位证件号")]
+    Ambiguous { key: String },
+
+    #[error("证件号:{cert_no}格式错误")]
+    InvalidCertNo { cert_no: String },
+
+    #[error("实名观演人只有{have}位, 少于购票数量{need}, 请先添加实名观演人")]
+    NotEnough { need: usize, have: usize },
+
+    #[error("指定了{have}位不同的实名观演人, 与购票数量{need}不一致")]
+    Mismatch { need: usize, have: usize },
+
+    #[error("实名观演人:{name}证件信息不完整")]
+    Incomplete { name: String },
 }
 
 // Api返回的错误信息
@@ -42,3 +106,69 @@ pub enum DmApiError {
     #[error("F-10001-10-16-103::对不起，系统繁忙，请稍候再试")]
     BuildOrderSystemBusy,
 }
+
+// mtop接口ret返回的错误类型
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MtopError {
+    #[error("FAIL_SYS_TOKEN_EMPTY::令牌为空")]
+    TokenEmpty,
+
+    #[error("FAIL_SYS_SESSION_EXPIRED::登录已过期")]
+    SessionExpired,
+
+    #[error("B-00203-200-008::对不起，您选购的商品库存不足，请重新选购")]
+    StockEmpty,
+
+    #[error("FAIL_SYS_TRAFFIC_LIMIT::请求过于频繁, 被限流")]
+    RateLimited,
+
+    #[error("RGV587_ERROR::触发风控")]
+    RiskControl,
+
+    #[error("{0}")]
+    Unknown(String),
+}
+
+// mtop错误码与错误类型的对应
+const MTOP_ERROR_CODES: [(&str, MtopError); 10] = [
+    ("FAIL_SYS_TOKEN_EMPTY", MtopError::TokenEmpty),
+    ("FAIL_SYS_TOKEN_EXOIRED", MtopError::TokenEmpty),
+    ("FAIL_SYS_ILLEGAL_ACCESS", MtopError::TokenEmpty),
+    ("FAIL_SYS_SESSION_EXPIRED", MtopError::SessionExpired),
+    ("B-00203-200-008", MtopError::StockEmpty),
+    ("FAIL_SYS_TRAFFIC_LIMIT", MtopError::RateLimited),
+    ("F-10001-10-16-103", MtopError::RateLimited),
+    ("FAIL_SYS_USER_VALIDATE", MtopError::RiskControl),
+    ("RGV587_ERROR", MtopError::RiskControl),
+    ("被挤爆", MtopError::RiskControl),
+];
+
+impl MtopError {
+    // 按错误码对应错误类型, 未知错误码保留原文
+    pub fn from_ret(ret: &str) -> Self {
+        MTOP_ERROR_CODES
+            .iter()
+            .find(|(code, _)| ret.contains(code))
+            .map(|(_, e)| e.clone())
+            .unwrap_or_else(|| Self::Unknown(ret.to_string()))
+    }
+
+    // 解析ret数组, 调用成功时返回None
+    pub fn from_rets(ret: &[String]) -> Option<Self> {
+        if ret.iter().any(|r| r.starts_with("SUCCESS")) {
+            return None;
+        }
+        let errors = ret.iter().map(|r| Self::from_ret(r)).collect::<Vec<_>>();
+        errors
+            .iter()
+            .find(|e| !matches!(e, Self::Unknown(_)))
+            .or(errors.first())
+            .cloned()
+            .or(Some(Self::Unknown("".to_string())))
+    }
+
+    // 触发限流/风控, 需要放慢请求
+    pub fn is_throttled(&self) -> bool {
+        matches!(self, Self::RateLimited | Self::RiskControl)
+    }
+}
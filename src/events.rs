@@ -0,0 +1,758 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, IsTerminal, Write},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{
+    clients::{bot::Bot, email::EmailClient, notify::NotifyClient, webhook::WebhookClient},
+    experiment::ExperimentSubscriber,
+    export::AttemptExportSubscriber,
+    format_timestamp,
+    health::HealthSubscriber,
+    history::{HistorySubscriber, RunStore},
+    i18n::Lang,
+    models::task::Task,
+    output, trace,
+};
+
+// 生成/提交订单成功时记录的结果码
+pub const SUCCESS: &str = "SUCCESS";
+
+// 抢票过程中的事件, 界面/通知/统计/webhook统一订阅
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TicketEvent {
+    // 已确定抢票时间
+    ScheduleArmed {
+        sale_start: i64,
+    },
+
+    // 开售时间变更, 已重新设定抢票时间
+    SaleTimeChanged {
+        old_start: i64,
+        new_start: i64,
+    },
+
+    // 开售前预热完成
+    TokenWarmed {
+        elapsed_ms: u64,
+    },
+
+    // 开售前提醒, 附带登录状态及服务器时钟偏差
+    SaleReminder {
+        lead_secs: u64,
+        sale_start: i64,
+        session_ok: Option<bool>, // 最近一次检查登录状态的结果
+        clock_offset_ms: Option<i64>,
+    },
+
+    // 已检查登录状态, 定时保持登录时发送
+    SessionChecked {
+        session_ok: bool,
+        clock_offset_ms: Option<i64>,
+    },
+
+    // 开始第n次生成/提交订单, stage: build/submit
+    AttemptStarted {
+        stage: &'static str,
+        n: u64,
+        attempt_id: String,
+    },
+
+    // 第n次生成/提交订单失败, code为接口返回的错误码
+    AttemptFailed {
+        stage: &'static str,
+        n: u64,
+        attempt_id: String,
+        code: String,
+    },
+
+    // 第n次生成/提交订单触发人机验证, 验证未完成
+    ChallengeFailed {
+        stage: &'static str,
+        n: u64,
+        attempt_id: String,
+        error: String,
+    },
+
+    // 第n次生成/提交订单结束, 成功时code为SUCCESS
+    AttemptFinished {
+        stage: &'static str,
+        n: u64,
+        attempt_id: String,
+        started_at: i64,             // 开始时间戳(服务器时间), 毫秒
+        sale_offset_ms: Option<i64>, // 相对官方开售时间的偏移, 未公布开售时间时为空
+        latency_ms: u64,
+        endpoint: String,
+        code: String,
+    },
+
+    // 提交订单成功
+    OrderCreated {
+        order_id: Option<String>,
+        pay_deadline: Option<i64>,
+        elapsed_ms: u64,
+    },
+
+    // 任务结束
+    RunFinished {
+        success: bool,
+        error: Option<String>,
+    },
+}
+
+impl TicketEvent {
+    // 事件名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            TicketEvent::ScheduleArmed { .. } => "schedule_armed",
+            TicketEvent::SaleTimeChanged { .. } => "sale_time_changed",
+            TicketEvent::TokenWarmed { .. } => "token_warmed",
+            TicketEvent::SaleReminder { .. } => "sale_reminder",
+            TicketEvent::SessionChecked { .. } => "session_checked",
+            TicketEvent::AttemptStarted { .. } => "attempt_started",
+            TicketEvent::AttemptFailed { .. } => "attempt_failed",
+            TicketEvent::ChallengeFailed { .. } => "challenge_failed",
+            TicketEvent::AttemptFinished { .. } => "attempt_finished",
+            TicketEvent::OrderCreated { .. } => "order_created",
+            TicketEvent::RunFinished { .. } => "run_finished",
+        }
+    }
+
+    // 事件描述, 按TICK_LANG选择语言
+    pub fn message(&self) -> String {
+        self.message_in(Lang::current())
+    }
+
+    pub fn message_in(&self, lang: Lang) -> String {
+        match lang {
+            Lang::Zh => self.message_zh(),
+            Lang::En => self.message_en(),
+        }
+    }
+
+    fn message_zh(&self) -> String {
+        match self {
+            TicketEvent::ScheduleArmed { sale_start } => {
+                format!("已设定抢票时间:{}", sale_start)
+            }
+            TicketEvent::SaleTimeChanged {
+                old_start,
+                new_start,
+            } => format!(
+                "开售时间变更, 抢票时间:{} -> {}, 已重新设定",
+                local_time(*old_start),
+                local_time(*new_start)
+            ),
+            TicketEvent::TokenWarmed { elapsed_ms } => {
+                format!("预热完成, 耗时:{}毫秒", elapsed_ms)
+            }
+            TicketEvent::SaleReminder {
+                lead_secs,
+                session_ok,
+                clock_offset_ms,
+                ..
+            } => format!(
+                "距离开抢还有{}, 登录状态:{}, 服务器时钟偏差:{}, {}",
+                lead_desc(*lead_secs),
+                match session_ok {
+                    Some(true) => "有效",
+                    Some(false) => "已失效, 请尽快重新登录",
+                    None => "未检查",
+                },
+                clock_offset_ms.map_or("-".to_string(), |offset| format!("{}毫秒", offset)),
+                match session_ok {
+                    Some(true) => "已就绪",
+                    _ => "未就绪",
+                }
+            ),
+            TicketEvent::SessionChecked {
+                session_ok,
+                clock_offset_ms,
+            } => format!(
+                "登录状态:{}, 服务器时钟偏差:{}",
+                match session_ok {
+                    true => "有效",
+                    false => "已失效",
+                },
+                clock_offset_ms.map_or("-".to_string(), |offset| format!("{}毫秒", offset))
+            ),
+            TicketEvent::AttemptStarted { stage, n, .. } => {
+                format!("第{}次{}", n, Self::stage_name(stage))
+            }
+            TicketEvent::AttemptFailed { stage, n, code, .. } => {
+                format!("第{}次{}失败:{}", n, Self::stage_name(stage), code)
+            }
+            TicketEvent::ChallengeFailed {
+                stage, n, error, ..
+            } => format!(
+                "第{}次{}触发人机验证, 验证未完成:{}",
+                n,
+                Self::stage_name(stage),
+                error
+            ),
+            TicketEvent::AttemptFinished {
+                stage,
+                n,
+                latency_ms,
+                code,
+                ..
+            } => format!(
+                "第{}次{}结束, 耗时:{}毫秒, 结果:{}",
+                n,
+                Self::stage_name(stage),
+                latency_ms,
+                code
+            ),
+            TicketEvent::OrderCreated { order_id, .. } => match order_id {
+                Some(id) => format!("下单成功, 订单号:{}, 请尽快前往手机APP付款!", id),
+                None => "下单成功, 请尽快前往手机APP付款!".to_string(),
+            },
+            TicketEvent::RunFinished { success, error } => match (success, error) {
+                (true, _) => "抢票任务结束, 已下单成功".to_string(),
+                (false, Some(error)) => format!("抢票任务结束, 未下单成功:{}", error),
+                (false, None) => "抢票任务结束, 未下单成功".to_string(),
+            },
+        }
+    }
+
+    fn message_en(&self) -> String {
+        match self {
+            TicketEvent::ScheduleArmed { sale_start } => {
+                format!("Scheduled to start at {}", local_time(*sale_start))
+            }
+            TicketEvent::SaleTimeChanged {
+                old_start,
+                new_start,
+            } => format!(
+                "Sale time changed, rescheduled from {} to {}",
+                local_time(*old_start),
+                local_time(*new_start)
+            ),
+            TicketEvent::TokenWarmed { elapsed_ms } => {
+                format!("Warmed up in {}ms", elapsed_ms)
+            }
+            TicketEvent::SaleReminder {
+                lead_secs,
+                session_ok,
+                clock_offset_ms,
+                ..
+            } => format!(
+                "{} until the sale, login: {}, server clock offset: {}, {}",
+                lead_desc_en(*lead_secs),
+                match session_ok {
+                    Some(true) => "valid",
+                    Some(false) => "expired, please log in again",
+                    None => "not checked",
+                },
+                clock_offset_ms.map_or("-".to_string(), |offset| format!("{}ms", offset)),
+                match session_ok {
+                    Some(true) => "ready",
+                    _ => "not ready",
+                }
+            ),
+            TicketEvent::SessionChecked {
+                session_ok,
+                clock_offset_ms,
+            } => format!(
+                "Login: {}, server clock offset: {}",
+                match session_ok {
+                    true => "valid",
+                    false => "expired",
+                },
+                clock_offset_ms.map_or("-".to_string(), |offset| format!("{}ms", offset))
+            ),
+            TicketEvent::AttemptStarted { stage, n, .. } => {
+                format!("{} attempt #{}", Self::stage_name_en(stage), n)
+            }
+            TicketEvent::AttemptFailed { stage, n, code, .. } => {
+                format!(
+                    "{} attempt #{} failed: {}",
+                    Self::stage_name_en(stage),
+                    n,
+                    code
+                )
+            }
+            TicketEvent::ChallengeFailed {
+                stage, n, error, ..
+            } => format!(
+                "{} attempt #{} hit a captcha that was not solved: {}",
+                Self::stage_name_en(stage),
+                n,
+                error
+            ),
+            TicketEvent::AttemptFinished {
+                stage,
+                n,
+                latency_ms,
+                code,
+                ..
+            } => format!(
+                "{} attempt #{} finished in {}ms: {}",
+                Self::stage_name_en(stage),
+                n,
+                latency_ms,
+                code
+            ),
+            TicketEvent::OrderCreated { order_id, .. } => match order_id {
+                Some(id) => format!("Order {} created, pay for it in the app soon!", id),
+                None => "Order created, pay for it in the app soon!".to_string(),
+            },
+            TicketEvent::RunFinished { success, error } => match (success, error) {
+                (true, _) => "Task finished, order created".to_string(),
+                (false, Some(error)) => format!("Task finished without an order: {}", error),
+                (false, None) => "Task finished without an order".to_string(),
+            },
+        }
+    }
+
+    fn stage_name(stage: &str) -> &'static str {
+        match stage {
+            "build" => "生成订单",
+            _ => "提交订单",
+        }
+    }
+
+    fn stage_name_en(stage: &str) -> &'static str {
+        match stage {
+            "build" => "Build order",
+            _ => "Submit order",
+        }
+    }
+
+    // 模板变量: 事件字段 + 任务信息 + 运行ID
+    pub fn vars(&self, task: &Task) -> HashMap<String, Value> {
+        let mut vars = match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map.into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        if let Some(run_id) = trace::current_run_id() {
+            vars.insert("run_id".to_string(), run_id.into());
+        }
+        vars.insert("message".to_string(), self.message().into());
+        vars.insert("nickname".to_string(), task.nickname.clone().into());
+        vars.insert("ticket_id".to_string(), task.ticket_id.clone().into());
+        vars.insert("ticket_name".to_string(), task.ticket_name.clone().into());
+        vars.insert(
+            "perform_name".to_string(),
+            task.ticket_perform_name.clone().into(),
+        );
+        vars.insert(
+            "sku_name".to_string(),
+            task.ticket_perform_sku_name.clone().into(),
+        );
+        vars.insert(
+            "time".to_string(),
+            Local::now()
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string()
+                .into(),
+        );
+        vars
+    }
+}
+
+// 提前时长描述, 如: 86400 => 24小时
+pub fn lead_desc(secs: u64) -> String {
+    match secs {
+        s if s >= 3600 && s % 3600 == 0 => format!("{}小时", s / 3600),
+        s if s >= 60 && s % 60 == 0 => format!("{}分钟", s / 60),
+        s => format!("{}秒", s),
+    }
+}
+
+// 英文的提前时长描述, 如: 86400 => 24h
+pub fn lead_desc_en(secs: u64) -> String {
+    match secs {
+        s if s >= 3600 && s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s >= 60 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+// 本地时间, 如: 2023-07-01 20:00:00
+fn local_time(millis: i64) -> String {
+    match Local.timestamp_millis_opt(millis).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => millis.to_string(),
+    }
+}
+
+// 从接口返回的错误信息中提取错误码, 如: ["B-00203-200-008::库存不足"] => B-00203-200-008
+pub fn error_code(message: &str) -> String {
+    message
+        .trim_start_matches(['[', '"'])
+        .split("::")
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+// 事件订阅者
+#[async_trait]
+pub trait EventSubscriber: Send + Sync {
+    async fn on_event(&self, task: &Task, event: &TicketEvent);
+}
+
+// 下单成功/开售前提醒/开售时间变更时推送通知, 配置NOTIFY_TOKEN时启用
+pub struct NotifySubscriber;
+
+#[async_trait]
+impl EventSubscriber for NotifySubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        if let TicketEvent::SaleReminder { .. } | TicketEvent::SaleTimeChanged { .. } = event {
+            let content = format!(
+                "{}, {}, {}",
+                task.nickname,
+                task.ticket_name,
+                event.message()
+            );
+            if let Err(e) = NotifyClient::notify(&content).await {
+                warn!("{}, 推送开抢提醒失败:{:?}", task.nickname, e);
+            }
+        }
+        if let TicketEvent::OrderCreated { order_id, .. } = event {
+            let content = format!(
+                "{}, {}, {}, 下单成功{}, 请尽快前往手机APP付款!",
+                task.nickname,
+                task.ticket_name,
+                task.ticket_perform_sku_name,
+                order_id
+                    .as_ref()
+                    .map_or(String::new(), |id| format!(", 订单号:{}", id))
+            );
+            if let Err(e) = NotifyClient::notify(&content).await {
+                warn!("{}, 推送下单成功通知失败:{:?}", task.nickname, e);
+            }
+        }
+    }
+}
+
+// 每个事件POST到webhook
+pub struct WebhookSubscriber {
+    client: WebhookClient,
+}
+
+#[async_trait]
+impl EventSubscriber for WebhookSubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        if !self.client.accepts(event.name()) {
+            return;
+        }
+        if let Err(e) = self.client.send(&event.vars(task)).await {
+            warn!("{}, 发送webhook失败:{:?}", task.nickname, e);
+        }
+    }
+}
+
+// 群机器人推送的事件
+const BOT_EVENTS: [&str; 4] = [
+    "sale_reminder",
+    "sale_time_changed",
+    "order_created",
+    "run_finished",
+];
+
+// 开售前提醒/开售时间变更/下单成功/任务结束时推送到钉钉/企业微信群机器人
+pub struct BotSubscriber {
+    bots: Vec<Bot>,
+}
+
+#[async_trait]
+impl EventSubscriber for BotSubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        if !BOT_EVENTS.contains(&event.name()) {
+            return;
+        }
+        let title = format!("{}抢票通知", task.nickname);
+        let content = format!(
+            "- 门票: {}\n- 场次: {}\n- 票档: {}\n- 结果: {}",
+            task.ticket_name,
+            task.ticket_perform_name,
+            task.ticket_perform_sku_name,
+            event.message()
+        );
+        for bot in self.bots.iter() {
+            if let Err(e) = bot.send(&title, &content).await {
+                warn!("{}, 推送{}通知失败:{:?}", task.nickname, bot.name(), e);
+            }
+        }
+    }
+}
+
+// 一次抢票任务的运行报告
+#[derive(Serialize, Debug, Default, Clone)]
+pub struct RunReport {
+    pub run_id: Option<String>,
+    pub sale_start: Option<i64>,
+    pub build_attempts: u64,
+    pub submit_attempts: u64,
+    pub errors: Vec<(String, u64)>, // 错误码及次数
+    pub order_id: Option<String>,
+    pub pay_deadline: Option<i64>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl RunReport {
+    pub fn record(&mut self, event: &TicketEvent) {
+        if self.run_id.is_none() {
+            self.run_id = trace::current_run_id();
+        }
+        match event {
+            TicketEvent::ScheduleArmed { sale_start } => self.sale_start = Some(*sale_start),
+            TicketEvent::AttemptStarted { stage: "build", .. } => self.build_attempts += 1,
+            TicketEvent::AttemptStarted { .. } => self.submit_attempts += 1,
+            TicketEvent::AttemptFailed { code, .. } => {
+                match self.errors.iter_mut().find(|(c, _)| c == code) {
+                    Some(entry) => entry.1 += 1,
+                    None => self.errors.push((code.clone(), 1)),
+                }
+            }
+            TicketEvent::OrderCreated {
+                order_id,
+                pay_deadline,
+                ..
+            } => {
+                self.order_id = order_id.clone();
+                self.pay_deadline = *pay_deadline;
+            }
+            TicketEvent::RunFinished { success, error } => {
+                self.success = *success;
+                self.error = error.clone();
+            }
+            // 开售时间变更后会重新发送ScheduleArmed
+            TicketEvent::SaleTimeChanged { .. }
+            | TicketEvent::TokenWarmed { .. }
+            | TicketEvent::SaleReminder { .. }
+            | TicketEvent::SessionChecked { .. }
+            | TicketEvent::ChallengeFailed { .. }
+            | TicketEvent::AttemptFinished { .. } => {}
+        }
+    }
+
+    // markdown格式的报告
+    pub fn markdown(&self, task: &Task) -> String {
+        let tz = task.tz();
+        let time = |t: Option<i64>| t.map_or("-".to_string(), |t| format_timestamp(t, tz));
+        let variant = task
+            .variant
+            .as_ref()
+            .map_or(String::new(), |v| format!("- 实验分组: {}\n", v));
+
+        let mut report = format!(
+            "# {} 抢票报告\n\n## 配置\n\n\
+            - 账号: {}\n- 门票: {}\n- 场次: {}\n- 票档: {}\n- 备选票档: {}个\n\
+            - 购票数量: {}\n- 重试次数: {}\n- 重试间隔: {}毫秒\n- 请求时间偏移量: {}毫秒\n{}\n\
+            ## 过程\n\n- 运行ID: {}\n- 抢票时间: {}\n- 生成订单: {}次\n- 提交订单: {}次\n",
+            task.ticket_name,
+            task.nickname,
+            task.ticket_name,
+            task.ticket_perform_name,
+            task.ticket_perform_sku_name,
+            task.backup_sku_ids.len(),
+            task.ticket_num,
+            task.retry_times,
+            task.retry_interval,
+            task.request_time_offset,
+            variant,
+            self.run_id.as_deref().unwrap_or("-"),
+            time(self.sale_start),
+            self.build_attempts,
+            self.submit_attempts,
+        );
+        for (code, count) in self.errors.iter() {
+            report.push_str(&format!("- 错误码 {}: {}次\n", code, count));
+        }
+
+        report.push_str(&format!(
+            "\n## 结果\n\n- 结果: {}\n- 订单号: {}\n- 付款截止: {}\n",
+            match (self.success, &self.error) {
+                (true, _) => "下单成功".to_string(),
+                (false, Some(error)) => format!("未下单成功, {}", error),
+                (false, None) => "未下单成功".to_string(),
+            },
+            self.order_id.as_deref().unwrap_or("-"),
+            time(self.pay_deadline),
+        ));
+        report
+    }
+}
+
+// 任务结束后发送运行报告邮件
+pub struct EmailSubscriber {
+    client: EmailClient,
+    report: Mutex<RunReport>,
+}
+
+#[async_trait]
+impl EventSubscriber for EmailSubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        let report = match self.report.lock() {
+            Ok(mut report) => {
+                report.record(event);
+                match event {
+                    TicketEvent::RunFinished { .. } => report.clone(),
+                    _ => return,
+                }
+            }
+            Err(_) => return,
+        };
+
+        let subject = format!(
+            "{}, {}, {}",
+            task.nickname,
+            task.ticket_name,
+            match report.success {
+                true => "下单成功",
+                false => "未下单成功",
+            }
+        );
+        if let Err(e) = self.client.send(&subject, &report.markdown(task)).await {
+            warn!("{}, 发送报告邮件失败:{:?}", task.nickname, e);
+        }
+    }
+}
+
+// --output json时任务结束后输出json格式的运行报告
+pub struct SummarySubscriber {
+    report: Mutex<RunReport>,
+}
+
+#[derive(Serialize)]
+struct RunSummary<'a> {
+    nickname: &'a str,
+    ticket_id: &'a str,
+    ticket_name: &'a str,
+    perform_name: &'a str,
+    sku_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variant: Option<&'a str>,
+    #[serde(flatten)]
+    report: &'a RunReport,
+}
+
+#[async_trait]
+impl EventSubscriber for SummarySubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        let report = match self.report.lock() {
+            Ok(mut report) => {
+                report.record(event);
+                match event {
+                    TicketEvent::RunFinished { .. } => report.clone(),
+                    _ => return,
+                }
+            }
+            Err(_) => return,
+        };
+
+        let summary = RunSummary {
+            nickname: &task.nickname,
+            ticket_id: &task.ticket_id,
+            ticket_name: &task.ticket_name,
+            perform_name: &task.ticket_perform_name,
+            sku_name: &task.ticket_perform_sku_name,
+            variant: task.variant.as_deref(),
+            report: &report,
+        };
+        if let Err(e) = output::print_json(&summary) {
+            warn!("{}, 输出运行报告失败:{:?}", task.nickname, e);
+        }
+    }
+}
+
+// 交互运行时下单成功响铃并弹出桌面通知, 避免错过付款时间
+pub struct DesktopSubscriber;
+
+impl DesktopSubscriber {
+    // 在终端中运行且DESKTOP_NOTIFY不为false时启用, json输出时不响铃
+    pub fn enabled() -> bool {
+        io::stdout().is_terminal()
+            && !output::is_json()
+            && env::var("DESKTOP_NOTIFY")
+                .map(|v| !v.eq_ignore_ascii_case("false"))
+                .unwrap_or(true)
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for DesktopSubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        if !matches!(event, TicketEvent::OrderCreated { .. }) {
+            return;
+        }
+
+        // 终端响铃
+        print!("\x07\x07\x07");
+        let _ = io::stdout().flush();
+
+        let summary = format!("{}抢票成功", task.nickname);
+        let body = format!("{}, {}", task.ticket_name, event.message());
+        let res = tokio::task::spawn_blocking(move || {
+            notify_rust::Notification::new()
+                .summary(&summary)
+                .body(&body)
+                .timeout(notify_rust::Timeout::Never)
+                .show()
+                .map(|_| ())
+        })
+        .await;
+        if let Ok(Err(e)) = res {
+            warn!("{}, 桌面通知失败:{:?}", task.nickname, e);
+        }
+    }
+}
+
+// 根据环境变量启用的订阅者
+pub fn default_subscribers() -> Vec<Box<dyn EventSubscriber>> {
+    let mut subscribers: Vec<Box<dyn EventSubscriber>> = vec![];
+    if DesktopSubscriber::enabled() {
+        subscribers.push(Box::new(DesktopSubscriber));
+    }
+    if output::is_json() {
+        subscribers.push(Box::new(SummarySubscriber {
+            report: Mutex::new(RunReport::default()),
+        }));
+    }
+    if env::var("NOTIFY_TOKEN").is_ok() {
+        subscribers.push(Box::new(NotifySubscriber));
+    }
+    let bots = Bot::from_env();
+    if !bots.is_empty() {
+        subscribers.push(Box::new(BotSubscriber { bots }));
+    }
+    match EmailClient::from_env() {
+        Ok(Some(client)) => subscribers.push(Box::new(EmailSubscriber {
+            client,
+            report: Mutex::new(RunReport::default()),
+        })),
+        Ok(None) => {}
+        Err(e) => warn!("邮件配置错误:{:?}", e),
+    }
+    match WebhookClient::from_env() {
+        Ok(Some(client)) => subscribers.push(Box::new(WebhookSubscriber { client })),
+        Ok(None) => {}
+        Err(e) => warn!("webhook配置错误:{:?}", e),
+    }
+    if let Some(subscriber) = AttemptExportSubscriber::from_env() {
+        subscribers.push(Box::new(subscriber));
+    }
+    if env::var("EXPERIMENT_VARIANTS").is_ok() {
+        subscribers.push(Box::new(ExperimentSubscriber));
+    }
+    if HistorySubscriber::enabled() {
+        subscribers.push(Box::new(HistorySubscriber::new(RunStore::from_env())));
+    }
+    if env::var("HEALTH_ADDR").is_ok_and(|addr| !addr.is_empty()) {
+        subscribers.push(Box::new(HealthSubscriber));
+    }
+    subscribers
+}
@@ -0,0 +1,107 @@
+use std::sync::Mutex;
+
+#[cfg(feature = "browser-login")]
+use crate::errors::ServerError;
+use crate::errors::{AccountError, ClientError, DmApiError, MtopError, PlatformError, ViewerError};
+
+// 进程退出码, 供脚本/systemd按结果处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    Success,     // 0: 下单成功或命令执行成功
+    Failure,     // 1: 其他错误, 或重试结束仍未下单成功
+    SoldOut,     // 2: 库存不足/已售罄
+    LoginFailed, // 3: 登录失败/cookie过期
+    RiskControl, // 4: 触发风控/人机验证
+    ConfigError, // 5: 配置错误(环境变量/账号/观演人等)
+    Interrupted, // 130: 手动停止(Ctrl-C)
+}
+
+// 本进程中任务的运行结果, 多个任务时任一下单成功即为成功
+static OUTCOME: Mutex<Option<ExitStatus>> = Mutex::new(None);
+
+impl ExitStatus {
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::Failure => 1,
+            Self::SoldOut => 2,
+            Self::LoginFailed => 3,
+            Self::RiskControl => 4,
+            Self::ConfigError => 5,
+            Self::Interrupted => 130,
+        }
+    }
+
+    // 按错误类型确定退出码
+    pub fn from_error(e: &anyhow::Error) -> Self {
+        for cause in e.chain() {
+            if let Some(e) = cause.downcast_ref::<PlatformError>() {
+                return match e {
+                    PlatformError::SessionExpired => Self::LoginFailed,
+                    PlatformError::Challenge { .. } | PlatformError::RiskControl { .. } => {
+                        Self::RiskControl
+                    }
+                    PlatformError::Unsupported { .. } => Self::ConfigError,
+                    PlatformError::Queued { .. } => Self::Failure,
+                };
+            }
+            if let Some(e) = cause.downcast_ref::<MtopError>() {
+                return match e {
+                    MtopError::TokenEmpty | MtopError::SessionExpired => Self::LoginFailed,
+                    MtopError::StockEmpty => Self::SoldOut,
+                    MtopError::RateLimited | MtopError::RiskControl => Self::RiskControl,
+                    MtopError::Unknown(_) => Self::Failure,
+                };
+            }
+            if let Some(e) = cause.downcast_ref::<DmApiError>() {
+                return match e {
+                    DmApiError::SoldOut => Self::SoldOut,
+                    DmApiError::SystemBusy | DmApiError::BuildOrderSystemBusy => Self::RiskControl,
+                    DmApiError::ProductEpired => Self::Failure,
+                };
+            }
+            if let Some(e) = cause.downcast_ref::<ClientError>() {
+                return match e {
+                    #[cfg(any(feature = "http-login", feature = "interactive"))]
+                    ClientError::LoginFailed => Self::LoginFailed,
+                    #[cfg(feature = "interactive")]
+                    ClientError::CookieError => Self::LoginFailed,
+                    ClientError::MissingSetting { .. } | ClientError::InvalidSetting { .. } => {
+                        Self::ConfigError
+                    }
+                    _ => Self::Failure,
+                };
+            }
+            #[cfg(feature = "browser-login")]
+            if let Some(ServerError::InvalidConfig { .. }) = cause.downcast_ref::<ServerError>() {
+                return Self::ConfigError;
+            }
+            if cause.is::<AccountError>() || cause.is::<ViewerError>() {
+                return Self::ConfigError;
+            }
+        }
+        Self::Failure
+    }
+}
+
+// 记录一次任务的运行结果
+pub fn record(status: ExitStatus) {
+    if let Ok(mut outcome) = OUTCOME.lock() {
+        if *outcome != Some(ExitStatus::Success) {
+            *outcome = Some(status);
+        }
+    }
+}
+
+// 已记录的任务运行结果
+pub fn outcome() -> Option<ExitStatus> {
+    OUTCOME.lock().ok().and_then(|outcome| *outcome)
+}
+
+// 命令执行结果对应的退出码: 出错时按错误类型, 否则按记录的任务运行结果
+pub fn status(res: &anyhow::Result<()>) -> ExitStatus {
+    match res {
+        Err(e) => ExitStatus::from_error(e),
+        Ok(_) => outcome().unwrap_or(ExitStatus::Success),
+    }
+}
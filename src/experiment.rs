@@ -0,0 +1,295 @@
+use std::{
+    collections::HashMap,
+    env,
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::{
+    events::{EventSubscriber, TicketEvent, SUCCESS},
+    models::task::Task,
+};
+
+// 分组对比表的表头
+const SUMMARY_HEADER: [&str; 7] = [
+    "实验分组",
+    "任务",
+    "成功",
+    "下单次数",
+    "失败次数",
+    "平均耗时",
+    "最早成功偏移",
+];
+
+// 抢票时间参数的实验分组, 多账号抢同一门票时轮流分配, 运行结束后按分组对比结果
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TimingVariant {
+    pub name: String,
+    pub request_time_offset: Option<i64>, // 请求时间偏移量, 毫秒
+    pub retry_interval: Option<u64>,      // 重试间隔, 毫秒
+    pub retry_times: Option<u64>,         // 重试次数
+    pub wait_for_submit_interval: Option<u64>, // 生成/提交订单的间隔, 毫秒
+}
+
+impl FromStr for TimingVariant {
+    type Err = anyhow::Error;
+
+    // 格式: 名称:参数=值,参数=值, 如: late:offset=30,interval=50
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, params) = s.split_once(':').unwrap_or((s, ""));
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("实验分组名称为空:{}", s));
+        }
+        let mut variant = Self {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        for param in params.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=').ok_or(anyhow!(
+                "实验分组{}的参数格式错误:{}, 应为参数=值",
+                name,
+                param
+            ))?;
+            let value = value.trim();
+            let invalid = || anyhow!("实验分组{}的参数{}应为整数:{}", name, key, value);
+            match key.trim() {
+                "offset" => {
+                    variant.request_time_offset = Some(value.parse().map_err(|_| invalid())?)
+                }
+                "interval" => variant.retry_interval = Some(value.parse().map_err(|_| invalid())?),
+                "retries" => variant.retry_times = Some(value.parse().map_err(|_| invalid())?),
+                "submit_wait" => {
+                    variant.wait_for_submit_interval = Some(value.parse().map_err(|_| invalid())?)
+                }
+                other => {
+                    return Err(anyhow!(
+                        "实验分组{}的参数{}不支持, 可选: offset/interval/retries/submit_wait",
+                        name,
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(variant)
+    }
+}
+
+impl TimingVariant {
+    // 使用分组的参数覆盖任务配置
+    pub fn apply(&self, task: &mut Task) {
+        task.variant = Some(self.name.clone());
+        if let Some(offset) = self.request_time_offset {
+            task.request_time_offset = offset;
+        }
+        if let Some(interval) = self.retry_interval {
+            task.retry_interval = interval;
+        }
+        if let Some(times) = self.retry_times {
+            task.retry_times = times;
+        }
+        if let Some(interval) = self.wait_for_submit_interval {
+            task.wait_for_submit_interval = interval;
+        }
+    }
+}
+
+// 解析实验分组, 多个分组用分号分隔
+pub fn parse_variants(value: &str) -> Result<Vec<TimingVariant>> {
+    let variants = value
+        .split(';')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::parse::<TimingVariant>)
+        .collect::<Result<Vec<_>>>()?;
+    for (i, variant) in variants.iter().enumerate() {
+        if variants[..i].iter().any(|v| v.name == variant.name) {
+            return Err(anyhow!("实验分组名称重复:{}", variant.name));
+        }
+    }
+    Ok(variants)
+}
+
+// 从环境变量EXPERIMENT_VARIANTS读取实验分组, 未配置时为空
+pub fn variants() -> Result<Vec<TimingVariant>> {
+    match env::var("EXPERIMENT_VARIANTS") {
+        Ok(value) => parse_variants(&value),
+        Err(_) => Ok(vec![]),
+    }
+}
+
+// 为未指定分组的任务分配分组, 同一门票的任务按顺序轮流分配
+pub fn assign<'a>(tasks: impl IntoIterator<Item = &'a mut Task>, variants: &[TimingVariant]) {
+    if variants.is_empty() {
+        return;
+    }
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for task in tasks {
+        let count = counts.entry(task.ticket_id.clone()).or_default();
+        if task.variant.is_none() {
+            task.variant = Some(variants[*count % variants.len()].name.clone());
+        }
+        *count += 1;
+    }
+}
+
+// 应用任务所在分组的参数, 分组不存在时报错
+pub fn apply(task: &mut Task, variants: &[TimingVariant]) -> Result<()> {
+    let name = match &task.variant {
+        Some(name) => name.clone(),
+        None => return Ok(()),
+    };
+    let variant = variants.iter().find(|v| v.name == name).ok_or(anyhow!(
+        "未配置实验分组:{}, 请检查EXPERIMENT_VARIANTS",
+        name
+    ))?;
+    variant.apply(task);
+    Ok(())
+}
+
+// 实验分组的运行结果
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct VariantStats {
+    pub variant: String,
+    pub runs: u64,                   // 运行的任务数
+    pub successes: u64,              // 下单成功的任务数
+    pub attempts: u64,               // 生成/提交订单次数
+    pub failures: u64,               // 失败的生成/提交订单次数
+    pub latency_ms: u64,             // 生成/提交订单的总耗时
+    pub best_offset_ms: Option<i64>, // 下单成功的提交相对开售时间的偏移, 取最早一次
+}
+
+impl VariantStats {
+    pub fn record(&mut self, event: &TicketEvent) {
+        match event {
+            TicketEvent::AttemptFinished {
+                stage,
+                sale_offset_ms,
+                latency_ms,
+                code,
+                ..
+            } => {
+                self.attempts += 1;
+                self.latency_ms += latency_ms;
+                match code.as_str() {
+                    SUCCESS if *stage == "submit" => {
+                        if let Some(offset) = sale_offset_ms {
+                            self.best_offset_ms = Some(
+                                self.best_offset_ms
+                                    .map_or(*offset, |best| best.min(*offset)),
+                            );
+                        }
+                    }
+                    SUCCESS => {}
+                    _ => self.failures += 1,
+                }
+            }
+            TicketEvent::RunFinished { success, .. } => {
+                self.runs += 1;
+                if *success {
+                    self.successes += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 下单成功率
+    pub fn success_rate(&self) -> f64 {
+        match self.runs {
+            0 => 0.0,
+            runs => self.successes as f64 / runs as f64,
+        }
+    }
+
+    // 平均每次生成/提交订单的耗时, 毫秒
+    pub fn avg_latency_ms(&self) -> u64 {
+        match self.attempts {
+            0 => 0,
+            attempts => self.latency_ms / attempts,
+        }
+    }
+}
+
+// 进程内各分组的运行结果
+#[derive(Debug, Default)]
+pub struct Experiment {
+    stats: Mutex<Vec<VariantStats>>,
+}
+
+impl Experiment {
+    pub fn global() -> &'static Experiment {
+        static EXPERIMENT: OnceLock<Experiment> = OnceLock::new();
+        EXPERIMENT.get_or_init(Experiment::default)
+    }
+
+    pub fn record(&self, variant: &str, event: &TicketEvent) {
+        if let Ok(mut stats) = self.stats.lock() {
+            match stats.iter_mut().find(|s| s.variant == variant) {
+                Some(s) => s.record(event),
+                None => {
+                    let mut s = VariantStats {
+                        variant: variant.to_string(),
+                        ..Default::default()
+                    };
+                    s.record(event);
+                    stats.push(s);
+                }
+            }
+        }
+    }
+
+    // 按成功率从高到低、平均耗时从低到高排列, 第一个为表现最好的分组
+    pub fn ranking(&self) -> Vec<VariantStats> {
+        let mut stats = self.stats.lock().map(|s| s.clone()).unwrap_or_default();
+        stats.sort_by(|a, b| {
+            b.success_rate()
+                .total_cmp(&a.success_rate())
+                .then(a.avg_latency_ms().cmp(&b.avg_latency_ms()))
+        });
+        stats
+    }
+}
+
+// 实验分组对比表
+pub fn summary(ranking: &[VariantStats]) -> String {
+    let mut lines = vec![SUMMARY_HEADER.join("\t")];
+    for stats in ranking {
+        lines.push(format!(
+            "{}\t{}\t{}\t{}\t{}\t{}毫秒\t{}",
+            stats.variant,
+            stats.runs,
+            stats.successes,
+            stats.attempts,
+            stats.failures,
+            stats.avg_latency_ms(),
+            stats
+                .best_offset_ms
+                .map_or("-".to_string(), |offset| format!("{}毫秒", offset))
+        ));
+    }
+    if let Some(best) = ranking.first().filter(|_| ranking.len() > 1) {
+        lines.push(format!(
+            "表现最好的分组:{}, 成功率:{:.0}%",
+            best.variant,
+            best.success_rate() * 100.0
+        ));
+    }
+    lines.join("\n")
+}
+
+// 按任务所在的实验分组汇总运行结果
+pub struct ExperimentSubscriber;
+
+#[async_trait]
+impl EventSubscriber for ExperimentSubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        if let Some(variant) = &task.variant {
+            Experiment::global().record(variant, event);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    events::{EventSubscriber, TicketEvent},
+    models::task::Task,
+    trace,
+};
+
+// 多个任务写入同一文件时串行写入
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// csv表头, 与AttemptRecord字段一致
+const CSV_HEADER: [&str; 12] = [
+    "run_id",
+    "nickname",
+    "ticket_id",
+    "stage",
+    "n",
+    "attempt_id",
+    "timestamp",
+    "sale_offset_ms",
+    "latency_ms",
+    "endpoint",
+    "code",
+    "variant",
+];
+
+// 导出文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+}
+
+impl ExportFormat {
+    // 按扩展名识别, .csv为csv, 其余为jsonl
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+        {
+            true => Self::Csv,
+            false => Self::Jsonl,
+        }
+    }
+}
+
+// 一次生成/提交订单的记录
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AttemptRecord {
+    pub run_id: String,
+    pub nickname: String,
+    pub ticket_id: String,
+    pub stage: String, // build/submit
+    pub n: u64,
+    pub attempt_id: String,
+    pub timestamp: i64,              // 开始时间戳(服务器时间), 毫秒
+    pub sale_offset_ms: Option<i64>, // 相对官方开售时间的偏移, 负数为开售前
+    pub latency_ms: u64,
+    pub endpoint: String,
+    pub code: String,            // 接口返回的错误码, 成功为SUCCESS
+    pub variant: Option<String>, // 实验分组
+}
+
+impl AttemptRecord {
+    // 从AttemptFinished事件生成记录, 其他事件返回None
+    pub fn from_event(task: &Task, run_id: &str, event: &TicketEvent) -> Option<Self> {
+        match event {
+            TicketEvent::AttemptFinished {
+                stage,
+                n,
+                attempt_id,
+                started_at,
+                sale_offset_ms,
+                latency_ms,
+                endpoint,
+                code,
+            } => Some(Self {
+                run_id: run_id.to_string(),
+                nickname: task.nickname.clone(),
+                ticket_id: task.ticket_id.clone(),
+                stage: stage.to_string(),
+                n: *n,
+                attempt_id: attempt_id.clone(),
+                timestamp: *started_at,
+                sale_offset_ms: *sale_offset_ms,
+                latency_ms: *latency_ms,
+                endpoint: endpoint.clone(),
+                code: code.clone(),
+                variant: task.variant.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    // csv的一行, 不含换行
+    pub fn csv_row(&self) -> String {
+        [
+            csv_field(&self.run_id),
+            csv_field(&self.nickname),
+            csv_field(&self.ticket_id),
+            csv_field(&self.stage),
+            self.n.to_string(),
+            csv_field(&self.attempt_id),
+            self.timestamp.to_string(),
+            self.sale_offset_ms.map_or(String::new(), |v| v.to_string()),
+            self.latency_ms.to_string(),
+            csv_field(&self.endpoint),
+            csv_field(&self.code),
+            csv_field(self.variant.as_deref().unwrap_or_default()),
+        ]
+        .join(",")
+    }
+}
+
+// 包含逗号/引号/换行的字段加引号, 引号转义为两个引号
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+// 按格式输出记录, header为true时csv包含表头
+pub fn render(records: &[AttemptRecord], format: ExportFormat, header: bool) -> Result<String> {
+    let mut content = String::new();
+    match format {
+        ExportFormat::Csv => {
+            if header {
+                content.push_str(&CSV_HEADER.join(","));
+                content.push('\n');
+            }
+            for record in records {
+                content.push_str(&record.csv_row());
+                content.push('\n');
+            }
+        }
+        ExportFormat::Jsonl => {
+            for record in records {
+                content.push_str(&serde_json::to_string(record)?);
+                content.push('\n');
+            }
+        }
+    }
+    Ok(content)
+}
+
+// 追加写入文件, 新文件或空文件写入csv表头
+pub fn append(path: &Path, records: &[AttemptRecord]) -> Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let _lock = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let header = fs::metadata(path).map_or(true, |meta| meta.len() == 0);
+    let content = render(records, ExportFormat::from_path(path), header)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+// 任务结束后导出每次生成/提交订单的记录, 配置ATTEMPTS_EXPORT时启用
+pub struct AttemptExportSubscriber {
+    path: PathBuf,
+    records: Mutex<Vec<AttemptRecord>>,
+}
+
+impl AttemptExportSubscriber {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            records: Mutex::new(vec![]),
+        }
+    }
+
+    // ATTEMPTS_EXPORT: 导出文件路径, .csv为csv格式, 其余为jsonl格式
+    pub fn from_env() -> Option<Self> {
+        env::var("ATTEMPTS_EXPORT")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(Self::new)
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for AttemptExportSubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        let records = match self.records.lock() {
+            Ok(mut records) => match event {
+                TicketEvent::RunFinished { .. } => std::mem::take(&mut *records),
+                _ => {
+                    let run_id = trace::current_run_id().unwrap_or_default();
+                    records.extend(AttemptRecord::from_event(task, &run_id, event));
+                    return;
+                }
+            },
+            Err(_) => return,
+        };
+        match append(&self.path, &records) {
+            Ok(_) if !records.is_empty() => info!(
+                "{}, 已导出{}次下单记录到:{}",
+                task.nickname,
+                records.len(),
+                self.path.display()
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("{}, 导出下单记录失败:{:?}", task.nickname, e),
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    env,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Local;
+use serde::Serialize;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tracing::{debug, info};
+
+use crate::{
+    events::{EventSubscriber, TicketEvent},
+    models::task::Task,
+};
+
+// 默认允许的服务器时钟偏差, 毫秒
+const DEFAULT_MAX_CLOCK_OFFSET: u64 = 1000;
+
+// 超过扫描间隔的倍数仍未完成扫描时, 视为调度已停止
+const SCAN_STALE_FACTOR: u32 = 3;
+
+// 任务的健康状态, 来自抢票事件
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskHealth {
+    pub nickname: String,
+    pub ticket_id: String,
+    pub ticket_name: String,
+    pub sale_start: Option<i64>,
+    pub session_ok: Option<bool>,     // 最近一次检查登录状态的结果
+    pub clock_offset_ms: Option<i64>, // 服务器时钟偏差
+    pub updated_at: i64,              // 最近一次更新的时间戳, 毫秒
+}
+
+// 守护进程的调度状态
+#[derive(Debug, Clone, Default)]
+struct Scheduler {
+    last_scan: Option<Instant>,
+    interval: Duration,
+    running: usize,
+    failed: usize,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedulerStatus {
+    pub last_scan_secs: Option<u64>, // 距离上次扫描任务目录的秒数, 未以守护进程运行时为空
+    pub running: usize,              // 运行中的任务数
+    pub failed: usize,               // 启动失败的任务数
+    pub error: Option<String>,       // 上次扫描的错误
+}
+
+// 健康检查结果, live: 进程及调度正常; ready: 所有任务可正常开抢
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthReport {
+    pub live: bool,
+    pub ready: bool,
+    pub reasons: Vec<String>, // 不健康/未就绪的原因
+    pub scheduler: SchedulerStatus,
+    pub tasks: Vec<TaskHealth>,
+}
+
+// 进程内的健康状态
+#[derive(Debug, Default)]
+pub struct Health {
+    scheduler: Mutex<Scheduler>,
+    tasks: Mutex<Vec<TaskHealth>>,
+}
+
+impl Health {
+    pub fn global() -> &'static Health {
+        static HEALTH: OnceLock<Health> = OnceLock::new();
+        HEALTH.get_or_init(Health::default)
+    }
+
+    // 守护进程完成一次扫描
+    pub fn scanned(
+        &self,
+        interval: Duration,
+        running: usize,
+        failed: usize,
+        error: Option<String>,
+    ) {
+        if let Ok(mut scheduler) = self.scheduler.lock() {
+            *scheduler = Scheduler {
+                last_scan: Some(Instant::now()),
+                interval,
+                running,
+                failed,
+                error,
+            };
+        }
+    }
+
+    pub fn record(&self, task: &Task, event: &TicketEvent) {
+        let mut tasks = match self.tasks.lock() {
+            Ok(tasks) => tasks,
+            Err(_) => return,
+        };
+        let position = tasks
+            .iter()
+            .position(|t| t.nickname == task.nickname && t.ticket_id == task.ticket_id);
+        if let TicketEvent::RunFinished { .. } = event {
+            if let Some(i) = position {
+                tasks.remove(i);
+            }
+            return;
+        }
+        let health = match position {
+            Some(i) => &mut tasks[i],
+            None => {
+                tasks.push(TaskHealth {
+                    nickname: task.nickname.clone(),
+                    ticket_id: task.ticket_id.clone(),
+                    ticket_name: task.ticket_name.clone(),
+                    ..Default::default()
+                });
+                tasks.last_mut().unwrap()
+            }
+        };
+        match event {
+            TicketEvent::ScheduleArmed { sale_start } => health.sale_start = Some(*sale_start),
+            TicketEvent::SaleReminder {
+                session_ok,
+                clock_offset_ms,
+                ..
+            } => {
+                health.session_ok = *session_ok;
+                health.clock_offset_ms = *clock_offset_ms;
+            }
+            TicketEvent::SessionChecked {
+                session_ok,
+                clock_offset_ms,
+            } => {
+                health.session_ok = Some(*session_ok);
+                health.clock_offset_ms = *clock_offset_ms;
+            }
+            _ => return,
+        }
+        health.updated_at = Local::now().timestamp_millis();
+    }
+
+    // 汇总健康状态, 时钟偏差超过max_clock_offset(毫秒)时未就绪
+    pub fn report(&self, max_clock_offset: u64) -> HealthReport {
+        let scheduler = self.scheduler.lock().map(|s| s.clone()).unwrap_or_default();
+        let tasks = self.tasks.lock().map(|t| t.clone()).unwrap_or_default();
+
+        let mut live = true;
+        let mut reasons = vec![];
+        let elapsed = scheduler.last_scan.map(|t| t.elapsed());
+        if let Some(stale) = elapsed.filter(|e| *e > scheduler.interval * SCAN_STALE_FACTOR) {
+            live = false;
+            reasons.push(format!("调度已停止, {}秒未扫描任务", stale.as_secs()));
+        }
+        let mut ready = live;
+        if let Some(error) = &scheduler.error {
+            ready = false;
+            reasons.push(format!("扫描任务失败:{}", error));
+        }
+        if scheduler.failed > 0 {
+            ready = false;
+            reasons.push(format!("启动失败的任务:{}个", scheduler.failed));
+        }
+        for task in tasks.iter() {
+            if task.session_ok == Some(false) {
+                ready = false;
+                reasons.push(format!(
+                    "{}, {}, 登录已失效",
+                    task.nickname, task.ticket_name
+                ));
+            }
+            if let Some(offset) = task
+                .clock_offset_ms
+                .filter(|offset| offset.unsigned_abs() > max_clock_offset)
+            {
+                ready = false;
+                reasons.push(format!(
+                    "{}, {}, 服务器时钟偏差:{}毫秒",
+                    task.nickname, task.ticket_name, offset
+                ));
+            }
+        }
+
+        HealthReport {
+            live,
+            ready,
+            reasons,
+            scheduler: SchedulerStatus {
+                last_scan_secs: elapsed.map(|e| e.as_secs()),
+                running: scheduler.running,
+                failed: scheduler.failed,
+                error: scheduler.error,
+            },
+            tasks,
+        }
+    }
+}
+
+// HEALTH_MAX_CLOCK_OFFSET: 允许的服务器时钟偏差, 毫秒
+pub fn max_clock_offset() -> u64 {
+    env::var("HEALTH_MAX_CLOCK_OFFSET")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_CLOCK_OFFSET)
+}
+
+// 记录任务的开抢时间、登录状态及时钟偏差, 配置HEALTH_ADDR时启用
+pub struct HealthSubscriber;
+
+#[async_trait]
+impl EventSubscriber for HealthSubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        Health::global().record(task, event);
+    }
+}
+
+// 健康检查http服务, /healthz: 存活检查, /readyz: 就绪检查, 正常返回200, 否则返回503
+// drop后停止服务
+pub struct HealthServer {
+    pub url: String,
+    handle: JoinHandle<()>,
+}
+
+impl HealthServer {
+    // 监听指定地址, 如0.0.0.0:8766
+    pub async fn start(addr: &str, health: &'static Health) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, health).await {
+                        debug!("健康检查连接异常:{:?}", e);
+                    }
+                });
+            }
+        });
+
+        info!("健康检查已启动:{}/healthz, {}/readyz", url, url);
+        Ok(Self { url, handle })
+    }
+
+    // 环境变量HEALTH_ADDR配置监听地址, 未配置时不启动
+    pub async fn from_env() -> Result<Option<Self>> {
+        match env::var("HEALTH_ADDR") {
+            Ok(addr) if !addr.is_empty() => Ok(Some(Self::start(&addr, Health::global()).await?)),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Drop for HealthServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+// 处理一个请求, 返回json格式的健康检查结果
+async fn serve(stream: TcpStream, health: &Health) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .split('?')
+        .next()
+        .unwrap_or("/")
+        .to_string();
+
+    let report = health.report(max_clock_offset());
+    let (ok, body) = match path.as_str() {
+        "/healthz" => (Some(report.live), serde_json::to_vec(&report)?),
+        "/readyz" => (Some(report.ready), serde_json::to_vec(&report)?),
+        _ => (None, br#"{"error":"not found"}"#.to_vec()),
+    };
+    let status = match ok {
+        Some(true) => "200 OK",
+        Some(false) => "503 Service Unavailable",
+        None => "404 Not Found",
+    };
+    let header = format!(
+        "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\ncache-control: no-store\r\nconnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    writer.write_all(header.as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.shutdown().await?;
+    Ok(())
+}
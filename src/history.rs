@@ -0,0 +1,264 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    config,
+    errors::MtopError,
+    events::{EventSubscriber, TicketEvent, SUCCESS},
+    models::task::Task,
+    trace,
+};
+
+// 一次抢票任务的运行记录, 按门票保存, 用于统计同一巡演的多次开抢
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RunRecord {
+    pub run_id: String,
+    pub nickname: String,
+    pub ticket_id: String,
+    pub ticket_name: String,
+    pub perform_name: String,
+    pub sku_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    pub sale_start: Option<i64>,
+    pub finished_at: i64,
+    pub attempts: u64,                  // 生成/提交订单次数
+    pub errors: Vec<(String, u64)>,     // 错误码及次数
+    pub best_latency_ms: Option<u64>,   // 生成/提交订单的最短耗时
+    pub first_sold_out_ms: Option<i64>, // 首次返回库存不足相对开售时间的偏移
+    pub success: bool,
+    pub order_id: Option<String>,
+    pub error: Option<String>,
+}
+
+impl RunRecord {
+    pub fn record(&mut self, event: &TicketEvent) {
+        match event {
+            TicketEvent::ScheduleArmed { sale_start } => self.sale_start = Some(*sale_start),
+            TicketEvent::AttemptFinished {
+                sale_offset_ms,
+                latency_ms,
+                code,
+                ..
+            } => {
+                self.attempts += 1;
+                self.best_latency_ms = min(self.best_latency_ms, Some(*latency_ms));
+                if code == SUCCESS {
+                    return;
+                }
+                match self.errors.iter_mut().find(|(c, _)| c == code) {
+                    Some(entry) => entry.1 += 1,
+                    None => self.errors.push((code.clone(), 1)),
+                }
+                if MtopError::from_ret(code) == MtopError::StockEmpty {
+                    self.first_sold_out_ms = min(self.first_sold_out_ms, *sale_offset_ms);
+                }
+            }
+            TicketEvent::OrderCreated { order_id, .. } => self.order_id = order_id.clone(),
+            TicketEvent::RunFinished { success, error } => {
+                self.success = *success;
+                self.error = error.clone();
+            }
+            _ => {}
+        }
+    }
+
+    // 填写任务信息, 任务结束时调用
+    pub fn finish(&mut self, task: &Task, run_id: String, finished_at: i64) {
+        self.run_id = run_id;
+        self.nickname = task.nickname.clone();
+        self.ticket_id = task.ticket_id.clone();
+        self.ticket_name = task.ticket_name.clone();
+        self.perform_name = task.ticket_perform_name.clone();
+        self.sku_name = task.ticket_perform_sku_name.clone();
+        self.variant = task.variant.clone();
+        self.finished_at = finished_at;
+    }
+
+    pub fn file_name(&self) -> String {
+        match self.run_id.is_empty() {
+            true => format!("{}.json", self.finished_at),
+            false => format!("{}.json", self.run_id),
+        }
+    }
+}
+
+// 运行记录的保存目录, 每个门票一个子目录
+pub struct RunStore {
+    dir: PathBuf,
+}
+
+impl RunStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // RUNS_DIR: 保存目录, 默认为配置目录下的runs
+    pub fn from_env() -> Self {
+        match env::var("RUNS_DIR") {
+            Ok(dir) if !dir.is_empty() => Self::new(dir),
+            _ => Self::new(config::config_dir().join("runs")),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn save(&self, record: &RunRecord) -> Result<()> {
+        let dir = self.dir.join(&record.ticket_id);
+        fs::create_dir_all(&dir)?;
+        let content = serde_json::to_string_pretty(record)?;
+        fs::write(dir.join(record.file_name()), content)?;
+        Ok(())
+    }
+
+    // 门票的所有运行记录, 按结束时间排列, 无法解析的文件跳过
+    pub fn list(&self, ticket_id: &str) -> Result<Vec<RunRecord>> {
+        let dir = self.dir.join(ticket_id);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut records = vec![];
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<RunRecord>(&content)?))
+            {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("跳过无法解析的运行记录:{}, {}", path.display(), e),
+            }
+        }
+        records.sort_by_key(|record| record.finished_at);
+        Ok(records)
+    }
+}
+
+// 同一门票多次运行的统计
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ShowStats {
+    pub ticket_id: String,
+    pub ticket_name: String,
+    pub runs: u64,
+    pub successes: u64,
+    pub attempts: u64,
+    pub errors: Vec<(String, u64)>, // 错误码及次数, 按次数从多到少
+    pub best_latency_ms: Option<u64>,
+    pub first_sold_out_ms: Option<i64>, // 各次运行中最早出现库存不足的偏移
+    pub last_run_at: Option<i64>,
+}
+
+impl ShowStats {
+    pub fn from_runs(ticket_id: &str, runs: &[RunRecord]) -> Self {
+        let mut stats = Self {
+            ticket_id: ticket_id.to_string(),
+            ..Default::default()
+        };
+        for run in runs {
+            stats.ticket_name = run.ticket_name.clone();
+            stats.runs += 1;
+            if run.success {
+                stats.successes += 1;
+            }
+            stats.attempts += run.attempts;
+            for (code, count) in run.errors.iter() {
+                match stats.errors.iter_mut().find(|(c, _)| c == code) {
+                    Some(entry) => entry.1 += count,
+                    None => stats.errors.push((code.clone(), *count)),
+                }
+            }
+            stats.best_latency_ms = min(stats.best_latency_ms, run.best_latency_ms);
+            stats.first_sold_out_ms = min(stats.first_sold_out_ms, run.first_sold_out_ms);
+            stats.last_run_at = Some(run.finished_at);
+        }
+        stats
+            .errors
+            .sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        stats
+    }
+
+    // 文本格式的统计
+    pub fn text(&self) -> String {
+        let ms = |v: Option<i64>| v.map_or("-".to_string(), |v| format!("{}毫秒", v));
+        let mut lines = vec![
+            format!("门票: {} {}", self.ticket_id, self.ticket_name),
+            format!("运行次数: {}, 下单成功: {}", self.runs, self.successes),
+            format!("生成/提交订单: {}次", self.attempts),
+            format!("最短耗时: {}", ms(self.best_latency_ms.map(|v| v as i64))),
+            format!("开售后首次库存不足: {}", ms(self.first_sold_out_ms)),
+        ];
+        if !self.errors.is_empty() {
+            lines.push("错误码:".to_string());
+            for (code, count) in self.errors.iter() {
+                lines.push(format!("  {}\t{}次", code, count));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+// 取较小值, 其中一个为None时取另一个
+fn min<T: Ord + Copy>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+// 任务结束后保存运行记录, RUN_HISTORY=false时不保存
+pub struct HistorySubscriber {
+    store: RunStore,
+    record: Mutex<RunRecord>,
+}
+
+impl HistorySubscriber {
+    pub fn new(store: RunStore) -> Self {
+        Self {
+            store,
+            record: Mutex::new(RunRecord::default()),
+        }
+    }
+
+    pub fn enabled() -> bool {
+        env::var("RUN_HISTORY")
+            .map(|v| !v.eq_ignore_ascii_case("false"))
+            .unwrap_or(true)
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for HistorySubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        let record = match self.record.lock() {
+            Ok(mut record) => {
+                record.record(event);
+                match event {
+                    TicketEvent::RunFinished { .. } => {
+                        let run_id = trace::current_run_id().unwrap_or_default();
+                        record.finish(task, run_id, Local::now().timestamp_millis());
+                        std::mem::take(&mut *record)
+                    }
+                    _ => return,
+                }
+            }
+            Err(_) => return,
+        };
+        match self.store.save(&record) {
+            Ok(_) => debug!("{}, 已保存运行记录:{}", task.nickname, record.file_name()),
+            Err(e) => warn!("{}, 保存运行记录失败:{:?}", task.nickname, e),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::{env, str::FromStr, sync::OnceLock};
+
+use anyhow::{anyhow, Result};
+
+// 界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    Zh,
+    En,
+}
+
+impl FromStr for Lang {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "" | "zh" | "zh_cn" | "chinese" => Ok(Self::Zh),
+            "en" | "en_us" | "en_gb" | "english" => Ok(Self::En),
+            other => Err(anyhow!("未知的语言:{}, 可选zh/en", other)),
+        }
+    }
+}
+
+impl Lang {
+    // 从环境变量TICK_LANG读取, 未配置或无法识别时使用中文
+    pub fn from_env() -> Self {
+        env::var("TICK_LANG")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+
+    // 进程内使用的语言, 首次调用时读取环境变量
+    pub fn current() -> Self {
+        static LANG: OnceLock<Lang> = OnceLock::new();
+        *LANG.get_or_init(Self::from_env)
+    }
+
+    // 翻译界面文本, 未收录的文本原样返回
+    pub fn translate(self, text: &'static str) -> &'static str {
+        match self {
+            Lang::Zh => text,
+            Lang::En => MESSAGES
+                .iter()
+                .find(|(zh, _)| *zh == text)
+                .map_or(text, |(_, en)| en),
+        }
+    }
+}
+
+// 按当前语言翻译界面文本
+pub fn tr(text: &'static str) -> &'static str {
+    Lang::current().translate(text)
+}
+
+// 界面文本对照表: (中文, 英文)
+const MESSAGES: [(&str, &str); 38] = [
+    ("请输入手机号/账号", "Phone number / account"),
+    (
+        "请输入密码(直接回车使用短信验证码登录)",
+        "Password (press Enter to log in with an SMS code)",
+    ),
+    ("请选择账号", "Select an account"),
+    ("是否修改筛选条件?", "Change the filters?"),
+    (
+        "分类, 多个用逗号分隔, 如演唱会,话剧,音乐节(留空不限)",
+        "Categories, comma separated, e.g. 演唱会,话剧,音乐节 (empty for any)",
+    ),
+    (
+        "城市, 多个用逗号分隔(留空不限)",
+        "Cities, comma separated (empty for any)",
+    ),
+    ("场馆关键词(留空不限)", "Venue keyword (empty for any)"),
+    (
+        "开抢日期起始, YYYY-MM-DD(留空不限)",
+        "Sale date from, YYYY-MM-DD (empty for any)",
+    ),
+    (
+        "开抢日期截止, YYYY-MM-DD(留空不限)",
+        "Sale date to, YYYY-MM-DD (empty for any)",
+    ),
+    (
+        "请选择演出(输入关键字过滤)",
+        "Select a show (type to filter)",
+    ),
+    ("请选择场次", "Select a session"),
+    (
+        "请选择票档(空格选择, 可多选, 回车确认)",
+        "Select price tiers (Space to toggle, multiple allowed, Enter to confirm)",
+    ),
+    (
+        "请调整票档优先级(空格选中后上下移动, 回车确认)",
+        "Order the price tiers by priority (Space to pick, arrows to move, Enter to confirm)",
+    ),
+    (
+        "所选票档均已售罄, 仅能等待回流票, 是否继续?",
+        "All selected tiers are sold out, only returned tickets are possible. Continue?",
+    ),
+    ("请选择收货地址", "Select a delivery address"),
+    (
+        "请确认以上信息, 是否创建抢票任务?",
+        "Create the task with the details above?",
+    ),
+    (
+        "是否导出开售时间到日历(.ics)?",
+        "Export the sale time to a calendar (.ics)?",
+    ),
+    ("日历文件路径", "Calendar file path"),
+    ("请选择登录方式", "Select a login method"),
+    ("1.扫码登录", "1.Scan QR code"),
+    ("2.输入cookie", "2.Enter cookie"),
+    ("3.使用已保存账号", "3.Use a saved account"),
+    ("4.账号密码/短信验证码登录", "4.Password / SMS code"),
+    ("请输入cookie", "Cookie"),
+    ("请选择演出来源", "Select where to pick the show from"),
+    ("1.即将开抢的演出", "1.Upcoming sales"),
+    ("2.我的想看", "2.My wish list"),
+    ("购票数量", "Number of tickets"),
+    ("购票数量为1-4", "The number of tickets must be 1-4"),
+    ("重试次数", "Retry times"),
+    ("重试间隔(毫秒)", "Retry interval (ms)"),
+    (
+        "生成-提交订单间隔(毫秒)",
+        "Interval between build and submit (ms)",
+    ),
+    ("请求时间偏移量(毫秒)", "Request time offset (ms)"),
+    (
+        "优先购时长(分钟, 0为自动识别)",
+        "Priority purchase window (minutes, 0 to detect)",
+    ),
+    (
+        "库存不足时是否进行缺货登记?",
+        "Register for restock when sold out?",
+    ),
+    ("是否为选座演出?", "Is this a seat-selection show?"),
+    (
+        "优先区域(多个用逗号分隔, 留空不限区域)",
+        "Preferred zones, comma separated (empty for any)",
+    ),
+    ("是否使用优惠券?", "Use coupons?"),
+];
@@ -1,15 +1,80 @@
+pub mod account;
+pub mod calendar;
+#[cfg(feature = "interactive")]
 pub mod client;
 pub mod clients;
+pub mod config;
+pub mod config_check;
+pub mod daemon;
+pub mod doctor;
 pub mod errors;
+pub mod events;
+pub mod exit;
+pub mod experiment;
+pub mod export;
+pub mod health;
+pub mod history;
+pub mod i18n;
+pub mod logger;
 pub mod models;
+pub mod output;
+pub mod pacer;
+pub mod platform;
+pub mod qrcode_render;
+pub mod resume;
+#[cfg(feature = "browser-login")]
 pub mod server;
+pub mod simulator;
+pub mod spin;
+pub mod submit_guard;
+#[cfg(feature = "otel")]
+pub mod telemetry;
 pub mod ticket;
+pub mod trace;
+#[cfg(windows)]
+pub mod winservice;
 
+use std::env;
+
+use chrono::{Local, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
 use rand::Rng;
 
+// 默认开售时区
+const DEFAULT_TIMEZONE: Tz = chrono_tz::Asia::Shanghai;
+
 fn rand_i64(value: i64) -> u64 {
     let min_value = value / 5 * 4;
     let max_value = value + value / 10;
     let mut rng = rand::thread_rng();
     rng.gen_range(min_value..max_value) as u64
 }
+
+// 开售时间所在时区, 环境变量SALE_TIMEZONE配置, 默认Asia/Shanghai
+pub fn sale_timezone() -> Tz {
+    env::var("SALE_TIMEZONE")
+        .ok()
+        .and_then(|tz| tz.parse::<Tz>().ok())
+        .unwrap_or(DEFAULT_TIMEZONE)
+}
+
+// 格式化时间戳(毫秒), 按开售时区显示, 与本机时区不同时同时显示本机时间
+pub fn format_timestamp(millis: i64, tz: Tz) -> String {
+    let utc = match Utc.timestamp_millis_opt(millis).single() {
+        Some(utc) => utc,
+        None => return millis.to_string(),
+    };
+    let sale = utc.with_timezone(&tz);
+    let local = utc.with_timezone(&Local);
+
+    let fmt = "%Y-%m-%d %H:%M:%S%.3f";
+    match sale.offset().fix() == local.offset().fix() {
+        true => format!("{} ({})", sale.format(fmt), tz.name()),
+        false => format!(
+            "{} ({}), 本机时间:{}",
+            sale.format(fmt),
+            tz.name(),
+            local.format(fmt)
+        ),
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Write as _,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::Local;
+use regex::Regex;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_appender::rolling::{self, RollingFileAppender};
+#[cfg(feature = "otel")]
+use tracing_subscriber::filter::LevelFilter;
+#[cfg(not(feature = "otel"))]
+use tracing_subscriber::layer::Identity;
+use tracing_subscriber::{
+    layer::Context, prelude::*, registry::LookupSpan, EnvFilter, Layer, Registry,
+};
+
+// 日志中需要脱敏的cookie字段
+const SECRET_COOKIES: &str = "cookie1|cookie2|_m_h5_tk|_m_h5_tk_enc|sgcookie|unb|munb|csg|_tb_token_|x5sec|_hvn_login|havana_lgc2_77|lgc|dnk|_nk_|tracknick|skey|isg|tfstk";
+
+// 日志中需要脱敏的token字段
+const SECRET_FIELDS: &str = "token|token_with_time|enc_token|cookie|sign|bx-ua|bx-umidtoken|bx_ua|bx_umid_token|secretValue|submitref|st|ck";
+
+// 脱敏规则
+fn scrub_rules() -> &'static Vec<(Regex, &'static str)> {
+    static RULES: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        vec![
+            // cookie: name=value
+            (
+                Regex::new(&format!(r#"\b({})=([^;\s"\\]+)"#, SECRET_COOKIES)).unwrap(),
+                "$1=***",
+            ),
+            // json/debug输出: "name": "value" 或 name: "value"
+            (
+                Regex::new(&format!(
+                    r#"(\\?"?\b(?:{})\b\\?"?\s*[:=]\s*\\?")[^"\\]*(\\?")"#,
+                    SECRET_FIELDS
+                ))
+                .unwrap(),
+                "${1}***${2}",
+            ),
+            // 身份证号
+            (
+                Regex::new(r"\b(\d{3})\d{11}(\d{3}[\dXx])\b").unwrap(),
+                "$1***********$2",
+            ),
+            // 手机号
+            (
+                Regex::new(r"\b(1[3-9]\d)\d{4}(\d{4})\b").unwrap(),
+                "$1****$2",
+            ),
+        ]
+    })
+}
+
+// 去除日志中的cookie/token/身份证号/手机号
+pub fn scrub(content: &str) -> String {
+    let mut content = content.to_string();
+    for (re, rep) in scrub_rules().iter() {
+        content = re.replace_all(&content, *rep).to_string();
+    }
+    content
+}
+
+// 是否开启日志脱敏, LOG_SCRUB=false时关闭
+fn scrub_enabled() -> bool {
+    env::var("LOG_SCRUB")
+        .map(|v| !v.eq_ignore_ascii_case("false"))
+        .unwrap_or(true)
+}
+
+// 写入前脱敏
+pub struct ScrubWriter<W: Write>(pub W);
+
+impl<W: Write> Write for ScrubWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let content = String::from_utf8_lossy(buf);
+        self.0.write_all(scrub(&content).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+// 任务span中用于区分日志文件的字段
+const TASK_FIELD: &str = "task";
+
+// 记录在span中的任务名称
+struct TaskName(String);
+
+// 记录在span中的字段
+struct SpanFields(String);
+
+// 收集事件/span的字段
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+    task: Option<String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == TASK_FIELD {
+            self.task = Some(value.to_string());
+        }
+        self.record_debug(field, &value)
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            name => {
+                if name == TASK_FIELD && self.task.is_none() {
+                    self.task = Some(format!("{:?}", value));
+                }
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+// 按任务写入日志文件, 每个任务一个按天滚动的文件
+pub struct TaskFileLayer {
+    dir: PathBuf,
+    appenders: Mutex<HashMap<String, RollingFileAppender>>,
+    scrub: bool,
+}
+
+impl TaskFileLayer {
+    pub fn new(dir: impl Into<PathBuf>, scrub: bool) -> Self {
+        Self {
+            dir: dir.into(),
+            appenders: Mutex::new(HashMap::new()),
+            scrub,
+        }
+    }
+
+    fn file_name(task: &str) -> String {
+        let name = task
+            .chars()
+            .map(|c| match c.is_alphanumeric() || c == '-' || c == '_' {
+                true => c,
+                false => '_',
+            })
+            .collect::<String>();
+        format!("{}.log", name)
+    }
+}
+
+impl<S> Layer<S> for TaskFileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            if let Some(task) = visitor.task {
+                extensions.insert(TaskName(task));
+            }
+            extensions.insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let scope = match ctx.event_scope(event) {
+            Some(scope) => scope,
+            None => return,
+        };
+
+        let mut task = None;
+        let mut span_fields = String::new();
+        for span in scope.from_root() {
+            let extensions = span.extensions();
+            if let Some(name) = extensions.get::<TaskName>() {
+                task = Some(name.0.clone());
+            }
+            match extensions.get::<SpanFields>() {
+                Some(fields) => {
+                    let _ = write!(span_fields, "{}{{{}}}:", span.name(), fields.0.trim());
+                }
+                None => {
+                    let _ = write!(span_fields, "{}:", span.name());
+                }
+            }
+        }
+
+        let task = match task {
+            Some(task) => task,
+            None => return,
+        };
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let line = format!(
+            "{} {:>5} {} {}: {}{}\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            event.metadata().level(),
+            span_fields,
+            event.metadata().target(),
+            visitor.message,
+            visitor.fields,
+        );
+
+        let line = match self.scrub {
+            true => scrub(&line),
+            false => line,
+        };
+
+        if let Ok(mut appenders) = self.appenders.lock() {
+            let appender = appenders
+                .entry(task.clone())
+                .or_insert_with(|| rolling::daily(&self.dir, Self::file_name(&task)));
+            let _ = appender.write_all(line.as_bytes());
+        }
+    }
+}
+
+// 初始化日志:
+// RUST_LOG: 日志级别
+// LOG_FORMAT: text(默认)/json
+// LOG_DIR: 按任务写入日志文件的目录, 未配置时不写文件
+// LOG_SCRUB: 日志脱敏, 默认开启
+// OTEL_EXPORTER_OTLP_ENDPOINT: 导出trace, 需启用otel功能, 不受RUST_LOG影响
+// OUTPUT_FORMAT=json时日志输出到标准错误, 标准输出只保留命令结果
+pub fn init() {
+    let json = env::var("LOG_FORMAT")
+        .map(|v| v.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+
+    let scrub = scrub_enabled();
+    let stderr = crate::output::is_json();
+
+    let make_writer = move || -> Box<dyn Write> {
+        let writer: Box<dyn Write> = match stderr {
+            true => Box::new(io::stderr()),
+            false => Box::new(io::stdout()),
+        };
+        match scrub {
+            true => Box::new(ScrubWriter(writer)),
+            false => writer,
+        }
+    };
+
+    let stdout_layer = match json {
+        true => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(make_writer)
+            .boxed(),
+        false => tracing_subscriber::fmt::layer()
+            .with_writer(make_writer)
+            .boxed(),
+    }
+    .with_filter(EnvFilter::from_default_env());
+
+    let file_layer = env::var("LOG_DIR")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| TaskFileLayer::new(dir, scrub).with_filter(EnvFilter::from_default_env()));
+
+    #[cfg(feature = "otel")]
+    let otel_layer = crate::telemetry::layer().map(|layer| layer.with_filter(LevelFilter::INFO));
+    #[cfg(not(feature = "otel"))]
+    let otel_layer: Option<Identity> = None;
+
+    Registry::default()
+        .with(stdout_layer)
+        .with(file_layer)
+        .with(otel_layer)
+        .init();
+}
+
+// 退出前导出剩余的trace
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    crate::telemetry::shutdown();
+}
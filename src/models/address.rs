@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// 查询收货地址表单
+pub struct GetAddressListForm;
+
+impl GetAddressListForm {
+    pub fn build() -> Value {
+        json!({
+            "pageIndex": "1",
+            "pageSize": "20",
+            "dmChannel": "damai@damaih5_h5"
+        })
+    }
+}
+
+// 添加收货地址表单
+pub struct AddAddressForm;
+
+impl AddAddressForm {
+    pub fn build(address: &NewAddress) -> Value {
+        json!({
+            "fullName": address.name,
+            "mobile": address.phone,
+            "province": address.province,
+            "city": address.city,
+            "area": address.area,
+            "addressDetail": address.detail,
+            "defaultAddress": address.is_default,
+            "dmChannel": "damai@damaih5_h5"
+        })
+    }
+}
+
+// 待添加的收货地址
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewAddress {
+    pub name: String,     // 收货人
+    pub phone: String,    // 手机号
+    pub province: String, // 省
+    pub city: String,     // 市
+    pub area: String,     // 区/县
+    pub detail: String,   // 详细地址
+    pub is_default: bool, // 设为默认地址
+}
+
+impl NewAddress {
+    // 校验必填项及手机号格式
+    pub fn check(&self) -> Result<()> {
+        let fields = [
+            ("收货人", &self.name),
+            ("省", &self.province),
+            ("市", &self.city),
+            ("详细地址", &self.detail),
+        ];
+        if let Some((field, _)) = fields.iter().find(|(_, value)| value.trim().is_empty()) {
+            return Err(anyhow!("收货地址缺少{}", field));
+        }
+        let phone = &self.phone;
+        if phone.len() != 11
+            || !phone.starts_with('1')
+            || !phone.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(anyhow!("手机号:{}格式错误", phone));
+        }
+        Ok(())
+    }
+}
+
+// 收货地址
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    pub address_id: String,
+    pub name: String,    // 收货人
+    pub phone: String,   // 手机号
+    pub address: String, // 省市区及详细地址
+    pub is_default: bool,
+}
+
+impl Address {
+    pub fn desc(&self) -> String {
+        format!("{} {} {}", self.name, self.phone, self.address)
+    }
+}
+
+// 字段可能为字符串或数字
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+// 解析收货地址列表, 跳过缺少地址ID的项
+pub fn parse_address_list(data: &Value) -> Vec<Address> {
+    let items = ["addressList", "list", "result"]
+        .iter()
+        .find_map(|key| data[key].as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let address_id = ["addressId", "deliverId", "id"]
+                .iter()
+                .map(|key| value_to_string(&item[key]))
+                .find(|id| !id.is_empty())?;
+            let address = ["province", "city", "area", "town", "addressDetail"]
+                .iter()
+                .map(|key| value_to_string(&item[key]))
+                .collect::<String>();
+            Some(Address {
+                address_id,
+                name: value_to_string(&item["fullName"]),
+                phone: value_to_string(&item["mobile"]),
+                address,
+                is_default: match &item["defaultAddress"] {
+                    Value::Bool(b) => *b,
+                    value => matches!(value_to_string(value).as_str(), "true" | "1"),
+                },
+            })
+        })
+        .collect()
+}
+
+// 按地址ID选择收货地址, 未指定时使用默认地址, 没有默认地址时使用第一个
+pub fn select_address<'a>(
+    addresses: &'a [Address],
+    address_id: Option<&str>,
+) -> Option<&'a Address> {
+    match address_id {
+        Some(id) => addresses.iter().find(|address| address.address_id == id),
+        None => addresses
+            .iter()
+            .find(|address| address.is_default)
+            .or(addresses.first()),
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// 查询门票可用优惠券表单
+pub struct GetCouponListForm;
+
+impl GetCouponListForm {
+    pub fn build(item_id: &str) -> Value {
+        json!({
+            "itemId": item_id,
+            "dmChannel": "damai@damaih5_h5"
+        })
+    }
+}
+
+// 优惠券
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Coupon {
+    pub coupon_id: String,
+    pub name: String,
+    pub discount: f64,  // 优惠金额, 元
+    pub threshold: f64, // 使用门槛, 订单金额满足时可用, 元
+    pub usable: bool,
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn value_to_f64(value: &Value) -> f64 {
+    value_to_string(value).parse::<f64>().unwrap_or_default()
+}
+
+// 解析优惠券列表, 跳过缺少优惠券ID的项
+pub fn parse_coupon_list(data: &Value) -> Vec<Coupon> {
+    let items = ["couponList", "list", "result"]
+        .iter()
+        .find_map(|key| data[key].as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let coupon_id = ["couponId", "couponInstanceId", "id"]
+                .iter()
+                .map(|key| value_to_string(&item[key]))
+                .find(|id| !id.is_empty())?;
+            Some(Coupon {
+                coupon_id,
+                name: ["couponName", "name", "title"]
+                    .iter()
+                    .map(|key| value_to_string(&item[key]))
+                    .find(|name| !name.is_empty())
+                    .unwrap_or_default(),
+                discount: value_to_f64(&item["discountAmount"]),
+                threshold: value_to_f64(&item["thresholdAmount"]),
+                usable: match &item["canUse"] {
+                    Value::Bool(b) => *b,
+                    Value::Null => true,
+                    value => matches!(value_to_string(value).as_str(), "true" | "1"),
+                },
+            })
+        })
+        .collect()
+}
+
+// 选择优惠金额最大的可用优惠券, amount为订单金额, 未知时不校验使用门槛
+pub fn best_coupon(coupons: &[Coupon], amount: Option<f64>) -> Option<&Coupon> {
+    coupons
+        .iter()
+        .filter(|coupon| coupon.usable && coupon.discount > 0.0)
+        .filter(|coupon| amount.is_none_or(|amount| coupon.threshold <= amount))
+        .max_by(|a, b| a.discount.total_cmp(&b.discount))
+}
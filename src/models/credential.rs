@@ -0,0 +1,121 @@
+use anyhow::Result;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// 登录表单公共字段
+fn common_form() -> Value {
+    let mut rng = thread_rng();
+    let csrf_token: String = (0..21).map(|_| rng.sample(Alphanumeric) as char).collect();
+    let umid_token: String = (0..40).map(|_| rng.sample(Alphanumeric) as char).collect();
+    json!({
+        "appName": "damai",
+        "appEntrance": "damai",
+        "fromSite": "18",
+        "_csrf_token": csrf_token,
+        "umidToken": umid_token.to_lowercase(),
+        "isMobile": "false",
+        "lang": "zh_CN",
+        "returnUrl": "https://passport.damai.cn/dologin.htm?redirectUrl=https%253A%252F%252Fwww.damai.cn%252F&platform=106002",
+        "bizParams": "",
+        "umidTag": "SERVER",
+        "navlanguage": "zh-CN",
+        "navPlatform": "MacIntel",
+    })
+}
+
+// 账号密码登录表单, password2为rsa加密后的密码
+pub struct PasswordLoginForm;
+
+impl PasswordLoginForm {
+    pub fn build(login_id: &str, password2: &str) -> Result<Value> {
+        let mut form = common_form();
+        form["loginId"] = login_id.into();
+        form["password2"] = password2.into();
+        form["keepLogin"] = "false".into();
+        Ok(form)
+    }
+}
+
+// 发送短信验证码表单
+pub struct SmsSendForm;
+
+impl SmsSendForm {
+    pub fn build(phone: &str) -> Result<Value> {
+        let mut form = common_form();
+        form["phoneCode"] = "86".into();
+        form["loginId"] = phone.into();
+        form["countryCode"] = "CN".into();
+        Ok(form)
+    }
+}
+
+// 短信验证码登录表单
+pub struct SmsLoginForm;
+
+impl SmsLoginForm {
+    pub fn build(phone: &str, sms_code: &str, sms_token: &str) -> Result<Value> {
+        let mut form = common_form();
+        form["phoneCode"] = "86".into();
+        form["loginId"] = phone.into();
+        form["countryCode"] = "CN".into();
+        form["smsCode"] = sms_code.into();
+        form["smsToken"] = sms_token.into();
+        form["keepLogin"] = "false".into();
+        Ok(form)
+    }
+}
+
+// 账号密码/短信登录返回
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CredentialLoginData {
+    #[serde(rename = "loginResult")]
+    pub login_result: Option<String>,
+
+    pub st: Option<String>,
+
+    #[serde(rename = "returnUrl")]
+    pub return_url: Option<String>,
+
+    pub cookie2: Option<String>,
+
+    // 需要二次验证时跳转验证页面
+    #[serde(rename = "iframeRedirect")]
+    pub iframe_redirect: Option<bool>,
+
+    #[serde(rename = "iframeRedirectUrl")]
+    pub iframe_redirect_url: Option<String>,
+
+    // 失败原因
+    #[serde(rename = "titleMsg")]
+    pub title_msg: Option<String>,
+}
+
+impl CredentialLoginData {
+    pub fn success(&self) -> bool {
+        self.login_result.as_deref() == Some("success") && self.st.is_some()
+    }
+
+    // 是否需要短信二次验证
+    pub fn needs_verify(&self) -> bool {
+        self.iframe_redirect == Some(true) || self.iframe_redirect_url.is_some()
+    }
+}
+
+// 从登录页面中读取rsa公钥(模数, 指数), 十六进制
+pub fn parse_rsa_key(html: &str) -> Option<(String, String)> {
+    let find = |name: &str| {
+        let re = Regex::new(&format!(r#""{}"\s*:\s*"([0-9a-fA-F]+)""#, name)).ok()?;
+        re.captures(html).map(|c| c[1].to_string())
+    };
+    Some((find("rsaModulus")?, find("rsaExponent")?))
+}
+
+// 是否为手机号
+pub fn is_phone(login_id: &str) -> bool {
+    login_id.len() == 11
+        && login_id.starts_with('1')
+        && login_id.chars().all(|c| c.is_ascii_digit())
+}
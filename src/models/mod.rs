@@ -1,10 +1,18 @@
+pub mod address;
+pub mod coupon;
+pub mod credential;
 pub mod order;
 pub mod perform;
+pub mod privilege;
 pub mod qrcode;
+pub mod search;
+pub mod seat;
 pub mod task;
 pub mod ticket;
 pub mod user;
+pub mod viewer;
 
+use crate::errors::MtopError;
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 use serde_json::{value, Value};
@@ -26,6 +34,13 @@ pub struct DmRes {
     pub v: Option<String>,
 }
 
+impl DmRes {
+    // 解析ret中的错误, 调用成功时返回None
+    pub fn error(&self) -> Option<MtopError> {
+        MtopError::from_rets(&self.ret)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DmLoginResContent {
     pub status: i32,
@@ -86,6 +101,13 @@ impl CommonParams {
             request_start: (millis - 1).to_string(),
         }
     }
+
+    // 更新预先构造的请求参数中的时间戳
+    pub fn refresh(params: &mut Value) {
+        let millis = Local::now().timestamp_millis();
+        params["t"] = millis.to_string().into();
+        params["requestStart"] = (millis - 1).to_string().into();
+    }
 }
 
 impl Default for CommonParams {
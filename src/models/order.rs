@@ -1,8 +1,29 @@
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::{json, Value};
 
-use super::CommonParams;
+use super::{
+    seat::Seat,
+    task::Task,
+    viewer::{parse_viewers, select_viewers},
+    CommonParams,
+};
+
+// 提交时需序列化为json字符串的字段
+fn json_string<T: Serialize, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    let text = serde_json::to_string(value).map_err(serde::ser::Error::custom)?;
+    serializer.serialize_str(&text)
+}
+
+fn json_string_opt<T: Serialize, S: Serializer>(
+    value: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => json_string(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
 
 // 大麦生成订单接口params
 pub struct OrderParams;
@@ -20,27 +41,121 @@ impl OrderParams {
     }
 }
 
+// 以下请求模型的字段按序列化后的键名字母顺序声明, 与大麦h5页面提交的数据一致
+
+// 生成订单exParams
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildOrderExParams {
+    pub atom_split: String,
+    pub channel: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coupon_id: Option<String>,
+    pub customer_type: String,
+    pub damai: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privilege_code: Option<String>, // 会员/粉丝优先购码
+    pub service_version: String,
+    pub sub_channel: String,
+    pub ump_channel: String,
+}
+
+impl Default for BuildOrderExParams {
+    fn default() -> Self {
+        Self {
+            atom_split: "1".to_string(),
+            channel: "damai_app".to_string(),
+            coupon_id: None,
+            customer_type: "default".to_string(),
+            damai: "1".to_string(),
+            privilege_code: None,
+            service_version: "2.0.0".to_string(),
+            sub_channel: "damai@damaih5_h5".to_string(),
+            ump_channel: "100031004".to_string(),
+        }
+    }
+}
+
+// 选座信息
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SeatInfo {
+    pub seat_id: String,
+    pub sku_id: String,
+}
+
+// 生成订单表单
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildOrderForm {
+    pub buy_now: String,
+    pub buy_param: String, // 门票ID_购票数量_票档ID
+    pub dm_channel: String,
+    #[serde(serialize_with = "json_string")]
+    pub ex_params: BuildOrderExParams,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "json_string_opt"
+    )]
+    pub seat_info: Option<Vec<SeatInfo>>,
+}
+
+impl BuildOrderForm {
+    pub fn new(item_id: &str, sku_id: &str, buy_num: usize) -> Self {
+        Self {
+            buy_now: "true".to_string(),
+            buy_param: format!("{}_{}_{}", item_id, buy_num, sku_id),
+            dm_channel: "damai@damaih5_h5".to_string(),
+            ex_params: BuildOrderExParams::default(),
+            seat_info: None,
+        }
+    }
+}
+
 pub struct OrderForm;
 
 // 生成订单表单参数
 impl OrderForm {
-    pub fn build(item_id: &String, sku_id: &String, by_num: usize) -> Result<Value> {
-        let ext_params = json!({
-            "channel": "damai_app",
-            "damai": "1",
-            "umpChannel": "100031004",
-            "subChannel": "damai@damaih5_h5",
-            "atomSplit": "1",
-            "serviceVersion": "2.0.0",
-            "customerType": "default"
-        });
-
-        let data = json!({
-            "buyNow": "true",
-            "exParams": serde_json::to_string(&ext_params)?,
-            "buyParam": format!("{}_{}_{}", item_id, by_num, sku_id),
-            "dmChannel": "damai@damaih5_h5"
-        });
+    pub fn build(item_id: &str, sku_id: &str, by_num: usize) -> Result<Value> {
+        Ok(serde_json::to_value(BuildOrderForm::new(
+            item_id, sku_id, by_num,
+        ))?)
+    }
+
+    // 选座演出的生成订单表单, 购票数量为选中的座位数
+    pub fn build_with_seats(item_id: &str, sku_id: &str, seats: &[Seat]) -> Result<Value> {
+        let mut form = BuildOrderForm::new(item_id, sku_id, seats.len());
+        form.seat_info = Some(
+            seats
+                .iter()
+                .map(|seat| SeatInfo {
+                    seat_id: seat.seat_id.clone(),
+                    sku_id: sku_id.to_string(),
+                })
+                .collect(),
+        );
+        Ok(serde_json::to_value(form)?)
+    }
+
+    // 生成订单时使用优惠券
+    pub fn with_coupon(data: Value, coupon_id: &str) -> Result<Value> {
+        Self::update_ex_params(data, |ext_params| {
+            ext_params.coupon_id = Some(coupon_id.to_string())
+        })
+    }
+
+    // 生成订单时携带会员/粉丝优先购码
+    pub fn with_privilege_code(data: Value, code: &str) -> Result<Value> {
+        Self::update_ex_params(data, |ext_params| {
+            ext_params.privilege_code = Some(code.to_string())
+        })
+    }
+
+    fn update_ex_params(mut data: Value, f: impl FnOnce(&mut BuildOrderExParams)) -> Result<Value> {
+        let mut ext_params: BuildOrderExParams =
+            serde_json::from_str(data["exParams"].as_str().unwrap_or("{}"))?;
+        f(&mut ext_params);
+        data["exParams"] = serde_json::to_string(&ext_params)?.into();
         Ok(data)
     }
 }
@@ -105,6 +220,16 @@ pub struct OrderInfo {
     pub linkage: OrderInfoLinkage,
 }
 
+impl OrderInfo {
+    // 订单是否需要选择实名观演人
+    pub fn requires_viewers(&self) -> bool {
+        self.linkage.input.iter().any(|key| {
+            key.starts_with("dmViewer_")
+                && !parse_viewers(&self.data[key]["fields"]["viewerList"]).is_empty()
+        })
+    }
+}
+
 // 提交订单params
 pub struct SubmitOrderParams;
 impl SubmitOrderParams {
@@ -123,3 +248,268 @@ impl SubmitOrderParams {
         Ok(params)
     }
 }
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitLinkageCommon {
+    pub compress: bool,
+    pub submit_params: String,
+    pub validate_params: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SubmitLinkage {
+    pub common: SubmitLinkageCommon,
+    pub signature: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SubmitHierarchy {
+    pub structure: Value,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SubmitParams {
+    #[serde(serialize_with = "json_string")]
+    pub data: Value,
+    #[serde(serialize_with = "json_string")]
+    pub hierarchy: SubmitHierarchy,
+    #[serde(serialize_with = "json_string")]
+    pub linkage: SubmitLinkage,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitFeature {
+    pub data_tags: String,
+    pub return_url: String,
+    pub service_version: String,
+    pub sub_channel: String,
+}
+
+impl Default for SubmitFeature {
+    fn default() -> Self {
+        Self {
+            data_tags: "sqm:dianying.h5.unknown.value".to_string(),
+            return_url: "https://m.damai.cn/damai/pay-success/index.html?spm=a2o71.orderconfirm.bottom.dconfirm&sqm=dianying.h5.unknown.value".to_string(),
+            service_version: "2.0.0".to_string(),
+            sub_channel: "damai@damaih5_h5".to_string(),
+        }
+    }
+}
+
+// 提交订单表单
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SubmitOrderForm {
+    #[serde(serialize_with = "json_string")]
+    pub feature: SubmitFeature,
+    #[serde(serialize_with = "json_string")]
+    pub params: SubmitParams,
+}
+
+impl SubmitOrderForm {
+    // 按生成订单返回的数据及任务配置(观演人/收货地址)构造提交订单表单
+    pub fn new(order_info: &OrderInfo, task: &Task) -> Result<Self> {
+        Ok(Self {
+            feature: SubmitFeature::default(),
+            params: SubmitParams {
+                data: submit_order_data(order_info, task)?,
+                hierarchy: SubmitHierarchy {
+                    structure: order_info.hierarchy.structure.clone(),
+                },
+                linkage: SubmitLinkage {
+                    common: SubmitLinkageCommon {
+                        compress: order_info.linkage.common.compress,
+                        submit_params: order_info.linkage.common.submit_params.clone(),
+                        validate_params: order_info.linkage.common.validate_params.clone(),
+                    },
+                    signature: order_info.linkage.signature.clone(),
+                },
+            },
+        })
+    }
+
+    pub fn build(order_info: &OrderInfo, task: &Task) -> Result<Value> {
+        Ok(serde_json::to_value(Self::new(order_info, task)?)?)
+    }
+}
+
+// 提交订单的组件数据: 需要提交的组件, 根组件及其下的订单组件
+fn submit_order_data(order_info: &OrderInfo, task: &Task) -> Result<Value> {
+    let mut order_data = json!({});
+
+    for key in order_info.linkage.input.iter() {
+        let mut item = order_info.data[key].clone();
+        if key.starts_with("dmViewer_") {
+            // 需选择实名观演人
+            let viewers = parse_viewers(&item["fields"]["viewerList"]);
+            if !viewers.is_empty() {
+                for i in select_viewers(&viewers, task)? {
+                    item["fields"]["viewerList"][i]["isUsed"] = true.into();
+                }
+            }
+        } else if key.starts_with("dmDeliveryAddress_") {
+            // 纸质票收货地址
+            if let Some(address_id) = &task.address_id {
+                item["fields"]["selectedId"] = address_id.clone().into();
+            }
+        }
+        order_data[key] = item;
+    }
+
+    let root = &order_info.hierarchy.root;
+    order_data[root] = order_info.data[root].clone();
+
+    if let Some(keys) = order_info.hierarchy.structure[root].as_array() {
+        for key in keys.iter().filter_map(|key| key.as_str()) {
+            if key.starts_with("order_") {
+                order_data[key] = order_info.data[key].clone();
+            }
+        }
+    }
+    strip_nulls(&mut order_data);
+    Ok(order_data)
+}
+
+// 删除值为null的字段, 与h5页面序列化时省略undefined字段一致, 同时减小提交数据
+pub fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+// 取消订单表单
+pub struct CancelOrderForm;
+
+impl CancelOrderForm {
+    pub fn build(order_id: &str) -> Result<Value> {
+        Ok(json!({
+            "orderId": order_id,
+            "reasonId": "1",
+            "dmChannel": "damai@damaih5_h5"
+        }))
+    }
+}
+
+// 订单列表每页数量
+const ORDER_LIST_PAGE_SIZE: usize = 20;
+
+// 查询订单列表表单
+pub struct OrderListForm;
+
+impl OrderListForm {
+    pub fn build(page: usize) -> Result<Value> {
+        Ok(json!({
+            "pageNum": page.to_string(),
+            "pageSize": ORDER_LIST_PAGE_SIZE.to_string(),
+            "queryType": "0",
+            "dmChannel": "damai@damaih5_h5"
+        }))
+    }
+}
+
+// 订单概要
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OrderSummary {
+    pub order_id: String,
+    pub item_id: String, // 门票ID
+    pub item_name: String,
+    pub perform_id: String,        // 场次ID, 部分订单不返回
+    pub status: String,            // 订单状态
+    pub amount: String,            // 订单金额
+    pub pay_deadline: Option<i64>, // 付款截止时间戳, 毫秒
+}
+
+impl OrderSummary {
+    // 是否待付款
+    pub fn is_unpaid(&self) -> bool {
+        self.status.contains("待付款") || self.status.eq_ignore_ascii_case("WAIT_PAY")
+    }
+
+    // 是否已取消/关闭
+    pub fn is_closed(&self) -> bool {
+        ["已取消", "已关闭", "交易关闭", "TRADE_CLOSED"]
+            .iter()
+            .any(|status| self.status.contains(status))
+    }
+}
+
+// 同一场次未取消的订单, 订单未返回场次ID时按门票ID匹配
+pub fn existing_order<'a>(orders: &'a [OrderSummary], task: &Task) -> Option<&'a OrderSummary> {
+    orders
+        .iter()
+        .filter(|order| !order.is_closed())
+        .find(|order| match order.perform_id.is_empty() {
+            true => !order.item_id.is_empty() && order.item_id == task.ticket_id,
+            false => order.perform_id == task.ticket_perform_id,
+        })
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+// 解析订单列表, 跳过缺少订单ID的项
+pub fn parse_order_list(data: &Value) -> Vec<OrderSummary> {
+    let items = ["orderList", "list", "result"]
+        .iter()
+        .find_map(|key| data[key].as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let order_id = ["orderId", "bizOrderId"]
+                .iter()
+                .map(|key| value_to_string(&item[key]))
+                .find(|id| !id.is_empty())?;
+            Some(OrderSummary {
+                order_id,
+                item_id: ["itemId", "projectId"]
+                    .iter()
+                    .map(|key| value_to_string(&item[key]))
+                    .find(|id| !id.is_empty())
+                    .unwrap_or_default(),
+                item_name: ["itemName", "projectName", "title"]
+                    .iter()
+                    .map(|key| value_to_string(&item[key]))
+                    .find(|name| !name.is_empty())
+                    .unwrap_or_default(),
+                perform_id: value_to_string(&item["performId"]),
+                status: ["orderStatusDesc", "statusDesc", "orderStatus"]
+                    .iter()
+                    .map(|key| value_to_string(&item[key]))
+                    .find(|status| !status.is_empty())
+                    .unwrap_or_default(),
+                amount: value_to_string(&item["totalAmount"]),
+                pay_deadline: ["payDeadline", "payExpireTime", "timeoutTime"]
+                    .iter()
+                    .find_map(|key| value_to_string(&item[key]).parse::<i64>().ok()),
+            })
+        })
+        .collect()
+}
+
+// 缺货登记表单
+pub struct WaitlistForm;
+
+impl WaitlistForm {
+    pub fn build(item_id: &str, perform_id: &str, sku_id: &str) -> Result<Value> {
+        Ok(json!({
+            "itemId": item_id,
+            "performId": perform_id,
+            "skuId": sku_id,
+            "dmChannel": "damai@damaih5_h5"
+        }))
+    }
+}
@@ -18,7 +18,7 @@ impl PerformParams {
 
 pub struct PerformForm;
 impl PerformForm {
-    pub fn build(ticket_id: &String, perform_id: &String) -> Result<Value> {
+    pub fn build(ticket_id: &str, perform_id: &str) -> Result<Value> {
         let ex_params = json!({
             "dataType": 2,
             "dataId": perform_id,
@@ -52,6 +52,108 @@ pub struct Sku {
     pub sku_salable: String,
 
     pub price: String,
+
+    // 票档标签, 如"缺货登记"/"售罄"
+    #[serde(default)]
+    pub tags: Value,
+
+    // 套票每份包含的票数, 普通票档不返回
+    #[serde(default, rename = "packageNum")]
+    pub package_num: Value,
+}
+
+impl Sku {
+    // 按票档数据判断的售卖状态, 不可售时暂记为未开售, 需结合开售时间修正
+    pub fn status(&self) -> SkuStatus {
+        let mut texts = vec![];
+        collect_texts(&self.tags, &mut texts);
+        if texts
+            .iter()
+            .any(|text| text.contains("售罄") || text.contains("缺货") || text.contains("无票"))
+        {
+            return SkuStatus::SoldOut;
+        }
+        match self.sku_salable.as_str() {
+            "true" => SkuStatus::Available,
+            "false" => SkuStatus::NotOnSale,
+            _ => SkuStatus::Unknown,
+        }
+    }
+
+    // 每份包含的票数, 未返回时按票档名称识别
+    pub fn bundle(&self) -> usize {
+        let num = match &self.package_num {
+            Value::String(s) => s.trim().parse().ok(),
+            value => value.as_u64().map(|n| n as usize),
+        };
+        match num {
+            Some(num) if num > 1 => num,
+            _ => bundle_size(&self.price_name),
+        }
+    }
+}
+
+// 按票档名称识别套票每份包含的票数, 如: 双人套票 => 2, 三人联票 => 3, 4张套票 => 4; 普通票档为1
+pub fn bundle_size(name: &str) -> usize {
+    if !["套票", "套餐", "联票"]
+        .iter()
+        .any(|key| name.contains(key))
+    {
+        return 1;
+    }
+    let chars = name.chars().collect::<Vec<_>>();
+    chars
+        .windows(2)
+        .filter(|pair| matches!(pair[1], '人' | '张'))
+        .filter_map(|pair| match pair[0] {
+            '双' | '二' | '两' => Some(2),
+            '三' => Some(3),
+            '四' => Some(4),
+            '五' => Some(5),
+            '六' => Some(6),
+            c => c.to_digit(10).map(|n| n as usize),
+        })
+        .find(|num| *num > 1)
+        .unwrap_or(1)
+}
+
+// 收集标签中的文本
+fn collect_texts(value: &Value, texts: &mut Vec<String>) {
+    match value {
+        Value::String(text) => texts.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_texts(item, texts)),
+        Value::Object(map) => map.values().for_each(|item| collect_texts(item, texts)),
+        _ => {}
+    }
+}
+
+// 票档售卖状态
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SkuStatus {
+    #[default]
+    Unknown,
+    NotOnSale,
+    SoldOut,
+    Available,
+}
+
+impl SkuStatus {
+    // 已开售(含优先购)后仍不可售的票档视为售罄
+    pub fn resolve(self, sale_started: bool) -> Self {
+        match (self, sale_started) {
+            (SkuStatus::NotOnSale, true) => SkuStatus::SoldOut,
+            (status, _) => status,
+        }
+    }
+
+    pub fn desc(&self) -> &'static str {
+        match self {
+            SkuStatus::Unknown => "未知",
+            SkuStatus::NotOnSale => "未开售",
+            SkuStatus::SoldOut => "售罄",
+            SkuStatus::Available => "可购",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -82,4 +184,67 @@ pub struct SkuItem {
     pub sku_id: String,
     #[serde(rename = "price_name")]
     pub sku_name: String,
+
+    #[serde(default)]
+    pub price: String,
+
+    #[serde(default)]
+    pub status: SkuStatus,
+
+    #[serde(default = "default_bundle")]
+    pub bundle: usize, // 套票每份包含的票数, 普通票档为1
+}
+
+fn default_bundle() -> usize {
+    1
+}
+
+impl SkuItem {
+    pub fn desc(&self) -> String {
+        let mut desc = self.sku_name.clone();
+        if !self.price.is_empty() {
+            desc.push_str(&format!(" | ¥{}", self.price));
+        }
+        if self.bundle > 1 {
+            desc.push_str(&format!(" | 套票(每份{}张)", self.bundle));
+        }
+        if self.status != SkuStatus::Unknown {
+            desc.push_str(&format!(" | {}", self.status.desc()));
+        }
+        desc
+    }
+
+    // 购买ticket_num张票时的下单数量, 套票为份数, 票数不是每份票数的倍数时返回None
+    pub fn buy_num(&self, ticket_num: usize) -> Option<usize> {
+        match self.bundle.max(1) {
+            1 => Some(ticket_num),
+            bundle if ticket_num.is_multiple_of(bundle) => Some(ticket_num / bundle),
+            _ => None,
+        }
+    }
+}
+
+// 所选票档(首选及备选)的售卖状态, 任一可购即为可购
+pub fn selected_status(skus: &[SkuItem], sku_ids: &[String]) -> SkuStatus {
+    let statuses = skus
+        .iter()
+        .filter(|sku| sku_ids.contains(&sku.sku_id))
+        .map(|sku| sku.status)
+        .collect::<Vec<_>>();
+    [
+        SkuStatus::Available,
+        SkuStatus::NotOnSale,
+        SkuStatus::SoldOut,
+    ]
+    .into_iter()
+    .find(|status| statuses.contains(status))
+    .unwrap_or_default()
+}
+
+// 票档库存快照, 如: 看台380元:可购, 内场1280元:售罄
+pub fn sku_snapshot(skus: &[SkuItem]) -> String {
+    skus.iter()
+        .map(|sku| format!("{}:{}", sku.sku_name, sku.status.desc()))
+        .collect::<Vec<String>>()
+        .join(", ")
 }
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// 校验会员/粉丝优先购码表单
+pub struct PrivilegeCheckForm;
+
+impl PrivilegeCheckForm {
+    pub fn build(item_id: &str, code: &str) -> Value {
+        json!({
+            "itemId": item_id,
+            "privilegeCode": code,
+            "dmChannel": "damai@damaih5_h5"
+        })
+    }
+}
+
+// 优先购码校验结果
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrivilegeCheck {
+    pub valid: bool,
+    pub message: String, // 校验失败的原因
+}
+
+// 解析优先购码校验结果, 未返回校验结果时视为无效
+pub fn parse_privilege_check(data: &Value) -> PrivilegeCheck {
+    let valid = ["valid", "pass", "result"]
+        .iter()
+        .find_map(|key| match &data[key] {
+            Value::Bool(b) => Some(*b),
+            Value::String(s) => Some(matches!(s.as_str(), "true" | "1")),
+            Value::Number(n) => Some(n.as_i64() == Some(1)),
+            _ => None,
+        })
+        .unwrap_or(false);
+    let message = ["errorMsg", "message", "msg"]
+        .iter()
+        .filter_map(|key| data[key].as_str())
+        .find(|msg| !msg.is_empty())
+        .unwrap_or_default()
+        .to_string();
+    PrivilegeCheck { valid, message }
+}
+
+// 日志中的优先购码, 只保留首尾字符
+pub fn mask_code(code: &str) -> String {
+    let chars = code.chars().collect::<Vec<_>>();
+    match chars.len() {
+        0..=2 => "*".repeat(chars.len()),
+        n => format!("{}{}{}", chars[0], "*".repeat(n - 2), chars[n - 1]),
+    }
+}
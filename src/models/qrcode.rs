@@ -114,3 +114,28 @@ pub struct QrCodeLoginStatusData {
     #[serde(rename = "returnUrl")]
     pub return_url: Option<String>,
 }
+
+// 扫码状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrcodeStatus {
+    New,             // 等待扫码
+    Scanned,         // 已扫码, 等待确认
+    Confirmed,       // 已确认登录
+    Expired,         // 二维码已失效
+    Canceled,        // 已取消登录
+    Unknown(String), // 未知状态
+}
+
+impl QrCodeLoginStatusData {
+    pub fn status(&self) -> QrcodeStatus {
+        match self.qrcode_status.as_str() {
+            "NEW" => QrcodeStatus::New,
+            "SCANED" => QrcodeStatus::Scanned,
+            "CONFIRMED" => QrcodeStatus::Confirmed,
+            "EXPIRED" => QrcodeStatus::Expired,
+            "CANCELED" => QrcodeStatus::Canceled,
+            status if status.contains("二维码已失效") => QrcodeStatus::Expired,
+            status => QrcodeStatus::Unknown(status.to_string()),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// 搜索结果每页数量
+const SEARCH_PAGE_SIZE: usize = 30;
+
+// 按关键词搜索演出表单
+pub struct SearchProjectForm;
+
+impl SearchProjectForm {
+    pub fn build(keyword: &str, page: usize) -> Result<Value> {
+        Ok(json!({
+            "keyword": keyword,
+            "cty": "",
+            "currentCityId": "0",
+            "pageIndex": page.to_string(),
+            "pageSize": SEARCH_PAGE_SIZE.to_string(),
+            "dmChannel": "damai@damaih5_h5"
+        }))
+    }
+}
+
+// 巡演的一站, 每个城市单独的门票ID
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CityStop {
+    pub item_id: String,
+    pub name: String,
+    pub city_name: String,
+    pub venue_name: String,
+    pub show_time: String, // 演出时间描述
+}
+
+// 字段可能为字符串或数字
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+// 第一个非空字段
+fn first_field(item: &Value, keys: &[&str]) -> String {
+    keys.iter()
+        .map(|key| value_to_string(&item[key]))
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+// 解析搜索结果, 跳过缺少门票ID的项
+pub fn parse_city_stops(data: &Value) -> Vec<CityStop> {
+    let items = ["projectInfo", "projects", "list", "result"]
+        .iter()
+        .find_map(|key| data[key].as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let item_id = first_field(item, &["projectId", "itemId", "id"]);
+            if item_id.is_empty() {
+                return None;
+            }
+            Some(CityStop {
+                item_id,
+                name: first_field(item, &["name", "projectName", "itemName"]),
+                city_name: first_field(item, &["cityName", "city"]),
+                venue_name: first_field(item, &["venueName", "venue"]),
+                show_time: first_field(item, &["showTime", "performTime"]),
+            })
+        })
+        .collect()
+}
+
+// 按城市优先级选择一站, 城市名去掉"市"后比较, 如"上海"与"上海市"
+pub fn select_city_stop<'a>(stops: &'a [CityStop], cities: &[String]) -> Option<&'a CityStop> {
+    let normalize = |city: &str| city.trim().trim_end_matches('市').to_string();
+    cities.iter().find_map(|city| {
+        let city = normalize(city);
+        stops
+            .iter()
+            .find(|stop| !city.is_empty() && normalize(&stop.city_name) == city)
+    })
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// 查询座位图表单
+pub struct SeatMapForm;
+
+impl SeatMapForm {
+    pub fn build(item_id: &str, perform_id: &str) -> Result<Value> {
+        Ok(json!({
+            "itemId": item_id,
+            "performId": perform_id,
+            "dmChannel": "damai@damaih5_h5"
+        }))
+    }
+}
+
+// 座位
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Seat {
+    #[serde(rename = "seatId")]
+    pub seat_id: String,
+
+    #[serde(rename = "skuId", default)]
+    pub sku_id: String, // 座位对应的票档
+
+    #[serde(rename = "zoneName", default)]
+    pub zone: String, // 区域, 如: 内场A区
+
+    #[serde(default)]
+    pub row: u32,
+
+    #[serde(rename = "col", default)]
+    pub col: u32,
+
+    #[serde(rename = "canSell", default)]
+    pub available: bool,
+}
+
+// 选座策略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SeatStrategy {
+    #[default]
+    BestAvailable, // 按区域优先级选择最好的可售座位, 优先同排相邻
+    Explicit, // 只购买指定的座位
+}
+
+// 选座配置
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SeatPreference {
+    #[serde(default)]
+    pub strategy: SeatStrategy,
+
+    // 区域优先级, 为空时不限区域
+    #[serde(default)]
+    pub zones: Vec<String>,
+
+    // 指定座位ID, Explicit策略使用
+    #[serde(default)]
+    pub seat_ids: Vec<String>,
+}
+
+impl SeatPreference {
+    // 从座位图中选择num个座位, 没有满足条件的座位时返回None
+    pub fn select(&self, seats: &[Seat], sku_id: &str, num: usize) -> Option<Vec<Seat>> {
+        if num == 0 {
+            return None;
+        }
+
+        let available = seats
+            .iter()
+            .filter(|seat| seat.available)
+            .filter(|seat| seat.sku_id.is_empty() || seat.sku_id == sku_id);
+
+        match self.strategy {
+            SeatStrategy::Explicit => {
+                let selected = self
+                    .seat_ids
+                    .iter()
+                    .filter_map(|id| available.clone().find(|seat| &seat.seat_id == id))
+                    .take(num)
+                    .cloned()
+                    .collect::<Vec<Seat>>();
+                (selected.len() == num).then_some(selected)
+            }
+            SeatStrategy::BestAvailable => {
+                let mut candidates = available
+                    .filter_map(|seat| self.zone_rank(&seat.zone).map(|rank| (rank, seat)))
+                    .collect::<Vec<(usize, &Seat)>>();
+                candidates.sort_by_key(|(rank, seat)| (*rank, seat.row, seat.col));
+
+                // 优先同区域同排相邻的座位
+                for window in candidates.windows(num) {
+                    let (rank, first) = window[0];
+                    let adjacent = window.iter().enumerate().all(|(i, (r, seat))| {
+                        *r == rank
+                            && seat.zone == first.zone
+                            && seat.row == first.row
+                            && seat.col == first.col + i as u32
+                    });
+                    if adjacent {
+                        return Some(window.iter().map(|(_, seat)| (*seat).clone()).collect());
+                    }
+                }
+
+                (candidates.len() >= num).then(|| {
+                    candidates
+                        .iter()
+                        .take(num)
+                        .map(|(_, seat)| (*seat).clone())
+                        .collect()
+                })
+            }
+        }
+    }
+
+    // 区域优先级, 越小越优先, 不在配置区域内时返回None
+    fn zone_rank(&self, zone: &str) -> Option<usize> {
+        match self.zones.is_empty() {
+            true => Some(0),
+            false => self.zones.iter().position(|z| zone.contains(z.as_str())),
+        }
+    }
+}
+
+// 解析座位图, 座位列表位置随接口版本变化
+pub fn parse_seat_map(data: &Value) -> Result<Vec<Seat>> {
+    let seats = ["seats", "seatList", "result"]
+        .iter()
+        .find_map(|key| data[key].as_array())
+        .cloned()
+        .unwrap_or_default();
+    Ok(seats
+        .into_iter()
+        .map(serde_json::from_value::<Seat>)
+        .collect::<Result<Vec<Seat>, _>>()?)
+}
@@ -1,5 +1,9 @@
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
+use super::seat::SeatPreference;
+use crate::sale_timezone;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Task {
     pub nickname: String,
@@ -19,6 +23,138 @@ pub struct Task {
     // 实名人选择
     #[serde(default = "default_real_names")]
     pub real_names: Vec<usize>,
+
+    // 按姓名或证件号后几位指定实名观演人, 优先于real_names
+    #[serde(default)]
+    pub viewers: Vec<String>,
+
+    // 一单一人: 每位观演人单独下单, 用于限制每单一张的演出
+    #[serde(default)]
+    pub split_per_viewer: bool,
+
+    // 开售前预热(获取门票信息/校验票档/构造订单参数)的提前量, 秒
+    #[serde(default = "default_warmup_time")]
+    pub warmup_time: u64,
+
+    // 任务开始后超过该时长停止, 秒
+    #[serde(default)]
+    pub stop_after_seconds: Option<u64>,
+
+    // 开售后超过该时长停止, 秒
+    #[serde(default)]
+    pub stop_after_sale_plus: Option<u64>,
+
+    // 返回包含以下错误码时停止, 如: B-00203-200-008(库存不足)
+    #[serde(default)]
+    pub stop_on_error_codes: Vec<String>,
+
+    // 备选票档ID, 按优先级排列, 首选票档库存不足时依次尝试
+    #[serde(default)]
+    pub backup_sku_ids: Vec<String>,
+
+    // 库存不足导致抢票失败时进行缺货登记
+    #[serde(default)]
+    pub waitlist_on_sold_out: bool,
+
+    // 开售时间所在时区, 默认Asia/Shanghai
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+
+    // 选座演出的选座配置, 未配置时按普通票档下单
+    #[serde(default)]
+    pub seats: Option<SeatPreference>,
+
+    // 纸质票收货地址ID, 未配置时使用账号默认地址
+    #[serde(default)]
+    pub address_id: Option<String>,
+
+    // 生成订单时使用优惠金额最大的可用优惠券
+    #[serde(default)]
+    pub use_coupons: bool,
+
+    // 多账号/多机抢票时, 其他账号已先下单成功则自动取消本账号的订单
+    #[serde(default)]
+    pub cancel_duplicates: bool,
+
+    // 账号已有同场次订单时仍继续抢票, 默认停止
+    #[serde(default)]
+    pub allow_existing_order: bool,
+
+    // 巡演搜索关键词, 与cities一起配置时按城市优先级自动选择门票,
+    // 并按场次名称/票档名称匹配对应的场次/票档
+    #[serde(default)]
+    pub keyword: Option<String>,
+
+    // 城市优先级, 如["上海", "杭州"]
+    #[serde(default)]
+    pub cities: Vec<String>,
+
+    // 未公布开售时间的门票: 监控票档售卖状态, 变为可购时立即下单, 不等待开售时间
+    #[serde(default)]
+    pub watch_status: bool,
+
+    // 会员/粉丝优先购码, 开售前校验, 生成订单时携带
+    #[serde(default)]
+    pub privilege_code: Option<String>,
+
+    // 实验分组(EXPERIMENT_VARIANTS), 未指定时多账号任务按顺序轮流分配
+    #[serde(default)]
+    pub variant: Option<String>,
+}
+
+impl Task {
+    // 开售时间所在时区
+    pub fn tz(&self) -> Tz {
+        self.timezone.parse::<Tz>().unwrap_or(sale_timezone())
+    }
+
+    // 任务标识, 用于日志/协同等区分不同任务
+    pub fn key(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.ticket_id, self.ticket_perform_id, self.ticket_perform_sku_id
+        )
+    }
+
+    // 一单一人模式下每位观演人对应的任务, 购票数量为1
+    pub fn split_by_viewer(&self) -> Vec<Task> {
+        let selectors: Vec<(Vec<String>, Vec<usize>)> = if !self.viewers.is_empty() {
+            self.viewers
+                .iter()
+                .map(|key| (vec![key.clone()], vec![]))
+                .collect()
+        } else if !self.real_names.is_empty() {
+            self.real_names
+                .iter()
+                .map(|idx| (vec![], vec![*idx]))
+                .collect()
+        } else {
+            (1..=self.ticket_num)
+                .map(|idx| (vec![], vec![idx]))
+                .collect()
+        };
+
+        selectors
+            .into_iter()
+            .map(|(viewers, real_names)| Task {
+                ticket_num: 1,
+                viewers,
+                real_names,
+                split_per_viewer: false,
+                ..self.clone()
+            })
+            .collect()
+    }
+}
+
+// 默认时区, 可通过环境变量SALE_TIMEZONE修改
+pub fn default_timezone() -> String {
+    sale_timezone().name().to_string()
+}
+
+// 默认开售前30秒预热
+pub fn default_warmup_time() -> u64 {
+    30
 }
 
 // 实名人, 默认自动选择前ticket->num位。
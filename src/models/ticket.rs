@@ -1,4 +1,6 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
@@ -14,7 +16,7 @@ pub struct TicketInfoForm {
 }
 
 impl TicketInfoForm {
-    pub fn build(ticket_id: &String) -> Result<Value> {
+    pub fn build(ticket_id: &str) -> Result<Value> {
         let data = Self {
             item_id: ticket_id.to_string(),
             dm_channel: "damai@damaih5_h5",
@@ -95,6 +97,18 @@ pub struct StaticDataItemBase {
 
     #[serde(rename = "itemName")]
     pub item_name: String,
+
+    #[serde(rename = "venueName", default)]
+    pub venue_name: String,
+
+    #[serde(rename = "cityName", default)]
+    pub city_name: String,
+
+    #[serde(rename = "showTime", default)]
+    pub show_time: String,
+
+    #[serde(rename = "priceRange", default)]
+    pub price_range: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -160,9 +174,218 @@ pub struct Ticket {
 
     #[serde(rename = "upTime")]
     pub sale_time: usize,
+
+    #[serde(rename = "cityName", default)]
+    pub city_name: String,
+
+    #[serde(rename = "venueName", default)]
+    pub venue_name: String,
+
+    #[serde(rename = "priceLow", default)]
+    pub price_low: Value,
+}
+
+impl Ticket {
+    // 价格描述
+    pub fn price_desc(&self) -> String {
+        match &self.price_low {
+            Value::String(price) if !price.is_empty() => format!("¥{}起", price),
+            Value::Number(price) => format!("¥{}起", price),
+            _ => "价格待定".to_string(),
+        }
+    }
+}
+
+// 即将开抢列表的筛选条件, 条件为空时不限
+#[derive(Debug, Clone, Default)]
+pub struct TicketFilter {
+    pub categories: Vec<String>, // 分类, 如演唱会/话剧/音乐节, 包含任一即可
+    pub cities: Vec<String>,     // 城市
+    pub venue: Option<String>,   // 场馆关键词
+    pub sale_from: Option<i64>,  // 开抢时间范围, 毫秒
+    pub sale_to: Option<i64>,
+}
+
+impl TicketFilter {
+    // 默认只显示演唱会
+    pub fn concerts() -> Self {
+        Self {
+            categories: vec!["演唱会".to_string()],
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, ticket: &Ticket) -> bool {
+        let normalize = |city: &str| city.trim().trim_end_matches('市').to_string();
+        let sale_time = ticket.sale_time as i64;
+        (self.categories.is_empty()
+            || self
+                .categories
+                .iter()
+                .any(|category| ticket.category_name.contains(category.as_str())))
+            && (self.cities.is_empty()
+                || self
+                    .cities
+                    .iter()
+                    .any(|city| normalize(city) == normalize(&ticket.city_name)))
+            && self
+                .venue
+                .as_ref()
+                .is_none_or(|venue| ticket.venue_name.contains(venue.as_str()))
+            && self.sale_from.is_none_or(|from| sale_time >= from)
+            && self.sale_to.is_none_or(|to| sale_time <= to)
+    }
+
+    pub fn apply(&self, tickets: Vec<Ticket>) -> Vec<Ticket> {
+        tickets
+            .into_iter()
+            .filter(|ticket| self.matches(ticket))
+            .collect()
+    }
+
+    // 筛选条件描述
+    pub fn desc(&self) -> String {
+        let mut items = vec![];
+        if !self.categories.is_empty() {
+            items.push(format!("分类:{}", self.categories.join("/")));
+        }
+        if !self.cities.is_empty() {
+            items.push(format!("城市:{}", self.cities.join("/")));
+        }
+        if let Some(venue) = &self.venue {
+            items.push(format!("场馆:{}", venue));
+        }
+        if self.sale_from.is_some() || self.sale_to.is_some() {
+            items.push("开抢时间范围".to_string());
+        }
+        match items.is_empty() {
+            true => "不限".to_string(),
+            false => items.join(", "),
+        }
+    }
+}
+
+// 解析日期(YYYY-MM-DD), 返回当天开始或结束的时间戳, 毫秒
+pub fn parse_date(date: &str, tz: Tz, end_of_day: bool) -> Result<i64> {
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow!("日期格式错误:{}, 应为YYYY-MM-DD", date))?;
+    let time = match end_of_day {
+        true => date.and_hms_milli_opt(23, 59, 59, 999),
+        false => date.and_hms_opt(0, 0, 0),
+    }
+    .ok_or(anyhow!("日期格式错误"))?;
+    tz.from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.timestamp_millis())
+        .ok_or(anyhow!("日期格式错误"))
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TicketList {
     pub items: Vec<Ticket>,
 }
+
+// 每单限购数量的字段
+const PURCHASE_LIMIT_KEYS: [&str; 4] = [
+    "limitQuantity",
+    "buyLimitCount",
+    "purchaseLimitation",
+    "perOrderLimit",
+];
+
+// 是否实名购票的字段
+const REAL_NAME_KEYS: [&str; 4] = ["realNameRequired", "isRealName", "realName", "needRealName"];
+
+// 是否快递配送纸质票的字段
+const EXPRESS_KEYS: [&str; 4] = ["needAddress", "isExpress", "expressDelivery", "paperTicket"];
+
+// 优先购开始时间的字段
+const PRIORITY_START_KEYS: [&str; 3] = [
+    "priorityStartTime",
+    "privilegeStartTime",
+    "prioritySellStartTime",
+];
+
+// 正式开售时间的字段
+const PUBLIC_START_KEYS: [&str; 3] = [
+    "publicSellStartTime",
+    "normalSellStartTime",
+    "publicSaleTime",
+];
+
+// 当前账号是否有优先购资格的字段
+const PRIVILEGE_KEYS: [&str; 3] = ["hasPrivilege", "privilegeQualified", "isPrivilegeUser"];
+
+// 递归查找字段, 返回第一个可以解析的值
+fn find_field<T>(value: &Value, keys: &[&str], parse: &impl Fn(&Value) -> Option<T>) -> Option<T> {
+    match value {
+        Value::Object(map) => map.iter().find_map(|(key, value)| {
+            keys.contains(&key.as_str())
+                .then(|| parse(value))
+                .flatten()
+                .or_else(|| find_field(value, keys, parse))
+        }),
+        Value::Array(items) => items.iter().find_map(|item| find_field(item, keys, parse)),
+        _ => None,
+    }
+}
+
+fn parse_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse::<i64>().ok(),
+        _ => None,
+    }
+}
+
+fn parse_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::Number(n) => n.as_i64().map(|n| n > 0),
+        Value::String(s) => match s.as_str() {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// 限购/实名/配送/优先购信息, 字段位置随接口版本变化, 递归查找
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PurchaseRules {
+    pub purchase_limit: Option<usize>,
+    pub real_name_required: Option<bool>,
+    pub express_delivery: Option<bool>,
+    pub priority_start: Option<i64>,
+    pub public_start: Option<i64>,
+    pub has_privilege: Option<bool>,
+}
+
+impl PurchaseRules {
+    pub fn parse(value: &Value) -> Self {
+        Self {
+            purchase_limit: find_field(value, &PURCHASE_LIMIT_KEYS, &|v| {
+                parse_i64(v).filter(|n| *n > 0).map(|n| n as usize)
+            }),
+            real_name_required: find_field(value, &REAL_NAME_KEYS, &parse_bool),
+            express_delivery: find_field(value, &EXPRESS_KEYS, &parse_bool),
+            priority_start: find_field(value, &PRIORITY_START_KEYS, &parse_i64),
+            public_start: find_field(value, &PUBLIC_START_KEYS, &parse_i64),
+            has_privilege: find_field(value, &PRIVILEGE_KEYS, &parse_bool),
+        }
+    }
+
+    // 优先购时长(分钟): 有优先购资格时为0, 否则为正式开售时间 - 优先购开始时间
+    pub fn priority_purchase_minutes(&self) -> Option<i64> {
+        if self.has_privilege == Some(true) {
+            return Some(0);
+        }
+        match (self.priority_start, self.public_start) {
+            (Some(priority), Some(public)) if public > priority => {
+                Some((public - priority) / 60 / 1000)
+            }
+            _ => None,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use super::CommonParams;
+use super::{ticket::Ticket, CommonParams};
 
 pub struct GetUserInfoParams;
 
@@ -30,3 +30,73 @@ pub struct UserInfoData {
     #[serde(rename = "userId")]
     pub user_id: u64,
 }
+
+// 想看列表每页数量
+const WISH_LIST_PAGE_SIZE: usize = 50;
+
+pub struct GetWishListParams;
+
+impl GetWishListParams {
+    pub fn build() -> Result<Value> {
+        let params = serde_json::to_value(CommonParams::build())?;
+        Ok(params)
+    }
+}
+
+pub struct GetWishListForm;
+
+impl GetWishListForm {
+    pub fn build(page: usize) -> Result<Value> {
+        Ok(json!({
+            "pageIndex": page.to_string(),
+            "pageSize": WISH_LIST_PAGE_SIZE.to_string(),
+            "dmChannel": "damai@damaih5_h5"
+        }))
+    }
+}
+
+// 字段可能为字符串或数字
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+// 解析想看列表, 跳过缺少门票ID的项
+pub fn parse_wish_list(data: &Value) -> Vec<Ticket> {
+    let items = ["list", "items", "result"]
+        .iter()
+        .find_map(|key| data[key].as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let ticket_id = ["itemId", "projectId", "id"]
+                .iter()
+                .map(|key| value_to_string(&item[key]))
+                .find(|id| !id.is_empty())?
+                .parse::<usize>()
+                .ok()?;
+            let ticket_name = ["name", "itemName", "projectName"]
+                .iter()
+                .map(|key| value_to_string(&item[key]))
+                .find(|name| !name.is_empty())
+                .unwrap_or_default();
+            Some(Ticket {
+                category_name: value_to_string(&item["categoryName"]),
+                ticket_name,
+                ticket_id,
+                sale_time: value_to_string(&item["sellStartTime"])
+                    .parse::<usize>()
+                    .unwrap_or_default(),
+                city_name: value_to_string(&item["cityName"]),
+                venue_name: value_to_string(&item["venueName"]),
+                price_low: item["priceLow"].clone(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,237 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::task::Task;
+use crate::errors::ViewerError;
+
+// 身份证的证件类型
+pub const ID_CARD: &str = "1";
+
+// 身份证前17位的加权因子
+const ID_CARD_WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+
+// 身份证校验码, 按加权和除以11的余数取
+const ID_CARD_CHECK: [char; 11] = ['1', '0', 'X', '9', '8', '7', '6', '5', '4', '3', '2'];
+
+// 查询观演人列表表单
+pub struct GetViewerListForm;
+
+impl GetViewerListForm {
+    pub fn build() -> Value {
+        json!({
+            "pageIndex": "1",
+            "pageSize": "50",
+            "dmChannel": "damai@damaih5_h5"
+        })
+    }
+}
+
+// 添加观演人表单
+pub struct AddViewerForm;
+
+impl AddViewerForm {
+    pub fn build(name: &str, cert_type: &str, cert_no: &str) -> Value {
+        json!({
+            "name": name,
+            "certType": cert_type,
+            "certNo": cert_no,
+            "dmChannel": "damai@damaih5_h5"
+        })
+    }
+}
+
+// 删除观演人表单
+pub struct RemoveViewerForm;
+
+impl RemoveViewerForm {
+    pub fn build(viewer_id: &str) -> Value {
+        json!({
+            "viewerId": viewer_id,
+            "dmChannel": "damai@damaih5_h5"
+        })
+    }
+}
+
+// 实名观演人
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Viewer {
+    pub viewer_id: String,
+    pub name: String,      // 姓名
+    pub cert_no: String,   // 证件号, 接口返回已脱敏
+    pub cert_type: String, // 证件类型
+}
+
+impl Viewer {
+    // 姓名及证件号是否完整
+    pub fn complete(&self) -> bool {
+        !self.name.is_empty() && !self.cert_no.is_empty()
+    }
+
+    // 按姓名或证件号后几位匹配
+    pub fn matches(&self, key: &str) -> bool {
+        let key = key.trim();
+        !key.is_empty() && (self.name == key || self.cert_no.ends_with(key))
+    }
+}
+
+// 字段可能为字符串或数字
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+// 第一个非空字段
+fn first_field(item: &Value, keys: &[&str]) -> String {
+    keys.iter()
+        .map(|key| value_to_string(&item[key]))
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+// 解析生成订单返回的观演人列表
+pub fn parse_viewers(viewer_list: &Value) -> Vec<Viewer> {
+    viewer_list
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| Viewer {
+                    viewer_id: first_field(item, &["viewerId", "id"]),
+                    name: first_field(item, &["viewerName", "name"]),
+                    cert_no: first_field(item, &["certNo", "idNo", "identityNo"]),
+                    cert_type: first_field(item, &["certType", "idType"]),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 解析观演人列表接口的返回
+pub fn parse_viewer_list(data: &Value) -> Vec<Viewer> {
+    ["userList", "viewerList", "list", "result"]
+        .iter()
+        .map(|key| &data[key])
+        .find(|list| list.is_array())
+        .map(parse_viewers)
+        .unwrap_or_default()
+}
+
+// 添加前校验证件号, 身份证校验长度及校验码, 其他证件只校验非空
+pub fn check_cert_no(cert_type: &str, cert_no: &str) -> Result<(), ViewerError> {
+    let invalid = || ViewerError::InvalidCertNo {
+        cert_no: mask_cert_no(cert_no),
+    };
+    if cert_no.trim().is_empty() {
+        return Err(invalid());
+    }
+    if cert_type != ID_CARD {
+        return Ok(());
+    }
+    let chars = cert_no.to_uppercase().chars().collect::<Vec<_>>();
+    if chars.len() != 18 {
+        return Err(invalid());
+    }
+    let sum = chars[..17]
+        .iter()
+        .zip(ID_CARD_WEIGHTS)
+        .map(|(c, weight)| c.to_digit(10).map(|d| d * weight))
+        .sum::<Option<u32>>()
+        .ok_or_else(invalid)?;
+    match ID_CARD_CHECK[(sum % 11) as usize] == chars[17] {
+        true => Ok(()),
+        false => Err(invalid()),
+    }
+}
+
+// 日志中的证件号, 只保留前4位及后4位
+pub fn mask_cert_no(cert_no: &str) -> String {
+    let chars = cert_no.chars().collect::<Vec<_>>();
+    match chars.len() {
+        0..=8 => "*".repeat(chars.len()),
+        n => format!(
+            "{}{}{}",
+            chars[..4].iter().collect::<String>(),
+            "*".repeat(n - 8),
+            chars[n - 4..].iter().collect::<String>()
+        ),
+    }
+}
+
+// 按姓名或证件号后几位查找唯一的观演人, 匹配多位时返回错误
+pub fn find_viewer<'a>(viewers: &'a [Viewer], key: &str) -> Result<&'a Viewer, ViewerError> {
+    let mut matched = viewers.iter().filter(|viewer| viewer.matches(key));
+    match (matched.next(), matched.next()) {
+        (Some(viewer), None) => Ok(viewer),
+        (Some(_), Some(_)) => Err(ViewerError::Ambiguous {
+            key: key.to_string(),
+        }),
+        (None, _) => Err(ViewerError::NotFound {
+            key: key.to_string(),
+        }),
+    }
+}
+
+// 按任务配置选择观演人, 返回观演人列表中的下标:
+// 1. viewers: 按姓名或证件号后几位指定
+// 2. real_names: 按序号(从1开始)指定
+// 3. 未配置时选择前ticket_num位
+// 指定的观演人数量与购票数量不一致或证件信息不完整时返回错误
+pub fn select_viewers(viewers: &[Viewer], task: &Task) -> Result<Vec<usize>> {
+    let selected = if !task.viewers.is_empty() {
+        task.viewers
+            .iter()
+            .map(|key| {
+                viewers
+                    .iter()
+                    .position(|viewer| viewer.matches(key))
+                    .ok_or(ViewerError::NotFound { key: key.clone() })
+            })
+            .collect::<Result<Vec<usize>, ViewerError>>()?
+    } else if !task.real_names.is_empty() {
+        task.real_names
+            .iter()
+            .map(|idx| match *idx {
+                idx if idx >= 1 && idx <= viewers.len() => Ok(idx - 1),
+                idx => Err(ViewerError::NotFound {
+                    key: format!("第{}位", idx),
+                }),
+            })
+            .collect::<Result<Vec<usize>, ViewerError>>()?
+    } else {
+        if viewers.len() < task.ticket_num {
+            return Err(ViewerError::NotEnough {
+                need: task.ticket_num,
+                have: viewers.len(),
+            }
+            .into());
+        }
+        (0..task.ticket_num).collect()
+    };
+
+    let mut unique = selected.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    if unique.len() != task.ticket_num {
+        return Err(ViewerError::Mismatch {
+            need: task.ticket_num,
+            have: unique.len(),
+        }
+        .into());
+    }
+
+    if let Some(viewer) = selected
+        .iter()
+        .map(|&i| &viewers[i])
+        .find(|v| !v.complete())
+    {
+        return Err(ViewerError::Incomplete {
+            name: viewer.name.clone(),
+        }
+        .into());
+    }
+    Ok(selected)
+}
@@ -0,0 +1,58 @@
+use std::{env, str::FromStr};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::errors::ClientError;
+
+// 命令输出格式, 由--output设置环境变量OUTPUT_FORMAT
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json, // 标准输出只输出json, 日志输出到标准错误
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(ClientError::InvalidSetting {
+                key: "OUTPUT_FORMAT".to_string(),
+                reason: format!("{}应为text或json", other),
+            }
+            .into()),
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+
+    // 未配置或配置错误时为text
+    pub fn from_env() -> Self {
+        env::var("OUTPUT_FORMAT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+// 是否以json输出
+pub fn is_json() -> bool {
+    OutputFormat::from_env() == OutputFormat::Json
+}
+
+// 输出一行json到标准输出
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string(value)?);
+    Ok(())
+}
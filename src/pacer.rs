@@ -0,0 +1,269 @@
+use std::{collections::HashMap, env, str::FromStr, time::Duration};
+
+use anyhow::Result;
+use rand::Rng;
+use tracing::warn;
+
+use crate::{
+    errors::{ClientError, MtopError},
+    rand_i64,
+};
+
+// 默认最小重试间隔, 避免请求过密被限流, 毫秒
+const DEFAULT_RETRY_FLOOR: u64 = 50;
+
+// 退避等待时长的默认上限, 毫秒
+const DEFAULT_BACKOFF_MAX: u64 = 5000;
+
+fn invalid(reason: String) -> anyhow::Error {
+    ClientError::InvalidSetting {
+        key: "RETRY_BACKOFF".to_string(),
+        reason,
+    }
+    .into()
+}
+
+// 退避策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    Fixed,              // 固定为重试间隔
+    Linear,             // 重试间隔 * 连续失败次数
+    Exponential,        // 重试间隔 * 2^(连续失败次数-1)
+    DecorrelatedJitter, // 在[重试间隔, 上次等待时长*3]之间随机
+}
+
+impl FromStr for Backoff {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "fixed" => Ok(Self::Fixed),
+            "linear" => Ok(Self::Linear),
+            "exponential" => Ok(Self::Exponential),
+            "decorrelated_jitter" | "jitter" => Ok(Self::DecorrelatedJitter),
+            other => Err(invalid(format!(
+                "未知的退避策略:{}, 可选fixed/linear/exponential/decorrelated_jitter",
+                other
+            ))),
+        }
+    }
+}
+
+impl Backoff {
+    // 连续第n次(从1开始)失败后的等待时长, prev为上次等待时长, 毫秒
+    pub fn delay(&self, base: u64, max: u64, n: u32, prev: u64) -> u64 {
+        let n = n.max(1);
+        let delay = match self {
+            Self::Fixed => base,
+            Self::Linear => base.saturating_mul(n as u64),
+            Self::Exponential => base.saturating_mul(1 << (n - 1).min(16)),
+            Self::DecorrelatedJitter => {
+                let upper = prev.max(base).saturating_mul(3);
+                match upper > base {
+                    true => rand::thread_rng().gen_range(base..=upper),
+                    false => base,
+                }
+            }
+        };
+        delay.min(max.max(base))
+    }
+}
+
+// 重试的错误类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    Throttled,  // 限流/风控
+    StockEmpty, // 库存不足
+    Other,
+}
+
+impl FromStr for ErrorClass {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "throttled" => Ok(Self::Throttled),
+            "stock_empty" => Ok(Self::StockEmpty),
+            "other" => Ok(Self::Other),
+            other => Err(invalid(format!(
+                "未知的错误类别:{}, 可选throttled/stock_empty/other",
+                other
+            ))),
+        }
+    }
+}
+
+impl ErrorClass {
+    // 按接口返回的错误信息分类, 成功时为None
+    pub fn from_message(message: &str) -> Option<Self> {
+        if message.is_empty() {
+            return None;
+        }
+        let error = MtopError::from_ret(message);
+        Some(match error {
+            _ if error.is_throttled() => Self::Throttled,
+            MtopError::StockEmpty => Self::StockEmpty,
+            _ => Self::Other,
+        })
+    }
+}
+
+// 按错误类别选择退避策略, 未配置的类别使用自适应重试间隔
+#[derive(Debug, Clone, Default)]
+pub struct RetryPolicy {
+    pub default: Option<Backoff>,
+    pub classes: HashMap<ErrorClass, Backoff>,
+    pub max: u64, // 等待时长上限, 毫秒
+}
+
+impl RetryPolicy {
+    // 格式: 类别=策略,类别=策略, 类别为throttled/stock_empty/other/default
+    // 如: throttled=decorrelated_jitter,default=exponential
+    pub fn parse(value: &str, max: u64) -> Result<Self> {
+        let mut policy = Self {
+            max,
+            ..Default::default()
+        };
+        for item in value.split(',').filter(|item| !item.trim().is_empty()) {
+            let (class, backoff) = item
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("{}格式错误, 应为类别=策略", item)))?;
+            let backoff = backoff.parse::<Backoff>()?;
+            match class.trim() {
+                "default" => policy.default = Some(backoff),
+                class => {
+                    policy.classes.insert(class.parse::<ErrorClass>()?, backoff);
+                }
+            }
+        }
+        Ok(policy)
+    }
+
+    // 环境变量RETRY_BACKOFF配置策略, RETRY_BACKOFF_MAX配置等待时长上限(毫秒)
+    pub fn from_env() -> Self {
+        let max = env::var("RETRY_BACKOFF_MAX")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_BACKOFF_MAX);
+        let value = env::var("RETRY_BACKOFF").unwrap_or_default();
+        Self::parse(&value, max).unwrap_or_else(|e| {
+            warn!("退避策略配置错误, 使用自适应重试间隔:{:?}", e);
+            Self {
+                max,
+                ..Default::default()
+            }
+        })
+    }
+
+    pub fn backoff(&self, class: ErrorClass) -> Option<Backoff> {
+        self.classes.get(&class).copied().or(self.default)
+    }
+}
+
+// 重试间隔最多放大到配置值的倍数
+const MAX_WIDEN_FACTOR: u64 = 8;
+
+// 按请求耗时自适应调整重试间隔:
+// 配置的重试间隔视为两次请求发起的间隔, 扣除上次请求耗时后再等待,
+// 使请求首尾相接而不重叠; 服务端返回繁忙/限流时成倍放宽, 恢复后收紧.
+#[derive(Debug, Clone)]
+pub struct RetryPacer {
+    base: u64,                 // 配置的重试间隔, 毫秒
+    floor: u64,                // 最小重试间隔, 毫秒
+    rtt: Option<u64>,          // 请求耗时的平滑值, 毫秒
+    widen: u32,                // 连续被限流次数
+    policy: RetryPolicy,       // 按错误类别的退避策略
+    class: Option<ErrorClass>, // 上次失败的错误类别
+    failures: u32,             // 同类错误连续失败次数
+    prev: u64,                 // 上次退避等待时长, 毫秒
+}
+
+impl RetryPacer {
+    pub fn new(base: u64, floor: u64) -> Self {
+        Self {
+            base,
+            floor,
+            rtt: None,
+            widen: 0,
+            policy: RetryPolicy::default(),
+            class: None,
+            failures: 0,
+            prev: 0,
+        }
+    }
+
+    // 最小重试间隔由环境变量RETRY_FLOOR配置, 毫秒
+    pub fn from_env(base: u64) -> Self {
+        let floor = env::var("RETRY_FLOOR")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_RETRY_FLOOR);
+        Self::new(base, floor).with_policy(RetryPolicy::from_env())
+    }
+
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    // 是否为服务端繁忙/限流
+    pub fn is_throttled(message: &str) -> bool {
+        MtopError::from_ret(message).is_throttled()
+    }
+
+    // 记录一次请求的耗时和结果
+    pub fn record(&mut self, elapsed: Duration, message: &str) {
+        let elapsed = elapsed.as_millis() as u64;
+        self.rtt = Some(match self.rtt {
+            Some(old) => (old * 3 + elapsed) / 4,
+            None => elapsed,
+        });
+        match Self::is_throttled(message) {
+            true => self.widen = (self.widen + 1).min(MAX_WIDEN_FACTOR.trailing_zeros()),
+            false => self.widen = self.widen.saturating_sub(1),
+        }
+
+        let class = ErrorClass::from_message(message);
+        self.failures = match class.is_some() && class == self.class {
+            true => self.failures + 1,
+            false => class.map_or(0, |_| 1),
+        };
+        if class != self.class {
+            self.prev = 0;
+        }
+        self.class = class;
+    }
+
+    // 请求耗时的平滑值
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt.map(Duration::from_millis)
+    }
+
+    // 下次重试前的等待时长
+    pub fn interval(&self) -> Duration {
+        let spacing = self.base << self.widen;
+        let wait = spacing.saturating_sub(self.rtt.unwrap_or(0));
+        Duration::from_millis(wait.max(self.floor))
+    }
+
+    // 下次重试前的等待时长: 上次失败的错误类别配置了退避策略时按策略计算, 否则为自适应间隔
+    pub fn backoff(&mut self) -> Duration {
+        let backoff = match self.class.and_then(|class| self.policy.backoff(class)) {
+            Some(backoff) => backoff,
+            None => return self.jittered(),
+        };
+        let delay = backoff.delay(self.base, self.policy.max, self.failures, self.prev);
+        self.prev = delay;
+        Duration::from_millis(delay.max(self.floor))
+    }
+
+    // 带随机抖动的等待时长, 不低于最小重试间隔
+    pub fn jittered(&self) -> Duration {
+        let wait = self.interval().as_millis() as i64;
+        let wait = match wait > 0 {
+            true => rand_i64(wait),
+            false => 0,
+        };
+        Duration::from_millis(wait.max(self.floor))
+    }
+}
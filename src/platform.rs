@@ -0,0 +1,375 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::{MtopError, PlatformError};
+use crate::models::{
+    address::{Address, NewAddress},
+    coupon::Coupon,
+    order::OrderSummary,
+    perform::{PerformItem, SkuItem},
+    privilege::PrivilegeCheck,
+    search::CityStop,
+    seat::Seat,
+    task::Task,
+    ticket::Ticket,
+    user::UserInfoData,
+    viewer::Viewer,
+};
+
+// 门票开售信息
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ItemDetail {
+    pub item_id: String,
+    pub item_name: String,
+    pub sell_start_timestamp: i64,
+    pub sell_start_time_str: String,
+    pub buy_btn_text: String,
+    pub supported: bool, // 当前渠道是否支持购买
+
+    #[serde(default)]
+    pub venue_name: String, // 场馆
+
+    #[serde(default)]
+    pub city_name: String, // 城市
+
+    #[serde(default)]
+    pub show_time: String, // 演出时间
+
+    #[serde(default)]
+    pub price_range: String, // 价格区间
+
+    #[serde(default)]
+    pub purchase_limit: Option<usize>, // 每单限购数量
+
+    #[serde(default)]
+    pub real_name_required: Option<bool>, // 是否需要实名购票
+
+    #[serde(default)]
+    pub express_delivery: Option<bool>, // 是否快递配送纸质票, 需要收货地址
+
+    #[serde(default)]
+    pub priority_purchase_time: Option<i64>, // 当前账号的优先购时长, 分钟
+}
+
+impl ItemDetail {
+    // 校验购票数量/实名人是否满足限购和实名要求
+    pub fn validate(&self, task: &Task) -> Result<()> {
+        if let Some(limit) = self.purchase_limit {
+            if task.ticket_num > limit {
+                return Err(anyhow!(
+                    "{}每单限购{}张, 购票数量:{}超出限制",
+                    self.item_name,
+                    limit,
+                    task.ticket_num
+                ));
+            }
+        }
+
+        if self.real_name_required == Some(true)
+            && !task.real_names.is_empty()
+            && task.real_names.len() != task.ticket_num
+        {
+            return Err(anyhow!(
+                "{}需要实名购票, 实名人数量:{}与购票数量:{}不一致",
+                self.item_name,
+                task.real_names.len(),
+                task.ticket_num
+            ));
+        }
+        Ok(())
+    }
+}
+
+// 提交订单结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubmitResult {
+    pub success: bool,
+    pub message: String,
+    pub data: Value,
+}
+
+impl SubmitResult {
+    // 提交失败的错误类型
+    pub fn error(&self) -> Option<MtopError> {
+        match self.success {
+            true => None,
+            false => Some(MtopError::from_ret(&self.message)),
+        }
+    }
+
+    // 提交成功返回的订单ID
+    pub fn order_id(&self) -> Option<String> {
+        ["orderId", "bizOrderId"]
+            .iter()
+            .find_map(|key| match &self.data[key] {
+                Value::String(id) if !id.is_empty() => Some(id.clone()),
+                Value::Number(id) => Some(id.to_string()),
+                _ => None,
+            })
+    }
+
+    // 付款截止时间戳, 毫秒
+    pub fn pay_deadline(&self) -> Option<i64> {
+        ["payDeadline", "payExpireTime", "timeoutTime"]
+            .iter()
+            .find_map(|key| match &self.data[key] {
+                Value::String(time) => time.parse::<i64>().ok(),
+                Value::Number(time) => time.as_i64(),
+                _ => None,
+            })
+    }
+}
+
+// 票务平台, 不同平台实现各自的查询/下单接口, 复用任务调度/重试/通知逻辑
+#[async_trait]
+pub trait TicketPlatform: Send + Sync {
+    // 生成订单返回的数据, 提交订单时原样传回
+    type Order: Clone + Send + Sync;
+
+    // 平台名称
+    fn name(&self) -> &'static str;
+
+    // 获取用户信息
+    async fn user_info(&self) -> Result<UserInfoData>;
+
+    // 获取即将开售的门票列表
+    async fn search(&self) -> Result<Vec<Ticket>>;
+
+    // 获取用户标记想看的门票
+    async fn wishlist(&self) -> Result<Vec<Ticket>> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "想看列表",
+        }
+        .into())
+    }
+
+    // 获取账号保存的收货地址
+    async fn addresses(&self) -> Result<Vec<Address>> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "收货地址",
+        }
+        .into())
+    }
+
+    // 添加收货地址
+    async fn add_address(&self, _address: &NewAddress) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "添加收货地址",
+        }
+        .into())
+    }
+
+    // 获取账号的实名观演人
+    async fn viewers(&self) -> Result<Vec<Viewer>> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "实名观演人",
+        }
+        .into())
+    }
+
+    // 添加实名观演人
+    async fn add_viewer(&self, _name: &str, _cert_type: &str, _cert_no: &str) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "添加观演人",
+        }
+        .into())
+    }
+
+    // 删除实名观演人
+    async fn remove_viewer(&self, _viewer_id: &str) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "删除观演人",
+        }
+        .into())
+    }
+
+    // 获取门票可用的优惠券
+    async fn coupons(&self, _item_id: &str) -> Result<Vec<Coupon>> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "优惠券",
+        }
+        .into())
+    }
+
+    // 生成订单时使用的优惠券
+    fn use_coupon(&mut self, _coupon_id: &str) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "优惠券",
+        }
+        .into())
+    }
+
+    // 校验会员/粉丝优先购码
+    async fn check_privilege(&self, _item_id: &str, _code: &str) -> Result<PrivilegeCheck> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "优先购码",
+        }
+        .into())
+    }
+
+    // 生成订单时携带的优先购码
+    fn use_privilege_code(&mut self, _code: &str) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "优先购码",
+        }
+        .into())
+    }
+
+    // 获取门票开售信息
+    async fn detail(&self, ticket_id: &str) -> Result<ItemDetail>;
+
+    // 获取场次列表
+    async fn performs(&self, ticket_id: &str) -> Result<Vec<PerformItem>>;
+
+    // 获取票档列表
+    async fn skus(&self, ticket_id: &str, perform_id: &str) -> Result<Vec<SkuItem>>;
+
+    // 生成订单
+    async fn build_order(&self, item_id: &str, sku_id: &str, buy_num: usize)
+        -> Result<Self::Order>;
+
+    // 获取场次座位图
+    async fn seats(&self, _item_id: &str, _perform_id: &str) -> Result<Vec<Seat>> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "选座",
+        }
+        .into())
+    }
+
+    // 选座演出生成订单, 购票数量为座位数
+    async fn build_seat_order(
+        &self,
+        _item_id: &str,
+        _sku_id: &str,
+        _seats: &[Seat],
+    ) -> Result<Self::Order> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "选座",
+        }
+        .into())
+    }
+
+    // 提交订单
+    async fn submit_order(&self, task: &Task, order: Self::Order) -> Result<SubmitResult>;
+
+    // 提交订单使用的订单token, 用于确认结果不明确的提交
+    fn submit_token(&self, _order: &Self::Order) -> Option<String> {
+        None
+    }
+
+    // 生成/提交订单请求的接口地址, 用于导出每次尝试的记录
+    fn endpoint(&self, _stage: &str) -> String {
+        self.name().to_string()
+    }
+
+    // 预览提交订单的数据, 试运行时使用
+    async fn preview_submit(&self, _task: &Task, _order: Self::Order) -> Result<Value> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "试运行",
+        }
+        .into())
+    }
+
+    // 开售前预热, 预先构造生成订单所需的参数
+    async fn prepare(&mut self, _item_id: &str, _sku_id: &str, _buy_num: usize) -> Result<()> {
+        Ok(())
+    }
+
+    // 开售前预先解析并固定平台域名
+    async fn pin_dns(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    // 本地时钟与服务器时钟的偏差(服务器时间 - 本地时间), 毫秒
+    async fn clock_offset(&self) -> Result<i64> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "获取服务器时间",
+        }
+        .into())
+    }
+
+    // 预热到平台的连接, 避免开售时首个请求建立连接的耗时
+    async fn prewarm(&self) -> Result<()> {
+        Ok(())
+    }
+
+    // 按关键词搜索演出, 巡演每个城市为单独的门票
+    async fn search_projects(&self, _keyword: &str) -> Result<Vec<CityStop>> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "搜索演出",
+        }
+        .into())
+    }
+
+    // 获取最近的订单
+    async fn orders(&self) -> Result<Vec<OrderSummary>> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "订单列表",
+        }
+        .into())
+    }
+
+    // 取消未付款的订单
+    async fn cancel_order(&self, _order_id: &str) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "取消订单",
+        }
+        .into())
+    }
+
+    // 缺货登记
+    async fn register_waitlist(
+        &self,
+        _item_id: &str,
+        _perform_id: &str,
+        _sku_id: &str,
+    ) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "缺货登记",
+        }
+        .into())
+    }
+
+    // 处理人机验证, 验证通过后刷新会话
+    async fn resolve_challenge(&mut self, _url: &str) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "人机验证",
+        }
+        .into())
+    }
+
+    // 重新登录后更新会话
+    async fn update_cookie(&mut self, _cookie: &str) -> Result<()> {
+        Err(PlatformError::Unsupported {
+            platform: self.name(),
+            action: "重新登录",
+        }
+        .into())
+    }
+
+    // 同步同一账号其他任务更新后的会话, 返回是否已更新
+    async fn sync_session(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+}
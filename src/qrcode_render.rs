@@ -0,0 +1,157 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::Result;
+#[cfg(feature = "http-login")]
+use fast_qr::QRCode;
+
+use crate::errors::ClientError;
+
+// 二维码四周的空白, 模块数
+const QUIET_ZONE: usize = 2;
+
+// 终端二维码尺寸
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QrSize {
+    #[default]
+    Small, // 半高字符, 一行显示两行模块
+    Large, // 每个模块占两个字符宽
+}
+
+// 二维码输出配置
+#[derive(Debug, Clone, Default)]
+pub struct QrOptions {
+    pub size: QrSize,
+    pub invert: bool,            // 反色, 用于深色背景的终端
+    pub export: Option<PathBuf>, // 保存二维码, 按扩展名保存为png/svg
+}
+
+impl QrOptions {
+    // 环境变量QRCODE_SIZE(small/large)/QRCODE_INVERT/QRCODE_EXPORT
+    pub fn from_env() -> Result<Self> {
+        let size = match env::var("QRCODE_SIZE").unwrap_or_default().as_str() {
+            "" | "small" => QrSize::Small,
+            "large" => QrSize::Large,
+            other => {
+                return Err(ClientError::InvalidSetting {
+                    key: "QRCODE_SIZE".to_string(),
+                    reason: format!("{}应为small或large", other),
+                }
+                .into())
+            }
+        };
+        let invert = env::var("QRCODE_INVERT").is_ok_and(|v| v == "true" || v == "1");
+        let export = match env::var("QRCODE_EXPORT") {
+            Ok(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => None,
+        };
+        Ok(Self {
+            size,
+            invert,
+            export,
+        })
+    }
+
+    // 在终端中显示的二维码
+    pub fn render(&self, modules: &[Vec<bool>]) -> String {
+        let grid = with_quiet_zone(modules);
+        // 反色时浅色模块显示为色块
+        let painted = |y: usize, x: usize| grid[y][x] != self.invert;
+
+        let mut lines = vec![];
+        match self.size {
+            QrSize::Small => {
+                for y in (0..grid.len()).step_by(2) {
+                    let line = (0..grid.len())
+                        .map(|x| {
+                            let bottom = y + 1 < grid.len() && painted(y + 1, x);
+                            match (painted(y, x), bottom) {
+                                (true, true) => '█',
+                                (true, false) => '▀',
+                                (false, true) => '▄',
+                                (false, false) => ' ',
+                            }
+                        })
+                        .collect::<String>();
+                    lines.push(line);
+                }
+            }
+            QrSize::Large => {
+                for y in 0..grid.len() {
+                    let line = (0..grid.len())
+                        .map(|x| if painted(y, x) { "██" } else { "  " })
+                        .collect::<String>();
+                    lines.push(line);
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
+    // 按配置保存二维码, png使用原始图片
+    pub fn export(&self, modules: &[Vec<bool>], png: &[u8]) -> Result<Option<PathBuf>> {
+        let path = match &self.export {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let is_svg = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+        match is_svg {
+            true => fs::write(path, render_svg(modules))?,
+            false => fs::write(path, png)?,
+        }
+        Ok(Some(path.clone()))
+    }
+}
+
+// 四周加上空白
+fn with_quiet_zone(modules: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let size = modules.len() + QUIET_ZONE * 2;
+    let mut grid = vec![vec![false; size]; size];
+    for (y, row) in modules.iter().enumerate() {
+        for (x, dark) in row.iter().enumerate() {
+            grid[y + QUIET_ZONE][x + QUIET_ZONE] = *dark;
+        }
+    }
+    grid
+}
+
+// svg格式的二维码, 每个模块为一个单位
+pub fn render_svg(modules: &[Vec<bool>]) -> String {
+    let grid = with_quiet_zone(modules);
+    let mut path = String::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, dark) in row.iter().enumerate() {
+            if *dark {
+                path.push_str(&format!("M{},{}h1v1h-1z", x, y));
+            }
+        }
+    }
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {size} {size}" width="{px}" height="{px}" shape-rendering="crispEdges"><rect width="{size}" height="{size}" fill="#fff"/><path d="{path}" fill="#000"/></svg>"##,
+        size = grid.len(),
+        px = grid.len() * 8,
+        path = path
+    )
+}
+
+// 按内容生成二维码的模块矩阵, 用于显示其他工具获取的登录链接等
+#[cfg(feature = "http-login")]
+pub fn encode(content: &str) -> Result<Vec<Vec<bool>>> {
+    let qrcode = fast_qr::QRBuilder::new(content)
+        .build()
+        .map_err(|e| anyhow::anyhow!("生成二维码失败:{:?}", e))?;
+    Ok(modules(&qrcode))
+}
+
+// 二维码的模块矩阵, true为深色
+#[cfg(feature = "http-login")]
+pub fn modules(qrcode: &QRCode) -> Vec<Vec<bool>> {
+    (0..qrcode.size)
+        .map(|y| {
+            (0..qrcode.size)
+                .map(|x| qrcode.data[y * qrcode.size + x].value())
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,246 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    account::AccountStore,
+    clients::dm::DmClient,
+    config,
+    errors::AccountError,
+    events::{EventSubscriber, TicketEvent},
+    experiment::{self, Experiment},
+    models::task::Task,
+    output,
+    platform::TicketPlatform,
+    ticket::run_task_with,
+};
+
+// 开售超过该时长的任务不再恢复, 毫秒
+const RESUME_EXPIRE_AFTER: i64 = 10 * 60 * 1000;
+
+// 已开始等待开售的抢票任务, 进程重启后可恢复
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArmedTask {
+    pub task: Task,
+    pub account: String,         // 已保存的账号名, 恢复时读取cookie
+    pub sale_start: Option<i64>, // 开抢时间戳, 毫秒
+    pub armed_at: i64,           // 创建时间戳, 毫秒
+}
+
+impl ArmedTask {
+    pub fn new(task: Task, account: String) -> Self {
+        Self {
+            task,
+            account,
+            sale_start: None,
+            armed_at: Local::now().timestamp_millis(),
+        }
+    }
+
+    // 文件名, 同一门票多个账号时以账号区分
+    pub fn file_name(&self) -> String {
+        format!(
+            "{}-{}.json",
+            self.task.key(),
+            &format!("{:?}", md5::compute(&self.account))[..8]
+        )
+    }
+
+    // 开售已超过一定时长, 无需恢复
+    pub fn expired(&self, now: i64) -> bool {
+        let expire_after = self
+            .task
+            .stop_after_sale_plus
+            .map_or(RESUME_EXPIRE_AFTER, |seconds| seconds as i64 * 1000);
+        self.sale_start
+            .is_some_and(|sale_start| now - sale_start > expire_after)
+    }
+}
+
+// 保存已开始等待的任务, 每个任务一个json文件
+#[derive(Debug, Clone)]
+pub struct ArmedStore {
+    dir: PathBuf,
+}
+
+impl ArmedStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // ARMED_DIR: 保存目录, 默认为配置目录下的armed
+    pub fn from_env() -> Self {
+        match env::var("ARMED_DIR") {
+            Ok(dir) if !dir.is_empty() => Self::new(dir),
+            _ => Self::new(config::config_dir().join("armed")),
+        }
+    }
+
+    // 保存目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn save(&self, armed: &ArmedTask) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_string_pretty(armed)?;
+        fs::write(self.dir.join(armed.file_name()), content)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, armed: &ArmedTask) -> Result<()> {
+        let path = self.dir.join(armed.file_name());
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    // 所有保存的任务, 无法解析的文件跳过
+    pub fn list(&self) -> Result<Vec<ArmedTask>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut tasks = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|content| Ok(serde_json::from_str::<ArmedTask>(&content)?))
+                {
+                    Ok(armed) => tasks.push(armed),
+                    Err(e) => warn!("解析任务文件{}失败:{:?}", path.display(), e),
+                }
+            }
+        }
+        tasks.sort_by_key(|armed| armed.armed_at);
+        Ok(tasks)
+    }
+}
+
+// 开始等待时保存任务, 记录开抢时间, 任务结束后删除
+pub struct ArmedSubscriber {
+    store: ArmedStore,
+    armed: Mutex<ArmedTask>,
+}
+
+impl ArmedSubscriber {
+    pub fn new(store: ArmedStore, armed: ArmedTask) -> Self {
+        Self {
+            store,
+            armed: Mutex::new(armed),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for ArmedSubscriber {
+    async fn on_event(&self, task: &Task, event: &TicketEvent) {
+        let res = match self.armed.lock() {
+            Ok(mut armed) => match event {
+                TicketEvent::ScheduleArmed { sale_start } => {
+                    // 巡演切换城市后门票ID变化, 删除原任务文件
+                    if armed.task.key() != task.key() {
+                        let _ = self.store.remove(&armed);
+                    }
+                    armed.sale_start = Some(*sale_start);
+                    armed.task = task.clone();
+                    self.store.save(&armed)
+                }
+                TicketEvent::RunFinished { .. } => self.store.remove(&armed),
+                _ => Ok(()),
+            },
+            Err(_) => Err(anyhow!("任务状态被占用")),
+        };
+        if let Err(e) = res {
+            warn!("{}, 保存任务状态失败:{:?}", task.nickname, e);
+        }
+    }
+}
+
+// 使用已保存账号执行任务, 进程中断后可通过resume恢复
+pub async fn run_armed(cookie: String, task: Task, account: String) -> Result<()> {
+    let store = ArmedStore::from_env();
+    let armed = ArmedTask::new(task.clone(), account);
+    store.save(&armed)?;
+    let subscriber = ArmedSubscriber::new(store, armed);
+    run_task_with(cookie, task, vec![Box::new(subscriber)]).await
+}
+
+// 读取已保存账号的cookie, 并校验登录状态
+pub(crate) async fn restore_cookie(store: &AccountStore, name: &str) -> Result<String> {
+    let cookie = store
+        .get(name)
+        .ok_or(AccountError::NotFound {
+            name: name.to_string(),
+        })?
+        .cookie
+        .clone();
+
+    let dm = DmClient::new(Some(cookie.clone()), None).await?;
+    dm.user_info()
+        .await
+        .map_err(|e| anyhow!("账号:{}登录已失效, 请重新添加账号:{}", name, e))?;
+    Ok(cookie)
+}
+
+// 恢复所有未完成的任务, 重新校验登录状态后继续等待开售
+pub async fn resume() -> Result<()> {
+    let store = ArmedStore::from_env();
+    let now = Local::now().timestamp_millis();
+    let mut accounts = None;
+
+    let variants = experiment::variants()?;
+    let mut list = store.list()?;
+    experiment::assign(list.iter_mut().map(|armed| &mut armed.task), &variants);
+
+    let mut set = tokio::task::JoinSet::new();
+    for armed in list {
+        let name = format!("{}, {}", armed.task.nickname, armed.task.ticket_name);
+        if armed.expired(now) {
+            info!("{}, 已开售超过恢复时限, 删除任务", name);
+            store.remove(&armed)?;
+            continue;
+        }
+        if accounts.is_none() {
+            accounts = Some(AccountStore::from_env()?);
+        }
+        let cookie = match restore_cookie(accounts.as_ref().unwrap(), &armed.account).await {
+            Ok(cookie) => cookie,
+            Err(e) => {
+                error!("{}, {}", name, e);
+                continue;
+            }
+        };
+
+        info!("{}, 恢复抢票任务...", name);
+        set.spawn(async move {
+            if let Err(e) = run_armed(cookie, armed.task, armed.account).await {
+                error!("{}, 抢票任务失败:{}", name, e);
+            }
+        });
+    }
+
+    if set.is_empty() {
+        info!("没有需要恢复的抢票任务");
+    }
+    while set.join_next().await.is_some() {}
+
+    let ranking = Experiment::global().ranking();
+    if !ranking.is_empty() {
+        match output::is_json() {
+            true => output::print_json(&ranking)?,
+            false => println!("{}", experiment::summary(&ranking)),
+        }
+    }
+    Ok(())
+}
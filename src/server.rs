@@ -1,13 +1,11 @@
 use anyhow::Result;
-use log::info;
 use redis::{AsyncCommands, Client};
 use std::{env, time::Duration};
-use thirtyfour::{
-    prelude::ElementQueryable, By, ChromeCapabilities, DesiredCapabilities, WebDriver,
-};
+use thirtyfour::{prelude::ElementQueryable, By, ChromeCapabilities, WebDriver};
 use tokio::signal;
+use tracing::info;
 
-use crate::errors::ServerError;
+use crate::{clients::webdriver, errors::ServerError};
 
 const KEY_BX_UA: &str = "bx_ua";
 
@@ -24,34 +22,15 @@ pub struct Server {
 
 impl Server {
     pub async fn new(webdriver_url: String, redis_url: String) -> Result<Self> {
-        let mut caps = DesiredCapabilities::chrome();
-
-        caps.set_disable_dev_shm_usage()?;
-        caps.set_headless()?;
-        caps.set_disable_gpu()?;
-        caps.set_disable_web_security()?;
-        caps.set_ignore_certificate_errors()?;
-        caps.add_chrome_arg("--disable-blink-features=AutomationControlled")?;
-        caps.add_chrome_arg("--disable-logging")?;
-        caps.add_chrome_arg("--blink-settings=imagesEnabled=false")?;
-        caps.add_chrome_arg("--incognito")?;
-        caps.add_chrome_arg("--disable-stylesheet")?;
-        caps.add_chrome_arg("--excludeSwitches=[\"enable-automation\"]")?;
-        caps.add_chrome_arg("--useAutomationExtension=false")?;
-        caps.add_chrome_arg("--disable-infobars")?;
-        caps.add_chrome_arg("--disable-software-rasterizer")?;
-        caps.add_chrome_arg("--disable-extensions")?;
-        caps.add_chrome_arg("--no-sandbox")?;
-        caps.add_chrome_arg("--user-agent=Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36")?;
-        caps.add_chrome_arg("--window-size=1920,1080")?;
-        caps.add_chrome_arg("--single-process")?;
+        let caps = webdriver::chrome_caps(false)?;
 
         let client = redis::Client::open(redis_url.clone())
             .map_err(|_| ServerError::RedisConnectionError)?;
 
-        let driver: WebDriver = WebDriver::new(&webdriver_url, caps.clone())
-            .await
-            .map_err(|_| ServerError::WebdriverConnectionError)?;
+        let driver: WebDriver =
+            webdriver::connect(&webdriver_url, caps.clone(), webdriver::webdriver_wait())
+                .await
+                .map_err(|_| ServerError::WebdriverConnectionError)?;
 
         let _ = driver.clone().quit().await;
         Ok(Server {
@@ -63,9 +42,13 @@ impl Server {
     }
 
     pub async fn refresh_driver(&mut self) -> Result<()> {
-        self.driver = WebDriver::new(&self.webdriver_url, self.caps.clone())
-            .await
-            .map_err(|_| ServerError::WebdriverConnectionError)?;
+        self.driver = webdriver::connect(
+            &self.webdriver_url,
+            self.caps.clone(),
+            webdriver::webdriver_wait(),
+        )
+        .await
+        .map_err(|_| ServerError::WebdriverConnectionError)?;
 
         let url = "https://login.m.taobao.com/login.htm?redirectURL=https%3A%2F%2Ferror.taobao.com%2Fapp%2Ftbhome%2Fcommon%2Ferror.html&loginFrom=wap_tbTop";
 
@@ -122,7 +105,7 @@ impl Server {
     pub(crate) async fn insert_bx_ua(&self, value: String) -> Result<()> {
         let mut conn = self.client.get_async_connection().await?;
 
-        conn.lpush(KEY_BX_UA, value).await?;
+        let _: () = conn.lpush(KEY_BX_UA, value).await?;
 
         Ok(())
     }
@@ -130,7 +113,7 @@ impl Server {
     pub(crate) async fn insert_bx_umid_token(&self, value: String) -> Result<()> {
         let mut conn = self.client.get_async_connection().await?;
 
-        conn.lpush(KEY_UMID_TOKEN, value).await?;
+        let _: () = conn.lpush(KEY_UMID_TOKEN, value).await?;
 
         Ok(())
     }
@@ -138,7 +121,7 @@ impl Server {
     pub(crate) async fn insert_ua(&self, value: String) -> Result<()> {
         let mut conn = self.client.get_async_connection().await?;
 
-        conn.lpush(KEY_UA, value).await?;
+        let _: () = conn.lpush(KEY_UA, value).await?;
 
         Ok(())
     }
@@ -0,0 +1,360 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{debug, info};
+
+use crate::{
+    clients::{dm::DmClient, endpoint::Api},
+    models::task::{default_timezone, Task},
+    ticket::DmTicket,
+};
+
+// 模拟的门票/场次/票档ID
+const SIM_ITEM_ID: &str = "10000";
+const SIM_PERFORM_ID: &str = "20000";
+const SIM_SKU_ID: &str = "30000";
+
+const SOLD_OUT_RET: &str = "B-00203-200-008::对不起，您选购的商品库存不足，请重新选购";
+
+const RISK_CONTROL_RET: &str = "RGV587_ERROR::SM::哎哟喂,被挤爆啦,请稍后重试";
+
+// 模拟大麦服务的配置
+#[derive(Debug, Clone)]
+pub struct SimulatorConfig {
+    pub latency: u64,      // 每个请求的响应延迟, 毫秒
+    pub stock: usize,      // 库存
+    pub error_rate: f64,   // 生成/提交订单触发风控的概率, 0-1
+    pub sale_in: u64,      // 距离开售的时长, 秒
+    pub ticket_num: usize, // 购票数量
+}
+
+// 模拟服务状态
+struct SimState {
+    config: SimulatorConfig,
+    stock: AtomicUsize,
+    sale_start: i64,
+    order_seq: AtomicU64,
+}
+
+impl SimState {
+    fn success(data: Value) -> Value {
+        json!({"ret": ["SUCCESS::调用成功"], "data": data, "v": "1.0"})
+    }
+
+    fn fail(ret: &str) -> Value {
+        json!({"ret": [ret], "data": {}})
+    }
+
+    fn ticket_info(&self) -> Value {
+        json!({
+            "detailViewComponentMap": {
+                "atmosphere": {},
+                "item": {
+                    "staticData": {
+                        "itemBase": {
+                            "itemId": SIM_ITEM_ID,
+                            "itemName": "模拟演唱会",
+                            "venueName": "模拟场馆",
+                            "cityName": "上海",
+                            "priceRange": "¥380-1280"
+                        }
+                    },
+                    "dynamicExtData": {},
+                    "item": {
+                        "sellStartTime": self.sale_start.to_string(),
+                        "sellStartTimeStr": "",
+                        "buyBtnText": "即将开抢",
+                        "performBases": [{
+                            "name": "模拟场次",
+                            "timeSpan": "",
+                            "performBaseTagDesc": "",
+                            "performs": [{
+                                "performId": SIM_PERFORM_ID,
+                                "itemId": SIM_ITEM_ID,
+                                "performName": "模拟场次"
+                            }]
+                        }]
+                    }
+                }
+            }
+        })
+    }
+
+    fn perform_info(&self) -> Value {
+        json!({
+            "perform": {
+                "performId": SIM_PERFORM_ID,
+                "performName": "模拟场次",
+                "skuList": [{
+                    "skuId": SIM_SKU_ID,
+                    "itemId": SIM_ITEM_ID,
+                    "priceName": "看台380元",
+                    "skuSalable": "true",
+                    "price": "380"
+                }]
+            }
+        })
+    }
+
+    fn order_info(&self) -> Value {
+        let viewers = (0..self.config.ticket_num)
+            .map(|i| {
+                json!({
+                    "viewerId": i + 1,
+                    "viewerName": format!("模拟观演人{}", i + 1),
+                    "certNo": format!("1101**********{:04}", i + 1),
+                    "certType": "1"
+                })
+            })
+            .collect::<Vec<Value>>();
+        json!({
+            "data": {
+                "confirmOrder_1": {"fields": {}},
+                "order_1": {"fields": {}},
+                "dmViewer_1": {"fields": {"viewerList": viewers}}
+            },
+            "global": {"secretKey": "simulator", "secretValue": "simulator"},
+            "hierarchy": {
+                "component": [],
+                "root": "confirmOrder_1",
+                "baseType": [],
+                "structure": {"confirmOrder_1": ["order_1", "dmViewer_1"]}
+            },
+            "linkage": {
+                "input": ["dmViewer_1"],
+                "request": [],
+                "signature": "simulator",
+                "common": {
+                    "queryParams": "",
+                    "compress": true,
+                    "validateParams": "",
+                    "structures": "",
+                    "submitParams": ""
+                }
+            }
+        })
+    }
+
+    // 下单: 未开售/触发风控/库存不足时返回对应错误
+    fn order(&self, api: Api) -> Value {
+        if Local::now().timestamp_millis() < self.sale_start {
+            return Self::fail("B-00203-200-034::商品未开售");
+        }
+        if rand::random::<f64>() < self.config.error_rate {
+            return Self::fail(RISK_CONTROL_RET);
+        }
+        match api {
+            Api::BuildOrder => match self.stock.load(Ordering::SeqCst) >= self.config.ticket_num {
+                true => Self::success(self.order_info()),
+                false => Self::fail(SOLD_OUT_RET),
+            },
+            _ => {
+                let num = self.config.ticket_num;
+                let res = self
+                    .stock
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |stock| {
+                        stock.checked_sub(num)
+                    });
+                match res {
+                    Ok(_) => {
+                        let order_id = self.order_seq.fetch_add(1, Ordering::SeqCst) + 1;
+                        Self::success(json!({"orderId": order_id.to_string()}))
+                    }
+                    Err(_) => Self::fail(SOLD_OUT_RET),
+                }
+            }
+        }
+    }
+
+    // 按接口返回模拟数据
+    fn handle(&self, path: &str) -> (Vec<String>, Value) {
+        let name = path.trim_matches('/').split('/').nth(1).unwrap_or_default();
+        let api = match Api::from_name(name) {
+            Some(api) => api,
+            None => return (vec![], json!({})),
+        };
+
+        let body = match api {
+            Api::TicketList => {
+                let cookies = vec![
+                    format!(
+                        "_m_h5_tk=simulator_{}; Path=/",
+                        Local::now().timestamp_millis()
+                    ),
+                    "_m_h5_tk_enc=simulator; Path=/".to_string(),
+                ];
+                return (
+                    cookies,
+                    Self::success(json!({"modules": [{"items": []}, {"items": []}]})),
+                );
+            }
+            Api::UserInfo => Self::success(json!({"nickname": "模拟用户", "userId": 10086})),
+            Api::TicketDetail => Self::success(json!({"result": self.ticket_info().to_string()})),
+            Api::PerformSkus => Self::success(json!({"result": self.perform_info().to_string()})),
+            Api::Timestamp => {
+                Self::success(json!({"t": Local::now().timestamp_millis().to_string()}))
+            }
+            Api::BuildOrder | Api::CreateOrder => self.order(api),
+            _ => Self::success(json!({})),
+        };
+        (vec![], body)
+    }
+}
+
+// 处理一个连接上的http/1.1请求, 支持keep-alive
+async fn serve(stream: TcpStream, state: Arc<SimState>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await? == 0 {
+            return Ok(());
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts
+            .next()
+            .unwrap_or("/")
+            .split('?')
+            .next()
+            .unwrap_or("/")
+            .to_string();
+
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 {
+                return Ok(());
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse::<usize>().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await?;
+
+        tokio::time::sleep(Duration::from_millis(state.config.latency)).await;
+
+        let (cookies, value) = state.handle(&path);
+        debug!("模拟服务, 请求:{}, 响应:{}", path, value);
+        let body = value.to_string();
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n",
+            body.len()
+        );
+        for cookie in cookies {
+            response.push_str(&format!("set-cookie: {}\r\n", cookie));
+        }
+        response.push_str("\r\n");
+        // HEAD请求不返回响应体
+        if method != "HEAD" {
+            response.push_str(&body);
+        }
+        writer.write_all(response.as_bytes()).await?;
+    }
+}
+
+// 启动模拟服务, 返回网关地址
+async fn start(config: SimulatorConfig) -> Result<(String, Arc<SimState>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let state = Arc::new(SimState {
+        stock: AtomicUsize::new(config.stock),
+        sale_start: Local::now().timestamp_millis() + (config.sale_in * 1000) as i64,
+        order_seq: AtomicU64::new(0),
+        config,
+    });
+
+    let server_state = state.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let state = server_state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve(stream, state).await {
+                    debug!("模拟服务连接异常:{:?}", e);
+                }
+            });
+        }
+    });
+
+    Ok((format!("http://{}", addr), state))
+}
+
+// 启动模拟服务并执行完整的抢票流程
+pub async fn simulate(config: SimulatorConfig) -> Result<()> {
+    if !(0.0..=1.0).contains(&config.error_rate) {
+        return Err(anyhow!("错误率应在0-1之间"));
+    }
+
+    let ticket_num = config.ticket_num;
+    let (base_url, state) = start(config).await?;
+    info!("模拟服务已启动:{}", base_url);
+
+    let cookie = Some("cookie2=simulator".to_string());
+    let client = DmClient::with_base_url(cookie, None, &base_url).await?;
+
+    let task = Task {
+        nickname: "模拟用户".to_string(),
+        ticket_id: SIM_ITEM_ID.to_string(),
+        ticket_name: "模拟演唱会".to_string(),
+        ticket_perform_id: SIM_PERFORM_ID.to_string(),
+        ticket_perform_name: "模拟场次".to_string(),
+        ticket_perform_sku_id: SIM_SKU_ID.to_string(),
+        ticket_perform_sku_name: "看台380元".to_string(),
+        ticket_num,
+        priority_purchase_time: 0,
+        request_time_offset: 0,
+        retry_interval: 100,
+        retry_times: 50,
+        wait_for_submit_interval: 30,
+        real_names: vec![],
+        viewers: vec![],
+        split_per_viewer: false,
+        warmup_time: 5,
+        stop_after_seconds: None,
+        stop_after_sale_plus: None,
+        stop_on_error_codes: vec![],
+        backup_sku_ids: vec![],
+        waitlist_on_sold_out: false,
+        timezone: default_timezone(),
+        seats: None,
+        address_id: None,
+        use_coupons: false,
+        cancel_duplicates: false,
+        allow_existing_order: false,
+        keyword: None,
+        cities: vec![],
+        watch_status: false,
+        privilege_code: None,
+        variant: None,
+    };
+
+    DmTicket::with_platform(client, task).await?.run().await?;
+
+    info!(
+        "模拟结束, 剩余库存:{}, 成功订单:{}个",
+        state.stock.load(Ordering::SeqCst),
+        state.order_seq.load(Ordering::SeqCst)
+    );
+    Ok(())
+}
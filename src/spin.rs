@@ -0,0 +1,74 @@
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+// 默认在开抢前5毫秒切换为忙等
+const DEFAULT_SPIN_WAIT: Duration = Duration::from_millis(5);
+
+// 忙等时长上限, 避免长时间占用线程
+const MAX_SPIN_WAIT: Duration = Duration::from_millis(20);
+
+// 校准时测量休眠唤醒误差的次数
+const CALIBRATE_SAMPLES: u32 = 5;
+
+// 校准时每次休眠的时长
+const CALIBRATE_SLEEP: Duration = Duration::from_millis(1);
+
+// 开抢前的高精度等待: 先休眠到开抢前一段时间, 再忙等到开抢时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpinWait {
+    pub spin: Duration,       // 忙等时长
+    pub wake_error: Duration, // 校准测得的休眠唤醒误差, 取最大值
+}
+
+impl Default for SpinWait {
+    fn default() -> Self {
+        Self::new(DEFAULT_SPIN_WAIT)
+    }
+}
+
+impl SpinWait {
+    pub fn new(spin: Duration) -> Self {
+        Self {
+            spin: spin.min(MAX_SPIN_WAIT),
+            wake_error: Duration::ZERO,
+        }
+    }
+
+    // SPIN_WAIT: 忙等时长, 毫秒, 配置后不再校准
+    pub fn from_env() -> Option<Self> {
+        env::var("SPIN_WAIT")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|millis| Self::new(Duration::from_millis(millis)))
+    }
+
+    // 测量本机休眠的唤醒误差, 忙等时长不小于误差的2倍, 保证休眠不会越过开抢时间
+    pub async fn calibrate() -> Self {
+        if let Some(spin) = Self::from_env() {
+            return spin;
+        }
+        let mut wake_error = Duration::ZERO;
+        for _ in 0..CALIBRATE_SAMPLES {
+            let start = Instant::now();
+            tokio::time::sleep(CALIBRATE_SLEEP).await;
+            wake_error = wake_error.max(start.elapsed().saturating_sub(CALIBRATE_SLEEP));
+        }
+        Self {
+            spin: (wake_error * 2).clamp(DEFAULT_SPIN_WAIT, MAX_SPIN_WAIT),
+            wake_error,
+        }
+    }
+
+    // 等待到deadline, 返回实际触发时间晚于deadline的时长, 微秒
+    pub async fn wait_until(&self, deadline: Instant) -> u64 {
+        if let Some(left) = deadline.checked_duration_since(Instant::now() + self.spin) {
+            tokio::time::sleep(left).await;
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
+        }
+        Instant::now().duration_since(deadline).as_micros() as u64
+    }
+}
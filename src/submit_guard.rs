@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use crate::models::{
+    order::{existing_order, OrderSummary},
+    task::Task,
+};
+
+// 结果不明确的一次提交
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingSubmit {
+    pub attempt_id: String,
+    pub token: Option<String>, // 提交时使用的订单token(submitref)摘要
+}
+
+// 提交订单的防重复记录: 超时/网络错误时请求可能已被服务器处理,
+// 重试前先查询订单列表确认是否已创建订单, 避免重复下单
+#[derive(Debug, Default)]
+pub struct SubmitGuard {
+    known: Option<HashSet<String>>, // 开抢前账号已有的订单ID
+    pending: Vec<PendingSubmit>,
+}
+
+impl SubmitGuard {
+    // 记录开抢前已有的订单, 确认时排除
+    pub fn baseline(&mut self, orders: &[OrderSummary]) {
+        self.known = Some(orders.iter().map(|o| o.order_id.clone()).collect());
+    }
+
+    pub fn record(&mut self, attempt_id: &str, token: Option<String>) {
+        self.pending.push(PendingSubmit {
+            attempt_id: attempt_id.to_string(),
+            token,
+        });
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn pending(&self) -> &[PendingSubmit] {
+        &self.pending
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    // 在订单列表中查找结果不明确的提交创建的订单, 同时清除待确认记录;
+    // 开抢前已有同场次订单且未记录已有订单时无法区分, 返回None
+    pub fn resolve(
+        &mut self,
+        orders: &[OrderSummary],
+        task: &Task,
+        allow_existing: bool,
+    ) -> Option<OrderSummary> {
+        self.pending.clear();
+        let known = match &self.known {
+            Some(known) => known.clone(),
+            None if allow_existing => return None,
+            None => HashSet::new(),
+        };
+        let created = orders
+            .iter()
+            .filter(|order| !known.contains(&order.order_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        existing_order(&created, task).cloned()
+    }
+}
+
+// 提交结果是否不明确: 请求已发出但超时或连接中断, 建立连接失败时请求未发出
+pub fn is_ambiguous(e: &anyhow::Error) -> bool {
+    if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+        return !e.is_connect() && (e.is_timeout() || e.is_request() || e.is_body());
+    }
+    e.to_string().contains("超时")
+}
+
+// 订单token摘要, 日志中不输出原文
+pub fn token_digest(token: &str) -> String {
+    format!("{:?}", md5::compute(token))[..8].to_string()
+}
@@ -0,0 +1,57 @@
+use std::env;
+
+use opentelemetry::{
+    global, runtime,
+    sdk::{trace, Resource},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+// 默认的服务名称
+const DEFAULT_SERVICE_NAME: &str = "dm-ticket";
+
+// 通过OTLP(grpc)导出span, 未配置OTEL_EXPORTER_OTLP_ENDPOINT时不启用:
+// OTEL_EXPORTER_OTLP_ENDPOINT: 如http://localhost:4317
+// OTEL_SERVICE_NAME: 服务名称, 默认dm-ticket
+// 多台机器时按HOSTNAME区分
+pub fn layer<S>() -> Option<OpenTelemetryLayer<S, trace::Tracer>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let endpoint = match env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(endpoint) if !endpoint.is_empty() => endpoint,
+        _ => return None,
+    };
+    let service_name = env::var("OTEL_SERVICE_NAME").unwrap_or(DEFAULT_SERVICE_NAME.to_string());
+    let mut attributes = vec![KeyValue::new("service.name", service_name)];
+    if let Ok(host) = env::var("HOSTNAME") {
+        attributes.push(KeyValue::new("host.name", host));
+    }
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(trace::config().with_resource(Resource::new(attributes)))
+        .install_batch(runtime::Tokio);
+
+    match tracer {
+        Ok(tracer) => Some(tracing_opentelemetry::layer().with_tracer(tracer)),
+        Err(e) => {
+            // 日志尚未初始化
+            eprintln!("初始化OpenTelemetry导出失败:{:?}", e);
+            None
+        }
+    }
+}
+
+// 退出前导出剩余的span
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
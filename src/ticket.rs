@@ -4,236 +4,1015 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::rand_i64;
+use crate::{
+    config,
+    exit::{self, ExitStatus},
+    experiment, format_timestamp,
+    i18n::Lang,
+    pacer::RetryPacer,
+    rand_i64,
+    spin::SpinWait,
+    submit_guard::{self, SubmitGuard},
+    trace,
+};
 
+#[cfg(feature = "http-login")]
+use crate::clients::login::LoginClient;
 use crate::{
-    clients::{dm::DmClient, token::TokenClient},
+    clients::{coordinator::Coordinator, dm::DmClient, notify::NotifyClient, token::TokenClient},
+    errors::{MtopError, PlatformError},
+    events::{default_subscribers, error_code, EventSubscriber, TicketEvent, SUCCESS},
     models::{
-        order::{OrderForm, OrderInfo, OrderParams, SubmitOrderParams},
+        address::select_address,
+        coupon::{best_coupon, Coupon},
+        order::existing_order,
+        perform::{selected_status, sku_snapshot, SkuItem, SkuStatus},
+        privilege::mask_code,
+        search::select_city_stop,
         task::Task,
-        ticket::{TicketInfo, TicketInfoForm, TicketInfoParams},
-        user::{GetUserInfoForm, GetUserInfoParams, UserInfoData},
-        DmRes,
     },
+    platform::{ItemDetail, TicketPlatform},
 };
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, Local, TimeZone};
-use log::{debug, error, info, warn};
-use serde_json::json;
+use chrono::{DateTime, Local};
 use tokio::signal;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+// 执行抢票任务
+pub async fn run_task(cookie: String, task: Task) -> Result<()> {
+    run_task_with(cookie, task, vec![]).await
+}
+
+// 执行抢票, 附加额外的事件订阅者
+pub async fn run_task_with(
+    cookie: String,
+    mut task: Task,
+    subscribers: Vec<Box<dyn EventSubscriber>>,
+) -> Result<()> {
+    experiment::apply(&mut task, &experiment::variants()?)?;
+    if let Some(variant) = &task.variant {
+        info!(
+            "{}, 实验分组:{}, 请求时间偏移量:{}毫秒, 重试间隔:{}毫秒",
+            task.nickname, variant, task.request_time_offset, task.retry_interval
+        );
+    }
+    let mut ticket = DmTicket::new(cookie, task).await?;
+    ticket.subscribers.extend(subscribers);
+    ticket.run().await
+}
+
+// 触发风控后的初始冷却时长, 毫秒
+const DEFAULT_RISK_COOLDOWN: u64 = 1000;
+
+// 触发风控后的最大冷却时长, 毫秒
+const DEFAULT_RISK_COOLDOWN_MAX: u64 = 30000;
+
+// 等待开抢时保持登录状态的请求间隔, 分钟
+const DEFAULT_KEEPALIVE_INTERVAL: u64 = 10;
+
+// 预热后保持连接的请求间隔, 秒
+const DEFAULT_PREWARM_INTERVAL: u64 = 10;
+
+// 开抢前2秒内不再预热连接/校准时钟, 毫秒
+const PREWARM_STOP_BEFORE: i64 = 2000;
+
+// 校准服务器时钟偏差的间隔, 秒
+const CALIBRATE_INTERVAL: u64 = 5;
+
+// 开抢前200毫秒内切换为高精度等待, 毫秒
+const PRECISE_WAIT_WINDOW: i64 = 200;
+
+// 开抢前1分钟内不再请求, 避免影响抢票, 毫秒
+const KEEPALIVE_STOP_BEFORE: i64 = 60 * 1000;
+
+// 等待开抢时检查开售时间是否变更的间隔, 分钟
+const DEFAULT_SALE_CHECK_INTERVAL: u64 = 5;
+
+// 开抢前10秒内不再检查开售时间, 毫秒
+const SALE_CHECK_STOP_BEFORE: i64 = 10 * 1000;
+
+// 监控票档售卖状态的间隔, 毫秒
+const DEFAULT_STATUS_WATCH_INTERVAL: u64 = 3000;
+
+// 默认的开售前提醒时间
+const DEFAULT_SALE_REMINDERS: &str = "24h,10m,1m";
+
+// 开售前提醒时间, 如: 24h,10m,1m,30s, 返回从大到小排列的秒数
+pub fn parse_reminders(value: &str) -> Result<Vec<u64>> {
+    let mut reminders = vec![];
+    for item in value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let (number, unit) = item.split_at(item.trim_end_matches(char::is_alphabetic).len());
+        let secs = match (number.parse::<u64>(), unit) {
+            (Ok(n), "h") => n * 3600,
+            (Ok(n), "m") => n * 60,
+            (Ok(n), "s") => n,
+            _ => return Err(anyhow!("提醒时间格式错误:{}, 应为数字加h/m/s, 如10m", item)),
+        };
+        reminders.push(secs);
+    }
+    reminders.sort_unstable_by(|a, b| b.cmp(a));
+    reminders.dedup();
+    Ok(reminders)
+}
+
+// 开售时间变更后的抢票时间, 保留请求时间偏移量/优先购时长, 未变更时返回None
+pub fn reschedule(start_timestamp: i64, old_sell_start: i64, new_sell_start: i64) -> Option<i64> {
+    match new_sell_start > 0 && new_sell_start != old_sell_start {
+        true => Some(start_timestamp + new_sell_start - old_sell_start),
+        false => None,
+    }
+}
 
-const SUCCESS_FLAG: &str = "SUCCESS::调用成功";
+// 错误是否为库存不足, 其他平台按错误信息中的错误码判断
+fn is_sold_out(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<MtopError>() {
+        Some(e) => *e == MtopError::StockEmpty,
+        None => MtopError::from_ret(&e.to_string()) == MtopError::StockEmpty,
+    }
+}
 
-pub struct DmTicket {
-    pub client: DmClient,
+pub struct DmTicket<P: TicketPlatform = DmClient> {
+    pub client: P,
     pub task: Task,
+    pub coordinator: Option<Coordinator>,
+    pub risk_hits: u32,              // 连续触发风控次数
+    pub detail: Option<ItemDetail>,  // 缓存的门票开售信息
+    pub started_at: Instant,         // 任务开始时间
+    pub sale_start: Option<i64>,     // 实际抢票时间戳, 毫秒
+    pub clock_offset: Option<i64>,   // 服务器时钟偏差, 毫秒
+    pub sold_out: bool,              // 是否返回过库存不足
+    pub coupon: Option<Coupon>,      // 生成订单时使用的优惠券
+    pub succeeded: bool,             // 是否下单成功
+    pub dry_run: bool,               // 试运行, 生成订单后不提交
+    pub pacer: RetryPacer,           // 自适应重试间隔
+    pub run_id: String,              // 运行ID, 关联日志/事件/webhook
+    pub stopped: Option<ExitStatus>, // 提前结束的原因, 如登录失败/手动停止
+    pub session_ok: Option<bool>,    // 最近一次检查登录状态的结果
+    pub guard: SubmitGuard,          // 结果不明确的提交, 重试前确认是否已下单
+    pub skus: Vec<SkuItem>,          // 场次的票档信息, 套票按份数下单
+    pub subscribers: Vec<Box<dyn EventSubscriber>>,
 }
 
 impl DmTicket {
     // Construct
     pub async fn new(cookie: String, task: Task) -> Result<Self> {
-        let redis_url = env::var("REDIS_URL").unwrap();
+        let redis_url = config::get("REDIS_URL")?;
         let token_client = TokenClient::new(redis_url).await?;
 
         let client = DmClient::new(Some(cookie), Some(token_client)).await?;
 
-        Ok(Self { client, task })
+        Self::with_platform(client, task).await
+    }
+}
+
+impl<P: TicketPlatform> DmTicket<P> {
+    // 使用指定的票务平台构造
+    pub async fn with_platform(client: P, task: Task) -> Result<Self> {
+        let coordinator = Coordinator::from_env(&task).await?;
+        let pacer = RetryPacer::from_env(task.retry_interval);
+
+        Ok(Self {
+            client,
+            task,
+            coordinator,
+            risk_hits: 0,
+            detail: None,
+            started_at: Instant::now(),
+            sale_start: None,
+            clock_offset: None,
+            sold_out: false,
+            coupon: None,
+            succeeded: false,
+            dry_run: Self::dry_run_enabled(),
+            pacer,
+            run_id: trace::new_run_id(),
+            stopped: None,
+            session_ok: None,
+            guard: SubmitGuard::default(),
+            skus: vec![],
+            subscribers: default_subscribers(),
+        })
+    }
+
+    // 订阅抢票事件
+    pub fn subscribe(&mut self, subscriber: Box<dyn EventSubscriber>) {
+        self.subscribers.push(subscriber);
+    }
+
+    // 发送事件给所有订阅者
+    pub async fn emit(&self, event: TicketEvent) {
+        debug!("{}, 事件:{:?}", self.task.nickname, event);
+        for subscriber in self.subscribers.iter() {
+            subscriber.on_event(&self.task, &event).await;
+        }
+    }
+
+    // 是否已有其他机器下单成功
+    pub fn is_done_elsewhere(&self) -> bool {
+        self.coordinator
+            .as_ref()
+            .is_some_and(|coordinator| coordinator.is_done())
+    }
+
+    // 通知其他机器停止抢票, 其他机器已先下单成功时按配置取消本次订单
+    pub async fn notify_done(&self, order_id: Option<&str>) {
+        if let Some(coordinator) = &self.coordinator {
+            let owner = env::var("HOSTNAME").unwrap_or(self.task.nickname.clone());
+            match coordinator.mark_done(&owner).await {
+                Ok(true) => info!("{}, 已通知其他机器停止抢票...", self.task.nickname),
+                Ok(false) => match (self.task.cancel_duplicates, order_id) {
+                    (true, Some(order_id)) => self.cancel_duplicate(order_id).await,
+                    _ => warn!(
+                        "{}, 其他机器已先下单成功, 请检查是否重复下单!",
+                        self.task.nickname
+                    ),
+                },
+                Err(e) => warn!("{}, 通知其他机器失败, {:?}", self.task.nickname, e),
+            }
+        }
+    }
+
+    // 取消重复的订单, 释放库存
+    pub async fn cancel_duplicate(&self, order_id: &str) {
+        warn!(
+            "{}, 其他机器已先下单成功, 正在取消重复订单:{}...",
+            self.task.nickname, order_id
+        );
+        let content = match self.client.cancel_order(order_id).await {
+            Ok(_) => {
+                info!("{}, 已取消重复订单:{}", self.task.nickname, order_id);
+                format!("已取消重复订单:{}", order_id)
+            }
+            Err(e) => {
+                error!("{}, 取消重复订单失败:{:?}", self.task.nickname, e);
+                format!("取消重复订单:{}失败, 请尽快手动取消", order_id)
+            }
+        };
+        if env::var("NOTIFY_TOKEN").is_ok() {
+            let content = format!(
+                "{}, {}, {}",
+                self.task.nickname, self.task.ticket_name, content
+            );
+            let _ = NotifyClient::notify(&content).await;
+        }
+    }
+
+    // 毫秒转时分秒
+    pub fn ms_to_hms(&self, ms: i64) -> (u64, u64, f64) {
+        let sec = ms as f64 / 1000.0;
+        let hour = (sec / 3600.0) as u64;
+        let rem = sec % 3600.0;
+        let min = (rem / 60.0) as u64;
+        let sec = rem % 60.0;
+        (hour, min, sec)
+    }
+
+    // 风控冷却时长, 连续触发时成倍增加
+    pub fn risk_cooldown(&self) -> Duration {
+        let base = env::var("RISK_COOLDOWN")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_RISK_COOLDOWN);
+        let max = env::var("RISK_COOLDOWN_MAX")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_RISK_COOLDOWN_MAX);
+
+        let exp = self.risk_hits.saturating_sub(1).min(16);
+        let cooldown = base.saturating_mul(1 << exp).min(max);
+        Duration::from_millis(rand_i64(cooldown as i64))
+    }
+
+    // 是否试运行, DRY_RUN=true时开启
+    fn dry_run_enabled() -> bool {
+        env::var("DRY_RUN")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+    }
+
+    // 是否在登录过期时自动重新登录, AUTO_RELOGIN=false时关闭
+    fn auto_relogin_enabled() -> bool {
+        env::var("AUTO_RELOGIN")
+            .map(|v| !v.eq_ignore_ascii_case("false"))
+            .unwrap_or(true)
+    }
+
+    // 扫码登录, 同时通过通知渠道推送二维码
+    #[cfg(feature = "http-login")]
+    async fn qrcode_login() -> Result<String> {
+        let webdriver_url = config::get("WEBDRIVER_URL")?;
+        LoginClient::new().await?.login(&webdriver_url, true).await
+    }
+
+    #[cfg(not(feature = "http-login"))]
+    async fn qrcode_login() -> Result<String> {
+        Err(anyhow!("未启用扫码登录(http-login), 无法重新登录"))
+    }
+
+    // 登录过期时暂停任务, 扫码重新登录后使用新的cookie继续
+    pub async fn relogin(&mut self) -> Result<()> {
+        // 同一账号的其他任务已重新登录时直接使用新的会话
+        if self.sync_session().await {
+            info!(
+                "{}, 已使用其他任务重新登录后的会话, 继续执行任务...",
+                self.task.nickname
+            );
+            return Ok(());
+        }
+        if !Self::auto_relogin_enabled() {
+            return Err(PlatformError::SessionExpired.into());
+        }
+
+        warn!("{}, 登录已过期, 请扫码重新登录...", self.task.nickname);
+        let cookie = Self::qrcode_login().await?;
+        self.client.update_cookie(&cookie).await?;
+        info!("{}, 重新登录成功, 继续执行任务...", self.task.nickname);
+        Ok(())
     }
 
-    // 获取用户信息
-    pub async fn get_user_info(&self) -> Result<UserInfoData> {
-        let url = "https://mtop.damai.cn/h5/mtop.damai.wireless.user.session.transform/1.0/";
-        let params = GetUserInfoParams::build()?;
-        let form = GetUserInfoForm::build()?;
-        let res = self.client.request(url, params, form).await?;
-        if res.ret.contains(&SUCCESS_FLAG.to_string()) {
-            let user_info_data = serde_json::from_value(res.data)?;
-            Ok(user_info_data)
-        } else {
-            Err(anyhow!("{}", res.ret[0]))
+    // 保持登录状态的请求间隔, KEEPALIVE_INTERVAL=0时关闭
+    fn keepalive_interval() -> Option<Duration> {
+        let minutes = env::var("KEEPALIVE_INTERVAL")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_KEEPALIVE_INTERVAL);
+        match minutes {
+            0 => None,
+            _ => Some(Duration::from_secs(minutes * 60)),
         }
     }
 
-    // 获取门票信息
-    pub async fn get_ticket_info(&self, ticket_id: String) -> Result<TicketInfo> {
-        let url = "https://mtop.damai.cn/h5/mtop.alibaba.damai.detail.getdetail/1.2";
+    // 检查开售时间是否变更的间隔, SALE_CHECK_INTERVAL=0时关闭
+    fn sale_check_interval() -> Option<Duration> {
+        let minutes = env::var("SALE_CHECK_INTERVAL")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_SALE_CHECK_INTERVAL);
+        match minutes {
+            0 => None,
+            _ => Some(Duration::from_secs(minutes * 60)),
+        }
+    }
 
-        let params = TicketInfoParams::build()?;
+    // 与机器网络相关的请求时间偏移量(毫秒), REQUEST_TIME_OFFSET配置, 叠加到任务的偏移量上
+    fn machine_time_offset() -> i64 {
+        env::var("REQUEST_TIME_OFFSET")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(0)
+    }
 
-        let data = TicketInfoForm::build(&ticket_id)?;
+    // 监控票档售卖状态的间隔, STATUS_WATCH_INTERVAL配置
+    fn status_watch_interval() -> Duration {
+        Duration::from_millis(
+            env::var("STATUS_WATCH_INTERVAL")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(DEFAULT_STATUS_WATCH_INTERVAL),
+        )
+    }
 
-        let res = self.client.request(url, params, data).await?;
+    // 开售前提醒时间, SALE_REMINDERS配置, 为空或off时关闭
+    fn sale_reminders() -> Vec<u64> {
+        let value = env::var("SALE_REMINDERS").unwrap_or(DEFAULT_SALE_REMINDERS.to_string());
+        if value.eq_ignore_ascii_case("off") {
+            return vec![];
+        }
+        parse_reminders(&value).unwrap_or_else(|e| {
+            warn!("{:?}, 使用默认提醒时间:{}", e, DEFAULT_SALE_REMINDERS);
+            parse_reminders(DEFAULT_SALE_REMINDERS).unwrap_or_default()
+        })
+    }
 
-        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
-            true => {
-                debug!("{}, 获取门票信息成功, {:?}", self.task.nickname, res);
+    // 开售前提醒, 开抢前1分钟内不再请求, 使用最近一次检查的登录状态
+    // 只提醒尚未到达的时间点
+    fn pending_reminders(time_left_millis: i64) -> Vec<u64> {
+        Self::sale_reminders()
+            .into_iter()
+            .filter(|lead| (*lead as i64) * 1000 < time_left_millis)
+            .collect()
+    }
 
-                let ticket_info: TicketInfo =
-                    serde_json::from_str(res.data["result"].clone().as_str().unwrap())?;
-                Ok(ticket_info)
+    // 重新获取门票信息, 开售时间变更时返回新的抢票时间并通知
+    async fn check_sale_time(&mut self, item_id: &str, start_timestamp: i64) -> Option<i64> {
+        let old_sell_start = self.detail.as_ref()?.sell_start_timestamp;
+        let detail = match self.client.detail(item_id).await {
+            Ok(detail) => detail,
+            Err(e) => {
+                warn!("{}, 检查开售时间失败:{:?}", self.task.nickname, e);
+                return None;
             }
-            false => {
-                error!(
-                    "{}, 获取门票信息失败, 结果:{:?}",
-                    self.task.nickname, res.ret
-                );
-                Err(anyhow!("获取门票信息失败..."))
+        };
+        let new_sell_start = detail.sell_start_timestamp;
+        self.detail = Some(detail);
+        let new_start = reschedule(start_timestamp, old_sell_start, new_sell_start)?;
+
+        let tz = self.task.tz();
+        warn!(
+            "{}, 开售时间变更:{} -> {}, 重新设定抢票时间:{}",
+            self.task.nickname,
+            format_timestamp(old_sell_start, tz),
+            format_timestamp(new_sell_start, tz),
+            format_timestamp(new_start, tz)
+        );
+        self.sale_start = Some(new_start);
+        self.emit(TicketEvent::SaleTimeChanged {
+            old_start: start_timestamp,
+            new_start,
+        })
+        .await;
+        self.emit(TicketEvent::ScheduleArmed {
+            sale_start: new_start,
+        })
+        .await;
+        Some(new_start)
+    }
+
+    pub async fn remind(&mut self, lead_secs: u64, sale_start: i64, time_left_millis: i64) {
+        if time_left_millis > KEEPALIVE_STOP_BEFORE {
+            if let Err(e) = self.keepalive().await {
+                error!("{}, {:?}", self.task.nickname, e);
             }
         }
+        let event = TicketEvent::SaleReminder {
+            lead_secs,
+            sale_start,
+            session_ok: self.session_ok,
+            clock_offset_ms: self.clock_offset,
+        };
+        info!("{}, {}", self.task.nickname, event.message());
+        self.emit(event).await;
     }
 
-    // 生成订单
-    pub async fn build_order(
-        &self,
-        item_id: &String,
-        sku_id: &String,
-        buy_num: usize,
-    ) -> Result<OrderInfo> {
+    // 同一账号的其他任务重新登录/刷新token后, 使用新的会话继续, 无需重启任务
+    pub async fn sync_session(&mut self) -> bool {
+        match self.client.sync_session().await {
+            Ok(updated) => updated,
+            Err(e) => {
+                warn!("{}, 同步登录会话失败:{:?}", self.task.nickname, e);
+                false
+            }
+        }
+    }
+
+    // 请求用户信息保持登录状态, cookie失效时提前通知并重新登录
+    pub async fn keepalive(&mut self) -> Result<()> {
+        self.sync_session().await;
+        let res = match self.client.user_info().await {
+            Ok(_) => {
+                debug!("{}, 登录状态正常", self.task.nickname);
+                self.session_ok = Some(true);
+                Ok(())
+            }
+            Err(e) => {
+                warn!("{}, 保持登录状态失败:{:?}", self.task.nickname, e);
+                if matches!(e.downcast_ref(), Some(PlatformError::SessionExpired))
+                    && env::var("NOTIFY_TOKEN").is_ok()
+                {
+                    let content = format!(
+                        "{}, {}, 登录已失效, 请尽快重新登录!",
+                        self.task.nickname, self.task.ticket_name
+                    );
+                    let _ = NotifyClient::notify(&content).await;
+                }
+                let res = self.handle_error(e).await;
+                self.session_ok = Some(res.is_ok());
+                res
+            }
+        };
+        self.emit(TicketEvent::SessionChecked {
+            session_ok: res.is_ok(),
+            clock_offset_ms: self.clock_offset,
+        })
+        .await;
+        res
+    }
+
+    // 开售前预热: 刷新门票信息, 校验票档, 预先构造订单参数, 开售时只需生成/提交订单
+    pub async fn warmup(&mut self, item_id: &str, sku_id: &str) -> Result<()> {
         let start = Instant::now();
 
-        let url = "https://mtop.damai.cn/h5/mtop.trade.order.build.h5/4.0/?";
+        let detail = self.client.detail(item_id).await?;
+        self.detail = Some(detail);
 
-        let params = OrderParams::build()?;
+        let skus = self
+            .client
+            .skus(item_id, &self.task.ticket_perform_id)
+            .await?;
+        if !skus.iter().any(|sku| sku.sku_id == sku_id) {
+            return Err(anyhow!(
+                "场次:{}中未找到票档:{}",
+                self.task.ticket_perform_name,
+                self.task.ticket_perform_sku_name
+            ));
+        }
+        self.skus = skus;
+
+        if self.task.seats.is_some() {
+            let seats = self
+                .client
+                .seats(item_id, &self.task.ticket_perform_id)
+                .await?;
+            info!(
+                "{}, 获取座位图成功, 可售座位:{}个",
+                self.task.nickname,
+                seats.iter().filter(|seat| seat.available).count()
+            );
+        }
 
-        let data = OrderForm::build(item_id, sku_id, buy_num)?;
+        let buy_num = self.buy_num(sku_id, self.task.ticket_num)?;
+        self.client.prepare(item_id, sku_id, buy_num).await?;
 
-        let res = self.client.request(url, params, data).await?;
+        if let Err(e) = self.client.pin_dns().await {
+            warn!("{}, 预先解析域名失败:{:?}", self.task.nickname, e);
+        }
 
-        debug!(
-            "{}, 生成订单结果:{:?}, 花费时间:{:?}",
+        if let Err(e) = self.client.prewarm().await {
+            warn!("{}, {:?}", self.task.nickname, e);
+        }
+
+        info!(
+            "{}, 预热完成, 耗时:{}毫秒",
             self.task.nickname,
-            res,
-            start.elapsed()
+            start.elapsed().as_millis()
         );
+        self.emit(TicketEvent::TokenWarmed {
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        })
+        .await;
+        Ok(())
+    }
 
-        match res.ret.contains(&SUCCESS_FLAG.to_string()) {
-            true => {
-                let order_info: OrderInfo = serde_json::from_value(res.data)?;
-                Ok(order_info)
+    // 快递配送纸质票的演出, 选择收货地址, 未配置时使用默认地址
+    pub async fn select_address(&mut self) -> Result<()> {
+        let addresses = self.client.addresses().await?;
+        let address = select_address(&addresses, self.task.address_id.as_deref()).ok_or_else(
+            || match &self.task.address_id {
+                Some(id) => anyhow!("未找到收货地址:{}, 请检查任务配置", id),
+                None => anyhow!("该演出需要快递配送纸质票, 请先在大麦APP中添加收货地址"),
+            },
+        )?;
+        info!("{}, 收货地址:{}", self.task.nickname, address.desc());
+        self.task.address_id = Some(address.address_id.clone());
+        Ok(())
+    }
+
+    // 选择优惠金额最大的可用优惠券, 订单金额按票档价格 * 购票数量计算
+    pub async fn select_coupon(&mut self, item_id: &str, sku_id: &str) -> Result<()> {
+        let coupons = self.client.coupons(item_id).await?;
+        let amount = self
+            .client
+            .skus(item_id, &self.task.ticket_perform_id)
+            .await?
+            .iter()
+            .find(|sku| sku.sku_id == sku_id)
+            .and_then(|sku| sku.price.parse::<f64>().ok())
+            .map(|price| price * self.task.ticket_num as f64);
+
+        match best_coupon(&coupons, amount) {
+            Some(coupon) => {
+                self.client.use_coupon(&coupon.coupon_id)?;
+                info!(
+                    "{}, 使用优惠券:{}, 优惠{}元",
+                    self.task.nickname, coupon.name, coupon.discount
+                );
+                self.coupon = Some(coupon.clone());
             }
-            false => Err(anyhow!("{:?}", res.ret)),
+            None => info!("{}, 暂无可用的优惠券", self.task.nickname),
         }
+        Ok(())
     }
 
-    // 提交订单
-    pub async fn submit_order(&self, order_info: OrderInfo) -> Result<DmRes> {
-        let start = Instant::now();
+    // 开售前校验会员/粉丝优先购码, 生成订单时携带; 校验接口异常时仍携带
+    pub async fn apply_privilege_code(&mut self, item_id: &str) -> Result<()> {
+        let code = match self.task.privilege_code.as_deref().map(str::trim) {
+            Some(code) if !code.is_empty() => code.to_string(),
+            _ => return Ok(()),
+        };
+        match self.client.check_privilege(item_id, &code).await {
+            Ok(check) if !check.valid => {
+                return Err(anyhow!(
+                    "优先购码:{}无效, {}",
+                    mask_code(&code),
+                    check.message
+                ));
+            }
+            Ok(_) => info!(
+                "{}, 优先购码:{}校验通过",
+                self.task.nickname,
+                mask_code(&code)
+            ),
+            Err(e) => warn!(
+                "{}, 校验优先购码失败, 仍携带优先购码下单:{:?}",
+                self.task.nickname, e
+            ),
+        }
+        self.client.use_privilege_code(&code)
+    }
 
-        let url = "https://mtop.damai.cn/h5/mtop.trade.order.create.h5/4.0/";
+    // 校准服务器时钟偏差, 多次测量取平滑值
+    pub async fn calibrate(&mut self) {
+        match self.client.clock_offset().await {
+            Ok(offset) => {
+                let offset = match self.clock_offset {
+                    Some(old) => (old * 3 + offset) / 4,
+                    None => offset,
+                };
+                debug!("{}, 服务器时钟偏差:{}毫秒", self.task.nickname, offset);
+                self.clock_offset = Some(offset);
+            }
+            Err(e) => debug!("{}, 校准时钟失败:{:?}", self.task.nickname, e),
+        }
+    }
 
-        // 添加提交订单需要的数据
-        let mut order_data = json!({});
+    // 按服务器时钟计算的当前时间戳, 毫秒
+    pub fn server_now(&self) -> i64 {
+        Local::now().timestamp_millis() + self.clock_offset.unwrap_or(0)
+    }
 
-        for key in order_info.linkage.input.iter() {
-            if key.starts_with("dmViewer_") {
-                let mut item = order_info.data[key].clone();
-                let mut num = self.task.ticket_num;
+    // 高精度等待: 先休眠, 最后阶段忙等, 返回触发时间晚于预定时间的时长, 微秒
+    pub async fn precise_wait(&self, spin: &SpinWait, millis: i64) -> u64 {
+        let deadline = Instant::now() + Duration::from_millis(millis.max(0) as u64);
+        spin.wait_until(deadline).await
+    }
 
-                let viewer_list = item["fields"]["viewerList"].clone();
+    // 是否满足停止条件, message为接口返回的错误信息
+    pub fn stop_reason(&self, message: Option<&str>) -> Option<String> {
+        if let Some(seconds) = self.task.stop_after_seconds {
+            if self.started_at.elapsed() >= Duration::from_secs(seconds) {
+                return Some(format!("任务已运行超过{}秒", seconds));
+            }
+        }
 
-                // 需选择实名观演人
-                if viewer_list.is_array() && !viewer_list.as_array().unwrap().is_empty() {
-                    // 实名观演人比购票数量少
-                    if viewer_list.as_array().unwrap().len() < num {
-                        warn!("实名观演人小于实际购票数量, 请先添加实名观演人!");
-                        num = viewer_list.as_array().unwrap().len();
-                    }
-                    if self.task.real_names.is_empty() {
-                        info!(
-                            "{}, 未配置实名观演人, 默认选择前{}位观演人...",
-                            self.task.nickname, self.task.ticket_num
-                        );
-                        for i in 0..num {
-                            item["fields"]["viewerList"][i]["isUsed"] = true.into();
-                        }
-                    } else {
-                        for i in 0..item["fields"]["viewerList"]
-                            .as_array()
-                            .unwrap_or(&Vec::new())
-                            .len()
-                        {
-                            let idx = i + 1;
-                            if self.task.real_names.contains(&idx) {
-                                item["fields"]["viewerList"][i]["isUsed"] = true.into();
-                            }
-                        }
-                    }
-                }
-                order_data[key] = item;
-            } else {
-                order_data[key] = order_info.data[key].clone();
+        if let (Some(seconds), Some(sale_start)) = (self.task.stop_after_sale_plus, self.sale_start)
+        {
+            if Local::now().timestamp_millis() - sale_start >= (seconds * 1000) as i64 {
+                return Some(format!("已开售超过{}秒", seconds));
             }
         }
 
-        let confirm_order_key = &order_info.hierarchy.root;
-        order_data[confirm_order_key] = order_info.data[confirm_order_key].clone();
+        if let Some(message) = message {
+            if let Some(code) = self
+                .task
+                .stop_on_error_codes
+                .iter()
+                .find(|code| message.contains(code.as_str()))
+            {
+                return Some(format!("返回错误码:{}", code));
+            }
+        }
+        None
+    }
 
-        let keys_list = order_info.hierarchy.structure[confirm_order_key].clone();
+    // 停止抢票并通知
+    pub async fn stop(&self, reason: &str) {
+        warn!("{}, {}, 停止抢票...", self.task.nickname, reason);
+        if env::var("NOTIFY_TOKEN").is_ok() {
+            let content = format!(
+                "{}, {}, {}, 已停止抢票",
+                self.task.nickname, self.task.ticket_name, reason
+            );
+            let _ = NotifyClient::notify(&content).await;
+        }
+    }
 
-        for k in keys_list.as_array().unwrap() {
-            let s = k.as_str().unwrap();
-            if s.starts_with("order_") {
-                order_data[s] = order_info.data[s].clone();
+    // 处理人机验证/风控, 其他错误原样返回
+    pub async fn handle_error(&mut self, e: anyhow::Error) -> Result<()> {
+        match e.downcast_ref::<PlatformError>() {
+            Some(PlatformError::Challenge { url }) => {
+                let url = url.clone();
+                warn!("{}, 触发人机验证, 等待验证完成...", self.task.nickname);
+                let res = self.client.resolve_challenge(&url).await;
+                if let Err(e) = &res {
+                    warn!("{}, 人机验证未完成:{:?}", self.task.nickname, e);
+                }
+                res
+            }
+            Some(PlatformError::RiskControl { message }) => {
+                self.risk_hits += 1;
+                let cooldown = self.risk_cooldown();
+                warn!(
+                    "{}, 第{}次触发风控:{}, 冷却{:?}后重试...",
+                    self.task.nickname, self.risk_hits, message, cooldown
+                );
+                tokio::time::sleep(cooldown).await;
+                Ok(())
             }
+            Some(PlatformError::SessionExpired) => self.relogin().await,
+            // 排队未轮到不是请求失败, 重新请求即可
+            Some(PlatformError::Queued { waited_ms, status }) => {
+                warn!(
+                    "{}, {}, 已等待{}毫秒, 重新请求...",
+                    self.task.nickname, status, waited_ms
+                );
+                Ok(())
+            }
+            _ => Err(e),
         }
+    }
 
-        let order_hierarchy = json!({
-            "structure": order_info.hierarchy.structure
-        });
+    // 巡演按城市优先级选择门票, 并按名称匹配该站的场次/票档
+    pub async fn resolve_city(&mut self) -> Result<()> {
+        let keyword = match &self.task.keyword {
+            Some(keyword) if !self.task.cities.is_empty() => keyword.clone(),
+            _ => return Ok(()),
+        };
+        let stops = self.client.search_projects(&keyword).await?;
+        let stop = select_city_stop(&stops, &self.task.cities)
+            .ok_or(anyhow!(
+                "未找到{}在{}的演出",
+                keyword,
+                self.task.cities.join("/")
+            ))?
+            .clone();
+        info!(
+            "{}, 已选择{}站:{}, {}",
+            self.task.nickname, stop.city_name, stop.name, stop.venue_name
+        );
+        if stop.item_id == self.task.ticket_id {
+            return Ok(());
+        }
 
-        let order_linkage = json!({
-            "common": {
-                "compress": order_info.linkage.common.compress,
-                "submitParams": order_info.linkage.common.submit_params,
-                "validateParams": order_info.linkage.common.validate_params,
-            },
-            "signature": order_info.linkage.signature,
-        });
-
-        let submit_order_params = SubmitOrderParams::build(order_info.global.secret_value)?;
-
-        let feature = json!({
-            "subChannel": "damai@damaih5_h5",
-            "returnUrl": "https://m.damai.cn/damai/pay-success/index.html?spm=a2o71.orderconfirm.bottom.dconfirm&sqm=dianying.h5.unknown.value",
-            "serviceVersion": "2.0.0",
-            "dataTags": "sqm:dianying.h5.unknown.value"
-        });
-
-        let params = json!({
-            "data": serde_json::to_string(&order_data)?,
-            "hierarchy": serde_json::to_string(&order_hierarchy)?,
-            "linkage": serde_json::to_string(&order_linkage)?,
-        });
-
-        let sumbit_order_data = json!({
-            "params": serde_json::to_string(&params)?,
-            "feature": serde_json::to_string(&feature)?,
-        });
-
-        let res = self
+        // 场次名称一般为演出时间, 各站不同, 未匹配时选择第一个场次
+        let performs = self.client.performs(&stop.item_id).await?;
+        let perform = match performs
+            .iter()
+            .find(|perform| perform.perfrom_name == self.task.ticket_perform_name)
+        {
+            Some(perform) => perform.clone(),
+            None => {
+                let perform = performs
+                    .first()
+                    .ok_or(anyhow!("{}站暂无场次", stop.city_name))?
+                    .clone();
+                warn!(
+                    "{}, 未找到场次:{}, 选择第一个场次:{}",
+                    self.task.nickname, self.task.ticket_perform_name, perform.perfrom_name
+                );
+                perform
+            }
+        };
+
+        let skus = self.client.skus(&stop.item_id, &perform.perform_id).await?;
+        let sku_name = &self.task.ticket_perform_sku_name;
+        let sku = skus
+            .iter()
+            .find(|sku| &sku.sku_name == sku_name)
+            .or_else(|| {
+                skus.iter().find(|sku| {
+                    sku.sku_name.contains(sku_name.as_str()) || sku_name.contains(&sku.sku_name)
+                })
+            })
+            .ok_or(anyhow!(
+                "{}站未找到票档:{}, 可选票档:{}",
+                stop.city_name,
+                sku_name,
+                skus.iter()
+                    .map(|sku| sku.sku_name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(",")
+            ))?
+            .clone();
+
+        // 备选票档为原门票的票档ID, 无法用于其他城市
+        if !self.task.backup_sku_ids.is_empty() {
+            warn!("{}, 已切换城市, 忽略备选票档", self.task.nickname);
+            self.task.backup_sku_ids.clear();
+        }
+        self.task.ticket_id = stop.item_id;
+        self.task.ticket_name = stop.name;
+        self.task.ticket_perform_id = perform.perform_id;
+        self.task.ticket_perform_name = perform.perfrom_name;
+        self.task.ticket_perform_sku_id = sku.sku_id;
+        self.task.ticket_perform_sku_name = sku.sku_name;
+        Ok(())
+    }
+
+    // 创建任务前检查票档售卖状态, 首选及备选票档均已售罄时提醒, 仍继续等待回流票
+    pub async fn check_sku_status(&mut self, item_id: &str, sell_start_timestamp: i64) {
+        let skus = match self
             .client
-            .request(url, submit_order_params, sumbit_order_data)
-            .await?;
-        debug!("提交订单结果:{:?}, 花费时间:{:?}", res, start.elapsed());
+            .skus(item_id, &self.task.ticket_perform_id)
+            .await
+        {
+            Ok(skus) => skus,
+            Err(e) => {
+                warn!("{}, 查询票档状态失败, 跳过检查:{:?}", self.task.nickname, e);
+                return;
+            }
+        };
+        let sale_started = Local::now().timestamp_millis() >= sell_start_timestamp;
+        let skus = skus
+            .into_iter()
+            .map(|mut sku| {
+                sku.status = sku.status.resolve(sale_started);
+                sku
+            })
+            .collect::<Vec<_>>();
+        info!("{}, 票档状态:{}", self.task.nickname, sku_snapshot(&skus));
+
+        let selected = skus
+            .iter()
+            .filter(|sku| {
+                sku.sku_id == self.task.ticket_perform_sku_id
+                    || self.task.backup_sku_ids.contains(&sku.sku_id)
+            })
+            .collect::<Vec<_>>();
+        if !selected.is_empty() && selected.iter().all(|sku| sku.status == SkuStatus::SoldOut) {
+            warn!(
+                "{}, 所选票档均已售罄, 仅能等待回流票, 建议更换票档!",
+                self.task.nickname
+            );
+        }
+        self.skus = skus;
+    }
 
-        Ok(res)
+    // 票档的下单数量: 套票按每份包含的票数换算为份数, 未获取到票档信息时按普通票档
+    pub fn buy_num(&self, sku_id: &str, ticket_num: usize) -> Result<usize> {
+        let sku = match self.skus.iter().find(|sku| sku.sku_id == sku_id) {
+            Some(sku) => sku,
+            None => return Ok(ticket_num),
+        };
+        sku.buy_num(ticket_num).ok_or(anyhow!(
+            "票档:{}为套票, 每份{}张, 购票数量:{}不是{}的倍数",
+            sku.sku_name,
+            sku.bundle,
+            ticket_num,
+            sku.bundle
+        ))
     }
 
-    // 毫秒转时分秒
-    pub fn ms_to_hms(&self, ms: i64) -> (u64, u64, f64) {
-        let sec = ms as f64 / 1000.0;
-        let hour = (sec / 3600.0) as u64;
-        let rem = sec % 3600.0;
-        let min = (rem / 60.0) as u64;
-        let sec = rem % 60.0;
-        (hour, min, sec)
+    // 账号已有同场次订单时停止, 避免重复下单被拒及触发风控, 返回是否继续
+    pub async fn check_existing_order(&mut self) -> bool {
+        let orders = match self.client.orders().await {
+            Ok(orders) => orders,
+            Err(e) => {
+                warn!("{}, 查询已有订单失败, 跳过检查:{:?}", self.task.nickname, e);
+                return true;
+            }
+        };
+        self.guard.baseline(&orders);
+        let order = match existing_order(&orders, &self.task) {
+            Some(order) => order,
+            None => return true,
+        };
+        if self.task.allow_existing_order {
+            warn!(
+                "{}, 账号已有同场次订单:{}({}), 继续抢票...",
+                self.task.nickname, order.order_id, order.status
+            );
+            return true;
+        }
+        error!(
+            "{}, 账号已有同场次订单:{}({}), 停止抢票, 如需继续请配置allow_existing_order",
+            self.task.nickname, order.order_id, order.status
+        );
+        false
     }
 
-    // 尝试多次购买
+    // 尝试多次购买, 因库存不足失败时按配置进行缺货登记
     pub async fn multiple_buy_attempts(
-        &self,
-        item_id: &String,
-        sku_id: &String,
+        &mut self,
+        item_id: &str,
+        sku_id: &str,
+        buy_num: Option<usize>,
+    ) -> Result<bool> {
+        let res = self.buy_attempts(item_id, sku_id, buy_num).await;
+        if !matches!(res, Ok(true))
+            && self.sold_out
+            && self.task.waitlist_on_sold_out
+            && !self.dry_run
+        {
+            self.register_waitlist(item_id, sku_id).await;
+        }
+        res
+    }
+
+    // 上次提交结果不明确时, 先查询订单列表确认是否已创建订单, 返回是否已下单
+    pub async fn confirm_pending_submit(&mut self, start: Instant) -> bool {
+        let tokens = self
+            .guard
+            .pending()
+            .iter()
+            .map(|p| {
+                let token = p.token.as_deref().map(submit_guard::token_digest);
+                format!("{}(token:{})", p.attempt_id, token.unwrap_or_default())
+            })
+            .collect::<Vec<_>>();
+        info!(
+            "{}, 提交结果不明确:{}, 查询订单列表确认是否已下单...",
+            self.task.nickname,
+            tokens.join(",")
+        );
+        let orders = match self.client.orders().await {
+            Ok(orders) => orders,
+            Err(e) => {
+                warn!("{}, 查询订单失败, 继续提交:{:?}", self.task.nickname, e);
+                self.guard.clear();
+                return false;
+            }
+        };
+        let order = match self
+            .guard
+            .resolve(&orders, &self.task, self.task.allow_existing_order)
+        {
+            Some(order) => order,
+            None => {
+                info!("{}, 未找到新创建的订单", self.task.nickname);
+                return false;
+            }
+        };
+        info!(
+            "{}, 结果不明确的提交已创建订单:{}({}), 请尽快前往手机APP付款!",
+            self.task.nickname, order.order_id, order.status
+        );
+        self.succeeded = true;
+        self.emit(TicketEvent::OrderCreated {
+            order_id: Some(order.order_id.clone()),
+            pay_deadline: order.pay_deadline,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        })
+        .await;
+        self.notify_done(Some(&order.order_id))
+            .instrument(info_span!("verify"))
+            .await;
+        true
+    }
+
+    // 缺货登记, 有退票时排队购买
+    pub async fn register_waitlist(&self, item_id: &str, sku_id: &str) {
+        info!("{}, 库存不足, 正在进行缺货登记...", self.task.nickname);
+        let content = match self
+            .client
+            .register_waitlist(item_id, &self.task.ticket_perform_id, sku_id)
+            .await
+        {
+            Ok(_) => {
+                info!("{}, 缺货登记成功", self.task.nickname);
+                "缺货登记成功, 有退票时将排队购买"
+            }
+            Err(e) => {
+                error!("{}, 缺货登记失败:{:?}", self.task.nickname, e);
+                "缺货登记失败"
+            }
+        };
+        if env::var("NOTIFY_TOKEN").is_ok() {
+            let content = format!(
+                "{}, {}, {}, {}",
+                self.task.nickname,
+                self.task.ticket_name,
+                self.task.ticket_perform_sku_name,
+                content
+            );
+            let _ = NotifyClient::notify(&content).await;
+        }
+    }
+
+    // 生成订单, 选座演出先获取座位图, 按选座配置选择座位后下单
+    async fn build_order(&self, item_id: &str, sku_id: &str, buy_num: usize) -> Result<P::Order> {
+        let preference = match &self.task.seats {
+            Some(preference) => preference,
+            None => return self.client.build_order(item_id, sku_id, buy_num).await,
+        };
+
+        let seats = self
+            .client
+            .seats(item_id, &self.task.ticket_perform_id)
+            .await?;
+
+        // 没有满足条件的座位时按库存不足处理, 以便切换备选票档/缺货登记
+        let selected = match preference.select(&seats, sku_id, buy_num) {
+            Some(selected) => selected,
+            None => {
+                warn!("{}, 没有满足选座配置的可售座位", self.task.nickname);
+                return Err(MtopError::StockEmpty.into());
+            }
+        };
+
+        info!(
+            "{}, 已选择座位:{}",
+            self.task.nickname,
+            selected
+                .iter()
+                .map(|seat| format!("{}{}排{}座", seat.zone, seat.row, seat.col))
+                .collect::<Vec<String>>()
+                .join(",")
+        );
+        self.client
+            .build_seat_order(item_id, sku_id, &selected)
+            .await
+    }
+
+    // 多次尝试生成/提交订单
+    async fn buy_attempts(
+        &mut self,
+        item_id: &str,
+        sku_id: &str,
         buy_num: Option<usize>,
     ) -> Result<bool> {
         let buy_num = match buy_num {
@@ -245,12 +1024,63 @@ impl DmTicket {
             retry_times = 3;
         }
 
-        let mut order_info: Option<OrderInfo> = None;
+        // 首选票档及备选票档, 套票按份数下单, 票数不是每份票数倍数的套票跳过
+        let mut skus = vec![];
+        let candidates = std::iter::once(sku_id).chain(
+            self.task
+                .backup_sku_ids
+                .iter()
+                .map(String::as_str)
+                .filter(|id| *id != sku_id),
+        );
+        for id in candidates {
+            match self.buy_num(id, buy_num) {
+                Ok(num) => skus.push((id.to_string(), num)),
+                Err(e) => warn!("{}, {}, 跳过该票档", self.task.nickname, e),
+            }
+        }
+        if skus.is_empty() {
+            return Err(anyhow!("没有可下单的票档"));
+        }
+        let mut sku_index = 0;
+
+        let mut order_info: Option<P::Order> = None;
 
         for i in 0..retry_times {
+            if self.is_done_elsewhere() {
+                info!("{}, 其他机器已下单成功, 停止抢票...", self.task.nickname);
+                return Ok(false);
+            }
+            if let Some(reason) = self.stop_reason(None) {
+                self.stop(&reason).await;
+                return Ok(false);
+            }
+            self.sync_session().await;
             let start = Instant::now();
-            order_info = match self.build_order(item_id, sku_id, buy_num).await {
+            let started_at = self.server_now();
+            let attempt_id = trace::attempt_id(&self.run_id, "build", i + 1);
+            self.emit(TicketEvent::AttemptStarted {
+                stage: "build",
+                n: i + 1,
+                attempt_id: attempt_id.clone(),
+            })
+            .await;
+            let attempt = info_span!(
+                "attempt",
+                stage = "build",
+                n = i + 1,
+                attempt_id = %attempt_id
+            );
+            order_info = match self
+                .build_order(item_id, &skus[sku_index].0, skus[sku_index].1)
+                .instrument(attempt)
+                .await
+            {
                 Ok(data) => {
+                    self.risk_hits = 0;
+                    self.pacer.record(start.elapsed(), "");
+                    self.finish_attempt("build", i + 1, &attempt_id, started_at, start, SUCCESS)
+                        .await;
                     info!(
                         "\n{}, 第{}次生成订单成功, 耗时:{:?}毫秒\n",
                         Local::now().format("%Y-%m-%d %H:%M:%S.%3f"),
@@ -267,9 +1097,57 @@ impl DmTicket {
                         start.elapsed().as_millis(),
                         e.to_string()
                     );
+                    let code = error_code(&e.to_string());
+                    self.emit(TicketEvent::AttemptFailed {
+                        stage: "build",
+                        n: i + 1,
+                        attempt_id: attempt_id.clone(),
+                        code: code.clone(),
+                    })
+                    .await;
+                    self.finish_attempt("build", i + 1, &attempt_id, started_at, start, &code)
+                        .await;
+                    self.pacer.record(start.elapsed(), &e.to_string());
+
+                    let sold_out = is_sold_out(&e);
+                    if sold_out {
+                        self.sold_out = true;
+                    }
+
+                    if sold_out && sku_index + 1 < skus.len() {
+                        sku_index += 1;
+                        warn!(
+                            "{}, 票档库存不足, 切换备选票档:{}",
+                            self.task.nickname, skus[sku_index].0
+                        );
+                        continue;
+                    }
 
-                    let retry_interval = rand_i64(self.task.retry_interval as i64);
-                    tokio::time::sleep(Duration::from_millis(retry_interval)).await;
+                    if let Some(reason) = self.stop_reason(Some(&e.to_string())) {
+                        self.stop(&reason).await;
+                        return Ok(false);
+                    }
+
+                    let challenge = matches!(
+                        e.downcast_ref::<PlatformError>(),
+                        Some(PlatformError::Challenge { .. })
+                    );
+                    if let Err(e) = self.handle_error(e).await {
+                        match challenge {
+                            true => {
+                                self.emit(TicketEvent::ChallengeFailed {
+                                    stage: "build",
+                                    n: i + 1,
+                                    attempt_id: attempt_id.clone(),
+                                    error: e.to_string(),
+                                })
+                                .await
+                            }
+                            false => debug!("{}, {:?}", self.task.nickname, e),
+                        }
+                    }
+
+                    tokio::time::sleep(self.pacer.backoff()).await;
                     continue;
                 }
             };
@@ -280,15 +1158,88 @@ impl DmTicket {
             return Err(anyhow!("生成订单失败!"));
         }
 
+        if self.dry_run {
+            let payload = self
+                .client
+                .preview_submit(&self.task, order_info.unwrap())
+                .await?;
+            info!(
+                "{}, 试运行, 不提交订单, 将提交的数据:\n{}",
+                self.task.nickname,
+                serde_json::to_string_pretty(&payload)?
+            );
+            return Ok(false);
+        }
+
         let wait_for_submit_time = rand_i64(self.task.wait_for_submit_interval as i64);
 
         tokio::time::sleep(Duration::from_millis(wait_for_submit_time)).await;
 
-        for _ in 0..retry_times {
+        for i in 0..retry_times {
+            if self.is_done_elsewhere() {
+                info!("{}, 其他机器已下单成功, 停止抢票...", self.task.nickname);
+                return Ok(false);
+            }
+            if let Some(reason) = self.stop_reason(None) {
+                self.stop(&reason).await;
+                return Ok(false);
+            }
             let start = Instant::now();
+            let started_at = self.server_now();
+            // 上次提交超时/连接中断时可能已下单, 确认后再重试
+            if self.guard.is_pending() && self.confirm_pending_submit(start).await {
+                return Ok(true);
+            }
             let order = order_info.clone();
-            let res = self.submit_order(order.unwrap()).await?;
-            match res.ret.contains(&SUCCESS_FLAG.to_string()) {
+            let token = order
+                .as_ref()
+                .and_then(|order| self.client.submit_token(order));
+            let attempt_id = trace::attempt_id(&self.run_id, "submit", i + 1);
+            self.emit(TicketEvent::AttemptStarted {
+                stage: "submit",
+                n: i + 1,
+                attempt_id: attempt_id.clone(),
+            })
+            .await;
+            let attempt = info_span!(
+                "attempt",
+                stage = "submit",
+                n = i + 1,
+                attempt_id = %attempt_id
+            );
+            let res = match self
+                .client
+                .submit_order(&self.task, order.unwrap())
+                .instrument(attempt)
+                .await
+            {
+                Ok(res) => {
+                    self.risk_hits = 0;
+                    res
+                }
+                Err(e) => {
+                    let code = error_code(&e.to_string());
+                    self.emit(TicketEvent::AttemptFailed {
+                        stage: "submit",
+                        n: i + 1,
+                        attempt_id: attempt_id.clone(),
+                        code: code.clone(),
+                    })
+                    .await;
+                    self.finish_attempt("submit", i + 1, &attempt_id, started_at, start, &code)
+                        .await;
+                    self.pacer.record(start.elapsed(), &e.to_string());
+                    // 结果不明确时可能已下单, 不能直接返回错误, 下次提交前或结束时查询订单确认
+                    if submit_guard::is_ambiguous(&e) {
+                        self.guard.record(&attempt_id, token);
+                        tokio::time::sleep(self.pacer.backoff()).await;
+                        continue;
+                    }
+                    self.handle_error(e).await?;
+                    continue;
+                }
+            };
+            match res.success {
                 true => {
                     info!(
                         "{}, {}, 提交订单成功, 请尽快前往手机APP付款,  耗时:{}毫秒!",
@@ -296,6 +1247,24 @@ impl DmTicket {
                         self.task.nickname,
                         start.elapsed().as_millis()
                     );
+                    if let Some(coupon) = &self.coupon {
+                        info!(
+                            "{}, 已使用优惠券:{}, 优惠{}元",
+                            self.task.nickname, coupon.name, coupon.discount
+                        );
+                    }
+                    self.succeeded = true;
+                    self.finish_attempt("submit", i + 1, &attempt_id, started_at, start, SUCCESS)
+                        .await;
+                    self.emit(TicketEvent::OrderCreated {
+                        order_id: res.order_id(),
+                        pay_deadline: res.pay_deadline(),
+                        elapsed_ms: start.elapsed().as_millis() as u64,
+                    })
+                    .await;
+                    self.notify_done(res.order_id().as_deref())
+                        .instrument(info_span!("verify"))
+                        .await;
                     return Ok(true);
                 }
                 false => {
@@ -303,58 +1272,175 @@ impl DmTicket {
                         "{}, {}, 提交订单失败, 原因:{}, 耗时:{:?}毫秒",
                         Local::now().format("%Y-%m-%d %H:%M:%S.%3f"),
                         self.task.nickname,
-                        res.ret[0],
+                        res.message,
                         start.elapsed().as_millis()
                     );
-                    let retry_interval = rand_i64(self.task.retry_interval as i64);
-                    tokio::time::sleep(Duration::from_millis(retry_interval)).await;
+                    let code = error_code(&res.message);
+                    self.emit(TicketEvent::AttemptFailed {
+                        stage: "submit",
+                        n: i + 1,
+                        attempt_id: attempt_id.clone(),
+                        code: code.clone(),
+                    })
+                    .await;
+                    self.finish_attempt("submit", i + 1, &attempt_id, started_at, start, &code)
+                        .await;
+                    self.pacer.record(start.elapsed(), &res.message);
+                    if res.error() == Some(MtopError::StockEmpty) {
+                        self.sold_out = true;
+                    }
+                    if let Some(reason) = self.stop_reason(Some(&res.message)) {
+                        self.stop(&reason).await;
+                        return Ok(false);
+                    }
+                    debug!(
+                        "{}, 请求耗时:{:?}, 下次重试间隔:{:?}",
+                        self.task.nickname,
+                        self.pacer.rtt(),
+                        self.pacer.interval()
+                    );
+                    tokio::time::sleep(self.pacer.backoff()).await;
                 }
             };
         }
+        if self.guard.is_pending() && self.confirm_pending_submit(Instant::now()).await {
+            return Ok(true);
+        }
         Ok(false)
     }
 
     // 程序入口
     pub async fn run(&mut self) -> Result<()> {
+        let span = info_span!(
+            "task",
+            account = %self.task.nickname,
+            task = %self.task.key(),
+            platform = self.client.name(),
+            run_id = %self.run_id
+        );
+        let run_id = self.run_id.clone();
+        trace::scope(run_id, async move {
+            info!("{}, 运行ID:{}", self.task.nickname, self.run_id);
+            let res = self.execute().instrument(span).await;
+            self.emit(TicketEvent::RunFinished {
+                success: self.succeeded,
+                error: res.as_ref().err().map(|e| e.to_string()),
+            })
+            .await;
+            exit::record(self.exit_status(&res));
+            res
+        })
+        .await
+    }
+
+    // 本次运行结果对应的退出码
+    pub fn exit_status(&self, res: &Result<()>) -> ExitStatus {
+        if self.succeeded {
+            return ExitStatus::Success;
+        }
+        if let Some(status) = self.stopped {
+            return status;
+        }
+        match res {
+            Err(e) => ExitStatus::from_error(e),
+            Ok(_) if self.sold_out => ExitStatus::SoldOut,
+            Ok(_) if self.risk_hits > 0 => ExitStatus::RiskControl,
+            Ok(_) => ExitStatus::Failure,
+        }
+    }
+
+    // 执行抢票任务
+    async fn execute(&mut self) -> Result<()> {
         info!("{}, 正在检查用户信息...", self.task.nickname);
-        let user_info = match self.get_user_info().await {
+        let user_info = match self
+            .client
+            .user_info()
+            .instrument(info_span!("login"))
+            .await
+        {
             Ok(info) => info,
-            Err(e) => {
-                if e.to_string().contains("FAIL_SYS_SESSION_EXPIRED::Session") {
+            Err(e) if matches!(e.downcast_ref(), Some(PlatformError::SessionExpired)) => {
+                if let Err(e) = self.relogin().instrument(info_span!("relogin")).await {
                     error!(
-                        "{}, 获取用户信息失败, cookie已过期, 请重新登陆!",
-                        self.task.nickname,
+                        "{}, 获取用户信息失败, cookie已过期, 请重新登陆! {:?}",
+                        self.task.nickname, e
                     );
-                } else {
-                    error!("{}, 获取用户信息失败, 原因:{:?}", self.task.nickname, e);
+                    self.stopped = Some(ExitStatus::LoginFailed);
+                    return Ok(());
                 }
+                self.client.user_info().await?
+            }
+            Err(e) => {
+                error!("{}, 获取用户信息失败, 原因:{:?}", self.task.nickname, e);
+                self.stopped = Some(ExitStatus::LoginFailed);
                 return Ok(());
             }
         };
+        self.session_ok = Some(true);
+        if let Err(e) = self.resolve_city().await {
+            error!("{}, 选择巡演城市失败:{:?}", self.task.nickname, e);
+            return Err(e);
+        }
         let ticket_id = self.task.ticket_id.clone();
 
-        let priority_purchase_time = self.task.priority_purchase_time; // 优先购时长分钟
-
         info!("{}, 正在获取演唱会信息...", self.task.nickname);
-        let ticket_info = match self.get_ticket_info(ticket_id.clone()).await {
-            Ok(info) => info,
+        let ticket_info = match self.client.detail(&ticket_id).await {
+            Ok(info) => {
+                self.detail = Some(info.clone());
+                info
+            }
             Err(e) => {
                 info!("{}, 获取演唱会信息失败, {:?}", self.task.nickname, e);
                 return Err(e);
             }
         };
 
-        if ticket_info
-            .detail_view_component_map
-            .item
-            .item
-            .buy_btn_text
-            .contains("不支持")
-        {
+        if !ticket_info.supported {
             info!("该渠道不支持购买, 请使用APP购票!");
             return Ok(());
         }
 
+        ticket_info.validate(&self.task)?;
+
+        if !self.check_existing_order().await {
+            return Ok(());
+        }
+
+        if ticket_info.express_delivery == Some(true) {
+            self.select_address().await?;
+        }
+
+        if self.task.use_coupons {
+            let sku_id = self.task.ticket_perform_sku_id.clone();
+            if let Err(e) = self.select_coupon(&ticket_id, &sku_id).await {
+                warn!(
+                    "{}, 选择优惠券失败, 不使用优惠券:{:?}",
+                    self.task.nickname, e
+                );
+            }
+        }
+
+        if let Err(e) = self.apply_privilege_code(&ticket_id).await {
+            error!("{}, {:?}", self.task.nickname, e);
+            self.stopped = Some(ExitStatus::ConfigError);
+            return Ok(());
+        }
+
+        // 优先购时长(分钟), 未手动设置时使用门票信息中识别的优先购时长
+        let priority_purchase_time = match self.task.priority_purchase_time {
+            0 => {
+                let detected = ticket_info.priority_purchase_time.unwrap_or(0);
+                if detected > 0 {
+                    info!(
+                        "{}, 当前账号无优先购资格, 优先购时长:{}分钟",
+                        self.task.nickname, detected
+                    );
+                }
+                detected
+            }
+            manual => manual,
+        };
+
         let ticket_name = self.task.ticket_name.clone();
 
         let perform_name = self.task.ticket_perform_name.clone();
@@ -363,28 +1449,40 @@ impl DmTicket {
         let sku_name = self.task.ticket_perform_sku_name.clone();
         let item_id = self.task.ticket_id.clone();
 
-        let start_time_str = ticket_info
-            .detail_view_component_map
-            .item
-            .item
-            .sell_start_time_str;
+        let mut start_timestamp = ticket_info.sell_start_timestamp;
 
-        let mut start_timestamp = ticket_info
-            .detail_view_component_map
-            .item
-            .item
-            .sell_start_timestamp
-            .parse::<i64>()?;
-
-        if self.task.request_time_offset > 0 {
-            start_timestamp += self.task.request_time_offset;
+        let request_time_offset = self.task.request_time_offset + Self::machine_time_offset();
+        if request_time_offset > 0 {
+            start_timestamp += request_time_offset;
         }
 
         if priority_purchase_time > 0 {
             start_timestamp += priority_purchase_time * 60 * 1000;
         }
 
-        let date_time = Local.timestamp_millis_opt(start_timestamp).unwrap();
+        self.check_sku_status(&item_id, ticket_info.sell_start_timestamp)
+            .await;
+
+        // 套票按份数下单, 每单的票数需为每份票数的倍数
+        let tickets = match self.task.split_per_viewer {
+            true => 1,
+            false => self.task.ticket_num,
+        };
+        let buy_num = self.buy_num(&sku_id, tickets)?;
+        if buy_num != tickets {
+            info!(
+                "{}, 所选票档为套票, 每单{}张票, 下单{}份",
+                self.task.nickname, tickets, buy_num
+            );
+        }
+
+        self.sale_start = Some(start_timestamp);
+        self.emit(TicketEvent::ScheduleArmed {
+            sale_start: start_timestamp,
+        })
+        .await;
+
+        let tz = self.task.tz();
 
         println!(
             "\n\t账号昵称: {}
@@ -399,12 +1497,35 @@ impl DmTicket {
             perform_name,
             sku_name,
             self.task.ticket_num,
-            start_time_str,
-            self.task.request_time_offset,
-            self.task.priority_purchase_time,
-            date_time.format("%Y-%m-%d %H:%M:%S.%3f")
+            format_timestamp(ticket_info.sell_start_timestamp, tz),
+            request_time_offset,
+            priority_purchase_time,
+            format_timestamp(start_timestamp, tz)
         );
 
+        if self.dry_run {
+            info!("{}, 试运行, 不等待开售...", self.task.nickname);
+            if let Err(e) = self
+                .warmup(&item_id, &sku_id)
+                .instrument(info_span!("warmup"))
+                .await
+            {
+                warn!("{}, 预热失败:{:?}", self.task.nickname, e);
+            }
+            if let Err(e) = self.buy_it_now(&item_id, &sku_id).await {
+                error!("{}, 试运行失败:{:?}", self.task.nickname, e);
+            }
+            return Ok(());
+        }
+
+        // 未公布开售时间的门票, 监控售卖状态, 不按开售时间等待
+        if self.task.watch_status {
+            if let Err(e) = self.watch_status(&item_id, &sku_id).await {
+                warn!("{}", e);
+            }
+            return Ok(());
+        }
+
         let local: DateTime<Local> = Local::now();
         let current_timestamp = local.timestamp_millis();
 
@@ -433,22 +1554,215 @@ impl DmTicket {
         Ok(())
     }
 
+    // 记录一次生成/提交订单的结果(耗时/相对开售时间的偏移/错误码), 用于导出分析
+    async fn finish_attempt(
+        &self,
+        stage: &'static str,
+        n: u64,
+        attempt_id: &str,
+        started_at: i64,
+        start: Instant,
+        code: &str,
+    ) {
+        let sale_offset_ms = self
+            .detail
+            .as_ref()
+            .map(|detail| detail.sell_start_timestamp)
+            .filter(|sell_start| *sell_start > 0)
+            .map(|sell_start| started_at - sell_start);
+        self.emit(TicketEvent::AttemptFinished {
+            stage,
+            n,
+            attempt_id: attempt_id.to_string(),
+            started_at,
+            sale_offset_ms,
+            latency_ms: start.elapsed().as_millis() as u64,
+            endpoint: self.client.endpoint(stage),
+            code: code.to_string(),
+        })
+        .await;
+    }
+
     // 立即购买
-    pub async fn buy_it_now(&self, item_id: &String, sku_id: &String) -> Result<bool> {
-        self.multiple_buy_attempts(item_id, sku_id, None).await
+    pub async fn buy_it_now(&mut self, item_id: &str, sku_id: &str) -> Result<bool> {
+        let span = info_span!("burst", item_id, sku_id);
+        async move {
+            if self.task.split_per_viewer && self.task.ticket_num > 1 {
+                return self.split_buy(item_id, sku_id).await;
+            }
+            self.multiple_buy_attempts(item_id, sku_id, None).await
+        }
+        .instrument(span)
+        .await
+    }
+
+    // 一单一人: 每位观演人依次单独下单, 任一成功即视为成功
+    async fn split_buy(&mut self, item_id: &str, sku_id: &str) -> Result<bool> {
+        if self.is_done_elsewhere() {
+            info!("{}, 其他机器已下单成功, 停止抢票...", self.task.nickname);
+            return Ok(false);
+        }
+        let task = self.task.clone();
+        // 各观演人的订单共用同一任务, 全部结束后再通知其他机器
+        let coordinator = self.coordinator.take();
+
+        let mut success = 0;
+        let mut last_error = None;
+        let tasks = task.split_by_viewer();
+        let total = tasks.len();
+        for (i, viewer_task) in tasks.into_iter().enumerate() {
+            info!(
+                "{}, 一单一人, 第{}/{}位观演人下单...",
+                task.nickname,
+                i + 1,
+                total
+            );
+            self.task = viewer_task;
+            self.sold_out = false;
+            match self.multiple_buy_attempts(item_id, sku_id, Some(1)).await {
+                Ok(true) => success += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    error!("{}, 第{}位观演人下单失败:{:?}", task.nickname, i + 1, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        self.task = task;
+        self.coordinator = coordinator;
+
+        info!(
+            "{}, 一单一人, 成功下单{}/{}笔",
+            self.task.nickname, success, total
+        );
+        if success > 0 {
+            self.notify_done(None).await;
+            return Ok(true);
+        }
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(false),
+        }
+    }
+
+    // 监控票档售卖状态, 所选票档变为可购时立即下单, 不等待开售时间
+    pub async fn watch_status(&mut self, item_id: &str, sku_id: &str) -> Result<bool> {
+        if let Err(e) = self
+            .warmup(item_id, sku_id)
+            .instrument(info_span!("warmup"))
+            .await
+        {
+            warn!("{}, 预热失败:{:?}", self.task.nickname, e);
+        }
+
+        let interval = Self::status_watch_interval();
+        let keepalive_interval = Self::keepalive_interval();
+        let mut last_keepalive = Instant::now();
+
+        let mut sku_ids = vec![sku_id.to_string()];
+        sku_ids.extend(self.task.backup_sku_ids.iter().cloned());
+        let mut status = SkuStatus::Unknown;
+
+        info!(
+            "{}, 监控票档售卖状态, 变为可购时立即下单, 间隔:{:?}",
+            self.task.nickname, interval
+        );
+        loop {
+            if let Some(reason) = self.stop_reason(None) {
+                self.stop(&reason).await;
+                return Ok(false);
+            }
+            if keepalive_interval.is_some_and(|d| last_keepalive.elapsed() >= d) {
+                last_keepalive = Instant::now();
+                if let Err(e) = self.keepalive().await {
+                    error!("{}, {:?}", self.task.nickname, e);
+                }
+            }
+
+            match self
+                .client
+                .skus(item_id, &self.task.ticket_perform_id)
+                .await
+            {
+                Ok(skus) => {
+                    let current = selected_status(&skus, &sku_ids);
+                    if current != status {
+                        info!(
+                            "{}, 票档状态:{} -> {}, {}",
+                            self.task.nickname,
+                            status.desc(),
+                            current.desc(),
+                            sku_snapshot(&skus)
+                        );
+                        status = current;
+                    }
+                    if current == SkuStatus::Available {
+                        info!("{}, 票档已可购, 立即下单...", self.task.nickname);
+                        self.sale_start = Some(self.server_now());
+                        return self.buy_it_now(item_id, sku_id).await;
+                    }
+                }
+                Err(e) => {
+                    warn!("{}, 查询票档状态失败:{:?}", self.task.nickname, e);
+                    if let Err(e) = self.handle_error(e).await {
+                        debug!("{}, {:?}", self.task.nickname, e);
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = signal::ctrl_c() => {
+                    self.stopped = Some(ExitStatus::Interrupted);
+                    return Err(anyhow!("{}, 停止抢票任务...", self.task.nickname));
+                }
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
     }
 
     // 等待开售
     pub async fn wait_for_buy(
-        &self,
-        start_timestamp: i64,
-        item_id: &String,
-        sku_id: &String,
+        &mut self,
+        mut start_timestamp: i64,
+        item_id: &str,
+        sku_id: &str,
     ) -> Result<bool> {
         let (s, r) = async_channel::unbounded::<bool>();
 
         let interval = rand_i64(30);
-        let earliest_submit_time = 0;
+
+        self.calibrate().await;
+        let mut last_calibrate = Instant::now();
+
+        // 测量休眠唤醒误差, 确定开抢前的忙等时长
+        let spin = SpinWait::calibrate().await;
+        debug!(
+            "{}, 休眠唤醒误差:{}微秒, 开抢前{}毫秒开始忙等",
+            self.task.nickname,
+            spin.wake_error.as_micros(),
+            spin.spin.as_millis()
+        );
+
+        let keepalive_interval = Self::keepalive_interval();
+        let mut last_keepalive = Instant::now();
+
+        // 定时检查开售时间, 延期/提前时重新设定抢票时间
+        let sale_check_interval = Self::sale_check_interval();
+        let mut last_sale_check = Instant::now();
+
+        let warmup_time = (self.task.warmup_time * 1000) as i64;
+        let mut warmed_up = false;
+
+        // 预热后定时请求网关, 保持连接
+        let prewarm_interval = Duration::from_secs(
+            env::var("PREWARM_INTERVAL")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_PREWARM_INTERVAL),
+        );
+        let mut last_prewarm = Instant::now();
+
+        let mut reminders = Self::pending_reminders(start_timestamp - self.server_now());
 
         info!("{}, 等待开抢...", self.task.nickname);
 
@@ -456,25 +1770,91 @@ impl DmTicket {
         loop {
             tokio::select! {
                 _ = signal::ctrl_c() => {
+                    self.stopped = Some(ExitStatus::Interrupted);
                     return Err(anyhow!("{}, 停止抢票任务...", self.task.nickname));
                 }
 
                 _ = tokio::time::sleep(Duration::from_millis(interval)) => {
-                    let local: DateTime<Local> = Local::now();
-                    let millis = local.timestamp_millis();
-                    let time_left_millis = start_timestamp - millis;
-                    if time_left_millis <= earliest_submit_time {
+                    let time_left_millis = start_timestamp - self.server_now();
+                    if time_left_millis <= PRECISE_WAIT_WINDOW {
+                        let late = self.precise_wait(&spin, time_left_millis).await;
+                        info!(
+                            "\n{}, 开抢, 触发误差:{:.3}毫秒(按服务器时钟:{}毫秒)",
+                            self.task.nickname,
+                            late as f64 / 1000.0,
+                            self.server_now() - start_timestamp
+                        );
                         let _ = s.send(true).await;
-                    }else{
+                    } else if let Some(reason) = self.stop_reason(None) {
+                        self.stop(&reason).await;
+                        return Ok(false);
+                    } else if reminders
+                        .first()
+                        .is_some_and(|lead| time_left_millis <= (*lead as i64) * 1000)
+                    {
+                        let lead = reminders.remove(0);
+                        self.remind(lead, start_timestamp, time_left_millis).await;
+                    } else if !warmed_up && time_left_millis <= warmup_time {
+                        warmed_up = true;
+                        if let Err(e) = self
+                            .warmup(item_id, sku_id)
+                            .instrument(info_span!("warmup"))
+                            .await
+                        {
+                            warn!("{}, 预热失败:{:?}", self.task.nickname, e);
+                        }
+                        last_prewarm = Instant::now();
+                    } else if warmed_up
+                        && time_left_millis > PREWARM_STOP_BEFORE
+                        && last_prewarm.elapsed() >= prewarm_interval
+                    {
+                        last_prewarm = Instant::now();
+                        if let Err(e) = self.client.prewarm().await {
+                            warn!("{}, {:?}", self.task.nickname, e);
+                        }
+                    } else if time_left_millis > SALE_CHECK_STOP_BEFORE
+                        && sale_check_interval.is_some_and(|d| last_sale_check.elapsed() >= d)
+                    {
+                        last_sale_check = Instant::now();
+                        if let Some(new_start) =
+                            self.check_sale_time(item_id, start_timestamp).await
+                        {
+                            start_timestamp = new_start;
+                            let time_left_millis = start_timestamp - self.server_now();
+                            reminders = Self::pending_reminders(time_left_millis);
+                            warmed_up = warmed_up && time_left_millis <= warmup_time;
+                        }
+                    } else if time_left_millis > KEEPALIVE_STOP_BEFORE
+                        && keepalive_interval.is_some_and(|d| last_keepalive.elapsed() >= d)
+                    {
+                        last_keepalive = Instant::now();
+                        if let Err(e) = self.keepalive().await {
+                            error!("{}, {:?}", self.task.nickname, e);
+                        }
+                    } else if time_left_millis > PREWARM_STOP_BEFORE
+                        && last_calibrate.elapsed() >= Duration::from_secs(CALIBRATE_INTERVAL)
+                    {
+                        last_calibrate = Instant::now();
+                        self.calibrate().await;
+                    } else {
                         let (hours, minutes, seconds) = self.ms_to_hms(time_left_millis);
-                        print!("\r\t开抢倒计时:{}小时:{}分钟:{:.3}秒\t", hours, minutes, seconds);
+                        let offset = self.clock_offset.unwrap_or(0);
+                        match Lang::current() {
+                            Lang::Zh => print!(
+                                "\r\t开抢倒计时:{}小时:{}分钟:{:.3}秒, 服务器时钟偏差:{}毫秒\t",
+                                hours, minutes, seconds, offset
+                            ),
+                            Lang::En => print!(
+                                "\r\tStarts in {}h:{}m:{:.3}s, server clock offset: {}ms\t",
+                                hours, minutes, seconds, offset
+                            ),
+                        }
                         let _ = io::stdout().flush();
-
                     }
 
                 }
                 _ = r.recv() => {
-                    return self.multiple_buy_attempts(item_id, sku_id, None).await
+                    return self.buy_it_now(item_id, sku_id).await
                 }
             }
         }
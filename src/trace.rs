@@ -0,0 +1,32 @@
+use std::future::Future;
+
+use rand::Rng;
+
+tokio::task_local! {
+    // 当前任务的运行ID, 用于关联日志/事件/webhook
+    static RUN_ID: String;
+}
+
+// 每次运行任务生成一个运行ID, 8位十六进制
+pub fn new_run_id() -> String {
+    format!("{:08x}", rand::thread_rng().gen::<u32>())
+}
+
+// 第n次生成/提交订单的尝试ID, 如: 1a2b3c4d-b3为第3次生成订单, 1a2b3c4d-s1为第1次提交订单
+pub fn attempt_id(run_id: &str, stage: &str, n: u64) -> String {
+    let stage = match stage {
+        "build" => 'b',
+        _ => 's',
+    };
+    format!("{}-{}{}", run_id, stage, n)
+}
+
+// 当前任务的运行ID, 不在任务中时为None
+pub fn current_run_id() -> Option<String> {
+    RUN_ID.try_with(|id| id.clone()).ok()
+}
+
+// 在指定运行ID下执行
+pub async fn scope<F: Future>(run_id: String, f: F) -> F::Output {
+    RUN_ID.scope(run_id, f).await
+}
@@ -0,0 +1,162 @@
+use std::{env, ffi::OsString, path::PathBuf, sync::OnceLock, time::Duration};
+
+use anyhow::{anyhow, Result};
+use tracing::{error, info};
+use windows_service::{
+    define_windows_service,
+    service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    },
+    service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle},
+    service_dispatcher,
+    service_manager::{ServiceManager, ServiceManagerAccess},
+};
+
+use crate::{
+    config,
+    daemon::{self, Control, DaemonOptions, SdNotify},
+    exit,
+};
+
+// 服务名称
+pub const SERVICE_NAME: &str = "dm-ticket";
+
+const SERVICE_DISPLAY_NAME: &str = "dm-ticket 抢票守护进程";
+
+const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+
+// 服务运行参数, 由service_main读取
+static OPTIONS: OnceLock<DaemonOptions> = OnceLock::new();
+
+define_windows_service!(ffi_service_main, service_main);
+
+// 由服务管理器启动时调用, 阻塞直到服务停止
+pub fn start(options: DaemonOptions) -> Result<()> {
+    let _ = OPTIONS.set(options);
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
+    Ok(())
+}
+
+fn service_main(_args: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!("服务运行失败:{:?}", e);
+    }
+}
+
+fn set_status(
+    handle: &ServiceStatusHandle,
+    state: ServiceState,
+    exit_code: ServiceExitCode,
+) -> Result<()> {
+    let controls_accepted = match state {
+        ServiceState::Running => {
+            ServiceControlAccept::STOP
+                | ServiceControlAccept::SHUTDOWN
+                | ServiceControlAccept::PARAM_CHANGE
+        }
+        _ => ServiceControlAccept::empty(),
+    };
+    handle.set_service_status(ServiceStatus {
+        service_type: SERVICE_TYPE,
+        current_state: state,
+        controls_accepted,
+        exit_code,
+        checkpoint: 0,
+        wait_hint: Duration::from_secs(10),
+        process_id: None,
+    })?;
+    Ok(())
+}
+
+// 停止/关机时停止任务, 修改参数(sc control dm-ticket paramchange)时重新加载配置
+fn run_service() -> Result<()> {
+    let options = OPTIONS
+        .get()
+        .cloned()
+        .ok_or(anyhow!("未设置服务运行参数"))?;
+
+    let (tx, rx) = async_channel::unbounded();
+    let handler = move |event| match event {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            let _ = tx.try_send(Control::Stop);
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Paramchange => {
+            let _ = tx.try_send(Control::Reload);
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+    let handle = service_control_handler::register(SERVICE_NAME, handler)?;
+    set_status(&handle, ServiceState::Running, ServiceExitCode::Win32(0))?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let res = runtime.block_on(daemon::serve(options, rx, &SdNotify::default()));
+    if let Err(e) = &res {
+        error!("守护进程异常退出:{:?}", e);
+    }
+
+    let exit_code = match exit::status(&res).code() {
+        0 => ServiceExitCode::Win32(0),
+        code => ServiceExitCode::ServiceSpecific(code as u32),
+    };
+    set_status(&handle, ServiceState::Stopped, exit_code)?;
+    Ok(())
+}
+
+// 安装为开机自动启动的服务, 使用当前的配置目录
+pub fn install(scan_interval: u64) -> Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+    let info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(SERVICE_DISPLAY_NAME),
+        service_type: SERVICE_TYPE,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: env::current_exe()?,
+        launch_arguments: vec![
+            OsString::from("service"),
+            OsString::from("run"),
+            OsString::from("--scan-interval"),
+            OsString::from(scan_interval.to_string()),
+            OsString::from("--config-dir"),
+            config::config_dir().into_os_string(),
+        ],
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager.create_service(&info, ServiceAccess::CHANGE_CONFIG)?;
+    service.set_description("运行任务目录中的大麦抢票任务")?;
+    info!(
+        "服务:{}已安装, 配置目录:{}, 执行sc start {}启动",
+        SERVICE_NAME,
+        config::config_dir().display(),
+        SERVICE_NAME
+    );
+    Ok(())
+}
+
+// 停止并删除服务
+pub fn uninstall() -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let access = ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE;
+    let service = manager.open_service(SERVICE_NAME, access)?;
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        service.stop()?;
+    }
+    service.delete()?;
+    info!("服务:{}已删除", SERVICE_NAME);
+    Ok(())
+}
+
+// 服务以LocalSystem运行, 使用安装时的配置目录
+pub fn use_config_dir(dir: PathBuf) -> Result<()> {
+    env::set_var("CONFIG_DIR", dir);
+    config::reload()
+}
@@ -0,0 +1,35 @@
+use dm_ticket::account::{parse_cookie, Account, AccountStore};
+
+#[test]
+fn test_account_store_roundtrip() {
+    let path = std::env::temp_dir().join(format!("dm-accounts-{}.enc", std::process::id()));
+
+    let mut store = AccountStore::open(&path, "passphrase").unwrap();
+    store.upsert(Account::new("main", "cookie2=abc;", "nick"));
+    store.save().unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(!content.contains("cookie2=abc"));
+
+    let store = AccountStore::open(&path, "passphrase").unwrap();
+    assert_eq!(store.get("main").unwrap().cookie, "cookie2=abc;");
+
+    assert!(AccountStore::open(&path, "wrong").is_err());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_parse_cookie() {
+    assert_eq!(
+        parse_cookie("cookie2=abc; _tb_token_=x\n").unwrap(),
+        "cookie2=abc;_tb_token_=x;"
+    );
+    // 请求头格式及多行输入
+    assert_eq!(
+        parse_cookie("Cookie: cookie2=abc;\n'munb=123'\n").unwrap(),
+        "cookie2=abc;munb=123;"
+    );
+    assert!(parse_cookie(" \n").is_err());
+    assert!(parse_cookie("cookie2").is_err());
+}
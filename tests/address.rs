@@ -0,0 +1,44 @@
+use dm_ticket::models::address::{parse_address_list, select_address, AddAddressForm, NewAddress};
+use serde_json::json;
+
+#[test]
+fn test_parse_and_select_address() {
+    let data = json!({
+        "addressList": [
+            {"addressId": 1, "fullName": "张三", "mobile": "13800000000", "province": "上海", "city": "上海市", "addressDetail": "xx路1号"},
+            {"deliverId": "2", "fullName": "李四", "mobile": "13900000000", "defaultAddress": true},
+            {"fullName": "缺少ID"}
+        ]
+    });
+    let addresses = parse_address_list(&data);
+    assert_eq!(addresses.len(), 2);
+    assert_eq!(addresses[0].address, "上海上海市xx路1号");
+
+    assert_eq!(select_address(&addresses, None).unwrap().address_id, "2");
+    assert_eq!(select_address(&addresses, Some("1")).unwrap().name, "张三");
+    assert!(select_address(&addresses, Some("3")).is_none());
+}
+
+#[test]
+fn test_new_address() {
+    let mut address = NewAddress {
+        name: "张三".to_string(),
+        phone: "13800000000".to_string(),
+        province: "上海".to_string(),
+        city: "上海市".to_string(),
+        area: "浦东新区".to_string(),
+        detail: "xx路1号".to_string(),
+        is_default: true,
+    };
+    assert!(address.check().is_ok());
+
+    let form = AddAddressForm::build(&address);
+    assert_eq!(form["fullName"], "张三");
+    assert_eq!(form["defaultAddress"], true);
+
+    address.phone = "1380000000".to_string();
+    assert!(address.check().is_err());
+    address.phone = "13800000000".to_string();
+    address.detail = " ".to_string();
+    assert!(address.check().is_err());
+}
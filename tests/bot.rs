@@ -0,0 +1,27 @@
+use dm_ticket::clients::bot::{dingtalk_sign, Bot};
+
+#[test]
+fn test_dingtalk_sign() {
+    let sign = dingtalk_sign(1700000000000, "SECxxx").unwrap();
+    assert!(!sign.is_empty());
+    assert!(!sign.contains('+') && !sign.contains('/') && !sign.contains('='));
+    assert_eq!(sign, dingtalk_sign(1700000000000, "SECxxx").unwrap());
+}
+
+#[test]
+fn test_bot_url() {
+    let bot = Bot::DingTalk {
+        token: "abc".to_string(),
+        secret: Some("SECxxx".to_string()),
+    };
+    let url = bot.url(1700000000000).unwrap();
+    assert!(url.contains("access_token=abc&timestamp=1700000000000&sign="));
+
+    let bot = Bot::WeCom {
+        key: "key".to_string(),
+    };
+    assert_eq!(
+        bot.url(0).unwrap(),
+        "https://qyapi.weixin.qq.com/cgi-bin/webhook/send?key=key"
+    );
+}
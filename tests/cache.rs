@@ -0,0 +1,71 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use dm_ticket::clients::{cache::ResponseCache, endpoint::Api};
+use serde_json::json;
+
+fn cache() -> ResponseCache {
+    let mut ttls = HashMap::new();
+    ttls.insert(Api::TicketDetail.name(), Duration::from_secs(2));
+    ttls.insert(Api::PerformSkus.name(), Duration::ZERO);
+    ResponseCache::new(ttls)
+}
+
+#[test]
+fn test_cache_hit_and_expire() {
+    let cache = cache();
+    let now = Instant::now();
+    let data = json!({"itemId": "1"});
+    let value = json!({"ret": ["SUCCESS::调用成功"], "data": {"itemId": "1"}});
+
+    assert!(cache.get(Api::TicketDetail, &data, now).is_none());
+    cache.put(Api::TicketDetail, &data, &value, now);
+    assert_eq!(
+        cache.get(Api::TicketDetail, &data, now),
+        Some(value.clone())
+    );
+
+    // 参数不同时不命中
+    let other = json!({"itemId": "2"});
+    assert!(cache.get(Api::TicketDetail, &other, now).is_none());
+
+    // 超过缓存时长后重新请求
+    let later = now + Duration::from_secs(3);
+    assert!(cache.get(Api::TicketDetail, &data, later).is_none());
+
+    cache.put(Api::TicketDetail, &data, &value, now);
+    cache.clear();
+    assert!(cache.get(Api::TicketDetail, &data, now).is_none());
+}
+
+#[test]
+fn test_cache_skip() {
+    let cache = cache();
+    let now = Instant::now();
+    let data = json!({"itemId": "1"});
+
+    // 调用失败的响应不缓存
+    let failed = json!({"ret": ["FAIL_SYS_USER_VALIDATE::哎哟喂,被挤爆啦"], "data": {}});
+    cache.put(Api::TicketDetail, &data, &failed, now);
+    assert!(cache.get(Api::TicketDetail, &data, now).is_none());
+
+    // 缓存时长为0及未配置的接口不缓存
+    let value = json!({"ret": ["SUCCESS::调用成功"], "data": {}});
+    assert!(cache.ttl(Api::PerformSkus).is_none());
+    cache.put(Api::PerformSkus, &data, &value, now);
+    assert!(cache.get(Api::PerformSkus, &data, now).is_none());
+    cache.put(Api::BuildOrder, &data, &value, now);
+    assert!(cache.get(Api::BuildOrder, &data, now).is_none());
+}
+
+#[test]
+fn test_parse_ttls() {
+    let ttls = ResponseCache::parse_ttls("mtop.alibaba.damai.detail.getdetail=5, unknown=1,bad");
+    assert_eq!(ttls.len(), 1);
+    assert_eq!(ttls[Api::TicketDetail.name()], Duration::from_secs(5));
+
+    let ttls = ResponseCache::parse_ttls("mtop.alibaba.detail.subpage.getdetail=0.5");
+    assert_eq!(ttls[Api::PerformSkus.name()], Duration::from_millis(500));
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::task;
+use dm_ticket::calendar::{render_ics, SaleEvent};
+
+#[test]
+fn test_render_ics() {
+    // 2023-07-01 20:00:00 +08:00
+    let event = SaleEvent::from_task(&task(), 1688212800000, "上海 梅赛德斯奔驰文化中心");
+    let ics = render_ics(&[event], 15, 1688000000000);
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(ics.contains("DTSTART:20230701T120000Z\r\n"));
+    assert!(ics.contains("DTEND:20230701T123000Z\r\n"));
+    assert!(ics.contains("SUMMARY:开抢: 演唱会\r\n"));
+    assert!(ics.contains("TRIGGER:-PT15M\r\n"));
+    assert!(ics.contains("URL:https://m.damai.cn/damai/detail/item.html?itemId=721163957291\r\n"));
+
+    // 每行不超过75字节, 续行以空格开头
+    for line in ics.split("\r\n") {
+        assert!(line.len() <= 75, "{}", line);
+    }
+    let description = ics
+        .split("\r\n")
+        .skip_while(|line| !line.starts_with("DESCRIPTION:账号"))
+        .take_while(|line| line.starts_with("DESCRIPTION:") || line.starts_with(' '))
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<String>();
+    assert!(description.starts_with("DESCRIPTION:账号: nick\\n场次: 2023-09-01 19:30\\n"));
+    assert!(description.contains("\\, "));
+}
+
+#[test]
+fn test_render_empty_ics() {
+    let ics = render_ics(&[], 10, 0);
+    assert!(!ics.contains("BEGIN:VEVENT"));
+}
@@ -0,0 +1,50 @@
+// 集成测试共用数据, 各测试文件按需使用
+#![allow(dead_code)]
+
+use dm_ticket::{events::TicketEvent, models::task::Task};
+use serde_json::{json, Value};
+
+pub fn task_json() -> Value {
+    json!({
+        "nickname": "nick",
+        "ticket_id": "721163957291",
+        "ticket_name": "演唱会",
+        "ticket_perform_id": "211234567890",
+        "ticket_perform_name": "2023-09-01 19:30",
+        "ticket_perform_sku_id": "5012345678901",
+        "ticket_perform_sku_name": "看台380元",
+        "ticket_num": 1,
+        "priority_purchase_time": 0,
+        "request_time_offset": 0,
+        "retry_interval": 100,
+        "retry_times": 5,
+        "wait_for_submit_interval": 30,
+    })
+}
+
+pub fn task() -> Task {
+    serde_json::from_value(task_json()).unwrap()
+}
+
+// 第n次请求结束事件, 开始时间按请求次数递增
+pub fn finished(
+    stage: &'static str,
+    n: u64,
+    code: &str,
+    latency_ms: u64,
+    sale_offset_ms: Option<i64>,
+) -> TicketEvent {
+    TicketEvent::AttemptFinished {
+        stage,
+        n,
+        attempt_id: format!("1a2b3c4d-{}{}", &stage[..1], n),
+        started_at: 1_700_000_000_000 + n as i64 * 100,
+        sale_offset_ms,
+        latency_ms,
+        endpoint: format!(
+            "https://mtop.damai.cn/h5/mtop.trade.order.{}.h5/4.0/",
+            stage
+        ),
+        code: code.to_string(),
+    }
+}
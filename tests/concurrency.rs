@@ -0,0 +1,136 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use dm_ticket::clients::{
+    concurrency::{account_key, ConcurrencyLimiter, Semaphore},
+    endpoint::Api,
+    middleware::{Chain, ConcurrencyMiddleware, MtopRequest, Transport},
+};
+use serde_json::{json, Value};
+
+// 记录同时进行中的请求数的最大值
+#[derive(Clone, Default)]
+struct SlowTransport {
+    inflight: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Transport for SlowTransport {
+    async fn send(&self, _req: &MtopRequest) -> Result<Value> {
+        let inflight = self.inflight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(inflight, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        self.inflight.fetch_sub(1, Ordering::SeqCst);
+        Ok(json!({"ret": ["SUCCESS::调用成功"], "data": {}}))
+    }
+}
+
+fn request() -> MtopRequest {
+    MtopRequest {
+        api: Api::TicketDetail,
+        url: "https://mtop.damai.cn/h5/primary".to_string(),
+        params: json!({}),
+        data: json!({}),
+        timeout: Duration::from_secs(1),
+        prepared: None,
+    }
+}
+
+#[tokio::test]
+async fn test_semaphore() {
+    let semaphore = Semaphore::new(2);
+    let first = semaphore.acquire().await;
+    let _second = semaphore.acquire().await;
+    assert_eq!(semaphore.available(), 0);
+
+    // 没有空位时等待, 许可释放后归还
+    let waiting = tokio::time::timeout(Duration::from_millis(20), semaphore.acquire()).await;
+    assert!(waiting.is_err());
+    drop(first);
+    assert_eq!(semaphore.available(), 1);
+    let _third = semaphore.acquire().await;
+    assert_eq!(semaphore.available(), 0);
+}
+
+#[tokio::test]
+async fn test_limiter_per_account() {
+    let limiter = ConcurrencyLimiter::new(0, 1);
+    let _first = limiter.acquire("1001").await;
+    assert_eq!(limiter.available("1001"), Some(0));
+
+    // 其他账号不受影响
+    assert_eq!(limiter.available("1002"), Some(1));
+    let _other = limiter.acquire("1002").await;
+
+    let waiting = tokio::time::timeout(Duration::from_millis(20), limiter.acquire("1001")).await;
+    assert!(waiting.is_err());
+
+    // 未限制时直接通过
+    let limiter = ConcurrencyLimiter::new(0, 0);
+    assert_eq!(limiter.available("1001"), None);
+    let _a = limiter.acquire("1001").await;
+    let _b = limiter.acquire("1001").await;
+}
+
+#[tokio::test]
+async fn test_limiter_global() {
+    let limiter = ConcurrencyLimiter::new(1, 0);
+    let first = limiter.acquire("1001").await;
+    let waiting = tokio::time::timeout(Duration::from_millis(20), limiter.acquire("1002")).await;
+    assert!(waiting.is_err());
+    drop(first);
+    let _second = limiter.acquire("1002").await;
+}
+
+// 并发发送请求, 等待全部完成
+async fn run_all(chains: &[Chain]) {
+    let handles = chains
+        .iter()
+        .cloned()
+        .map(|chain| tokio::spawn(async move { chain.run(request()).await }))
+        .collect::<Vec<_>>();
+    for handle in handles {
+        assert!(handle.await.unwrap().is_ok());
+    }
+}
+
+#[tokio::test]
+async fn test_concurrency_middleware() {
+    let transport = SlowTransport::default();
+    let limiter = ConcurrencyLimiter::new(3, 2);
+    let chain = |account: &str| {
+        Chain::new(transport.clone()).with(ConcurrencyMiddleware {
+            limiter: limiter.clone(),
+            account: account.to_string(),
+        })
+    };
+
+    // 同一账号最多2个请求同时进行
+    let chains = (0..6).map(|_| chain("1001")).collect::<Vec<_>>();
+    run_all(&chains).await;
+    assert_eq!(transport.peak.load(Ordering::SeqCst), 2);
+
+    // 多个账号时受全局限制
+    transport.peak.store(0, Ordering::SeqCst);
+    let chains = ["1001", "1002", "1003"]
+        .iter()
+        .flat_map(|account| [chain(account), chain(account)])
+        .collect::<Vec<_>>();
+    run_all(&chains).await;
+    assert_eq!(transport.peak.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_account_key() {
+    assert_eq!(account_key("cookie2=abc;munb=2200;unb=2201"), "2200");
+    assert_eq!(account_key("cookie2=abc; unb=2201"), "2201");
+    assert_eq!(account_key("cookie2=abc;munb="), "cookie2=abc;munb=");
+}
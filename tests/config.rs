@@ -0,0 +1,75 @@
+use dm_ticket::config::{get, in_container, skip_webdriver, Settings};
+
+#[test]
+fn test_settings_load_and_apply() {
+    let path = std::env::temp_dir().join(format!("dm-settings-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        "settings_test_url = \"http://127.0.0.1:9515\"\nsettings_test_num = 10\nsettings_test_env = \"file\"\n",
+    )
+    .unwrap();
+
+    std::env::set_var("SETTINGS_TEST_ENV", "env");
+    let settings = Settings::load(&path).unwrap();
+    settings.apply();
+
+    assert_eq!(get("SETTINGS_TEST_URL").unwrap(), "http://127.0.0.1:9515");
+    assert_eq!(get("SETTINGS_TEST_NUM").unwrap(), "10");
+    // 环境变量优先于配置文件
+    assert_eq!(get("SETTINGS_TEST_ENV").unwrap(), "env");
+    assert!(get("SETTINGS_TEST_MISSING").is_err());
+
+    std::fs::write(&path, "settings_test_url = ").unwrap();
+    assert!(Settings::load(&path).is_err());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_settings_profile() {
+    let content = "webdriver_url = \"http://localhost:9515\"\n\
+                   notify_token = \"token\"\n\
+                   \n\
+                   [profiles.home]\n\
+                   request_time_offset = 20\n\
+                   \n\
+                   [profiles.vps-shanghai]\n\
+                   WEBDRIVER_URL = \"http://10.0.0.2:9515\"\n";
+    let settings: Settings = toml::from_str(content).unwrap();
+    assert_eq!(settings.profiles(), ["home", "vps-shanghai"]);
+
+    // 未选择环境时忽略环境配置
+    let vars = settings.vars();
+    assert!(!vars.iter().any(|(key, _)| key == "PROFILES"));
+
+    // 环境中的配置项覆盖顶层的同名配置项, 键不区分大小写
+    let mut vars = settings.with_profile("vps-shanghai").unwrap().vars();
+    vars.sort();
+    assert_eq!(
+        vars,
+        [
+            ("NOTIFY_TOKEN".to_string(), "token".to_string()),
+            (
+                "WEBDRIVER_URL".to_string(),
+                "http://10.0.0.2:9515".to_string()
+            ),
+        ]
+    );
+
+    let settings: Settings = toml::from_str(content).unwrap();
+    assert!(settings.with_profile("office").is_err());
+}
+
+#[test]
+fn test_container_mode() {
+    std::env::set_var("CONTAINER_MODE", "true");
+    assert!(in_container());
+    std::env::set_var("CONTAINER_MODE", "FALSE");
+    assert!(!in_container());
+    std::env::remove_var("CONTAINER_MODE");
+
+    assert!(!skip_webdriver());
+    std::env::set_var("SKIP_WEBDRIVER", "true");
+    assert!(skip_webdriver());
+    std::env::remove_var("SKIP_WEBDRIVER");
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use dm_ticket::{
+    config::check_value,
+    config_check::{check_settings, check_task, check_task_file},
+    models::task::Task,
+};
+use serde_json::{json, Value};
+
+// 购票数量为2, 用于校验观演人/实名人数量
+fn task_json() -> Value {
+    let mut value = common::task_json();
+    value["ticket_num"] = json!(2);
+    value
+}
+
+fn task(patch: Value) -> Task {
+    let mut value = task_json();
+    for (key, v) in patch.as_object().unwrap() {
+        value[key] = v.clone();
+    }
+    serde_json::from_value(value).unwrap()
+}
+
+fn fields(issues: &[(&'static str, String)]) -> Vec<&'static str> {
+    issues.iter().map(|(field, _)| *field).collect()
+}
+
+#[test]
+fn test_check_value() {
+    assert_eq!(check_value("WEBDRIVER_URL", "http://localhost:9515"), None);
+    assert!(check_value("WEBDRIVER_URL", "localhost:9515").is_some());
+    assert!(check_value("REDIS_URL", "127.0.0.1:6379").is_some());
+    assert!(check_value("QUEUE_MAX_WAIT", "30s").is_some());
+    assert!(check_value("SALE_TIMEZONE", "Asia/Beijing").is_some());
+    assert!(check_value("TICK_LANG", "fr").is_some());
+    assert_eq!(check_value("NOTIFY_TOKEN", "anything"), None);
+}
+
+#[test]
+fn test_check_settings() {
+    let content = "webdriver_url = \"http://localhost:9515\"\n\
+                   redis_url = \"localhost\"\n\
+                   queue_max_wait = 3000\n\
+                   [proxy]\n\
+                   url = \"http://127.0.0.1\"\n";
+    let issues = check_settings("settings.toml", content);
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].field, "redis_url");
+    assert_eq!(issues[0].line, Some(2));
+    assert_eq!(
+        issues[0].to_string(),
+        "settings.toml:2: redis_url: localhost不是redis地址"
+    );
+    assert_eq!(issues[1].field, "proxy");
+
+    // 环境配置中的配置项定位到对应表中的行
+    let content = "redis_url = \"redis://127.0.0.1:6379/0\"\n\
+                   [profiles.home]\n\
+                   redis_url = \"redis://127.0.0.1\"\n\
+                   [profiles.vps]\n\
+                   redis_url = \"127.0.0.1\"\n";
+    let issues = check_settings("settings.toml", content);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].field, "profiles.vps.redis_url");
+    assert_eq!(issues[0].line, Some(5));
+
+    // 语法错误定位到所在行
+    let issues = check_settings("settings.toml", "a = 1\nb = = 2\n");
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, Some(2));
+}
+
+#[test]
+fn test_check_task() {
+    assert!(check_task(&task(json!({}))).is_empty());
+
+    // 观演人/实名人数量与购票数量不一致
+    let issues = check_task(&task(json!({"viewers": ["张三"]})));
+    assert_eq!(fields(&issues), ["viewers"]);
+    let issues = check_task(&task(json!({"real_names": [0, 0]})));
+    assert_eq!(fields(&issues), ["real_names", "real_names"]);
+
+    let issues = check_task(&task(json!({
+        "ticket_num": 0,
+        "backup_sku_ids": ["5012345678901"],
+        "timezone": "Mars/Base",
+        "cities": ["上海"],
+    })));
+    assert_eq!(
+        fields(&issues),
+        ["cities", "ticket_num", "backup_sku_ids", "timezone"]
+    );
+
+    // 巡演任务无需配置ID
+    let issues = check_task(&task(json!({
+        "ticket_id": "",
+        "ticket_perform_id": "",
+        "ticket_perform_sku_id": "",
+        "keyword": "巡演",
+        "cities": ["上海"],
+    })));
+    assert!(issues.is_empty());
+    let issues = check_task(&task(json!({"ticket_perform_sku_id": ""})));
+    assert_eq!(fields(&issues), ["ticket_perform_sku_id"]);
+}
+
+#[test]
+fn test_check_task_file() {
+    let now = 1_700_000_000_000_i64;
+    let mut task = task_json();
+    task["viewers"] = json!(["张三"]);
+    let armed = json!({
+        "task": task,
+        "account": "main",
+        "sale_start": now - 60 * 60 * 1000,
+        "armed_at": now - 2 * 60 * 60 * 1000,
+    });
+    let content = serde_json::to_string_pretty(&armed).unwrap();
+    let issues = check_task_file("a.json", &content, now);
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].field, "viewers");
+    let line = content
+        .lines()
+        .position(|l| l.contains("\"viewers\""))
+        .unwrap()
+        + 1;
+    assert_eq!(issues[0].line, Some(line));
+    assert_eq!(issues[1].field, "sale_start");
+
+    // 格式错误时返回解析错误所在行
+    let issues = check_task_file("a.json", "{\n  \"task\": 1\n}", now);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, Some(2));
+}
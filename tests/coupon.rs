@@ -0,0 +1,20 @@
+use dm_ticket::models::coupon::{best_coupon, parse_coupon_list};
+use serde_json::json;
+
+#[test]
+fn test_best_coupon() {
+    let data = json!({
+        "couponList": [
+            {"couponId": "1", "couponName": "满300减20", "discountAmount": "20", "thresholdAmount": "300"},
+            {"couponId": "2", "couponName": "满1000减100", "discountAmount": 100, "thresholdAmount": 1000},
+            {"couponId": "3", "couponName": "不可用", "discountAmount": "500", "canUse": false}
+        ]
+    });
+    let coupons = parse_coupon_list(&data);
+    assert_eq!(coupons.len(), 3);
+
+    assert_eq!(best_coupon(&coupons, Some(580.0)).unwrap().coupon_id, "1");
+    assert_eq!(best_coupon(&coupons, Some(1160.0)).unwrap().coupon_id, "2");
+    assert_eq!(best_coupon(&coupons, None).unwrap().coupon_id, "2");
+    assert!(best_coupon(&coupons, Some(100.0)).is_none());
+}
@@ -0,0 +1,54 @@
+use dm_ticket::models::credential::{is_phone, parse_rsa_key, CredentialLoginData};
+use serde_json::json;
+
+#[test]
+fn test_parse_rsa_key() {
+    let html = r#"<script>window.viewData = {"loginFormData":{"rsaModulus":"d3bcef1f00424f3261c89323fa8cdfa1","rsaExponent" : "10001"}};</script>"#;
+    let (modulus, exponent) = parse_rsa_key(html).unwrap();
+    assert_eq!(modulus, "d3bcef1f00424f3261c89323fa8cdfa1");
+    assert_eq!(exponent, "10001");
+    assert!(parse_rsa_key("<html></html>").is_none());
+}
+
+#[test]
+fn test_credential_login_data() {
+    let data: CredentialLoginData = serde_json::from_value(json!({
+        "loginResult": "success",
+        "st": "1abc",
+        "returnUrl": "https://passport.damai.cn/dologin.htm"
+    }))
+    .unwrap();
+    assert!(data.success() && !data.needs_verify());
+
+    let data: CredentialLoginData = serde_json::from_value(json!({
+        "iframeRedirect": true,
+        "iframeRedirectUrl": "https://passport.damai.cn/iv/identity_verify.htm"
+    }))
+    .unwrap();
+    assert!(!data.success() && data.needs_verify());
+
+    assert!(is_phone("13800000000"));
+    assert!(!is_phone("nickname"));
+}
+
+#[cfg(feature = "http-login")]
+#[test]
+fn test_encrypt_password() {
+    use dm_ticket::clients::login::encrypt_password;
+    use rsa::{traits::PublicKeyParts, Pkcs1v15Encrypt, RsaPrivateKey};
+
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 512).unwrap();
+    let key = (
+        private_key.n().to_str_radix(16),
+        private_key.e().to_str_radix(16),
+    );
+    let encrypted = encrypt_password("password", &key).unwrap();
+    assert_eq!(encrypted.len(), 128);
+
+    let bytes = (0..encrypted.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&encrypted[i..i + 2], 16).unwrap())
+        .collect::<Vec<u8>>();
+    let decrypted = private_key.decrypt(Pkcs1v15Encrypt, &bytes).unwrap();
+    assert_eq!(decrypted, b"password");
+}
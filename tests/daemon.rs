@@ -0,0 +1,49 @@
+#![cfg(unix)]
+
+use std::{env, fs, os::unix::net::UnixDatagram, process, time::Duration};
+
+use dm_ticket::daemon::{PidFile, SdNotify};
+
+#[test]
+fn test_pid_file() {
+    let path = env::temp_dir().join(format!("dm-ticket-test-{}.pid", process::id()));
+    let pid_file = PidFile::create(&path).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap().trim(),
+        process::id().to_string()
+    );
+
+    // 本进程仍在运行
+    #[cfg(target_os = "linux")]
+    assert!(PidFile::create(&path).is_err());
+
+    drop(pid_file);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_sd_notify() {
+    let path = env::temp_dir().join(format!("dm-ticket-test-{}.sock", process::id()));
+    let _ = fs::remove_file(&path);
+    let server = UnixDatagram::bind(&path).unwrap();
+    server
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+
+    env::set_var("NOTIFY_SOCKET", &path);
+    SdNotify::from_env().notify("READY=1");
+    let mut buf = [0; 64];
+    let n = server.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"READY=1");
+
+    env::set_var("WATCHDOG_USEC", "20000000");
+    env::set_var("WATCHDOG_PID", process::id().to_string());
+    assert_eq!(SdNotify::watchdog_interval(), Some(Duration::from_secs(10)));
+    env::set_var("WATCHDOG_PID", "1");
+    assert_eq!(SdNotify::watchdog_interval(), None);
+
+    env::remove_var("NOTIFY_SOCKET");
+    env::remove_var("WATCHDOG_USEC");
+    env::remove_var("WATCHDOG_PID");
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,43 @@
+use dm_ticket::clients::endpoint::{EndpointRegistry, Http2Mode};
+
+#[test]
+fn test_http2_mode() {
+    assert_eq!("".parse::<Http2Mode>().unwrap(), Http2Mode::Prior);
+    assert_eq!("prior".parse::<Http2Mode>().unwrap(), Http2Mode::Prior);
+    assert_eq!("ALPN".parse::<Http2Mode>().unwrap(), Http2Mode::Alpn);
+    assert_eq!("off".parse::<Http2Mode>().unwrap(), Http2Mode::Off);
+    assert!("h3".parse::<Http2Mode>().is_err());
+}
+
+#[test]
+fn test_http1_fallback() {
+    // https网关使用http2时另外构建http1.1客户端用于回退
+    let registry = EndpointRegistry::default();
+    assert!(registry.http1_builder().is_some());
+    assert!(registry.client_builder().build().is_ok());
+
+    let registry = EndpointRegistry {
+        http2: Http2Mode::Off,
+        ..Default::default()
+    };
+    assert!(registry.http1_builder().is_none());
+
+    // http网关(如本地模拟服务)不使用http2
+    let registry = EndpointRegistry {
+        gateway: "http://127.0.0.1:8080".to_string(),
+        ..Default::default()
+    };
+    assert!(registry.http1_builder().is_none());
+}
+
+#[test]
+fn test_race_builder() {
+    let registry = EndpointRegistry::default();
+    assert!(registry.race_builder().unwrap().is_none());
+
+    let registry = EndpointRegistry {
+        race_proxy: Some("http://127.0.0.1:7890".to_string()),
+        ..Default::default()
+    };
+    assert!(registry.race_builder().unwrap().unwrap().build().is_ok());
+}
@@ -0,0 +1,138 @@
+use dm_ticket::{
+    events::{error_code, lead_desc, TicketEvent},
+    ticket::{parse_reminders, reschedule},
+};
+
+#[test]
+fn test_error_code() {
+    assert_eq!(
+        error_code(r#"["B-00203-200-008::对不起，您选购的商品库存不足，请重新选购"]"#),
+        "B-00203-200-008"
+    );
+    assert_eq!(error_code("RGV587_ERROR::SM::哎哟喂"), "RGV587_ERROR");
+}
+
+#[test]
+fn test_event_json() {
+    let event = TicketEvent::AttemptFailed {
+        stage: "build",
+        n: 1,
+        attempt_id: "1a2b3c4d-b1".to_string(),
+        code: "B-00203-200-008".to_string(),
+    };
+    let value = serde_json::to_value(&event).unwrap();
+    assert_eq!(value["event"], event.name());
+    assert_eq!(value["code"], "B-00203-200-008");
+    assert_eq!(value["attempt_id"], "1a2b3c4d-b1");
+
+    let event = TicketEvent::ChallengeFailed {
+        stage: "build",
+        n: 2,
+        attempt_id: "1a2b3c4d-b2".to_string(),
+        error: "需要完成滑块验证".to_string(),
+    };
+    let value = serde_json::to_value(&event).unwrap();
+    assert_eq!(value["event"], "challenge_failed");
+    assert_eq!(value["attempt_id"], "1a2b3c4d-b2");
+}
+
+#[test]
+fn test_run_report() {
+    use dm_ticket::events::RunReport;
+
+    let mut report = RunReport::default();
+    let events = [
+        TicketEvent::AttemptStarted {
+            stage: "build",
+            n: 1,
+            attempt_id: "1a2b3c4d-b1".to_string(),
+        },
+        TicketEvent::AttemptFailed {
+            stage: "build",
+            n: 1,
+            attempt_id: "1a2b3c4d-b1".to_string(),
+            code: "B-00203-200-008".to_string(),
+        },
+        TicketEvent::AttemptStarted {
+            stage: "build",
+            n: 2,
+            attempt_id: "1a2b3c4d-b2".to_string(),
+        },
+        TicketEvent::AttemptStarted {
+            stage: "submit",
+            n: 1,
+            attempt_id: "1a2b3c4d-s1".to_string(),
+        },
+        TicketEvent::OrderCreated {
+            order_id: Some("1001".to_string()),
+            pay_deadline: None,
+            elapsed_ms: 20,
+        },
+        TicketEvent::RunFinished {
+            success: true,
+            error: None,
+        },
+    ];
+    for event in events.iter() {
+        report.record(event);
+    }
+    assert_eq!(report.build_attempts, 2);
+    assert_eq!(report.submit_attempts, 1);
+    assert_eq!(report.errors, vec![("B-00203-200-008".to_string(), 1)]);
+    assert_eq!(report.order_id.as_deref(), Some("1001"));
+    assert!(report.success);
+}
+
+#[test]
+fn test_sale_reminder() {
+    assert_eq!(
+        parse_reminders("1m, 24h,10m").unwrap(),
+        vec![86400, 600, 60]
+    );
+    assert_eq!(parse_reminders("30s,30s").unwrap(), vec![30]);
+    assert!(parse_reminders("").unwrap().is_empty());
+    assert!(parse_reminders("10").is_err());
+    assert!(parse_reminders("10分").is_err());
+
+    assert_eq!(lead_desc(86400), "24小时");
+    assert_eq!(lead_desc(600), "10分钟");
+    assert_eq!(lead_desc(90), "90秒");
+
+    let event = TicketEvent::SaleReminder {
+        lead_secs: 60,
+        sale_start: 1688212800000,
+        session_ok: Some(true),
+        clock_offset_ms: Some(-12),
+    };
+    assert_eq!(event.name(), "sale_reminder");
+    assert_eq!(
+        event.message(),
+        "距离开抢还有1分钟, 登录状态:有效, 服务器时钟偏差:-12毫秒, 已就绪"
+    );
+}
+
+#[test]
+fn test_sale_time_changed() {
+    // 延期/提前时保留请求时间偏移量
+    let old_sell = 1688212800000;
+    let start = old_sell + 100;
+    assert_eq!(
+        reschedule(start, old_sell, old_sell + 3_600_000),
+        Some(start + 3_600_000)
+    );
+    assert_eq!(
+        reschedule(start, old_sell, old_sell - 60_000),
+        Some(start - 60_000)
+    );
+
+    // 未变更或未返回开售时间时不重新设定
+    assert_eq!(reschedule(start, old_sell, old_sell), None);
+    assert_eq!(reschedule(start, old_sell, 0), None);
+
+    let event = TicketEvent::SaleTimeChanged {
+        old_start: start,
+        new_start: start + 3_600_000,
+    };
+    assert_eq!(event.name(), "sale_time_changed");
+    assert!(event.message().starts_with("开售时间变更"));
+}
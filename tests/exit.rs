@@ -0,0 +1,59 @@
+use anyhow::anyhow;
+use dm_ticket::{
+    errors::{AccountError, MtopError, PlatformError, ViewerError},
+    exit::{self, ExitStatus},
+};
+
+#[test]
+fn test_exit_codes() {
+    assert_eq!(ExitStatus::Success.code(), 0);
+    assert_eq!(ExitStatus::Failure.code(), 1);
+    assert_eq!(ExitStatus::SoldOut.code(), 2);
+    assert_eq!(ExitStatus::LoginFailed.code(), 3);
+    assert_eq!(ExitStatus::RiskControl.code(), 4);
+    assert_eq!(ExitStatus::ConfigError.code(), 5);
+    assert_eq!(ExitStatus::Interrupted.code(), 130);
+}
+
+#[test]
+fn test_exit_status_from_error() {
+    let status = |e: anyhow::Error| ExitStatus::from_error(&e);
+
+    assert_eq!(
+        status(PlatformError::SessionExpired.into()),
+        ExitStatus::LoginFailed
+    );
+    let risk = PlatformError::RiskControl {
+        message: "RGV587_ERROR".to_string(),
+    };
+    assert_eq!(status(risk.into()), ExitStatus::RiskControl);
+    assert_eq!(status(MtopError::StockEmpty.into()), ExitStatus::SoldOut);
+    assert_eq!(
+        status(MtopError::RateLimited.into()),
+        ExitStatus::RiskControl
+    );
+
+    let account = AccountError::NotFound {
+        name: "a".to_string(),
+    };
+    assert_eq!(status(account.into()), ExitStatus::ConfigError);
+    let viewer = anyhow::Error::from(ViewerError::NotEnough { need: 2, have: 1 });
+    assert_eq!(
+        status(viewer.context("校验观演人")),
+        ExitStatus::ConfigError
+    );
+    assert_eq!(status(anyhow!("网络错误")), ExitStatus::Failure);
+}
+
+#[test]
+fn test_outcome_success_wins() {
+    assert_eq!(exit::status(&Ok(())), ExitStatus::Success);
+
+    exit::record(ExitStatus::SoldOut);
+    assert_eq!(exit::status(&Ok(())), ExitStatus::SoldOut);
+
+    exit::record(ExitStatus::Success);
+    exit::record(ExitStatus::RiskControl);
+    assert_eq!(exit::outcome(), Some(ExitStatus::Success));
+    assert_eq!(exit::status(&Err(anyhow!("失败"))), ExitStatus::Failure);
+}
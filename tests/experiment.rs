@@ -0,0 +1,110 @@
+mod common;
+
+use common::finished;
+use dm_ticket::{
+    events::TicketEvent,
+    experiment::{self, parse_variants, summary, TimingVariant, VariantStats},
+    models::task::Task,
+};
+
+fn task(nickname: &str, ticket_id: &str) -> Task {
+    Task {
+        nickname: nickname.to_string(),
+        ticket_id: ticket_id.to_string(),
+        ..common::task()
+    }
+}
+
+fn run_finished(success: bool) -> TicketEvent {
+    TicketEvent::RunFinished {
+        success,
+        error: None,
+    }
+}
+
+#[test]
+fn test_parse_variants() {
+    let variants =
+        parse_variants("early:offset=-10; late:offset=30,interval=50,retries=3;base").unwrap();
+    assert_eq!(variants.len(), 3);
+    assert_eq!(variants[0].request_time_offset, Some(-10));
+    assert_eq!(
+        variants[1],
+        TimingVariant {
+            name: "late".to_string(),
+            request_time_offset: Some(30),
+            retry_interval: Some(50),
+            retry_times: Some(3),
+            wait_for_submit_interval: None,
+        }
+    );
+    assert_eq!(variants[2].retry_interval, None);
+
+    assert!(parse_variants("a:offset=1;a:offset=2").is_err());
+    assert!(parse_variants("a:offset=x").is_err());
+    assert!(parse_variants("a:delay=1").is_err());
+    assert!(parse_variants(":offset=1").is_err());
+}
+
+#[test]
+fn test_assign_and_apply() {
+    let variants = parse_variants("early:offset=0;late:offset=30,submit_wait=10").unwrap();
+    let mut tasks = [
+        task("a", "1"),
+        task("b", "1"),
+        task("c", "2"),
+        task("d", "1"),
+    ];
+    tasks[3].variant = Some("late".to_string());
+    experiment::assign(tasks.iter_mut(), &variants);
+    let assigned = tasks
+        .iter()
+        .map(|t| t.variant.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    // 同一门票按顺序轮流分配, 已指定的分组保留
+    assert_eq!(assigned, ["early", "late", "early", "late"]);
+
+    experiment::apply(&mut tasks[1], &variants).unwrap();
+    assert_eq!(tasks[1].variant.as_deref(), Some("late"));
+    assert_eq!(tasks[1].request_time_offset, 30);
+    assert_eq!(tasks[1].wait_for_submit_interval, 10);
+    assert_eq!(tasks[1].retry_interval, 100);
+
+    let mut unknown = task("e", "1");
+    unknown.variant = Some("missing".to_string());
+    assert!(experiment::apply(&mut unknown, &variants).is_err());
+    let mut plain = task("f", "1");
+    experiment::apply(&mut plain, &variants).unwrap();
+    assert_eq!(plain.variant, None);
+}
+
+#[test]
+fn test_variant_stats_summary() {
+    let mut early = VariantStats {
+        variant: "early".to_string(),
+        ..Default::default()
+    };
+    early.record(&finished("build", 1, "RGV587_ERROR", 40, Some(-20)));
+    early.record(&run_finished(false));
+
+    let mut late = VariantStats {
+        variant: "late".to_string(),
+        ..Default::default()
+    };
+    late.record(&finished("build", 1, "SUCCESS", 30, Some(10)));
+    late.record(&finished("submit", 1, "SUCCESS", 50, Some(60)));
+    late.record(&run_finished(true));
+
+    assert_eq!(early.failures, 1);
+    assert_eq!(late.attempts, 2);
+    assert_eq!(late.avg_latency_ms(), 40);
+    assert_eq!(late.best_offset_ms, Some(60));
+    assert_eq!(late.success_rate(), 1.0);
+
+    let text = summary(&[late, early]);
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[1], "late\t1\t1\t2\t0\t40毫秒\t60毫秒");
+    assert_eq!(lines[2], "early\t1\t0\t1\t1\t40毫秒\t-");
+    assert_eq!(lines[3], "表现最好的分组:late, 成功率:100%");
+}
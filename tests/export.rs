@@ -0,0 +1,90 @@
+mod common;
+
+use common::{finished, task};
+use dm_ticket::{
+    events::TicketEvent,
+    export::{append, render, AttemptRecord, ExportFormat},
+};
+
+fn records() -> Vec<AttemptRecord> {
+    let task = task();
+    [
+        finished("build", 1, "RGV587_ERROR", 35, Some(-20)),
+        finished("build", 2, "SUCCESS", 35, None),
+    ]
+    .iter()
+    .filter_map(|event| AttemptRecord::from_event(&task, "1a2b3c4d", event))
+    .collect()
+}
+
+#[test]
+fn test_attempt_record_from_event() {
+    let task = task();
+    let event = TicketEvent::AttemptStarted {
+        stage: "build",
+        n: 1,
+        attempt_id: "1a2b3c4d-b1".to_string(),
+    };
+    assert!(AttemptRecord::from_event(&task, "1a2b3c4d", &event).is_none());
+
+    let records = records();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].timestamp, 1_700_000_000_100);
+    assert_eq!(records[0].sale_offset_ms, Some(-20));
+    assert_eq!(records[1].code, "SUCCESS");
+}
+
+#[test]
+fn test_render_csv_and_jsonl() {
+    let records = records();
+
+    let csv = render(&records, ExportFormat::Csv, true).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("run_id,nickname,ticket_id,stage,n,"));
+    assert_eq!(
+        lines[1],
+        "1a2b3c4d,nick,721163957291,build,1,1a2b3c4d-b1,1700000000100,-20,35,\
+         https://mtop.damai.cn/h5/mtop.trade.order.build.h5/4.0/,RGV587_ERROR,"
+    );
+    // 未公布开售时间时偏移为空
+    assert!(lines[2].contains(",1700000000200,,35,"));
+
+    let csv = render(&records, ExportFormat::Csv, false).unwrap();
+    assert_eq!(csv.lines().count(), 2);
+
+    let jsonl = render(&records, ExportFormat::Jsonl, true).unwrap();
+    let rows = jsonl
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["sale_offset_ms"], -20);
+    assert!(rows[1]["sale_offset_ms"].is_null());
+}
+
+#[test]
+fn test_csv_escape() {
+    let mut record = records().remove(0);
+    record.nickname = "a,\"b\"".to_string();
+    assert!(record.csv_row().contains(",\"a,\"\"b\"\"\","));
+}
+
+#[test]
+fn test_append_writes_header_once() {
+    let path = std::env::temp_dir().join(format!("dm-attempts-{}.csv", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(ExportFormat::from_path(&path), ExportFormat::Csv);
+    assert_eq!(
+        ExportFormat::from_path(std::path::Path::new("a.jsonl")),
+        ExportFormat::Jsonl
+    );
+
+    append(&path, &records()).unwrap();
+    append(&path, &records()).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(content.lines().count(), 5);
+    assert_eq!(content.matches("run_id,").count(), 1);
+
+    let _ = std::fs::remove_file(&path);
+}
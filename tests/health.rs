@@ -0,0 +1,108 @@
+mod common;
+
+use std::time::Duration;
+
+use common::task;
+
+use dm_ticket::{
+    events::TicketEvent,
+    health::{Health, HealthServer},
+};
+
+fn checked(session_ok: bool, clock_offset_ms: i64) -> TicketEvent {
+    TicketEvent::SessionChecked {
+        session_ok,
+        clock_offset_ms: Some(clock_offset_ms),
+    }
+}
+
+#[test]
+fn test_health_report() {
+    let task = task();
+    let health = Health::default();
+    health.scanned(Duration::from_secs(30), 1, 0, None);
+    health.record(
+        &task,
+        &TicketEvent::ScheduleArmed {
+            sale_start: 1_700_000_000_000,
+        },
+    );
+    health.record(&task, &checked(true, 20));
+
+    let report = health.report(1000);
+    assert!(report.live && report.ready);
+    assert_eq!(report.scheduler.running, 1);
+    assert_eq!(report.tasks.len(), 1);
+    assert_eq!(report.tasks[0].sale_start, Some(1_700_000_000_000));
+    assert_eq!(report.tasks[0].clock_offset_ms, Some(20));
+
+    // 登录失效或时钟偏差过大时未就绪, 但仍存活
+    health.record(&task, &checked(false, -1500));
+    let report = health.report(1000);
+    assert!(report.live && !report.ready);
+    assert_eq!(report.reasons.len(), 2);
+    assert_eq!(report.tasks.len(), 1);
+
+    health.record(
+        &task,
+        &TicketEvent::RunFinished {
+            success: false,
+            error: None,
+        },
+    );
+    let report = health.report(1000);
+    assert!(report.ready);
+    assert!(report.tasks.is_empty());
+
+    health.scanned(
+        Duration::from_secs(30),
+        0,
+        1,
+        Some("读取任务目录失败".to_string()),
+    );
+    let report = health.report(1000);
+    assert!(report.live && !report.ready);
+    assert_eq!(report.reasons.len(), 2);
+}
+
+#[test]
+fn test_health_stale_scan() {
+    let health = Health::default();
+    // 未以守护进程运行时不检查调度
+    assert!(health.report(1000).live);
+
+    health.scanned(Duration::from_millis(1), 0, 0, None);
+    std::thread::sleep(Duration::from_millis(20));
+    let report = health.report(1000);
+    assert!(!report.live && !report.ready);
+    assert!(report.reasons[0].starts_with("调度已停止"));
+}
+
+#[tokio::test]
+async fn test_health_server() {
+    let health: &'static Health = Box::leak(Box::default());
+    health.scanned(Duration::from_secs(30), 0, 0, None);
+    let server = HealthServer::start("127.0.0.1:0", health).await.unwrap();
+
+    let res = reqwest::get(format!("{}/healthz", server.url))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(body["live"], true);
+
+    health.record(&task(), &checked(false, 0));
+    let res = reqwest::get(format!("{}/readyz", server.url))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 503);
+    let res = reqwest::get(format!("{}/healthz", server.url))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let res = reqwest::get(format!("{}/metrics", server.url))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{finished, task};
+use dm_ticket::{
+    events::TicketEvent,
+    history::{RunRecord, RunStore, ShowStats},
+};
+
+fn run(run_id: &str, finished_at: i64, events: &[TicketEvent]) -> RunRecord {
+    let mut record = RunRecord::default();
+    for event in events {
+        record.record(event);
+    }
+    record.finish(&task(), run_id.to_string(), finished_at);
+    record
+}
+
+#[test]
+fn test_run_record() {
+    let record = run(
+        "1a2b3c4d",
+        1_700_000_001_000,
+        &[
+            TicketEvent::ScheduleArmed {
+                sale_start: 1_700_000_000_000,
+            },
+            finished("build", 1, "RGV587_ERROR", 80, Some(-10)),
+            finished("build", 1, "B-00203-200-008", 45, Some(230)),
+            finished("build", 1, "B-00203-200-008", 60, Some(120)),
+            TicketEvent::RunFinished {
+                success: false,
+                error: Some("库存不足".to_string()),
+            },
+        ],
+    );
+    assert_eq!(record.sale_start, Some(1_700_000_000_000));
+    assert_eq!(record.attempts, 3);
+    assert_eq!(
+        record.errors,
+        [
+            ("RGV587_ERROR".to_string(), 1),
+            ("B-00203-200-008".to_string(), 2)
+        ]
+    );
+    assert_eq!(record.best_latency_ms, Some(45));
+    assert_eq!(record.first_sold_out_ms, Some(120));
+    assert!(!record.success);
+    assert_eq!(record.ticket_id, "721163957291");
+    assert_eq!(record.file_name(), "1a2b3c4d.json");
+}
+
+#[test]
+fn test_show_stats() {
+    let runs = [
+        run(
+            "a",
+            1,
+            &[
+                finished("build", 1, "B-00203-200-008", 50, Some(300)),
+                finished("build", 1, "RGV587_ERROR", 70, Some(20)),
+                finished("build", 1, "RGV587_ERROR", 70, Some(40)),
+            ],
+        ),
+        run(
+            "b",
+            2,
+            &[
+                finished("build", 1, "SUCCESS", 30, Some(5)),
+                finished("build", 1, "B-00203-200-008", 90, Some(180)),
+                TicketEvent::RunFinished {
+                    success: true,
+                    error: None,
+                },
+            ],
+        ),
+    ];
+    let stats = ShowStats::from_runs("721163957291", &runs);
+    assert_eq!(stats.runs, 2);
+    assert_eq!(stats.successes, 1);
+    assert_eq!(stats.attempts, 5);
+    assert_eq!(
+        stats.errors,
+        [
+            ("B-00203-200-008".to_string(), 2),
+            ("RGV587_ERROR".to_string(), 2)
+        ]
+    );
+    assert_eq!(stats.best_latency_ms, Some(30));
+    assert_eq!(stats.first_sold_out_ms, Some(180));
+    assert_eq!(stats.last_run_at, Some(2));
+    assert!(stats.text().contains("开售后首次库存不足: 180毫秒"));
+
+    let empty = ShowStats::from_runs("721163957291", &[]);
+    assert_eq!(empty.runs, 0);
+    assert!(empty.text().contains("最短耗时: -"));
+}
+
+#[test]
+fn test_run_store() {
+    let dir = std::env::temp_dir().join(format!("dm-runs-{}", std::process::id()));
+    let store = RunStore::new(&dir);
+    assert!(store.list("721163957291").unwrap().is_empty());
+
+    store.save(&run("b", 2, &[])).unwrap();
+    store.save(&run("a", 1, &[])).unwrap();
+    std::fs::write(dir.join("721163957291").join("broken.json"), "{").unwrap();
+
+    let runs = store.list("721163957291").unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].run_id, "a");
+    assert!(store.list("0").unwrap().is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
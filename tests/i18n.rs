@@ -0,0 +1,50 @@
+use dm_ticket::{events::TicketEvent, i18n::Lang};
+
+#[test]
+fn test_parse_lang() {
+    assert_eq!("en".parse::<Lang>().unwrap(), Lang::En);
+    assert_eq!("en-US".parse::<Lang>().unwrap(), Lang::En);
+    assert_eq!("zh_CN".parse::<Lang>().unwrap(), Lang::Zh);
+    assert_eq!("".parse::<Lang>().unwrap(), Lang::Zh);
+    assert!("fr".parse::<Lang>().is_err());
+}
+
+#[test]
+fn test_translate() {
+    assert_eq!(
+        Lang::En.translate("请选择登录方式"),
+        "Select a login method"
+    );
+    assert_eq!(Lang::Zh.translate("请选择登录方式"), "请选择登录方式");
+    // 未收录的文本原样返回
+    assert_eq!(Lang::En.translate("未收录"), "未收录");
+}
+
+#[test]
+fn test_event_message_en() {
+    let event = TicketEvent::SaleReminder {
+        lead_secs: 600,
+        sale_start: 1688212800000,
+        session_ok: Some(true),
+        clock_offset_ms: Some(-12),
+    };
+    assert_eq!(
+        event.message_in(Lang::En),
+        "10m until the sale, login: valid, server clock offset: -12ms, ready"
+    );
+
+    let event = TicketEvent::AttemptFailed {
+        stage: "build",
+        n: 2,
+        attempt_id: "1a2b3c4d-build-2".to_string(),
+        code: "B-00203-200-008".to_string(),
+    };
+    assert_eq!(
+        event.message_in(Lang::En),
+        "Build order attempt #2 failed: B-00203-200-008"
+    );
+    assert_eq!(
+        event.message_in(Lang::Zh),
+        "第2次生成订单失败:B-00203-200-008"
+    );
+}
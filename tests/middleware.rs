@@ -0,0 +1,247 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use dm_ticket::clients::{
+    endpoint::{Api, EndpointRegistry},
+    middleware::{
+        Chain, HttpTransport, MetricsMiddleware, MtopRequest, NetworkPath, PreparedBody,
+        RetryMiddleware, SignMiddleware, Transport,
+    },
+};
+use serde_json::{json, Value};
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+// 记录收到的请求, 前几次返回网络错误
+#[derive(Clone, Default)]
+struct FakeTransport {
+    fails: usize,
+    timeout: bool,
+    calls: Arc<AtomicUsize>,
+    requests: Arc<Mutex<Vec<MtopRequest>>>,
+}
+
+#[async_trait]
+impl Transport for FakeTransport {
+    async fn send(&self, req: &MtopRequest) -> Result<Value> {
+        self.requests.lock().unwrap().push(req.clone());
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.fails {
+            match self.timeout {
+                // 连接成功但不返回响应, 构造超时错误
+                true => {
+                    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+                    reqwest::Client::builder()
+                        .timeout(Duration::from_millis(50))
+                        .build()?
+                        .get(format!("http://{}", listener.local_addr()?))
+                        .send()
+                        .await?;
+                }
+                // 连接不存在的端口, 构造网络错误
+                false => {
+                    reqwest::get("http://127.0.0.1:1").await?;
+                }
+            }
+        }
+        Ok(json!({"ret": ["SUCCESS::调用成功"], "data": {}}))
+    }
+}
+
+fn request(api: Api) -> MtopRequest {
+    MtopRequest {
+        api,
+        url: "https://mtop.damai.cn/h5/primary".to_string(),
+        params: json!({"t": "1700000000000", "appKey": "12574478"}),
+        data: json!({"itemId": "1"}),
+        timeout: Duration::from_secs(1),
+        prepared: None,
+    }
+}
+
+#[tokio::test]
+async fn test_sign_middleware() {
+    let transport = FakeTransport::default();
+    let chain = Chain::new(transport.clone()).with(SignMiddleware {
+        token: "token".to_string(),
+        token_client: None,
+    });
+    chain.run(request(Api::TicketDetail)).await.unwrap();
+
+    let requests = transport.requests.lock().unwrap();
+    let expected = format!(
+        "{:?}",
+        md5::compute(r#"token&1700000000000&12574478&{"itemId":"1"}"#)
+    );
+    assert_eq!(requests[0].params["sign"], expected);
+}
+
+#[test]
+fn test_prepared_body() {
+    let params = json!({"api": "mtop.trade.order.build.h5", "v": "4.0", "appKey": "12574478"});
+    let data = json!({"buyParam": "1_1_2", "exParams": "{\"channel\":\"damai_app\"}"});
+    let prepared = PreparedBody::build(&params, &data).unwrap();
+    assert_eq!(prepared.data, serde_json::to_string(&data).unwrap());
+    assert!(prepared
+        .form
+        .starts_with("data=%7B%22buyParam%22%3A%221_1_2%22"));
+    assert_eq!(
+        prepared.query,
+        "api=mtop.trade.order.build.h5&appKey=12574478&v=4.0"
+    );
+
+    // 只补上时间戳/签名
+    let mut params = params;
+    params["t"] = "1700000000000".into();
+    params["sign"] = "abc".into();
+    assert_eq!(
+        prepared.query(&params),
+        "api=mtop.trade.order.build.h5&appKey=12574478&v=4.0&sign=abc&t=1700000000000"
+    );
+}
+
+#[tokio::test]
+async fn test_sign_prepared_request() {
+    let transport = FakeTransport::default();
+    let chain = Chain::new(transport.clone()).with(SignMiddleware {
+        token: "token".to_string(),
+        token_client: None,
+    });
+    let mut req = request(Api::BuildOrder);
+    req.prepared = Some(Arc::new(
+        PreparedBody::build(&req.params, &req.data).unwrap(),
+    ));
+    chain.run(req).await.unwrap();
+    chain.run(request(Api::BuildOrder)).await.unwrap();
+
+    // 预先编码的data与实时序列化的签名一致
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests[0].params["sign"], requests[1].params["sign"]);
+}
+
+#[tokio::test]
+async fn test_retry_middleware_failover() {
+    let transport = FakeTransport {
+        fails: 1,
+        ..Default::default()
+    };
+    let endpoints = EndpointRegistry {
+        failover_gateway: Some("https://backup.example.com".to_string()),
+        ..Default::default()
+    };
+    let chain = Chain::new(transport.clone()).with(RetryMiddleware {
+        endpoints: endpoints.clone(),
+        retries: 0,
+    });
+
+    chain.run(request(Api::CreateOrder)).await.unwrap();
+    let requests = transport.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[1].url,
+        endpoints.failover_url(Api::CreateOrder).unwrap()
+    );
+
+    // 查询接口不切换网关, 未配置重试时直接返回错误
+    let transport = FakeTransport {
+        fails: 1,
+        ..Default::default()
+    };
+    let chain = Chain::new(transport).with(RetryMiddleware {
+        endpoints: endpoints.clone(),
+        retries: 0,
+    });
+    assert!(chain.run(request(Api::TicketDetail)).await.is_err());
+
+    // 提交订单超时后服务器可能已创建订单, 不切换网关也不重试
+    let transport = FakeTransport {
+        fails: 1,
+        timeout: true,
+        ..Default::default()
+    };
+    let chain = Chain::new(transport.clone()).with(RetryMiddleware {
+        endpoints: endpoints.clone(),
+        retries: 1,
+    });
+    assert!(chain.run(request(Api::CreateOrder)).await.is_err());
+    assert_eq!(transport.requests.lock().unwrap().len(), 1);
+
+    // 生成订单超时可切换网关
+    let transport = FakeTransport {
+        fails: 1,
+        timeout: true,
+        ..Default::default()
+    };
+    let chain = Chain::new(transport.clone()).with(RetryMiddleware {
+        endpoints,
+        retries: 0,
+    });
+    chain.run(request(Api::BuildOrder)).await.unwrap();
+    assert_eq!(transport.requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_metrics_middleware() {
+    let metrics = MetricsMiddleware::default();
+    let transport = FakeTransport {
+        fails: 1,
+        ..Default::default()
+    };
+    let chain = Chain::new(transport).with(metrics.clone());
+    assert!(chain.run(request(Api::BuildOrder)).await.is_err());
+    chain.run(request(Api::BuildOrder)).await.unwrap();
+
+    let stats = metrics.snapshot();
+    let build = &stats[Api::BuildOrder.name()];
+    assert_eq!(build.count, 2);
+    assert_eq!(build.errors, 1);
+}
+
+// 模拟网关, 按指定延迟返回成功
+async fn gateway(delay: Duration) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"ret": ["SUCCESS::调用成功"], "data": {}}))
+                .set_delay(delay),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn test_race_network_path() {
+    // 直连较慢, 代理线路先返回
+    let direct = gateway(Duration::from_millis(500)).await;
+    let proxy = gateway(Duration::ZERO).await;
+    let registry = EndpointRegistry {
+        gateway: direct.uri(),
+        race_proxy: Some(proxy.uri()),
+        ..Default::default()
+    };
+    let client = registry.client_builder().build().unwrap();
+    let race = registry.race_builder().unwrap().unwrap().build().unwrap();
+    let transport = HttpTransport::new(client, None).with_race(Some(race));
+    assert_eq!(transport.winner(), None);
+
+    let mut req = request(Api::TicketDetail);
+    req.url = format!("{}/h5/detail/", direct.uri());
+    transport.send(&req).await.unwrap();
+    assert_eq!(transport.winner(), None);
+
+    req.api = Api::BuildOrder;
+    transport.send(&req).await.unwrap();
+    assert_eq!(transport.winner(), Some(NetworkPath::Proxy));
+
+    // 之后的下单请求只走胜出的线路
+    req.api = Api::CreateOrder;
+    transport.send(&req).await.unwrap();
+    assert_eq!(proxy.received_requests().await.unwrap().len(), 2);
+}
@@ -0,0 +1,81 @@
+use dm_ticket::{clients::dm::DmClient, platform::TicketPlatform};
+use serde_json::json;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+// 模拟大麦网关, 返回token cookie
+async fn mock_gateway() -> MockServer {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/h5/mtop.damai.wireless.search.broadcast.list/1.0/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .append_header("set-cookie", "_m_h5_tk=token_1700000000000; Path=/")
+                .append_header("set-cookie", "_m_h5_tk_enc=enc; Path=/")
+                .set_body_json(json!({"ret": ["FAIL_SYS_TOKEN_EMPTY::令牌为空"], "data": {}})),
+        )
+        .mount(&server)
+        .await;
+
+    server
+}
+
+#[tokio::test]
+async fn test_token_from_mock_gateway() {
+    let server = mock_gateway().await;
+
+    let client = DmClient::with_base_url(Some("cookie2=abc".into()), None, &server.uri())
+        .await
+        .unwrap();
+
+    assert_eq!(client.token.token, "token");
+    assert_eq!(client.token.enc_token, "enc");
+}
+
+#[tokio::test]
+async fn test_user_info_from_mock_gateway() {
+    let server = mock_gateway().await;
+
+    Mock::given(method("POST"))
+        .and(path("/h5/mtop.damai.wireless.user.session.transform/1.0/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "api": "mtop.damai.wireless.user.session.transform",
+            "data": {"nickname": "tester", "userId": 10086},
+            "ret": ["SUCCESS::调用成功"],
+            "v": "1.0",
+        })))
+        .mount(&server)
+        .await;
+
+    let client = DmClient::with_base_url(Some("cookie2=abc".into()), None, &server.uri())
+        .await
+        .unwrap();
+
+    let user_info = client.user_info().await.unwrap();
+    assert_eq!(user_info.nickname, "tester");
+    assert_eq!(user_info.user_id, 10086);
+}
+
+#[tokio::test]
+async fn test_risk_control_from_mock_gateway() {
+    let server = mock_gateway().await;
+
+    Mock::given(method("POST"))
+        .and(path("/h5/mtop.trade.order.build.h5/4.0/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": {},
+            "ret": ["RGV587_ERROR::SM::哎哟喂,被挤爆啦,请稍后重试"],
+        })))
+        .mount(&server)
+        .await;
+
+    let client = DmClient::with_base_url(Some("cookie2=abc".into()), None, &server.uri())
+        .await
+        .unwrap();
+
+    let err = client.build_order("1", "2", 1).await.unwrap_err();
+    assert!(err.to_string().contains("风控"));
+}
@@ -0,0 +1,53 @@
+use dm_ticket::{errors::MtopError, models::DmRes};
+use serde_json::json;
+
+#[test]
+fn test_mtop_error_from_ret() {
+    assert_eq!(
+        MtopError::from_ret("FAIL_SYS_TOKEN_EXOIRED::令牌过期"),
+        MtopError::TokenEmpty
+    );
+    assert_eq!(
+        MtopError::from_ret("FAIL_SYS_SESSION_EXPIRED::Session过期"),
+        MtopError::SessionExpired
+    );
+    assert_eq!(
+        MtopError::from_ret("B-00203-200-008::对不起，您选购的商品库存不足，请重新选购"),
+        MtopError::StockEmpty
+    );
+    assert_eq!(
+        MtopError::from_ret("F-10001-10-16-103::对不起，系统繁忙，请稍候再试"),
+        MtopError::RateLimited
+    );
+    assert_eq!(
+        MtopError::from_ret("RGV587_ERROR::SM::哎哟喂,被挤爆啦,请稍后重试"),
+        MtopError::RiskControl
+    );
+    assert_eq!(
+        MtopError::from_ret("B-00203-200-034::您选购的商品信息已过期"),
+        MtopError::Unknown("B-00203-200-034::您选购的商品信息已过期".to_string())
+    );
+}
+
+#[test]
+fn test_mtop_error_from_rets() {
+    assert_eq!(
+        MtopError::from_rets(&["SUCCESS::调用成功".to_string()]),
+        None
+    );
+    let ret = vec![
+        "B-00203-200-034::您选购的商品信息已过期".to_string(),
+        "B-00203-200-008::库存不足".to_string(),
+    ];
+    assert_eq!(MtopError::from_rets(&ret), Some(MtopError::StockEmpty));
+
+    let res: DmRes = serde_json::from_value(json!({
+        "api": "mtop.trade.order.build.h5",
+        "data": {},
+        "ret": ["FAIL_SYS_TRAFFIC_LIMIT::被限流"],
+        "v": "4.0",
+    }))
+    .unwrap();
+    assert_eq!(res.error(), Some(MtopError::RateLimited));
+    assert!(res.error().unwrap().is_throttled());
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::task;
+use dm_ticket::models::{
+    order::{strip_nulls, OrderForm, OrderInfo, SubmitOrderForm},
+    seat::Seat,
+    task::Task,
+};
+use serde_json::{json, Value};
+
+// 以下为大麦h5页面提交的数据, 接口变化时同步更新
+
+const EX_PARAMS: &str = r#"{"atomSplit":"1","channel":"damai_app","customerType":"default","damai":"1","serviceVersion":"2.0.0","subChannel":"damai@damaih5_h5","umpChannel":"100031004"}"#;
+
+const EX_PARAMS_WITH_COUPON: &str = r#"{"atomSplit":"1","channel":"damai_app","couponId":"c1","customerType":"default","damai":"1","serviceVersion":"2.0.0","subChannel":"damai@damaih5_h5","umpChannel":"100031004"}"#;
+
+const EX_PARAMS_WITH_PRIVILEGE: &str = r#"{"atomSplit":"1","channel":"damai_app","couponId":"c1","customerType":"default","damai":"1","privilegeCode":"FAN2023","serviceVersion":"2.0.0","subChannel":"damai@damaih5_h5","umpChannel":"100031004"}"#;
+
+const SEAT_INFO: &str = r#"[{"seatId":"s1","skuId":"5001"},{"seatId":"s2","skuId":"5001"}]"#;
+
+const SUBMIT_FEATURE: &str = r#"{"dataTags":"sqm:dianying.h5.unknown.value","returnUrl":"https://m.damai.cn/damai/pay-success/index.html?spm=a2o71.orderconfirm.bottom.dconfirm&sqm=dianying.h5.unknown.value","serviceVersion":"2.0.0","subChannel":"damai@damaih5_h5"}"#;
+
+const SUBMIT_DATA: &str = r#"{"confirmOrder_1":{"fields":{}},"dmDeliveryAddress_1":{"fields":{"selectedId":"addr1"}},"dmViewer_1":{"fields":{"viewerList":[{"certNo":"1101","certType":"1","isUsed":true,"viewerId":1,"viewerName":"张三"},{"certNo":"3101","certType":"1","viewerId":2,"viewerName":"李四"}]}},"order_1":{"fields":{"a":1}}}"#;
+
+const SUBMIT_HIERARCHY: &str = r#"{"structure":{"confirmOrder_1":["order_1","item_1"]}}"#;
+
+const SUBMIT_LINKAGE: &str =
+    r#"{"common":{"compress":true,"submitParams":"sp","validateParams":"vp"},"signature":"sig"}"#;
+
+fn order_info() -> OrderInfo {
+    serde_json::from_value(json!({
+        "data": {
+            "dmViewer_1": {"fields": {"viewerList": [
+                {"viewerId": 1, "viewerName": "张三", "certNo": "1101", "certType": "1"},
+                {"viewerId": 2, "viewerName": "李四", "certNo": "3101", "certType": "1"}
+            ]}},
+            "dmDeliveryAddress_1": {"fields": {"selectedId": ""}},
+            "confirmOrder_1": {"fields": {}},
+            "order_1": {"fields": {"a": 1}},
+            "item_1": {"fields": {}}
+        },
+        "global": {"secretKey": "k", "secretValue": "v"},
+        "hierarchy": {
+            "component": [],
+            "root": "confirmOrder_1",
+            "baseType": [],
+            "structure": {"confirmOrder_1": ["order_1", "item_1"]}
+        },
+        "linkage": {
+            "input": ["dmViewer_1", "dmDeliveryAddress_1"],
+            "request": [],
+            "signature": "sig",
+            "common": {
+                "queryParams": "q",
+                "compress": true,
+                "validateParams": "vp",
+                "structures": "s",
+                "submitParams": "sp"
+            }
+        }
+    }))
+    .unwrap()
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value[key].as_str().unwrap()
+}
+
+#[test]
+fn test_build_order_form() {
+    let data = OrderForm::build("721", "5001", 2).unwrap();
+    assert_eq!(
+        data,
+        json!({
+            "buyNow": "true",
+            "buyParam": "721_2_5001",
+            "dmChannel": "damai@damaih5_h5",
+            "exParams": EX_PARAMS,
+        })
+    );
+
+    let data = OrderForm::with_coupon(data, "c1").unwrap();
+    assert_eq!(field(&data, "exParams"), EX_PARAMS_WITH_COUPON);
+
+    let data = OrderForm::with_privilege_code(data, "FAN2023").unwrap();
+    assert_eq!(field(&data, "exParams"), EX_PARAMS_WITH_PRIVILEGE);
+}
+
+#[test]
+fn test_build_order_form_with_seats() {
+    let seats: Vec<Seat> = serde_json::from_value(json!([
+        {"seatId": "s1", "skuId": "5001"},
+        {"seatId": "s2", "skuId": "5001"}
+    ]))
+    .unwrap();
+    let data = OrderForm::build_with_seats("721", "5001", &seats).unwrap();
+    assert_eq!(field(&data, "buyParam"), "721_2_5001");
+    assert_eq!(field(&data, "seatInfo"), SEAT_INFO);
+}
+
+#[test]
+fn test_submit_order_form() {
+    let order_info = order_info();
+    assert!(order_info.requires_viewers());
+    let task = Task {
+        address_id: Some("addr1".to_string()),
+        ..task()
+    };
+
+    let data = SubmitOrderForm::build(&order_info, &task).unwrap();
+    assert_eq!(field(&data, "feature"), SUBMIT_FEATURE);
+
+    let params: Value = serde_json::from_str(field(&data, "params")).unwrap();
+    assert_eq!(field(&params, "data"), SUBMIT_DATA);
+    assert_eq!(field(&params, "hierarchy"), SUBMIT_HIERARCHY);
+    assert_eq!(field(&params, "linkage"), SUBMIT_LINKAGE);
+}
+
+#[test]
+fn test_strip_nulls() {
+    let mut data = json!({
+        "order_1": {"fields": {"a": 1, "b": null, "list": [{"c": null}, null]}},
+        "order_2": null,
+    });
+    strip_nulls(&mut data);
+    // 数组中的null保留, 与JSON.stringify一致
+    assert_eq!(
+        data,
+        json!({"order_1": {"fields": {"a": 1, "list": [{}, null]}}})
+    );
+}
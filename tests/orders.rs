@@ -0,0 +1,43 @@
+mod common;
+
+use common::task;
+use dm_ticket::models::order::{existing_order, parse_order_list};
+use serde_json::json;
+
+#[test]
+fn test_parse_order_list() {
+    let data = json!({
+        "orderList": [
+            {"orderId": 1001, "itemName": "演唱会", "orderStatusDesc": "待付款", "totalAmount": "580.00", "payDeadline": "1700000000000"},
+            {"bizOrderId": "1002", "projectName": "话剧", "orderStatus": "TRADE_FINISHED"},
+            {"itemName": "缺少ID"}
+        ]
+    });
+    let orders = parse_order_list(&data);
+    assert_eq!(orders.len(), 2);
+    assert!(orders[0].is_unpaid());
+    assert_eq!(orders[0].order_id, "1001");
+    assert_eq!(orders[0].pay_deadline, Some(1700000000000));
+    assert!(!orders[1].is_unpaid());
+    assert_eq!(orders[1].pay_deadline, None);
+}
+
+#[test]
+fn test_existing_order() {
+    let task = task();
+
+    let data = json!({
+        "orderList": [
+            {"orderId": "1", "itemId": "721163957291", "performId": "211234567890", "orderStatusDesc": "已取消"},
+            {"orderId": "2", "itemId": "721163957291", "performId": "211234567891", "orderStatusDesc": "待付款"}
+        ]
+    });
+    assert!(existing_order(&parse_order_list(&data), &task).is_none());
+
+    // 未返回场次ID时按门票ID匹配
+    let data = json!({
+        "orderList": [{"orderId": "3", "itemId": 721163957291i64, "orderStatusDesc": "待付款"}]
+    });
+    let orders = parse_order_list(&data);
+    assert_eq!(existing_order(&orders, &task).unwrap().order_id, "3");
+}
@@ -0,0 +1,39 @@
+use dm_ticket::{
+    events::{RunReport, TicketEvent},
+    output::OutputFormat,
+};
+
+#[test]
+fn test_output_format_parse() {
+    assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+    assert_eq!("JSON".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+    assert_eq!("text".parse::<OutputFormat>().unwrap(), OutputFormat::Text);
+    assert_eq!("".parse::<OutputFormat>().unwrap(), OutputFormat::Text);
+    assert!("yaml".parse::<OutputFormat>().is_err());
+}
+
+#[test]
+fn test_run_report_json() {
+    let mut report = RunReport::default();
+    report.record(&TicketEvent::AttemptFailed {
+        stage: "build",
+        n: 1,
+        attempt_id: "x-b1".to_string(),
+        code: "RGV587_ERROR".to_string(),
+    });
+    report.record(&TicketEvent::OrderCreated {
+        order_id: Some("123".to_string()),
+        pay_deadline: None,
+        elapsed_ms: 10,
+    });
+    report.record(&TicketEvent::RunFinished {
+        success: true,
+        error: None,
+    });
+
+    let value = serde_json::to_value(&report).unwrap();
+    assert_eq!(value["success"], true);
+    assert_eq!(value["order_id"], "123");
+    assert_eq!(value["errors"][0][0], "RGV587_ERROR");
+    assert_eq!(value["errors"][0][1], 1);
+}
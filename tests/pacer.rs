@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use dm_ticket::pacer::{Backoff, ErrorClass, RetryPacer, RetryPolicy};
+
+#[test]
+fn test_interval_subtracts_rtt() {
+    let mut pacer = RetryPacer::new(300, 50);
+    assert_eq!(pacer.interval(), Duration::from_millis(300));
+
+    pacer.record(Duration::from_millis(200), "");
+    assert_eq!(pacer.interval(), Duration::from_millis(100));
+
+    pacer.record(Duration::from_millis(600), "");
+    assert_eq!(pacer.interval(), Duration::from_millis(50));
+}
+
+#[test]
+fn test_interval_widens_when_throttled() {
+    let mut pacer = RetryPacer::new(100, 50);
+    pacer.record(
+        Duration::ZERO,
+        "RGV587_ERROR::SM::哎哟喂,被挤爆啦,请稍后重试",
+    );
+    assert_eq!(pacer.interval(), Duration::from_millis(200));
+    pacer.record(
+        Duration::ZERO,
+        "RGV587_ERROR::SM::哎哟喂,被挤爆啦,请稍后重试",
+    );
+    assert_eq!(pacer.interval(), Duration::from_millis(400));
+
+    for _ in 0..10 {
+        pacer.record(Duration::ZERO, "RGV587_ERROR");
+    }
+    assert_eq!(pacer.interval(), Duration::from_millis(800));
+
+    pacer.record(Duration::ZERO, "B-00203-200-008::库存不足");
+    assert_eq!(pacer.interval(), Duration::from_millis(400));
+}
+
+#[test]
+fn test_jittered_respects_floor() {
+    let mut pacer = RetryPacer::new(100, 80);
+    pacer.record(Duration::from_millis(90), "");
+    for _ in 0..20 {
+        assert!(pacer.jittered() >= Duration::from_millis(80));
+    }
+}
+
+#[test]
+fn test_backoff_delays() {
+    assert_eq!(Backoff::Fixed.delay(100, 5000, 3, 0), 100);
+    assert_eq!(Backoff::Linear.delay(100, 5000, 3, 0), 300);
+    assert_eq!(Backoff::Exponential.delay(100, 5000, 1, 0), 100);
+    assert_eq!(Backoff::Exponential.delay(100, 5000, 4, 0), 800);
+    assert_eq!(Backoff::Exponential.delay(100, 500, 10, 0), 500);
+
+    for _ in 0..20 {
+        let delay = Backoff::DecorrelatedJitter.delay(100, 5000, 2, 400);
+        assert!((100..=1200).contains(&delay));
+    }
+}
+
+#[test]
+fn test_retry_policy_parse() {
+    let policy =
+        RetryPolicy::parse("throttled=decorrelated_jitter, default=exponential", 2000).unwrap();
+    assert_eq!(policy.max, 2000);
+    assert_eq!(
+        policy.backoff(ErrorClass::Throttled),
+        Some(Backoff::DecorrelatedJitter)
+    );
+    assert_eq!(
+        policy.backoff(ErrorClass::StockEmpty),
+        Some(Backoff::Exponential)
+    );
+
+    assert!(RetryPolicy::parse("throttled=forever", 2000).is_err());
+    assert!(RetryPolicy::parse("timeout=fixed", 2000).is_err());
+    assert!(RetryPolicy::parse("fixed", 2000).is_err());
+    assert!(RetryPolicy::parse("", 2000).unwrap().default.is_none());
+}
+
+#[test]
+fn test_backoff_per_error_class() {
+    let policy = RetryPolicy::parse("stock_empty=linear", 5000).unwrap();
+    let mut pacer = RetryPacer::new(100, 50).with_policy(policy);
+
+    for n in 1..=3 {
+        pacer.record(Duration::ZERO, "B-00203-200-008::库存不足");
+        assert_eq!(pacer.backoff(), Duration::from_millis(100 * n));
+    }
+
+    // 其他类别未配置策略, 使用自适应间隔
+    pacer.record(Duration::ZERO, "FAIL_SYS_SESSION_EXPIRED");
+    assert!(pacer.backoff() <= Duration::from_millis(150));
+
+    // 类别变化后重新计数
+    pacer.record(Duration::ZERO, "B-00203-200-008::库存不足");
+    assert_eq!(pacer.backoff(), Duration::from_millis(100));
+}
@@ -0,0 +1,29 @@
+use dm_ticket::models::privilege::{mask_code, parse_privilege_check, PrivilegeCheck};
+use serde_json::json;
+
+#[test]
+fn test_parse_privilege_check() {
+    assert_eq!(
+        parse_privilege_check(&json!({"valid": true})),
+        PrivilegeCheck {
+            valid: true,
+            message: String::new(),
+        }
+    );
+    assert_eq!(
+        parse_privilege_check(&json!({"result": "false", "errorMsg": "优先购码已被使用"})),
+        PrivilegeCheck {
+            valid: false,
+            message: "优先购码已被使用".to_string(),
+        }
+    );
+    assert!(parse_privilege_check(&json!({"pass": 1})).valid);
+    assert!(!parse_privilege_check(&json!({})).valid);
+}
+
+#[test]
+fn test_mask_code() {
+    assert_eq!(mask_code("FAN2023"), "F*****3");
+    assert_eq!(mask_code("AB"), "**");
+    assert_eq!(mask_code(""), "");
+}
@@ -0,0 +1,31 @@
+use dm_ticket::models::ticket::PurchaseRules;
+use serde_json::json;
+
+#[test]
+fn test_purchase_rules_nested() {
+    let value = json!({
+        "item": {
+            "item": {"limitQuantity": "6", "buyBtnText": "即将开抢"},
+            "dynamicExtData": [{"realName": "张三"}, {"isRealName": 1}],
+            "privilege": {"priorityStartTime": 1700000000000i64, "publicSellStartTime": "1700001800000"}
+        }
+    });
+    let rules = PurchaseRules::parse(&value);
+    assert_eq!(rules.purchase_limit, Some(6));
+    assert_eq!(rules.real_name_required, Some(true));
+    assert_eq!(rules.priority_purchase_minutes(), Some(30));
+}
+
+#[test]
+fn test_purchase_rules_privilege() {
+    let value = json!({
+        "priorityStartTime": 1700000000000i64,
+        "publicSellStartTime": 1700001800000i64,
+        "hasPrivilege": true
+    });
+    assert_eq!(
+        PurchaseRules::parse(&value).priority_purchase_minutes(),
+        Some(0)
+    );
+    assert_eq!(PurchaseRules::parse(&json!({})), PurchaseRules::default());
+}
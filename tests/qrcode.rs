@@ -0,0 +1,22 @@
+use dm_ticket::models::qrcode::{QrCodeLoginStatusData, QrcodeStatus};
+use serde_json::json;
+
+fn status(qrcode_status: &str) -> QrcodeStatus {
+    let data: QrCodeLoginStatusData = serde_json::from_value(json!({
+        "resultCode": 100,
+        "qrCodeStatus": qrcode_status,
+    }))
+    .unwrap();
+    data.status()
+}
+
+#[test]
+fn test_qrcode_status() {
+    assert_eq!(status("NEW"), QrcodeStatus::New);
+    assert_eq!(status("SCANED"), QrcodeStatus::Scanned);
+    assert_eq!(status("CONFIRMED"), QrcodeStatus::Confirmed);
+    assert_eq!(status("EXPIRED"), QrcodeStatus::Expired);
+    assert_eq!(status("二维码已失效, 请刷新"), QrcodeStatus::Expired);
+    assert_eq!(status("CANCELED"), QrcodeStatus::Canceled);
+    assert_eq!(status("OTHER"), QrcodeStatus::Unknown("OTHER".to_string()));
+}
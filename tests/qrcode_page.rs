@@ -0,0 +1,19 @@
+use dm_ticket::clients::qrcode_page::QrcodePage;
+
+#[tokio::test]
+async fn test_qrcode_page() {
+    let page = QrcodePage::start("127.0.0.1:0").await.unwrap();
+    page.update(vec![0x89, b'P', b'N', b'G']);
+
+    let html = reqwest::get(&page.url).await.unwrap().text().await.unwrap();
+    assert!(html.contains("/qrcode.png"));
+
+    let res = reqwest::get(format!("{}/qrcode.png", page.url))
+        .await
+        .unwrap();
+    assert_eq!(res.headers()["content-type"], "image/png");
+    assert_eq!(
+        res.bytes().await.unwrap().as_ref(),
+        &[0x89, b'P', b'N', b'G']
+    );
+}
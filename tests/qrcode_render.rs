@@ -0,0 +1,57 @@
+use dm_ticket::qrcode_render::{render_svg, QrOptions, QrSize};
+
+fn modules() -> Vec<Vec<bool>> {
+    vec![vec![true, false], vec![false, true]]
+}
+
+#[test]
+fn test_render_terminal() {
+    // 2x2加上四周空白为6x6, 半高字符显示为3行
+    let small = QrOptions::default().render(&modules());
+    let lines: Vec<&str> = small.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1], "  ▀▄  ");
+
+    let large = QrOptions {
+        size: QrSize::Large,
+        ..Default::default()
+    }
+    .render(&modules());
+    assert_eq!(large.lines().count(), 6);
+    assert_eq!(large.lines().nth(2).unwrap(), "    ██      ");
+
+    // 反色时空白显示为色块
+    let inverted = QrOptions {
+        invert: true,
+        ..Default::default()
+    }
+    .render(&modules());
+    assert_eq!(inverted.lines().next().unwrap(), "██████");
+    assert_eq!(inverted.lines().nth(1).unwrap(), "██▄▀██");
+}
+
+#[test]
+fn test_export_svg() {
+    let svg = render_svg(&modules());
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("viewBox=\"0 0 6 6\""));
+    assert!(svg.contains("M2,2h1v1h-1z") && svg.contains("M3,3h1v1h-1z"));
+
+    let path = std::env::temp_dir().join(format!("dm-qrcode-{}.svg", std::process::id()));
+    let options = QrOptions {
+        export: Some(path.clone()),
+        ..Default::default()
+    };
+    assert_eq!(options.export(&modules(), &[]).unwrap(), Some(path.clone()));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), svg);
+    let _ = std::fs::remove_file(path);
+}
+
+#[cfg(feature = "http-login")]
+#[test]
+fn test_encode() {
+    let content = "https://passport.damai.cn/qrcodeCheck.htm";
+    let modules = dm_ticket::qrcode_render::encode(content).unwrap();
+    assert!(modules.len() >= 21);
+    assert!(modules.iter().all(|row| row.len() == modules.len()));
+}
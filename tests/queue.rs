@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use dm_ticket::{
+    clients::queue::{QueuePolicy, QueueTicket},
+    models::DmRes,
+};
+use serde_json::{json, Value};
+
+fn res(ret: &str, data: Value) -> DmRes {
+    DmRes {
+        api: Some("mtop.trade.order.build.h5".to_string()),
+        data,
+        ret: vec![ret.to_string()],
+        v: Some("4.0".to_string()),
+    }
+}
+
+#[test]
+fn test_detect_queue() {
+    let policy = QueuePolicy::default();
+
+    // 正常响应不是排队
+    let normal = res("SUCCESS::调用成功", json!({"global": {}}));
+    assert!(QueueTicket::detect(&normal, &policy).is_none());
+    let failed = res("B-00203-200-008::库存不足", json!({}));
+    assert!(QueueTicket::detect(&failed, &policy).is_none());
+
+    // 提示排队, 未返回token和间隔
+    let queued = res("FAIL_BIZ_QUEUE::排队中, 请稍候", json!({}));
+    let ticket = QueueTicket::detect(&queued, &policy).unwrap();
+    assert_eq!(ticket.token, None);
+    assert_eq!(ticket.interval, policy.default_interval);
+    assert_eq!(ticket.describe(), "排队中, 位置未知");
+
+    // 调用成功但返回排队token
+    let queued = res(
+        "SUCCESS::调用成功",
+        json!({"queueToken": "q-1", "pollInterval": "1500", "position": 12}),
+    );
+    let ticket = QueueTicket::detect(&queued, &policy).unwrap();
+    assert_eq!(ticket.token.as_deref(), Some("q-1"));
+    assert_eq!(ticket.interval, Duration::from_millis(1500));
+    assert_eq!(ticket.position, Some(12));
+    assert_eq!(ticket.describe(), "排队中, 当前位置:12");
+}
+
+#[test]
+fn test_queue_interval_clamp() {
+    let policy = QueuePolicy::default();
+
+    let fast = res("排队中", json!({"interval": 10}));
+    let ticket = QueueTicket::detect(&fast, &policy).unwrap();
+    assert_eq!(ticket.interval, policy.min_interval);
+
+    let slow = res("排队中", json!({"interval": 60000}));
+    let ticket = QueueTicket::detect(&slow, &policy).unwrap();
+    assert_eq!(ticket.interval, policy.max_interval);
+}
@@ -0,0 +1,55 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use dm_ticket::clients::{
+    endpoint::Api,
+    rate_limit::{RateLimiter, TokenBucket},
+};
+
+#[test]
+fn test_token_bucket() {
+    let start = Instant::now();
+    let mut bucket = TokenBucket::new(2.0, 2.0, start);
+
+    // 突发2个请求不等待, 之后每个请求依次排队
+    assert_eq!(bucket.acquire(start), Duration::ZERO);
+    assert_eq!(bucket.acquire(start), Duration::ZERO);
+    assert_eq!(bucket.acquire(start), Duration::from_millis(500));
+    assert_eq!(bucket.acquire(start), Duration::from_millis(1000));
+
+    // 2秒后补充4个令牌, 抵扣预支的2个
+    let later = start + Duration::from_secs(2);
+    assert_eq!(bucket.acquire(later), Duration::ZERO);
+    assert_eq!(bucket.acquire(later), Duration::ZERO);
+    assert_eq!(bucket.acquire(later), Duration::from_millis(500));
+}
+
+#[test]
+fn test_parse_endpoints() {
+    let endpoints = RateLimiter::parse_endpoints(
+        "mtop.damai.item.detail.getdetail=1, mtop.trade.order.build.h5=20/40,bad",
+    );
+    assert_eq!(endpoints.len(), 2);
+    assert_eq!(endpoints["mtop.damai.item.detail.getdetail"], (1.0, 1.0));
+    assert_eq!(endpoints["mtop.trade.order.build.h5"], (20.0, 40.0));
+}
+
+#[test]
+fn test_rate_limiter() {
+    let mut endpoints = HashMap::new();
+    endpoints.insert(Api::TicketDetail.name().to_string(), (1.0, 1.0));
+    let limiter = RateLimiter::new(Some((1000.0, 100.0)), endpoints);
+
+    // 单独配置的接口
+    assert!(limiter.acquire(Api::TicketDetail).is_zero());
+    assert!(limiter.acquire(Api::TicketDetail) > Duration::from_millis(900));
+
+    // 下单接口不受全局限速
+    let limiter = RateLimiter::new(Some((1.0, 1.0)), HashMap::new());
+    assert!(limiter.acquire(Api::UserInfo).is_zero());
+    assert!(!limiter.acquire(Api::UserInfo).is_zero());
+    assert!(limiter.acquire(Api::BuildOrder).is_zero());
+    assert!(limiter.acquire(Api::CreateOrder).is_zero());
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::task;
+use dm_ticket::resume::{ArmedStore, ArmedTask};
+
+#[test]
+fn test_armed_store() {
+    let dir = std::env::temp_dir().join(format!("dm-armed-{}", std::process::id()));
+    let store = ArmedStore::new(&dir);
+    assert!(store.list().unwrap().is_empty());
+
+    let main = ArmedTask::new(task(), "main".to_string());
+    let backup = ArmedTask::new(task(), "backup".to_string());
+    assert_ne!(main.file_name(), backup.file_name());
+
+    store.save(&main).unwrap();
+    store.save(&backup).unwrap();
+    let tasks = store.list().unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].task.key(), main.task.key());
+
+    store.remove(&main).unwrap();
+    let tasks = store.list().unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].account, "backup");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_armed_task_expired() {
+    let mut armed = ArmedTask::new(task(), "main".to_string());
+    let now = 1_700_000_000_000;
+    assert!(!armed.expired(now));
+
+    armed.sale_start = Some(now - 60 * 1000);
+    assert!(!armed.expired(now));
+
+    armed.sale_start = Some(now - 60 * 60 * 1000);
+    assert!(armed.expired(now));
+
+    armed.task.stop_after_sale_plus = Some(30);
+    armed.sale_start = Some(now - 60 * 1000);
+    assert!(armed.expired(now));
+}
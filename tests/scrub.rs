@@ -0,0 +1,51 @@
+use dm_ticket::{clients::record::scrub_value, logger::scrub};
+use serde_json::json;
+
+#[test]
+fn test_scrub_cookie() {
+    let line = "cookie: cookie2=abc123;_m_h5_tk=deadbeef_1700000000000;lang=zh";
+    assert_eq!(scrub(line), "cookie: cookie2=***;_m_h5_tk=***;lang=zh");
+}
+
+#[test]
+fn test_scrub_token_fields() {
+    let line = r#"DmToken { token_with_time: "abc_1", token: "abc", enc_token: "xyz" } {"sign":"0f0f","t":"1"}"#;
+    assert_eq!(
+        scrub(line),
+        r#"DmToken { token_with_time: "***", token: "***", enc_token: "***" } {"sign":"***","t":"1"}"#
+    );
+}
+
+#[test]
+fn test_scrub_personal_info() {
+    let line = "viewer 110101199003071234 phone 13812345678 item 720285340913";
+    assert_eq!(
+        scrub(line),
+        "viewer 110***********1234 phone 138****5678 item 720285340913"
+    );
+}
+
+#[test]
+fn test_scrub_record() {
+    let data = json!({
+        "exParams": "{\"cookie\": \"cookie2=abc\"}",
+        "viewerList": [{"viewerId": 1, "viewerName": "张三", "certNo": "110101199003071234"}],
+        "addressList": [{"fullName": "李四", "mobile": 13812345678u64, "addressDetail": "xx路1号"}],
+        "remark": "联系电话: 13812345678",
+        "itemId": 720285340913u64,
+    });
+    let scrubbed = scrub_value(&data);
+    let text = scrubbed.to_string();
+    for secret in [
+        "abc",
+        "张三",
+        "110101199003071234",
+        "李四",
+        "13812345678",
+        "xx路1号",
+    ] {
+        assert!(!text.contains(secret), "{}", secret);
+    }
+    assert_eq!(scrubbed["viewerList"][0]["viewerId"], 1);
+    assert_eq!(scrubbed["itemId"], 720285340913u64);
+}
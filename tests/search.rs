@@ -0,0 +1,27 @@
+use dm_ticket::models::search::{parse_city_stops, select_city_stop};
+use serde_json::json;
+
+#[test]
+fn test_select_city_stop() {
+    let data = json!({
+        "projectInfo": [
+            {"id": 721000000001i64, "name": "巡回演唱会-北京站", "cityName": "北京", "venueName": "国家体育场"},
+            {"projectId": "721000000002", "name": "巡回演唱会-上海站", "cityName": "上海市", "showTime": "2023.10.01"},
+            {"name": "缺少ID", "cityName": "杭州"}
+        ]
+    });
+    let stops = parse_city_stops(&data);
+    assert_eq!(stops.len(), 2);
+    assert_eq!(stops[0].item_id, "721000000001");
+
+    let cities = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let stop = select_city_stop(&stops, &cities(&["杭州", "上海", "北京"])).unwrap();
+    assert_eq!(stop.item_id, "721000000002");
+    assert_eq!(
+        select_city_stop(&stops, &cities(&["北京市"]))
+            .unwrap()
+            .item_id,
+        "721000000001"
+    );
+    assert!(select_city_stop(&stops, &cities(&["杭州"])).is_none());
+}
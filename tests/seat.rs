@@ -0,0 +1,47 @@
+use dm_ticket::models::seat::{Seat, SeatPreference, SeatStrategy};
+
+fn seat(id: &str, zone: &str, row: u32, col: u32) -> Seat {
+    Seat {
+        seat_id: id.to_string(),
+        sku_id: "1".to_string(),
+        zone: zone.to_string(),
+        row,
+        col,
+        available: true,
+    }
+}
+
+#[test]
+fn test_best_available_adjacent() {
+    let seats = vec![
+        seat("a", "看台", 1, 1),
+        seat("b", "内场A区", 2, 1),
+        seat("c", "内场A区", 2, 3),
+        seat("d", "内场A区", 3, 1),
+        seat("e", "内场A区", 3, 2),
+    ];
+    let preference = SeatPreference {
+        zones: vec!["内场".to_string(), "看台".to_string()],
+        ..Default::default()
+    };
+    let selected = preference.select(&seats, "1", 2).unwrap();
+    let ids = selected
+        .iter()
+        .map(|s| s.seat_id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["d", "e"]);
+}
+
+#[test]
+fn test_explicit_requires_all_seats() {
+    let mut seats = vec![seat("a", "看台", 1, 1), seat("b", "看台", 1, 2)];
+    let preference = SeatPreference {
+        strategy: SeatStrategy::Explicit,
+        seat_ids: vec!["a".to_string(), "b".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(preference.select(&seats, "1", 2).map(|s| s.len()), Some(2));
+
+    seats[1].available = false;
+    assert!(preference.select(&seats, "1", 2).is_none());
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use dm_ticket::{
+    clients::session::{self, Session, SessionStore},
+    models::DmToken,
+};
+
+fn token(value: &str) -> DmToken {
+    DmToken {
+        token_with_time: format!("{}_1700000000000", value),
+        token: value.to_string(),
+        enc_token: "enc".to_string(),
+    }
+}
+
+#[test]
+fn test_session_update() {
+    let mut session = Session::new("munb=1001;cookie2=a".to_string(), token("a"));
+    assert_eq!(session.version, 0);
+
+    session.update("munb=1001;cookie2=b".to_string(), token("b"));
+    assert_eq!(session.version, 1);
+    assert_eq!(session.cookie, "munb=1001;cookie2=b");
+    assert_eq!(session.token.token, "b");
+}
+
+#[test]
+fn test_session_store() {
+    let store = SessionStore::default();
+    assert!(store.get("1001").is_none());
+
+    let first = store.join(
+        "1001",
+        Session::new("munb=1001;cookie2=a".into(), token("a")),
+    );
+
+    // 已有会话时使用已有的, 不覆盖
+    let second = store.join(
+        "1001",
+        Session::new("munb=1001;cookie2=old".into(), token("x")),
+    );
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(session::snapshot(&second).cookie, "munb=1001;cookie2=a");
+
+    // 其他账号为单独的会话
+    let other = store.join("1002", Session::new("munb=1002".into(), token("c")));
+    assert!(!Arc::ptr_eq(&first, &other));
+}
+
+#[test]
+fn test_session_publish() {
+    let store = SessionStore::default();
+    let shared = store.join(
+        "1001",
+        Session::new("munb=1001;cookie2=a".into(), token("a")),
+    );
+    let worker = store.get("1001").unwrap();
+    let seen = session::version(&worker);
+
+    // 任一客户端更新后, 其他客户端读取到新的版本和cookie
+    let version = session::publish(&shared, "munb=1001;cookie2=b".into(), token("b"));
+    assert_eq!(version, seen + 1);
+    assert_eq!(session::version(&worker), version);
+    let current = session::snapshot(&worker);
+    assert_eq!(current.cookie, "munb=1001;cookie2=b");
+    assert_eq!(current.token.token, "b");
+}
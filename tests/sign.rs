@@ -0,0 +1,58 @@
+use chrono::Local;
+use dm_ticket::{
+    clients::sign::{sign, sign_params, token_expire_at, token_valid, TokenCache},
+    models::DmToken,
+};
+use serde_json::json;
+
+#[test]
+fn test_sign_golden() {
+    assert_eq!(
+        sign("token", "1700000000000", "12574478", r#"{"itemId":"1"}"#),
+        "f019a7fbfac5ce8765f6f25d5cc0c4c1"
+    );
+    assert_eq!(
+        sign("", "1700000000000", "12574478", "{}"),
+        "891c0608188f528e66fde340a09faa4d"
+    );
+    assert_eq!(
+        sign(
+            "f3d1a0b2c4e5",
+            "1693987200000",
+            "12574478",
+            r#"{"itemId":"721163957291","platform":"8","comboChannel":"2","dmChannel":"damai@damaih5_h5"}"#
+        ),
+        "18d8a69a97c70b7a09cd88d6995b8667"
+    );
+}
+
+#[test]
+fn test_sign_params() {
+    let mut params = json!({"t": "1700000000000", "appKey": "12574478"});
+    sign_params("token", &mut params, &json!({"itemId": "1"})).unwrap();
+    assert_eq!(params["sign"], "f019a7fbfac5ce8765f6f25d5cc0c4c1");
+
+    let mut params = json!({"appKey": "12574478"});
+    assert!(sign_params("token", &mut params, &json!({})).is_err());
+}
+
+#[test]
+fn test_token_cache() {
+    let now = Local::now().timestamp_millis();
+    let token = |expire: i64| DmToken {
+        token_with_time: format!("abc_{}", expire),
+        token: "abc".to_string(),
+        enc_token: "enc".to_string(),
+    };
+    assert_eq!(token_expire_at("abc_1700000000000"), Some(1700000000000));
+    assert!(token_valid(&token(now + 3600 * 1000), now));
+    assert!(!token_valid(&token(now + 1000), now));
+
+    let cache = TokenCache::default();
+    cache.put("cookie1=a", token(now + 3600 * 1000));
+    cache.put("cookie1=b", token(now - 1000));
+    assert!(cache.get("cookie1=a").is_some());
+    assert!(cache.get("cookie1=b").is_none());
+    cache.remove("cookie1=a");
+    assert!(cache.get("cookie1=a").is_none());
+}
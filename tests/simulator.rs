@@ -0,0 +1,14 @@
+use dm_ticket::simulator::{simulate, SimulatorConfig};
+
+#[tokio::test]
+async fn test_simulate_full_pipeline() {
+    std::env::set_var("DESKTOP_NOTIFY", "false");
+    let config = SimulatorConfig {
+        latency: 0,
+        stock: 2,
+        error_rate: 0.0,
+        sale_in: 0,
+        ticket_num: 1,
+    };
+    simulate(config).await.unwrap();
+}
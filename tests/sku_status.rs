@@ -0,0 +1,103 @@
+use dm_ticket::models::perform::{
+    bundle_size, selected_status, sku_snapshot, Sku, SkuItem, SkuStatus,
+};
+use serde_json::json;
+
+fn sku(salable: &str, tags: serde_json::Value) -> Sku {
+    serde_json::from_value(json!({
+        "skuId": "1",
+        "itemId": "2",
+        "priceName": "看台380元",
+        "skuSalable": salable,
+        "price": "380",
+        "tags": tags,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_sku_status() {
+    assert_eq!(sku("true", json!(null)).status(), SkuStatus::Available);
+    assert_eq!(sku("false", json!(null)).status(), SkuStatus::NotOnSale);
+    assert_eq!(
+        sku("false", json!([{"tagDesc": "缺货登记"}])).status(),
+        SkuStatus::SoldOut
+    );
+    assert_eq!(sku("", json!(null)).status(), SkuStatus::Unknown);
+
+    assert_eq!(SkuStatus::NotOnSale.resolve(false), SkuStatus::NotOnSale);
+    assert_eq!(SkuStatus::NotOnSale.resolve(true), SkuStatus::SoldOut);
+    assert_eq!(SkuStatus::Available.resolve(true), SkuStatus::Available);
+}
+
+#[test]
+fn test_sku_snapshot() {
+    let skus: Vec<SkuItem> = serde_json::from_value(json!([
+        {"sku_id": "1", "price_name": "看台380元", "price": "380", "status": "Available"},
+        {"sku_id": "2", "price_name": "内场1280元", "status": "SoldOut"},
+        {"sku_id": "3", "price_name": "看台580元"}
+    ]))
+    .unwrap();
+    assert_eq!(
+        sku_snapshot(&skus),
+        "看台380元:可购, 内场1280元:售罄, 看台580元:未知"
+    );
+    assert_eq!(skus[0].desc(), "看台380元 | ¥380 | 可购");
+    assert_eq!(skus[2].desc(), "看台580元");
+}
+
+#[test]
+fn test_selected_status() {
+    let item = |sku_id: &str, status| SkuItem {
+        sku_id: sku_id.to_string(),
+        sku_name: format!("票档{}", sku_id),
+        price: String::new(),
+        status,
+        bundle: 1,
+    };
+    let skus = [
+        item("1", SkuStatus::SoldOut),
+        item("2", SkuStatus::NotOnSale),
+        item("3", SkuStatus::Available),
+    ];
+
+    // 只看所选票档, 任一可购即为可购
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        selected_status(&skus, &ids(&["1", "2"])),
+        SkuStatus::NotOnSale
+    );
+    assert_eq!(selected_status(&skus, &ids(&["1"])), SkuStatus::SoldOut);
+    assert_eq!(
+        selected_status(&skus, &ids(&["2", "3"])),
+        SkuStatus::Available
+    );
+    assert_eq!(selected_status(&skus, &ids(&["4"])), SkuStatus::Unknown);
+}
+
+#[test]
+fn test_package_sku() {
+    assert_eq!(bundle_size("看台380元"), 1);
+    assert_eq!(bundle_size("双人套票760元"), 2);
+    assert_eq!(bundle_size("三人联票1080元"), 3);
+    assert_eq!(bundle_size("4张套票(看台)"), 4);
+    // 未标明人数的套餐按普通票档
+    assert_eq!(bundle_size("周边套餐"), 1);
+
+    // 优先使用票档数据中的每份票数
+    let mut package = sku("true", json!(null));
+    package.package_num = json!("2");
+    assert_eq!(package.bundle(), 2);
+    assert_eq!(sku("true", json!(null)).bundle(), 1);
+
+    let item = SkuItem {
+        sku_id: "1".to_string(),
+        sku_name: "双人套票760元".to_string(),
+        price: "760".to_string(),
+        status: SkuStatus::Available,
+        bundle: 2,
+    };
+    assert_eq!(item.buy_num(4), Some(2));
+    assert_eq!(item.buy_num(3), None);
+    assert_eq!(item.desc(), "双人套票760元 | ¥760 | 套票(每份2张) | 可购");
+}
@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+
+use dm_ticket::spin::SpinWait;
+
+#[tokio::test]
+async fn test_calibrate() {
+    std::env::remove_var("SPIN_WAIT");
+    let spin = SpinWait::calibrate().await;
+    assert!(spin.spin >= Duration::from_millis(5));
+    assert!(spin.spin <= Duration::from_millis(20));
+
+    std::env::set_var("SPIN_WAIT", "100");
+    let spin = SpinWait::calibrate().await;
+    assert_eq!(spin.spin, Duration::from_millis(20));
+    std::env::remove_var("SPIN_WAIT");
+}
+
+#[tokio::test]
+async fn test_wait_until() {
+    let spin = SpinWait::default();
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(30);
+    let late = spin.wait_until(deadline).await;
+    // 不会早于deadline触发
+    assert!(start.elapsed() >= Duration::from_millis(30));
+    assert!(Duration::from_micros(late) <= start.elapsed());
+
+    // deadline已过时立即返回
+    let late = spin.wait_until(start).await;
+    assert!(late >= 30_000);
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use common::task;
+use dm_ticket::{
+    models::{
+        order::OrderSummary,
+        perform::{PerformItem, SkuItem},
+        task::Task,
+        ticket::Ticket,
+        user::UserInfoData,
+    },
+    platform::{ItemDetail, SubmitResult, TicketPlatform},
+    submit_guard::{is_ambiguous, token_digest, SubmitGuard},
+    ticket::DmTicket,
+};
+
+fn order(order_id: &str, perform_id: &str) -> OrderSummary {
+    OrderSummary {
+        order_id: order_id.to_string(),
+        item_id: "721163957291".to_string(),
+        item_name: "演唱会".to_string(),
+        perform_id: perform_id.to_string(),
+        status: "待付款".to_string(),
+        amount: "380.00".to_string(),
+        pay_deadline: None,
+    }
+}
+
+// 提交订单超时, 但服务器已创建订单的平台
+#[derive(Default)]
+struct TimeoutPlatform {
+    submits: AtomicUsize,
+}
+
+#[async_trait]
+impl TicketPlatform for TimeoutPlatform {
+    type Order = ();
+
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn user_info(&self) -> Result<UserInfoData> {
+        Err(anyhow!("unused"))
+    }
+
+    async fn search(&self) -> Result<Vec<Ticket>> {
+        Ok(vec![])
+    }
+
+    async fn detail(&self, _ticket_id: &str) -> Result<ItemDetail> {
+        Err(anyhow!("unused"))
+    }
+
+    async fn performs(&self, _ticket_id: &str) -> Result<Vec<PerformItem>> {
+        Ok(vec![])
+    }
+
+    async fn skus(&self, _ticket_id: &str, _perform_id: &str) -> Result<Vec<SkuItem>> {
+        Ok(vec![])
+    }
+
+    async fn build_order(&self, _item_id: &str, _sku_id: &str, _buy_num: usize) -> Result<()> {
+        Ok(())
+    }
+
+    async fn submit_order(&self, _task: &Task, _order: ()) -> Result<SubmitResult> {
+        self.submits.fetch_add(1, Ordering::SeqCst);
+        Err(anyhow!("请求mtop.trade.order.create.h5超时(2s)"))
+    }
+
+    async fn orders(&self) -> Result<Vec<OrderSummary>> {
+        match self.submits.load(Ordering::SeqCst) {
+            0 => Ok(vec![]),
+            _ => Ok(vec![order("9", "211234567890")]),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_confirm_order_after_submit_timeout() {
+    // 提交超时不直接失败, 重试前查询订单列表确认已下单
+    let mut ticket = DmTicket::with_platform(TimeoutPlatform::default(), task())
+        .await
+        .unwrap();
+    ticket.subscribers.clear();
+    let res = ticket
+        .multiple_buy_attempts("721163957291", "5012345678901", None)
+        .await;
+    assert!(res.unwrap());
+    assert!(ticket.succeeded);
+    assert!(!ticket.guard.is_pending());
+    assert_eq!(ticket.client.submits.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_resolve_created_order() {
+    let mut guard = SubmitGuard::default();
+    guard.baseline(&[order("1", "211234567890")]);
+    guard.record("run-submit-1", Some("submitref".to_string()));
+    assert!(guard.is_pending());
+
+    // 开抢前已有的订单不算作本次创建
+    let orders = vec![
+        order("1", "211234567890"),
+        order("2", "999"),
+        order("3", "211234567890"),
+    ];
+    let created = guard.resolve(&orders, &task(), true).unwrap();
+    assert_eq!(created.order_id, "3");
+    assert!(!guard.is_pending());
+
+    guard.record("run-submit-2", None);
+    assert!(guard
+        .resolve(&[order("1", "211234567890")], &task(), true)
+        .is_none());
+}
+
+#[test]
+fn test_resolve_without_baseline() {
+    // 未记录已有订单且允许已有订单时无法区分, 继续提交
+    let mut guard = SubmitGuard::default();
+    guard.record("run-submit-1", None);
+    assert!(guard
+        .resolve(&[order("1", "211234567890")], &task(), true)
+        .is_none());
+
+    // 不允许已有订单时, 同场次订单即为本次创建
+    guard.record("run-submit-1", None);
+    let created = guard.resolve(&[order("1", "211234567890")], &task(), false);
+    assert_eq!(created.unwrap().order_id, "1");
+}
+
+#[test]
+fn test_is_ambiguous() {
+    assert!(is_ambiguous(&anyhow!(
+        "请求mtop.trade.order.create.h5超时(2s)"
+    )));
+    assert!(!is_ambiguous(&anyhow!("FAIL_SYS_USER_VALIDATE")));
+}
+
+#[test]
+fn test_token_digest() {
+    let digest = token_digest("submitref");
+    assert_eq!(digest.len(), 8);
+    assert!(!digest.contains("submitref"));
+}
@@ -0,0 +1,60 @@
+use dm_ticket::{
+    models::ticket::{parse_date, Ticket, TicketFilter},
+    sale_timezone,
+};
+use serde_json::json;
+
+fn ticket(category: &str, city: &str, venue: &str, sale_time: i64) -> Ticket {
+    serde_json::from_value(json!({
+        "categoryName": category,
+        "name": "测试演出",
+        "itemId": 1,
+        "upTime": sale_time,
+        "cityName": city,
+        "venueName": venue,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_ticket_filter() {
+    let tz = sale_timezone();
+    let day = parse_date("2023-10-01", tz, false).unwrap();
+    let tickets = vec![
+        ticket("演唱会", "上海", "梅赛德斯奔驰文化中心", day + 3_600_000),
+        ticket("话剧歌剧", "北京市", "国家大剧院", day + 7_200_000),
+        ticket("音乐节", "北京", "奥林匹克公园", day + 86_400_000),
+    ];
+
+    assert_eq!(TicketFilter::concerts().apply(tickets.clone()).len(), 1);
+    assert_eq!(TicketFilter::default().apply(tickets.clone()).len(), 3);
+
+    let filter = TicketFilter {
+        categories: vec!["话剧".to_string(), "音乐节".to_string()],
+        cities: vec!["北京".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(filter.apply(tickets.clone()).len(), 2);
+
+    let filter = TicketFilter {
+        venue: Some("大剧院".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(filter.apply(tickets.clone())[0].city_name, "北京市");
+
+    let filter = TicketFilter {
+        sale_from: Some(day),
+        sale_to: Some(parse_date("2023-10-01", tz, true).unwrap()),
+        ..Default::default()
+    };
+    assert_eq!(filter.apply(tickets).len(), 2);
+}
+
+#[test]
+fn test_parse_date() {
+    let tz = sale_timezone();
+    let start = parse_date("2023-10-01", tz, false).unwrap();
+    let end = parse_date("2023-10-01", tz, true).unwrap();
+    assert_eq!(end - start, 86_400_000 - 1);
+    assert!(parse_date("2023/10/01", tz, false).is_err());
+}
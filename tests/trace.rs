@@ -0,0 +1,32 @@
+mod common;
+
+use common::task;
+use dm_ticket::{events::TicketEvent, trace};
+
+#[test]
+fn test_attempt_id() {
+    let run_id = trace::new_run_id();
+    assert_eq!(run_id.len(), 8);
+    assert_eq!(
+        trace::attempt_id(&run_id, "build", 3),
+        format!("{}-b3", run_id)
+    );
+    assert_eq!(trace::attempt_id("1a2b3c4d", "submit", 1), "1a2b3c4d-s1");
+}
+
+#[tokio::test]
+async fn test_run_id_in_vars() {
+    let task = task();
+    let event = TicketEvent::AttemptStarted {
+        stage: "submit",
+        n: 1,
+        attempt_id: "1a2b3c4d-s1".to_string(),
+    };
+
+    assert!(trace::current_run_id().is_none());
+    assert!(!event.vars(&task).contains_key("run_id"));
+
+    let vars = trace::scope("1a2b3c4d".to_string(), async { event.vars(&task) }).await;
+    assert_eq!(vars["run_id"], "1a2b3c4d");
+    assert_eq!(vars["attempt_id"], "1a2b3c4d-s1");
+}
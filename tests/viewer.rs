@@ -0,0 +1,101 @@
+mod common;
+
+use dm_ticket::models::{
+    task::Task,
+    viewer::{
+        check_cert_no, find_viewer, mask_cert_no, parse_viewer_list, parse_viewers, select_viewers,
+        ID_CARD,
+    },
+};
+use serde_json::json;
+
+fn task(ticket_num: usize) -> Task {
+    Task {
+        ticket_num,
+        ..common::task()
+    }
+}
+
+#[test]
+fn test_select_viewers() {
+    let viewers = parse_viewers(&json!([
+        {"viewerId": 1, "viewerName": "张三", "certNo": "1101**********1234", "certType": 1},
+        {"viewerId": "2", "viewerName": "李四", "certNo": "3101**********5678"},
+        {"viewerId": "3", "viewerName": "王五"}
+    ]));
+    assert_eq!(viewers.len(), 3);
+    assert_eq!(viewers[0].cert_type, "1");
+
+    // 未配置时选择前几位
+    assert_eq!(select_viewers(&viewers, &task(2)).unwrap(), vec![0, 1]);
+
+    // 按姓名/证件号后几位指定
+    let mut t = task(2);
+    t.viewers = vec!["5678".to_string(), "张三".to_string()];
+    assert_eq!(select_viewers(&viewers, &t).unwrap(), vec![1, 0]);
+
+    // 数量不一致
+    t.viewers = vec!["张三".to_string()];
+    assert!(select_viewers(&viewers, &t).is_err());
+
+    // 证件信息不完整
+    t.viewers = vec!["张三".to_string(), "王五".to_string()];
+    assert!(select_viewers(&viewers, &t).is_err());
+
+    // 按序号指定
+    let mut t = task(1);
+    t.real_names = vec![2];
+    assert_eq!(select_viewers(&viewers, &t).unwrap(), vec![1]);
+    t.real_names = vec![4];
+    assert!(select_viewers(&viewers, &t).is_err());
+
+    assert!(select_viewers(&viewers, &task(4)).is_err());
+}
+
+#[test]
+fn test_split_by_viewer() {
+    let mut t = task(2);
+    t.split_per_viewer = true;
+    let tasks = t.split_by_viewer();
+    assert_eq!(tasks.len(), 2);
+    assert!(tasks
+        .iter()
+        .all(|t| t.ticket_num == 1 && !t.split_per_viewer));
+    assert_eq!(tasks[1].real_names, vec![2]);
+
+    t.viewers = vec!["张三".to_string(), "李四".to_string()];
+    let tasks = t.split_by_viewer();
+    assert_eq!(tasks[0].viewers, vec!["张三".to_string()]);
+    assert!(tasks[0].real_names.is_empty());
+}
+
+#[test]
+fn test_viewer_list() {
+    let viewers = parse_viewer_list(&json!({
+        "userList": [
+            {"id": 1, "name": "张三", "idNo": "1101**********1234", "idType": "1"},
+            {"id": 2, "name": "张三丰", "idNo": "3101**********1234", "idType": "1"}
+        ]
+    }));
+    assert_eq!(viewers.len(), 2);
+    assert_eq!(viewers[1].viewer_id, "2");
+    assert!(parse_viewer_list(&json!({})).is_empty());
+
+    assert_eq!(find_viewer(&viewers, "张三丰").unwrap().viewer_id, "2");
+    // 证件号后几位相同时需要更多位或姓名
+    assert!(find_viewer(&viewers, "1234").is_err());
+    assert!(find_viewer(&viewers, "王五").is_err());
+}
+
+#[test]
+fn test_check_cert_no() {
+    assert!(check_cert_no(ID_CARD, "11010519491231002X").is_ok());
+    assert!(check_cert_no(ID_CARD, "11010519491231002x").is_ok());
+    assert!(check_cert_no(ID_CARD, "110105194912310021").is_err());
+    assert!(check_cert_no(ID_CARD, "1101051949123100").is_err());
+    assert!(check_cert_no("2", "E12345678").is_ok());
+    assert!(check_cert_no("2", " ").is_err());
+
+    assert_eq!(mask_cert_no("11010519491231002X"), "1101**********002X");
+    assert_eq!(mask_cert_no("E1234567"), "********");
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "browser-login")]
+
+use std::time::{Duration, Instant};
+
+use dm_ticket::clients::webdriver::{chrome_caps, connect};
+
+#[tokio::test]
+async fn test_connect_retry() {
+    // 无法连接时按退避间隔重试, 超过等待时长后返回错误
+    let start = Instant::now();
+    let res = connect(
+        "http://127.0.0.1:1",
+        chrome_caps(true).unwrap(),
+        Duration::from_millis(800),
+    )
+    .await;
+    assert!(res.unwrap_err().to_string().contains("webdriver"));
+    assert!(start.elapsed() >= Duration::from_millis(500));
+
+    let start = Instant::now();
+    let res = connect(
+        "http://127.0.0.1:1",
+        chrome_caps(true).unwrap(),
+        Duration::ZERO,
+    )
+    .await;
+    assert!(res.is_err());
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use dm_ticket::clients::webhook::render;
+use serde_json::{json, Value};
+
+#[test]
+fn test_render_template() {
+    let vars: HashMap<String, Value> = [
+        ("event".to_string(), json!("order_created")),
+        ("message".to_string(), json!("下单成功 \"VIP\"")),
+        ("elapsed_ms".to_string(), json!(12)),
+        ("order_id".to_string(), Value::Null),
+    ]
+    .into_iter()
+    .collect();
+
+    let body = render(
+        r#"{"text":"{{message}}","cost":{{elapsed_ms}},"id":"{{order_id}}"}"#,
+        &vars,
+    );
+    let value: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(value["text"], "下单成功 \"VIP\"");
+    assert_eq!(value["cost"], 12);
+    assert_eq!(value["id"], "");
+
+    let body = render(r#"{"data":{{payload}}}"#, &vars);
+    let value: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(value["data"]["event"], "order_created");
+}